@@ -74,6 +74,10 @@ pub fn path_map(map_name: &str) -> String {
     format!("../data/maps/{}.bin", map_name)
 }
 
+pub fn path_validation_report(map_name: &str) -> String {
+    format!("../data/validation/{}.json", map_name)
+}
+
 pub fn path_polygon(polygon_name: &str) -> String {
     format!("../data/polygons/{}.poly", polygon_name)
 }
@@ -1,9 +1,13 @@
 mod clip;
 mod neighborhoods;
 mod osm;
+pub mod reader;
 mod remove_disconnected;
 mod split_ways;
 
+pub use crate::osm::{is_road, StopSign};
+pub use crate::split_ways::tag_stop_signs;
+
 use abstutil::Timer;
 use geom::{Distance, FindClosest, GPSBounds, LonLat, PolyLine, Polygon, Pt2D};
 use kml::ExtraShapes;
@@ -15,10 +19,19 @@ use structopt::StructOpt;
 #[derive(StructOpt, Debug)]
 #[structopt(name = "convert_osm")]
 pub struct Flags {
-    /// OSM XML file to read
+    /// OSM XML (.osm) or PBF (.osm.pbf) file to read, or a .bin previously written by --osm_cache
     #[structopt(long = "osm")]
     pub osm: String,
 
+    /// OSM change file (.osc) to apply on top of --osm. Optional.
+    #[structopt(long = "osc", default_value = "")]
+    pub osc: String,
+
+    /// Where to save the clipped OSM data (after applying --osc), so later imports can pass it as
+    /// --osm. Optional.
+    #[structopt(long = "osm_cache", default_value = "")]
+    pub osm_cache: String,
+
     /// ExtraShapes file with blockface, produced using the kml crate. Optional.
     #[structopt(long = "parking_shapes", default_value = "")]
     pub parking_shapes: String,
@@ -46,14 +59,28 @@ pub struct Flags {
     /// Disable blockface
     #[structopt(long = "fast_dev")]
     pub fast_dev: bool,
+
+    /// Also import footways, cycleways, paths, stairs, and alleys
+    #[structopt(long = "footways_and_alleys")]
+    pub footways_and_alleys: bool,
 }
 
 pub fn convert(flags: &Flags, timer: &mut abstutil::Timer) -> raw_data::Map {
     if flags.clip.is_empty() {
         panic!("You must specify an Osmosis boundary polygon with --clip");
     }
+    let boundary = read_osmosis_polygon(&flags.clip);
+    let mut doc = reader::read(&flags.osm, &boundary, timer);
+    if !flags.osc.is_empty() {
+        reader::apply_osc(&mut doc, &flags.osc, &boundary, timer);
+    }
+    if !flags.osm_cache.is_empty() {
+        timer.start("save OSM cache");
+        abstutil::write_binary(&flags.osm_cache, &doc).expect("saving OSM cache failed");
+        timer.stop("save OSM cache");
+    }
     let mut map = split_ways::split_up_roads(
-        osm::extract_osm(&flags.osm, read_osmosis_polygon(&flags.clip), timer),
+        osm::extract_osm(&doc, boundary, flags.footways_and_alleys, timer),
         timer,
     );
     clip::clip_map(&mut map, timer);
@@ -136,7 +163,7 @@ fn use_parking_hints(map: &mut raw_data::Map, path: &str, timer: &mut Timer) {
     timer.stop("apply parking hints");
 }
 
-fn read_osmosis_polygon(path: &str) -> raw_data::Map {
+pub fn read_osmosis_polygon(path: &str) -> raw_data::Map {
     let mut pts: Vec<LonLat> = Vec::new();
     let mut gps_bounds = GPSBounds::new();
     for (idx, maybe_line) in BufReader::new(File::open(path).unwrap())
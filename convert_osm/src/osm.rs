@@ -1,12 +1,13 @@
-use abstutil::{FileWithProgress, Timer};
-use geom::{HashablePt2D, LonLat, Polygon, Pt2D};
+use crate::reader::{Document, MemberType};
+use abstutil::Timer;
+use geom::{HashablePt2D, Polygon, Pt2D};
 use map_model::{raw_data, AreaType};
-use osm_xml;
 use std::collections::{BTreeMap, HashMap, HashSet};
 
 pub fn extract_osm(
-    osm_path: &str,
+    doc: &Document,
     mut map: raw_data::Map,
+    footways_and_alleys: bool,
     timer: &mut Timer,
 ) -> (
     raw_data::Map,
@@ -14,65 +15,63 @@ pub fn extract_osm(
     Vec<raw_data::Road>,
     // Traffic signals
     HashSet<HashablePt2D>,
+    HashMap<HashablePt2D, StopSign>,
 ) {
-    let (reader, done) = FileWithProgress::new(osm_path).unwrap();
-    let doc = osm_xml::OSM::parse(reader).expect("OSM parsing failed");
-    println!(
-        "OSM doc has {} nodes, {} ways, {} relations",
-        doc.nodes.len(),
-        doc.ways.len(),
-        doc.relations.len()
-    );
-    done(timer);
-
     let mut id_to_way: HashMap<i64, Vec<Pt2D>> = HashMap::new();
     let mut roads: Vec<raw_data::Road> = Vec::new();
     let mut traffic_signals: HashSet<HashablePt2D> = HashSet::new();
+    let mut stop_signs: HashMap<HashablePt2D, StopSign> = HashMap::new();
 
     timer.start_iter("processing OSM nodes", doc.nodes.len());
     for node in doc.nodes.values() {
         timer.next();
-        let tags = tags_to_map(&node.tags);
-        if tags.get("highway") == Some(&"traffic_signals".to_string()) {
-            traffic_signals.insert(
-                Pt2D::forcibly_from_gps(LonLat::new(node.lon, node.lat), &map.gps_bounds)
-                    .to_hashable(),
+        if node.tags.get("highway") == Some(&"traffic_signals".to_string()) {
+            traffic_signals.insert(Pt2D::forcibly_from_gps(node.pt, &map.gps_bounds).to_hashable());
+        }
+        if node.tags.get("highway") == Some(&"stop".to_string()) {
+            stop_signs.insert(
+                Pt2D::forcibly_from_gps(node.pt, &map.gps_bounds).to_hashable(),
+                StopSign {
+                    all_way: node.tags.get("stop") == Some(&"all".to_string()),
+                    direction: node.tags.get("direction").cloned(),
+                },
             );
         }
     }
 
     timer.start_iter("processing OSM ways", doc.ways.len());
-    for way in doc.ways.values() {
+    for (id, way) in &doc.ways {
         timer.next();
 
         let mut valid = true;
         let mut gps_pts = Vec::new();
-        for node_ref in &way.nodes {
-            match doc.resolve_reference(node_ref) {
-                osm_xml::Reference::Node(node) => {
-                    gps_pts.push(LonLat::new(node.lon, node.lat));
-                }
-                // Don't handle nested ways/relations yet
-                _ => {
-                    valid = false;
-                }
+        for node_id in &way.nodes {
+            if let Some(node) = doc.nodes.get(node_id) {
+                gps_pts.push(node.pt);
+            } else {
+                // Clipped out
+                valid = false;
             }
         }
-        if !valid {
+        if !valid || gps_pts.is_empty() {
             continue;
         }
         let pts = map.gps_bounds.forcibly_convert(&gps_pts);
-        let tags = tags_to_map(&way.tags);
-        if is_road(&tags) {
+        let tags = way.tags.clone();
+        if is_road(&tags, footways_and_alleys) {
             roads.push(raw_data::Road {
-                osm_way_id: way.id,
+                osm_way_id: *id,
                 center_points: pts,
                 orig_id: raw_data::OriginalRoad {
                     pt1: gps_pts[0],
                     pt2: *gps_pts.last().unwrap(),
                 },
                 osm_tags: tags,
-                // We'll fill this out later
+                // We'll fill these out later, once the way is split into segments
+                segment_id: raw_data::OriginalRoad {
+                    pt1: gps_pts[0],
+                    pt2: *gps_pts.last().unwrap(),
+                },
                 i1: raw_data::StableIntersectionID(0),
                 i2: raw_data::StableIntersectionID(0),
                 parking_lane_fwd: false,
@@ -80,7 +79,7 @@ pub fn extract_osm(
             });
         } else if is_bldg(&tags) {
             map.buildings.push(raw_data::Building {
-                osm_way_id: way.id,
+                osm_way_id: *id,
                 polygon: Polygon::new(&Pt2D::approx_dedupe(pts, geom::EPSILON_DIST)),
                 osm_tags: tags,
                 parking: None,
@@ -88,41 +87,41 @@ pub fn extract_osm(
         } else if let Some(at) = get_area_type(&tags) {
             map.areas.push(raw_data::Area {
                 area_type: at,
-                osm_id: way.id,
+                osm_id: *id,
                 polygon: Polygon::new(&pts),
                 osm_tags: tags,
             });
         } else {
             // The way might be part of a relation later.
-            id_to_way.insert(way.id, pts);
+            id_to_way.insert(*id, pts);
         }
     }
 
     timer.start_iter("processing OSM relations", doc.relations.len());
-    for rel in doc.relations.values() {
+    for (rel_id, rel) in &doc.relations {
         timer.next();
-        let tags = tags_to_map(&rel.tags);
-        if let Some(at) = get_area_type(&tags) {
+        let tags = &rel.tags;
+        if let Some(at) = get_area_type(tags) {
             if tags.get("type") == Some(&"multipolygon".to_string()) {
                 let mut ok = true;
                 let mut pts_per_way: Vec<Vec<Pt2D>> = Vec::new();
                 for member in &rel.members {
-                    match member {
-                        osm_xml::Member::Way(osm_xml::UnresolvedReference::Way(id), ref role) => {
+                    match member.member_type {
+                        MemberType::Way => {
                             // If the way is clipped out, that's fine
-                            if let Some(pts) = id_to_way.get(id) {
-                                if role == "outer" {
+                            if let Some(pts) = id_to_way.get(&member.id) {
+                                if member.role == "outer" {
                                     pts_per_way.push(pts.to_vec());
                                 } else {
                                     println!(
                                         "Relation {} has unhandled member role {}, ignoring it",
-                                        rel.id, role
+                                        rel_id, member.role
                                     );
                                 }
                             }
                         }
                         _ => {
-                            println!("Relation {} refers to {:?}", rel.id, member);
+                            println!("Relation {} refers to {:?}", rel_id, member);
                             ok = false;
                         }
                     }
@@ -130,12 +129,12 @@ pub fn extract_osm(
                 if ok {
                     let polygons = glue_multipolygon(pts_per_way);
                     if polygons.is_empty() {
-                        println!("Relation {} failed to glue multipolygon", rel.id);
+                        println!("Relation {} failed to glue multipolygon", rel_id);
                     } else {
                         for polygon in polygons {
                             map.areas.push(raw_data::Area {
                                 area_type: at,
-                                osm_id: rel.id,
+                                osm_id: *rel_id,
                                 polygon,
                                 osm_tags: tags.clone(),
                             });
@@ -145,44 +144,60 @@ pub fn extract_osm(
             }
         } else if tags.get("type") == Some(&"restriction".to_string()) {
             let mut from_way_id: Option<i64> = None;
+            let mut via_ways: Vec<i64> = Vec::new();
             let mut to_way_id: Option<i64> = None;
             for member in &rel.members {
-                if let osm_xml::Member::Way(osm_xml::UnresolvedReference::Way(id), ref role) =
-                    member
-                {
-                    if role == "from" {
-                        from_way_id = Some(*id);
-                    } else if role == "to" {
-                        to_way_id = Some(*id);
+                if member.member_type == MemberType::Way {
+                    if member.role == "from" {
+                        from_way_id = Some(member.id);
+                    } else if member.role == "via" {
+                        via_ways.push(member.id);
+                    } else if member.role == "to" {
+                        to_way_id = Some(member.id);
                     }
                 }
             }
             if let (Some(from_way_id), Some(to_way_id)) = (from_way_id, to_way_id) {
-                if let Some(restriction) = tags.get("restriction") {
+                if let Some((restriction, conditions)) = get_restriction(tags) {
                     map.turn_restrictions
                         .entry(from_way_id)
                         .or_insert_with(Vec::new)
-                        .push((restriction.to_string(), to_way_id));
+                        .push(raw_data::TurnRestriction {
+                            restriction,
+                            via_ways,
+                            to_way: to_way_id,
+                            except: tags
+                                .get("except")
+                                .map(|e| e.split(';').map(|x| x.trim().to_string()).collect())
+                                .unwrap_or_else(Vec::new),
+                            conditions,
+                        });
                 }
             }
         }
     }
 
-    (map, roads, traffic_signals)
+    (map, roads, traffic_signals, stop_signs)
 }
 
-fn tags_to_map(raw_tags: &[osm_xml::Tag]) -> BTreeMap<String, String> {
-    raw_tags
-        .iter()
-        .map(|tag| (tag.key.clone(), tag.val.clone()))
-        .collect()
+// A highway=stop node. Usually it's on the way just before the intersection, but sometimes it's
+// the intersection itself.
+pub struct StopSign {
+    // stop=all. Otherwise, only the minor roads stop.
+    pub all_way: bool,
+    // "forward" or "backward", relative to the way
+    pub direction: Option<String>,
 }
 
-fn is_road(tags: &BTreeMap<String, String>) -> bool {
+pub fn is_road(tags: &BTreeMap<String, String>, footways_and_alleys: bool) -> bool {
     if !tags.contains_key("highway") {
         return false;
     }
 
+    if footways_and_alleys && is_footway_or_alley(tags) {
+        return true;
+    }
+
     // https://github.com/Project-OSRM/osrm-backend/blob/master/profiles/car.lua is another
     // potential reference
     for &value in &[
@@ -217,6 +232,53 @@ fn is_road(tags: &BTreeMap<String, String>) -> bool {
     true
 }
 
+// These become roads with only sidewalks, only bike lanes, or both. map_model's lane_specs decides
+// exactly what.
+fn is_footway_or_alley(tags: &BTreeMap<String, String>) -> bool {
+    // Pedestrian plazas are areas, not paths.
+    if tags.get("area") == Some(&"yes".to_string()) {
+        return false;
+    }
+    if tags.get("access") == Some(&"private".to_string()) {
+        return false;
+    }
+    match tags.get("highway").map(|s| s.as_str()) {
+        Some("footway") | Some("pedestrian") | Some("path") | Some("steps") | Some("cycleway") => {
+            // Sidewalks are already modeled as part of the road they're next to.
+            tags.get("footway") != Some(&"sidewalk".to_string())
+                && tags.get("footway") != Some(&"crossing".to_string())
+        }
+        // Driveways and parking lot aisles would swamp the map, and untagged service roads are
+        // usually one of those.
+        Some("service") => tags.get("service") == Some(&"alley".to_string()),
+        _ => false,
+    }
+}
+
+// Returns the restriction and any time conditions. Restrictions only for particular vehicles (like
+// restriction:hgv) are ignored; we don't model them.
+fn get_restriction(tags: &BTreeMap<String, String>) -> Option<(String, Option<String>)> {
+    let (value, always) = if let Some(r) = tags.get("restriction") {
+        (r, true)
+    } else {
+        (tags.get("restriction:conditional")?, false)
+    };
+    // Like "no_left_turn @ (Mo-Fr 07:00-09:00)"
+    let mut parts = value.splitn(2, '@');
+    let restriction = parts.next().unwrap().trim().to_string();
+    let conditions = parts.next().map(|c| {
+        c.trim()
+            .trim_start_matches('(')
+            .trim_end_matches(')')
+            .trim()
+            .to_string()
+    });
+    if !always && conditions.is_none() {
+        return None;
+    }
+    Some((restriction, conditions))
+}
+
 fn is_bldg(tags: &BTreeMap<String, String>) -> bool {
     tags.contains_key("building")
 }
@@ -0,0 +1,477 @@
+use abstutil::{FileWithProgress, Timer};
+use geom::{LonLat, Pt2D};
+use map_model::raw_data;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+
+// The raw OSM elements, independent of the input format. This is cheap to serialize, so a clipped
+// city-wide import can be cached and later refreshed with change files, skipping the slow parse.
+#[derive(Serialize, Deserialize)]
+pub struct Document {
+    pub nodes: BTreeMap<i64, Node>,
+    pub ways: BTreeMap<i64, Way>,
+    pub relations: BTreeMap<i64, Relation>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Node {
+    pub pt: LonLat,
+    pub tags: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Way {
+    // Might refer to nodes that were clipped out
+    pub nodes: Vec<i64>,
+    pub tags: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Relation {
+    pub members: Vec<Member>,
+    pub tags: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum MemberType {
+    Node,
+    Way,
+    Relation,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Member {
+    pub member_type: MemberType,
+    pub id: i64,
+    pub role: String,
+}
+
+impl Document {
+    fn new() -> Document {
+        Document {
+            nodes: BTreeMap::new(),
+            ways: BTreeMap::new(),
+            relations: BTreeMap::new(),
+        }
+    }
+}
+
+// Handles OSM XML (.osm), PBF (.osm.pbf), or a Document cached from a previous import (.bin).
+// Only PBF files are clipped, since those are usually much bigger than the map.
+pub fn read(path: &str, boundary: &raw_data::Map, timer: &mut Timer) -> Document {
+    let doc = if path.ends_with(".pbf") {
+        read_pbf(path, boundary, timer)
+    } else if path.ends_with(".bin") {
+        abstutil::read_binary(path, timer).expect("loading cached OSM document failed")
+    } else {
+        read_xml(path, timer)
+    };
+    println!(
+        "OSM doc has {} nodes, {} ways, {} relations",
+        doc.nodes.len(),
+        doc.ways.len(),
+        doc.relations.len()
+    );
+    doc
+}
+
+fn read_xml(path: &str, timer: &mut Timer) -> Document {
+    let (reader, done) = FileWithProgress::new(path).unwrap();
+    let osm = osm_xml::OSM::parse(reader).expect("OSM parsing failed");
+    done(timer);
+
+    let mut doc = Document::new();
+    for node in osm.nodes.values() {
+        doc.nodes.insert(
+            node.id,
+            Node {
+                pt: LonLat::new(node.lon, node.lat),
+                tags: xml_tags(&node.tags),
+            },
+        );
+    }
+    for way in osm.ways.values() {
+        let nodes = way
+            .nodes
+            .iter()
+            .filter_map(|r| match r {
+                osm_xml::UnresolvedReference::Node(id) => Some(*id),
+                _ => None,
+            })
+            .collect();
+        doc.ways.insert(
+            way.id,
+            Way {
+                nodes,
+                tags: xml_tags(&way.tags),
+            },
+        );
+    }
+    for rel in osm.relations.values() {
+        let members = rel
+            .members
+            .iter()
+            .map(|m| {
+                let (member_type, id, role) = match m {
+                    osm_xml::Member::Node(osm_xml::UnresolvedReference::Node(id), role) => {
+                        (MemberType::Node, *id, role)
+                    }
+                    osm_xml::Member::Way(osm_xml::UnresolvedReference::Way(id), role) => {
+                        (MemberType::Way, *id, role)
+                    }
+                    osm_xml::Member::Relation(osm_xml::UnresolvedReference::Relation(id), role) => {
+                        (MemberType::Relation, *id, role)
+                    }
+                    _ => unreachable!(),
+                };
+                Member {
+                    member_type,
+                    id,
+                    role: role.clone(),
+                }
+            })
+            .collect();
+        doc.relations.insert(
+            rel.id,
+            Relation {
+                members,
+                tags: xml_tags(&rel.tags),
+            },
+        );
+    }
+    doc
+}
+
+fn xml_tags(raw_tags: &[osm_xml::Tag]) -> BTreeMap<String, String> {
+    raw_tags
+        .iter()
+        .map(|tag| (tag.key.clone(), tag.val.clone()))
+        .collect()
+}
+
+// Keeps nodes inside the boundary, ways touching any of those nodes (including their nodes outside
+// the boundary, so clip_map can trim them properly), and relations with a member that was kept.
+// PBF files list all nodes, then ways, then relations, so this works in one pass, plus a second to
+// pick up the outside nodes.
+fn read_pbf(path: &str, boundary: &raw_data::Map, timer: &mut Timer) -> Document {
+    let inside = |pt: LonLat| is_inside(pt, boundary);
+
+    let mut doc = Document::new();
+    let mut outside_nodes: HashSet<i64> = HashSet::new();
+
+    timer.start(&format!("read {}", path));
+    let mut pbf = osmpbfreader::OsmPbfReader::new(File::open(path).unwrap());
+    for obj in pbf.iter() {
+        match obj.expect("PBF parsing failed") {
+            osmpbfreader::OsmObj::Node(node) => {
+                let pt = LonLat::new(node.lon(), node.lat());
+                if inside(pt) {
+                    doc.nodes.insert(
+                        node.id.0,
+                        Node {
+                            pt,
+                            tags: pbf_tags(&node.tags),
+                        },
+                    );
+                }
+            }
+            osmpbfreader::OsmObj::Way(way) => {
+                let nodes: Vec<i64> = way.nodes.iter().map(|id| id.0).collect();
+                if nodes.iter().any(|id| doc.nodes.contains_key(id)) {
+                    for id in &nodes {
+                        if !doc.nodes.contains_key(id) {
+                            outside_nodes.insert(*id);
+                        }
+                    }
+                    doc.ways.insert(
+                        way.id.0,
+                        Way {
+                            nodes,
+                            tags: pbf_tags(&way.tags),
+                        },
+                    );
+                }
+            }
+            osmpbfreader::OsmObj::Relation(rel) => {
+                let members: Vec<Member> = rel
+                    .refs
+                    .iter()
+                    .map(|r| {
+                        let (member_type, id) = match r.member {
+                            osmpbfreader::OsmId::Node(id) => (MemberType::Node, id.0),
+                            osmpbfreader::OsmId::Way(id) => (MemberType::Way, id.0),
+                            osmpbfreader::OsmId::Relation(id) => (MemberType::Relation, id.0),
+                        };
+                        Member {
+                            member_type,
+                            id,
+                            role: r.role.to_string(),
+                        }
+                    })
+                    .collect();
+                if members.iter().any(|m| match m.member_type {
+                    MemberType::Node => doc.nodes.contains_key(&m.id),
+                    MemberType::Way => doc.ways.contains_key(&m.id),
+                    MemberType::Relation => false,
+                }) {
+                    doc.relations.insert(
+                        rel.id.0,
+                        Relation {
+                            members,
+                            tags: pbf_tags(&rel.tags),
+                        },
+                    );
+                }
+            }
+        }
+    }
+    timer.stop(&format!("read {}", path));
+
+    timer.start(&format!(
+        "read {} nodes outside the boundary",
+        outside_nodes.len()
+    ));
+    let mut pbf = osmpbfreader::OsmPbfReader::new(File::open(path).unwrap());
+    for obj in pbf.iter() {
+        if let osmpbfreader::OsmObj::Node(node) = obj.expect("PBF parsing failed") {
+            if outside_nodes.contains(&node.id.0) {
+                doc.nodes.insert(
+                    node.id.0,
+                    Node {
+                        pt: LonLat::new(node.lon(), node.lat()),
+                        tags: pbf_tags(&node.tags),
+                    },
+                );
+            }
+        }
+    }
+    timer.stop(&format!(
+        "read {} nodes outside the boundary",
+        outside_nodes.len()
+    ));
+
+    doc
+}
+
+fn is_inside(pt: LonLat, boundary: &raw_data::Map) -> bool {
+    Pt2D::from_gps(pt, &boundary.gps_bounds)
+        .map(|pt| boundary.boundary_polygon.contains_pt(pt))
+        .unwrap_or(false)
+}
+
+fn pbf_tags(tags: &osmpbfreader::Tags) -> BTreeMap<String, String> {
+    tags.iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Action {
+    Create,
+    Modify,
+    Delete,
+}
+
+// An element from a change file, partly built
+enum Element {
+    Node(i64, Node),
+    Way(i64, Way),
+    Relation(i64, Relation),
+}
+
+// Applies an osmChange (.osc) file, like the daily diffs from planet.openstreetmap.org or Geofabrik.
+// Created and modified elements are clipped to the boundary the same way read_pbf does.
+pub fn apply_osc(doc: &mut Document, path: &str, boundary: &raw_data::Map, timer: &mut Timer) {
+    timer.start(&format!("apply {}", path));
+    let mut parser = xml::Parser::new();
+    parser.feed_str(&std::fs::read_to_string(path).expect("reading change file failed"));
+
+    let mut action: Option<Action> = None;
+    let mut current: Option<Element> = None;
+    let mut changes: Vec<(Action, Element)> = Vec::new();
+    for event in parser {
+        match event.expect("change file parsing failed") {
+            xml::Event::ElementStart(tag) => {
+                let attr = |key: &str| tag.attributes.get(&(key.to_string(), None));
+                let id = || attr("id").and_then(|x| x.parse::<i64>().ok());
+                match tag.name.as_ref() {
+                    "create" => {
+                        action = Some(Action::Create);
+                    }
+                    "modify" => {
+                        action = Some(Action::Modify);
+                    }
+                    "delete" => {
+                        action = Some(Action::Delete);
+                    }
+                    "node" => {
+                        let lon = attr("lon").and_then(|x| x.parse::<f64>().ok());
+                        let lat = attr("lat").and_then(|x| x.parse::<f64>().ok());
+                        current = Some(Element::Node(
+                            id().expect("node without id"),
+                            Node {
+                                // Deleted nodes don't need a position
+                                pt: LonLat::new(lon.unwrap_or(0.0), lat.unwrap_or(0.0)),
+                                tags: BTreeMap::new(),
+                            },
+                        ));
+                    }
+                    "way" => {
+                        current = Some(Element::Way(
+                            id().expect("way without id"),
+                            Way {
+                                nodes: Vec::new(),
+                                tags: BTreeMap::new(),
+                            },
+                        ));
+                    }
+                    "relation" => {
+                        current = Some(Element::Relation(
+                            id().expect("relation without id"),
+                            Relation {
+                                members: Vec::new(),
+                                tags: BTreeMap::new(),
+                            },
+                        ));
+                    }
+                    "tag" => {
+                        if let (Some(k), Some(v)) = (attr("k"), attr("v")) {
+                            let tags = match current {
+                                Some(Element::Node(_, ref mut n)) => &mut n.tags,
+                                Some(Element::Way(_, ref mut w)) => &mut w.tags,
+                                Some(Element::Relation(_, ref mut r)) => &mut r.tags,
+                                None => continue,
+                            };
+                            tags.insert(k.to_string(), v.to_string());
+                        }
+                    }
+                    "nd" => {
+                        if let Some(Element::Way(_, ref mut w)) = current {
+                            if let Some(id) = attr("ref").and_then(|x| x.parse::<i64>().ok()) {
+                                w.nodes.push(id);
+                            }
+                        }
+                    }
+                    "member" => {
+                        if let Some(Element::Relation(_, ref mut r)) = current {
+                            let member_type = match attr("type").map(|x| x.as_str()) {
+                                Some("node") => MemberType::Node,
+                                Some("way") => MemberType::Way,
+                                Some("relation") => MemberType::Relation,
+                                _ => continue,
+                            };
+                            if let Some(id) = attr("ref").and_then(|x| x.parse::<i64>().ok()) {
+                                r.members.push(Member {
+                                    member_type,
+                                    id,
+                                    role: attr("role").cloned().unwrap_or_else(String::new),
+                                });
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            xml::Event::ElementEnd(tag) => {
+                if tag.name != "node" && tag.name != "way" && tag.name != "relation" {
+                    continue;
+                }
+                if let (Some(a), Some(elem)) = (action, current.take()) {
+                    changes.push((a, elem));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Nodes first, since whether ways and relations are kept depends on them. Like read_pbf, nodes
+    // outside the boundary are only kept when a kept way uses them.
+    let (mut changed, mut deleted, mut clipped) = (0, 0, 0);
+    let mut outside_nodes: BTreeMap<i64, Node> = BTreeMap::new();
+    let mut ways: Vec<(Action, i64, Way)> = Vec::new();
+    let mut relations: Vec<(Action, i64, Relation)> = Vec::new();
+    for (action, elem) in changes {
+        match elem {
+            Element::Node(id, n) => {
+                if action == Action::Delete {
+                    doc.nodes.remove(&id);
+                    deleted += 1;
+                } else if is_inside(n.pt, boundary) || doc.nodes.contains_key(&id) {
+                    doc.nodes.insert(id, n);
+                    changed += 1;
+                } else {
+                    outside_nodes.insert(id, n);
+                }
+            }
+            Element::Way(id, w) => ways.push((action, id, w)),
+            Element::Relation(id, r) => relations.push((action, id, r)),
+        }
+    }
+
+    let mut missing_nodes: HashSet<i64> = HashSet::new();
+    for (action, id, way) in ways {
+        if action == Action::Delete {
+            doc.ways.remove(&id);
+            deleted += 1;
+            continue;
+        }
+        if !way.nodes.iter().any(|n| {
+            doc.nodes
+                .get(n)
+                .map(|n| is_inside(n.pt, boundary))
+                .unwrap_or(false)
+        }) {
+            // It might've been inside before the change
+            doc.ways.remove(&id);
+            clipped += 1;
+            continue;
+        }
+        for n in &way.nodes {
+            if doc.nodes.contains_key(n) {
+                continue;
+            }
+            if let Some(node) = outside_nodes.remove(n) {
+                doc.nodes.insert(*n, node);
+            } else {
+                missing_nodes.insert(*n);
+            }
+        }
+        doc.ways.insert(id, way);
+        changed += 1;
+    }
+    if !missing_nodes.is_empty() {
+        timer.warn(format!(
+            "{} nodes used by changed ways were clipped out earlier. The ways are still updated, \
+             but won't become roads or buildings without those nodes. Read the original extract \
+             again to include them.",
+            missing_nodes.len()
+        ));
+    }
+
+    for (action, id, rel) in relations {
+        if action == Action::Delete {
+            doc.relations.remove(&id);
+            deleted += 1;
+            continue;
+        }
+        if rel.members.iter().any(|m| match m.member_type {
+            MemberType::Node => doc.nodes.contains_key(&m.id),
+            MemberType::Way => doc.ways.contains_key(&m.id),
+            MemberType::Relation => false,
+        }) {
+            doc.relations.insert(id, rel);
+            changed += 1;
+        } else {
+            doc.relations.remove(&id);
+            clipped += 1;
+        }
+    }
+    clipped += outside_nodes.len();
+
+    timer.note(format!(
+        "{} created or modified {} elements and deleted {}. {} were outside the boundary.",
+        path, changed, deleted, clipped
+    ));
+    timer.stop(&format!("apply {}", path));
+}
@@ -1,13 +1,15 @@
+use crate::osm::StopSign;
 use abstutil::Timer;
 use geom::{HashablePt2D, Pt2D};
 use map_model::{raw_data, IntersectionType};
 use std::collections::{HashMap, HashSet};
 
 pub fn split_up_roads(
-    (mut map, mut roads, traffic_signals): (
+    (mut map, mut roads, traffic_signals, stop_signs): (
         raw_data::Map,
         Vec<raw_data::Road>,
         HashSet<HashablePt2D>,
+        HashMap<HashablePt2D, StopSign>,
     ),
     timer: &mut Timer,
 ) -> raw_data::Map {
@@ -121,6 +123,11 @@ pub fn split_up_roads(
                 }
 
                 r.i2 = *i2;
+                // A way crossing other roads becomes many segments, which each need their own ID
+                r.segment_id = raw_data::OriginalRoad {
+                    pt1: map.intersections[&r.i1].orig_id.point,
+                    pt2: map.intersections[&r.i2].orig_id.point,
+                };
                 if r.i1 == endpt1 {
                     r.osm_tags
                         .insert("abst:endpt_back".to_string(), "true".to_string());
@@ -130,11 +137,14 @@ pub fn split_up_roads(
                         .insert("abst:endpt_fwd".to_string(), "true".to_string());
                 }
                 r.center_points = std::mem::replace(&mut pts, Vec::new());
+                tag_stop_signs(&mut r, &stop_signs);
                 // Start a new road
                 map.roads
                     .insert(raw_data::StableRoadID(map.roads.len()), r.clone());
                 r.osm_tags.remove("abst:endpt_fwd");
                 r.osm_tags.remove("abst:endpt_back");
+                r.osm_tags.remove("abst:stop_fwd");
+                r.osm_tags.remove("abst:stop_back");
                 r.i1 = *i2;
                 pts.push(*pt);
             }
@@ -145,3 +155,37 @@ pub fn split_up_roads(
     timer.stop("splitting up roads");
     map
 }
+
+// Records which end of the road has a stop sign. "yes" means traffic always stops there; "minor"
+// means it stops only if the other roads at the intersection are bigger.
+pub fn tag_stop_signs(r: &mut raw_data::Road, stop_signs: &HashMap<HashablePt2D, StopSign>) {
+    let pts = &r.center_points;
+    let last = pts.len() - 1;
+    let mut tags = Vec::new();
+    for (idx, pt) in pts.iter().enumerate() {
+        if let Some(stop) = stop_signs.get(&pt.to_hashable()) {
+            if idx == 0 || idx == last {
+                // Right at the intersection, so it applies to every road there.
+                tags.push((idx == last, if stop.all_way { "yes" } else { "minor" }));
+            } else {
+                let fwd = match stop.direction.as_ref().map(|d| d.as_str()) {
+                    Some("forward") => true,
+                    Some("backward") => false,
+                    _ => pt.dist_to(pts[last]) < pt.dist_to(pts[0]),
+                };
+                tags.push((fwd, "yes"));
+            }
+        }
+    }
+    for (fwd, value) in tags {
+        let key = if fwd {
+            "abst:stop_fwd"
+        } else {
+            "abst:stop_back"
+        };
+        // A stop sign on the way itself is more specific than one at the intersection.
+        if value == "yes" || !r.osm_tags.contains_key(key) {
+            r.osm_tags.insert(key.to_string(), value.to_string());
+        }
+    }
+}
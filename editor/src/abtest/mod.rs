@@ -4,6 +4,7 @@ pub mod setup;
 use crate::common::{
     time_controls, AgentTools, CommonState, RouteExplorer, SpeedControls, TripExplorer,
 };
+use crate::edit::step_sim;
 use crate::game::{State, Transition};
 use crate::render::MIN_ZOOM_FOR_DETAIL;
 use crate::ui::{PerMapUI, UI};
@@ -173,11 +174,8 @@ impl State for ABTestMode {
         }
 
         if let Some(dt) = self.speed.event(ctx, &mut self.menu, ui.primary.sim.time()) {
-            ui.primary.sim.step(&ui.primary.map, dt);
-            {
-                let s = ui.secondary.as_mut().unwrap();
-                s.sim.step(&s.map, dt);
-            }
+            step_sim(&mut ui.primary, &ui.cs, ctx, dt);
+            step_sim(ui.secondary.as_mut().unwrap(), &ui.cs, ctx, dt);
             self.recalculate_stuff(ui, ctx);
         }
 
@@ -9,7 +9,6 @@ use ezgui::{
 use geom::Duration;
 use itertools::Itertools;
 use sim::{FinishedTrips, TripID, TripMode};
-use std::collections::BTreeMap;
 
 pub struct Scoreboard {
     menu: ModalMenu,
@@ -175,26 +174,9 @@ pub struct CompareTrips {
 
 impl CompareTrips {
     fn new(t1: FinishedTrips, t2: FinishedTrips) -> CompareTrips {
-        let trips1: BTreeMap<TripID, (TripMode, Duration)> = t1
-            .finished_trips
-            .into_iter()
-            .map(|(id, mode, time)| (id, (mode, time)))
-            .collect();
-        let trips2: BTreeMap<TripID, (TripMode, Duration)> = t2
-            .finished_trips
-            .into_iter()
-            .map(|(id, mode, time)| (id, (mode, time)))
-            .collect();
-
-        let mut cmp = CompareTrips {
-            finished_trips: Vec::new(),
-        };
-        for (id, (mode, time1)) in trips1 {
-            if let Some((_, time2)) = trips2.get(&id) {
-                cmp.finished_trips.push((id, mode, time1, *time2));
-            }
+        CompareTrips {
+            finished_trips: t1.pair_with(t2),
         }
-        cmp
     }
 }
 
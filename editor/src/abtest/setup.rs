@@ -1,5 +1,5 @@
 use crate::abtest::{ABTestMode, ABTestSavestate};
-use crate::edit::apply_map_edits;
+use crate::edit::{apply_map_edits, step_sim};
 use crate::game::{State, Transition, WizardState};
 use crate::render::DrawMap;
 use crate::ui::{Flags, PerMapUI, UI};
@@ -137,16 +137,13 @@ fn launch_test(test: &ABTest, ui: &mut UI, ctx: &mut EventCtx) -> ABTestMode {
                     ctx,
                     MapEdits::load(&test.map_name, &test.edits1_name),
                 );
-                ui.primary
-                    .map
-                    .recalculate_pathfinding_after_edits(&mut timer);
 
                 let scenario: Scenario = abstutil::read_binary(load.to_str().unwrap(), &mut timer)
                     .expect("loading scenario failed");
                 ui.primary.reset_sim();
                 let mut rng = ui.primary.current_flags.sim_flags.make_rng();
                 scenario.instantiate(&mut ui.primary.sim, &ui.primary.map, &mut rng, &mut timer);
-                ui.primary.sim.step(&ui.primary.map, Duration::seconds(0.1));
+                step_sim(&mut ui.primary, &ui.cs, ctx, Duration::seconds(0.1));
                 timer.stop("load primary");
             }
             {
@@ -155,11 +152,13 @@ fn launch_test(test: &ABTest, ui: &mut UI, ctx: &mut EventCtx) -> ABTestMode {
                 // TODO We could try to be cheaper by cloning primary's Map, but cloning DrawMap
                 // won't help -- we need to upload new stuff to the GPU. :\  The alternative is
                 // doing apply_map_edits every time we swap.
+                // The edits are applied while loading, before the scenario is instantiated.
                 let mut secondary = PerMapUI::new(
                     Flags {
                         sim_flags: SimFlags {
                             load,
                             rng_seed: current_flags.sim_flags.rng_seed,
+                            edits_name: test.edits2_name.clone(),
                             run_name: Some(format!("{} with {}", test.test_name, test.edits2_name)),
                             savestate_every: None,
                             freeform_policy: current_flags.sim_flags.freeform_policy,
@@ -171,16 +170,7 @@ fn launch_test(test: &ABTest, ui: &mut UI, ctx: &mut EventCtx) -> ABTestMode {
                     ctx,
                     &mut timer,
                 );
-                apply_map_edits(
-                    &mut secondary,
-                    &ui.cs,
-                    ctx,
-                    MapEdits::load(&test.map_name, &test.edits2_name),
-                );
-                secondary
-                    .map
-                    .recalculate_pathfinding_after_edits(&mut timer);
-                secondary.sim.step(&secondary.map, Duration::seconds(0.1));
+                step_sim(&mut secondary, &ui.cs, ctx, Duration::seconds(0.1));
                 timer.stop("load secondary");
                 secondary
             }
@@ -1,3 +1,4 @@
+use crate::edit::{step_sim, timed_step_sim};
 use crate::game::{Transition, WizardState};
 use crate::ui::UI;
 use ezgui::{EventCtx, ModalMenu, Wizard};
@@ -5,18 +6,17 @@ use geom::Duration;
 
 pub fn time_controls(ctx: &mut EventCtx, ui: &mut UI, menu: &mut ModalMenu) -> Option<Transition> {
     if menu.action("step forwards 0.1s") {
-        ui.primary.sim.step(&ui.primary.map, Duration::seconds(0.1));
+        step_sim(&mut ui.primary, &ui.cs, ctx, Duration::seconds(0.1));
         if let Some(ref mut s) = ui.secondary {
-            s.sim.step(&s.map, Duration::seconds(0.1));
+            step_sim(s, &ui.cs, ctx, Duration::seconds(0.1));
         }
         ui.recalculate_current_selection(ctx);
     } else if menu.action("step forwards 10 mins") {
-        ctx.loading_screen("step forwards 10 minutes", |_, mut timer| {
-            ui.primary
-                .sim
-                .timed_step(&ui.primary.map, Duration::minutes(10), &mut timer);
+        ctx.loading_screen("step forwards 10 minutes", |ctx, mut timer| {
+            let dt = Duration::minutes(10);
+            timed_step_sim(&mut ui.primary, &ui.cs, ctx, dt, &mut timer);
             if let Some(ref mut s) = ui.secondary {
-                s.sim.timed_step(&s.map, Duration::minutes(10), &mut timer);
+                timed_step_sim(s, &ui.cs, ctx, dt, &mut timer);
             }
         });
         ui.recalculate_current_selection(ctx);
@@ -33,10 +33,10 @@ fn jump_to_time(wiz: &mut Wizard, ctx: &mut EventCtx, ui: &mut UI) -> Option<Tra
         Duration::END_OF_DAY,
     )?;
     let dt = t - ui.primary.sim.time();
-    ctx.loading_screen(&format!("step forwards {}", dt), |_, mut timer| {
-        ui.primary.sim.timed_step(&ui.primary.map, dt, &mut timer);
+    ctx.loading_screen(&format!("step forwards {}", dt), |ctx, mut timer| {
+        timed_step_sim(&mut ui.primary, &ui.cs, ctx, dt, &mut timer);
         if let Some(ref mut s) = ui.secondary {
-            s.sim.timed_step(&s.map, dt, &mut timer);
+            timed_step_sim(s, &ui.cs, ctx, dt, &mut timer);
         }
     });
     Some(Transition::Pop)
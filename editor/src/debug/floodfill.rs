@@ -56,7 +56,7 @@ impl Floodfiller {
         let mut colorer = RoadColorerBuilder::new(vec![unreachable_color, reachable_color]);
         let mut num_unreachable = 0;
         for lane in map.all_lanes() {
-            if !lane.is_driving() {
+            if !lane.is_driving() || !map.lane_exists(lane.id) {
                 continue;
             }
             colorer.add(
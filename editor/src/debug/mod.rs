@@ -7,6 +7,7 @@ mod neighborhood_summary;
 mod objects;
 mod polygons;
 mod routes;
+mod validation;
 
 use crate::common::CommonState;
 use crate::edit::EditMode;
@@ -70,6 +71,7 @@ impl DebugMode {
                         (hotkey(Key::Slash), "search OSM metadata"),
                         (hotkey(Key::S), "configure colors"),
                         (None, "explore a bus route"),
+                        (None, "browse map validation issues"),
                     ],
                     vec![
                         (hotkey(Key::Escape), "quit"),
@@ -156,7 +158,7 @@ impl State for DebugMode {
             return Transition::Replace(Box::new(SandboxMode::new(ctx)));
         }
         if self.menu.action("edit mode") {
-            return Transition::Replace(Box::new(EditMode::new(ctx, ui)));
+            return Transition::Replace(Box::new(EditMode::new(ctx)));
         }
 
         if self.menu.action("show/hide chokepoints") {
@@ -281,6 +283,9 @@ impl State for DebugMode {
         if let Some(picker) = bus_explorer::BusRoutePicker::new(ui, &mut self.menu) {
             return Transition::Push(picker);
         }
+        if let Some(explorer) = validation::ValidationExplorer::new(ui, &mut self.menu) {
+            return Transition::Push(explorer);
+        }
         if let Some(floodfiller) = floodfill::Floodfiller::new(ctx, ui, &mut self.menu) {
             return Transition::Push(floodfiller);
         }
@@ -172,14 +172,11 @@ fn tooltip_lines(id: ID, g: &mut GfxCtx, ctx: &PerMapUI) -> Text {
                     l.parking_blackhole
                 ));
             }
-            if let Some(types) = l.get_turn_restrictions(r) {
-                txt.add_line(format!("Turn restriction for this lane: {:?}", types));
+            if let Some(ref types) = l.allowed_turns {
+                txt.add_line(format!("Allowed turns from this lane: {:?}", types));
             }
-            for (restriction, to) in &r.turn_restrictions {
-                txt.add_line(format!(
-                    "Restriction from this road to {}: {}",
-                    to, restriction
-                ));
+            for tr in &r.turn_restrictions {
+                txt.add_line(format!("Restriction from this road: {}", tr));
             }
         }
         ID::Intersection(id) => {
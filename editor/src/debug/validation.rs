@@ -0,0 +1,105 @@
+use crate::common::CommonState;
+use crate::game::{State, Transition, WizardState};
+use crate::helpers::ID;
+use crate::ui::UI;
+use ezgui::{EventCtx, GfxCtx, ModalMenu, Text, WarpingItemSlider};
+use geom::Pt2D;
+use map_model::{IssueType, ValidationIssue};
+use std::collections::BTreeSet;
+
+// Steps through the problems found while importing the map, to triage OSM fixes.
+pub struct ValidationExplorer {
+    slider: WarpingItemSlider<Option<ID>>,
+}
+
+impl ValidationExplorer {
+    pub fn new(ui: &UI, menu: &mut ModalMenu) -> Option<Box<dyn State>> {
+        if !menu.action("browse map validation issues") {
+            return None;
+        }
+        let report = ui.primary.map.get_validation_report();
+        if report.issues.is_empty() {
+            println!("{} has no validation issues", ui.primary.map.get_name());
+            return None;
+        }
+        let types: BTreeSet<IssueType> = report.issues.iter().map(|i| i.issue_type).collect();
+        let mut choices: Vec<(String, Option<IssueType>)> =
+            vec![(format!("all ({})", report.issues.len()), None)];
+        for t in types {
+            choices.push((format!("{} ({})", t, report.count(t)), Some(t)));
+        }
+
+        Some(WizardState::new(Box::new(move |wiz, ctx, ui| {
+            let choice = wiz.wrap(ctx).choose_string("Browse which issues?", || {
+                choices.iter().map(|(label, _)| label.clone()).collect()
+            })?;
+            let issue_type = choices
+                .iter()
+                .find(|(label, _)| *label == choice)
+                .and_then(|(_, t)| *t);
+            let report = ui.primary.map.get_validation_report();
+            let items: Vec<(Pt2D, Option<ID>, Text)> = report
+                .issues
+                .iter()
+                .filter(|i| issue_type.map(|t| t == i.issue_type).unwrap_or(true))
+                .map(|i| (i.pt, select(i), describe(i)))
+                .collect();
+            Some(Transition::Replace(Box::new(ValidationExplorer {
+                slider: WarpingItemSlider::new(items, "Map Validation Explorer", "issue", ctx),
+            })))
+        })))
+    }
+}
+
+impl State for ValidationExplorer {
+    fn event(&mut self, ctx: &mut EventCtx, ui: &mut UI) -> Transition {
+        if ctx.redo_mouseover() {
+            ui.recalculate_current_selection(ctx);
+        }
+        ctx.canvas.handle_event(ctx.input);
+
+        if let Some((evmode, done_warping)) = self.slider.event(ctx) {
+            if done_warping {
+                ui.primary.current_selection = self.slider.get().1.clone();
+            }
+            Transition::KeepWithMode(evmode)
+        } else {
+            Transition::Pop
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, ui: &UI) {
+        self.slider.draw(g);
+        CommonState::draw_osd(g, ui, &ui.primary.current_selection);
+    }
+}
+
+fn select(issue: &ValidationIssue) -> Option<ID> {
+    if let Some(l) = issue.lanes.get(0) {
+        Some(ID::Lane(*l))
+    } else if let Some(i) = issue.intersections.get(0) {
+        Some(ID::Intersection(*i))
+    } else {
+        None
+    }
+}
+
+fn describe(issue: &ValidationIssue) -> Text {
+    let mut txt = Text::from_line(format!("{}", issue.issue_type));
+    txt.add_line(issue.description.clone());
+    if !issue.osm_way_ids.is_empty() {
+        txt.add_line(format!(
+            "OSM ways: {}",
+            issue
+                .osm_way_ids
+                .iter()
+                .map(|id| format!("https://www.openstreetmap.org/way/{}", id))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    if let Some(gps) = issue.gps {
+        txt.add_line(format!("{}, {}", gps.latitude, gps.longitude));
+    }
+    txt
+}
@@ -0,0 +1,94 @@
+use crate::game::{State, Transition, WizardState};
+use crate::ui::UI;
+use abstutil::Timer;
+use ezgui::{hotkey, Color, EventCtx, GfxCtx, Key, ModalMenu, Text};
+use map_model::MapEdits;
+use sim::{ImpactPreview, ImpactSummary, Scenario};
+
+// Roughly how the current edits change everyone's routes in a scenario, without running it
+pub struct ImpactViewer {
+    menu: ModalMenu,
+    summary: ImpactSummary,
+}
+
+impl ImpactViewer {
+    pub fn new_wizard() -> Box<dyn State> {
+        WizardState::new(Box::new(|wiz, ctx, ui| {
+            let map_name = ui.primary.map.get_name().to_string();
+            let mut wizard = wiz.wrap(ctx);
+            let scenario_name = wizard
+                .choose_string("Preview the impact on which scenario?", || {
+                    abstutil::list_all_objects(abstutil::SCENARIOS, &map_name)
+                })?;
+            let (_, baseline) = wizard.choose_something("Compared to which edits?", || {
+                let mut list = abstutil::load_all_objects(abstutil::EDITS, &map_name);
+                list.push(("no_edits".to_string(), MapEdits::new(map_name.clone())));
+                list
+            })?;
+            let scenario: Scenario = abstutil::read_binary(
+                &abstutil::path1_bin(&map_name, abstutil::SCENARIOS, &scenario_name),
+                &mut Timer::throwaway(),
+            )
+            .unwrap();
+
+            let mut rng = ui.primary.current_flags.sim_flags.make_rng();
+            let summary = ctx.loading_screen("preview impact of edits", |_, timer| {
+                let map = &mut ui.primary.map;
+                let current = map.get_edits().clone();
+                // Putting the current edits back restores exactly the same lanes and
+                // intersections, so nothing has to be redrawn.
+                map.apply_edits(baseline, timer);
+                map.recalculate_pathfinding_after_edits(timer);
+                let preview = ImpactPreview::new(&scenario, map, &mut rng, timer);
+                map.apply_edits(current, timer);
+                map.recalculate_pathfinding_after_edits(timer);
+                preview.compare(map, timer)
+            });
+            Some(Transition::Replace(Box::new(ImpactViewer {
+                menu: ModalMenu::new(
+                    "Impact of Edits",
+                    vec![vec![(hotkey(Key::Escape), "quit")]],
+                    ctx,
+                ),
+                summary,
+            })))
+        }))
+    }
+}
+
+impl State for ImpactViewer {
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut UI) -> Transition {
+        let mut txt = Text::prompt("Impact of Edits");
+        for line in self.summary.describe() {
+            txt.add_line(line);
+        }
+        txt.add_line("Roads used by more trips are red, fewer are green".to_string());
+        self.menu.handle_event(ctx, Some(txt));
+        ctx.canvas.handle_event(ctx.input);
+
+        if self.menu.action("quit") {
+            return Transition::Pop;
+        }
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, ui: &UI) {
+        let more = ui
+            .cs
+            .get_def("road used by more trips", Color::RED.alpha(0.5));
+        let fewer = ui
+            .cs
+            .get_def("road used by fewer trips", Color::GREEN.alpha(0.5));
+        for r in ui.primary.map.all_roads() {
+            let change = self.summary.road_trips_change(r.id);
+            if change == 0 {
+                continue;
+            }
+            g.draw_polygon(
+                if change > 0 { more } else { fewer },
+                &r.get_thick_polygon().unwrap(),
+            );
+        }
+        self.menu.draw(g);
+    }
+}
@@ -1,3 +1,4 @@
+mod impact;
 mod stop_signs;
 mod traffic_signals;
 
@@ -11,12 +12,15 @@ use crate::render::{
 };
 use crate::sandbox::SandboxMode;
 use crate::ui::{PerMapUI, ShowEverything, UI};
-use abstutil::Timer;
+use abstutil::{elapsed_seconds, Timer};
 use ezgui::{hotkey, lctrl, Color, EventCtx, GfxCtx, Key, ModalMenu, Text, Wizard};
+use geom::{Distance, Duration, Speed};
 use map_model::{
-    IntersectionID, Lane, LaneID, LaneType, Map, MapEdits, Road, RoadID, TurnID, TurnType,
+    Closure, CrossSection, EditID, IntersectionID, IntersectionType, Lane, LaneID, LaneType, Map,
+    MapEdits, Road, RoadID, TurnID, TurnOverride, TurnType,
 };
 use std::collections::{BTreeSet, HashMap};
+use std::time::Instant;
 
 pub struct EditMode {
     common: CommonState,
@@ -24,10 +28,7 @@ pub struct EditMode {
 }
 
 impl EditMode {
-    pub fn new(ctx: &EventCtx, ui: &mut UI) -> EditMode {
-        // TODO Warn first?
-        ui.primary.reset_sim();
-
+    pub fn new(ctx: &EventCtx) -> EditMode {
         EditMode {
             common: CommonState::new(),
             menu: ModalMenu::new(
@@ -36,6 +37,8 @@ impl EditMode {
                     vec![
                         (hotkey(Key::S), "save edits"),
                         (hotkey(Key::L), "load different edits"),
+                        (lctrl(Key::M), "merge in other edits"),
+                        (hotkey(Key::I), "preview impact of edits"),
                     ],
                     vec![
                         (hotkey(Key::Escape), "quit"),
@@ -62,6 +65,22 @@ impl State for EditMode {
         {
             txt.add_line(orig_edits.edits_name.clone());
             txt.add_line(format!("{} lanes", orig_edits.lane_overrides.len()));
+            txt.add_line(format!(
+                "{} lanes with different turns",
+                orig_edits.allowed_turns_overrides.len()
+            ));
+            txt.add_line(format!(
+                "{} lanes with different widths",
+                orig_edits.lane_width_overrides.len()
+            ));
+            txt.add_line(format!(
+                "{} roads with lanes added or removed",
+                orig_edits.cross_section_overrides.len()
+            ));
+            txt.add_line(format!(
+                "{} roads with different speed limits",
+                orig_edits.speed_limit_overrides.len()
+            ));
             txt.add_line(format!(
                 "{} stop signs ",
                 orig_edits.stop_sign_overrides.len()
@@ -70,6 +89,25 @@ impl State for EditMode {
                 "{} traffic signals",
                 orig_edits.traffic_signal_overrides.len()
             ));
+            txt.add_line(format!(
+                "{} intersections with a different type",
+                orig_edits.intersection_type_overrides.len()
+            ));
+            txt.add_line(format!(
+                "{} turns banned or allowed",
+                orig_edits.turn_overrides.len()
+            ));
+            txt.add_line(format!(
+                "{} closures",
+                orig_edits.lane_closures.len()
+                    + orig_edits.road_closures.len()
+                    + orig_edits.intersection_closures.len()
+            ));
+            for id in selected_edits(&ui.primary) {
+                if let Some(p) = orig_edits.provenance.get(&id) {
+                    txt.add_line(format!("Changed {} by {}", id, p));
+                }
+            }
             txt.add_line("Right-click a lane or intersection to start editing".to_string());
         }
         self.menu.handle_event(ctx, Some(txt));
@@ -102,6 +140,10 @@ impl State for EditMode {
             return Transition::Push(WizardState::new(Box::new(save_edits)));
         } else if self.menu.action("load different edits") {
             return Transition::Push(WizardState::new(Box::new(load_edits)));
+        } else if self.menu.action("merge in other edits") {
+            return Transition::Push(WizardState::new(Box::new(merge_edits)));
+        } else if self.menu.action("preview impact of edits") {
+            return Transition::Push(impact::ImpactViewer::new_wizard());
         }
 
         if let Some(ID::Lane(id)) = ui.primary.current_selection {
@@ -146,20 +188,101 @@ impl State for EditMode {
                 }
             }
 
+            let lt = ui.primary.map.get_l(id).lane_type;
+            if (lt == LaneType::Driving || lt == LaneType::Bus)
+                && ctx
+                    .input
+                    .contextual_action(Key::M, "change allowed turns from this lane")
+            {
+                return Transition::Push(make_edit_allowed_turns(id));
+            }
+            if ctx
+                .input
+                .contextual_action(Key::W, "change width of this lane")
+            {
+                return Transition::Push(make_edit_lane_width(id));
+            }
+            if ctx
+                .input
+                .contextual_action(Key::A, "add a lane next to this one")
+            {
+                return Transition::Push(make_add_lane(id));
+            }
+            if lt != LaneType::Sidewalk
+                && can_remove_lane(id, &ui.primary.map)
+                && ctx
+                    .input
+                    .contextual_action(Key::Backspace, "remove this lane")
+            {
+                return Transition::Push(make_remove_lane(id));
+            }
+
+            let road = ui.primary.map.get_l(id).parent;
             if ctx
                 .input
                 .contextual_action(Key::U, "bulk edit lanes on this road")
             {
-                return Transition::Push(make_bulk_edit_lanes(ui.primary.map.get_l(id).parent));
-            } else if orig_edits.lane_overrides.contains_key(&id)
+                return Transition::Push(make_bulk_edit_lanes(road));
+            } else if ctx
+                .input
+                .contextual_action(Key::V, "change speed limit on this road")
+            {
+                return Transition::Push(make_edit_speed_limit(road));
+            } else if ctx.input.contextual_action(Key::X, "close this lane") {
+                return Transition::Push(make_edit_closure(ClosureTarget::Lane(id)));
+            } else if ctx.input.contextual_action(Key::Z, "close this road") {
+                return Transition::Push(make_edit_closure(ClosureTarget::Road(road)));
+            } else if (orig_edits.lane_overrides.contains_key(&id)
+                || orig_edits.allowed_turns_overrides.contains_key(&id)
+                || orig_edits.lane_width_overrides.contains_key(&id)
+                || orig_edits.cross_section_overrides.contains_key(&road)
+                || orig_edits.speed_limit_overrides.contains_key(&road)
+                || orig_edits.lane_closures.contains_key(&id)
+                || orig_edits.road_closures.contains_key(&road))
                 && ctx.input.contextual_action(Key::R, "revert")
             {
                 let mut new_edits = orig_edits.clone();
                 new_edits.lane_overrides.remove(&id);
+                new_edits.allowed_turns_overrides.remove(&id);
+                new_edits.lane_width_overrides.remove(&id);
+                new_edits.cross_section_overrides.remove(&road);
+                new_edits.speed_limit_overrides.remove(&road);
+                new_edits.lane_closures.remove(&id);
+                new_edits.road_closures.remove(&road);
+                ui.primary
+                    .map
+                    .renumber_added_lanes(&orig_edits, &mut new_edits);
                 apply_map_edits(&mut ui.primary, &ui.cs, ctx, new_edits);
             }
         }
         if let Some(ID::Intersection(id)) = ui.primary.current_selection {
+            if ctx
+                .input
+                .contextual_action(Key::X, "close this intersection")
+            {
+                return Transition::Push(make_edit_closure(ClosureTarget::Intersection(id)));
+            }
+            if orig_edits.intersection_closures.contains_key(&id)
+                && ctx
+                    .input
+                    .contextual_action(Key::O, "reopen this intersection")
+            {
+                let mut new_edits = orig_edits.clone();
+                new_edits.intersection_closures.remove(&id);
+                apply_map_edits(&mut ui.primary, &ui.cs, ctx, new_edits);
+            }
+            if ui.primary.map.get_i(id).intersection_type != IntersectionType::Border
+                && ctx
+                    .input
+                    .contextual_action(Key::C, "change intersection type")
+            {
+                return Transition::Push(make_change_intersection_type(id));
+            }
+            if ui.primary.map.get_i(id).intersection_type != IntersectionType::Border
+                && ctx.input.contextual_action(Key::T, "ban or allow turns")
+            {
+                return Transition::Push(make_edit_turns(id));
+            }
             if ui.primary.map.maybe_get_stop_sign(id).is_some() {
                 if ctx
                     .input
@@ -168,11 +291,13 @@ impl State for EditMode {
                     return Transition::Push(Box::new(stop_signs::StopSignEditor::new(
                         id, ctx, ui,
                     )));
-                } else if orig_edits.stop_sign_overrides.contains_key(&id)
+                } else if (orig_edits.stop_sign_overrides.contains_key(&id)
+                    || orig_edits.intersection_type_overrides.contains_key(&id))
                     && ctx.input.contextual_action(Key::R, "revert")
                 {
                     let mut new_edits = orig_edits.clone();
                     new_edits.stop_sign_overrides.remove(&id);
+                    new_edits.intersection_type_overrides.remove(&id);
                     apply_map_edits(&mut ui.primary, &ui.cs, ctx, new_edits);
                 }
             }
@@ -184,11 +309,13 @@ impl State for EditMode {
                     return Transition::Push(Box::new(traffic_signals::TrafficSignalEditor::new(
                         id, ctx, ui,
                     )));
-                } else if orig_edits.traffic_signal_overrides.contains_key(&id)
+                } else if (orig_edits.traffic_signal_overrides.contains_key(&id)
+                    || orig_edits.intersection_type_overrides.contains_key(&id))
                     && ctx.input.contextual_action(Key::R, "revert")
                 {
                     let mut new_edits = orig_edits.clone();
                     new_edits.traffic_signal_overrides.remove(&id);
+                    new_edits.intersection_type_overrides.remove(&id);
                     apply_map_edits(&mut ui.primary, &ui.cs, ctx, new_edits);
                 }
             }
@@ -227,13 +354,16 @@ impl State for EditMode {
         if g.canvas.cam_zoom >= MIN_ZOOM_FOR_DETAIL {
             g.enable_hatching();
 
-            for l in edits.lane_overrides.keys() {
-                ctx.draw_map.get_l(*l).draw(g, &opts, &ctx);
+            for l in edited_lanes(edits, &ctx.map) {
+                ctx.draw_map.get_l(l).draw(g, &opts, &ctx);
             }
             for i in edits
                 .stop_sign_overrides
                 .keys()
                 .chain(edits.traffic_signal_overrides.keys())
+                .chain(edits.intersection_type_overrides.keys())
+                .chain(edits.turn_overrides.keys().map(|t| &t.parent))
+                .chain(edits.intersection_closures.keys())
             {
                 ctx.draw_map.get_i(*i).draw(g, &opts, &ctx);
             }
@@ -258,14 +388,17 @@ impl State for EditMode {
             }
         } else {
             let color = ui.cs.get_def("unzoomed map diffs", Color::RED);
-            for l in edits.lane_overrides.keys() {
-                g.draw_polygon(color, &ctx.map.get_parent(*l).get_thick_polygon().unwrap());
+            for l in edited_lanes(edits, &ctx.map) {
+                g.draw_polygon(color, &ctx.map.get_parent(l).get_thick_polygon().unwrap());
             }
 
             for i in edits
                 .stop_sign_overrides
                 .keys()
                 .chain(edits.traffic_signal_overrides.keys())
+                .chain(edits.intersection_type_overrides.keys())
+                .chain(edits.turn_overrides.keys().map(|t| &t.parent))
+                .chain(edits.intersection_closures.keys())
             {
                 opts.override_colors.insert(ID::Intersection(*i), color);
                 ctx.draw_map.get_i(*i).draw(g, &opts, &ctx);
@@ -276,12 +409,9 @@ impl State for EditMode {
         self.menu.draw(g);
     }
 
-    fn on_destroy(&mut self, _: &mut EventCtx, ui: &mut UI) {
+    fn on_destroy(&mut self, _: &mut EventCtx, _: &mut UI) {
         // TODO Warn about unsaved edits
-        // TODO Maybe put a loading screen around these.
-        ui.primary
-            .map
-            .recalculate_pathfinding_after_edits(&mut Timer::new("apply pending map edits"));
+        // The simulation keeps running on the edited map; apply_map_edits already updated it.
     }
 }
 
@@ -294,6 +424,7 @@ fn save_edits(wiz: &mut Wizard, ctx: &mut EventCtx, ui: &mut UI) -> Option<Trans
     } else {
         None
     };
+    let rationale = wizard.input_string("Why these changes? (Can be blank)")?;
 
     // TODO Do it this weird way to avoid saving edits on every event. :P
     let save = "save edits";
@@ -303,11 +434,22 @@ fn save_edits(wiz: &mut Wizard, ctx: &mut EventCtx, ui: &mut UI) -> Option<Trans
         .as_str()
         == save
     {
+        let mut edits = map.get_edits().clone();
         if let Some(name) = rename {
-            let mut edits = map.get_edits().clone();
             edits.edits_name = name;
-            map.apply_edits(edits, &mut Timer::new("name map edits"));
         }
+        // Everything that changed since the last save gets credited to whoever's saving now,
+        // unless it's already credited to someone else.
+        let map_name = map.get_name().to_string();
+        let last_saved =
+            if abstutil::list_all_objects(abstutil::EDITS, &map_name).contains(&edits.edits_name) {
+                MapEdits::load(&map_name, &edits.edits_name)
+            } else {
+                MapEdits::new(map_name)
+            };
+        let author = std::env::var("USER").unwrap_or_else(|_| "unknown".to_string());
+        edits.record_provenance(&last_saved, &author, &rationale, map);
+        map.apply_edits(edits, &mut Timer::new("save map edits"));
         map.get_edits().save();
     }
     Some(Transition::Pop)
@@ -328,8 +470,93 @@ fn load_edits(wiz: &mut Wizard, ctx: &mut EventCtx, ui: &mut UI) -> Option<Trans
     Some(Transition::Pop)
 }
 
+// Combines another proposal into the current edits. Both have to have started from the same base.
+fn merge_edits(wiz: &mut Wizard, ctx: &mut EventCtx, ui: &mut UI) -> Option<Transition> {
+    let mut wizard = wiz.wrap(ctx);
+    let map_name = ui.primary.map.get_name().to_string();
+    let current = ui.primary.map.get_edits().edits_name.clone();
+
+    let (_, theirs) = wizard.choose_something("Merge in which map edits?", || {
+        abstutil::load_all_objects(abstutil::EDITS, &map_name)
+            .into_iter()
+            .filter(|(name, _)| *name != current)
+            .collect()
+    })?;
+    let (_, base) = wizard.choose_something("What did both edits start from?", || {
+        let mut list = abstutil::load_all_objects(abstutil::EDITS, &map_name);
+        list.push(("no_edits".to_string(), MapEdits::new(map_name.clone())));
+        list
+    })?;
+    let (merged, conflicts) =
+        MapEdits::merge(&base, ui.primary.map.get_edits(), &theirs, &ui.primary.map);
+    if !conflicts.is_empty() {
+        let lines: Vec<String> = conflicts.iter().map(|c| c.describe()).collect();
+        if !wizard.acknowledge(
+            "These conflicts keep the current edits",
+            lines.iter().map(|l| l.as_str()).collect(),
+        ) {
+            return None;
+        }
+    }
+    apply_map_edits(&mut ui.primary, &ui.cs, ctx, merged);
+    Some(Transition::Pop)
+}
+
+// All of the edits that could touch the selected lane or intersection
+fn selected_edits(bundle: &PerMapUI) -> Vec<EditID> {
+    match bundle.current_selection {
+        Some(ID::Lane(l)) => {
+            let r = bundle.map.get_l(l).parent;
+            vec![
+                EditID::LaneType(l),
+                EditID::AllowedTurns(l),
+                EditID::LaneWidth(l),
+                EditID::CrossSection(r),
+                EditID::SpeedLimit(r),
+                EditID::LaneClosure(l),
+                EditID::RoadClosure(r),
+            ]
+        }
+        Some(ID::Intersection(i)) => {
+            let mut ids = vec![
+                EditID::StopSign(i),
+                EditID::TrafficSignal(i),
+                EditID::IntersectionType(i),
+                EditID::IntersectionClosure(i),
+            ];
+            for t in bundle.map.get_edits().turn_overrides.keys() {
+                if t.parent == i {
+                    ids.push(EditID::Turn(*t));
+                }
+            }
+            ids
+        }
+        _ => Vec::new(),
+    }
+}
+
 // For lane editing
 
+fn edited_lanes(edits: &MapEdits, map: &Map) -> BTreeSet<LaneID> {
+    let mut lanes: BTreeSet<LaneID> = edits
+        .lane_overrides
+        .keys()
+        .chain(edits.allowed_turns_overrides.keys())
+        .chain(edits.lane_width_overrides.keys())
+        .cloned()
+        .collect();
+    for r in edits
+        .cross_section_overrides
+        .keys()
+        .chain(edits.speed_limit_overrides.keys())
+        .chain(edits.road_closures.keys())
+    {
+        lanes.extend(map.get_r(*r).all_lanes());
+    }
+    lanes.extend(edits.lane_closures.keys().cloned());
+    lanes
+}
+
 fn next_valid_type(r: &Road, l: &Lane, map: &Map) -> Option<LaneType> {
     let mut new_type = next_type(l.lane_type);
     while new_type != l.lane_type {
@@ -366,23 +593,10 @@ fn can_change_lane_type(r: &Road, l: &Lane, new_lt: LaneType, map: &Map) -> bool
         return false;
     }
 
-    // Only one parking lane per side.
-    if proposed_lts
-        .iter()
-        .filter(|lt| **lt == LaneType::Parking)
-        .count()
-        > 1
-    {
+    if !valid_side_of_road(&proposed_lts) {
         return false;
     }
 
-    // Two adjacent bike lanes is unnecessary.
-    for pair in proposed_lts.windows(2) {
-        if pair[0] == LaneType::Biking && pair[1] == LaneType::Biking {
-            return false;
-        }
-    }
-
     // Don't let players orphan a bus stop.
     if !r.all_bus_stops(map).is_empty()
         && (new_lt == LaneType::Parking || new_lt == LaneType::Biking)
@@ -403,6 +617,28 @@ fn can_change_lane_type(r: &Road, l: &Lane, new_lt: LaneType, map: &Map) -> bool
         }
     }
 
+    true
+}
+
+// The lane types on one side of a road, from the center outwards
+fn valid_side_of_road(proposed_lts: &[LaneType]) -> bool {
+    // Only one parking lane per side.
+    if proposed_lts
+        .iter()
+        .filter(|lt| **lt == LaneType::Parking)
+        .count()
+        > 1
+    {
+        return false;
+    }
+
+    // Two adjacent bike lanes is unnecessary.
+    for pair in proposed_lts.windows(2) {
+        if pair[0] == LaneType::Biking && pair[1] == LaneType::Biking {
+            return false;
+        }
+    }
+
     // A parking lane must have a driving lane on the same side of the road.
     if proposed_lts.contains(&LaneType::Parking) && !proposed_lts.contains(&LaneType::Driving) {
         return false;
@@ -419,17 +655,120 @@ pub fn apply_map_edits(
 ) {
     let mut timer = Timer::new("apply map edits");
 
-    let (lanes_changed, turns_deleted, turns_added) = bundle.map.apply_edits(edits, &mut timer);
+    let changes = bundle.map.apply_edits(edits, &mut timer);
+    redraw_after_edits(bundle, cs, ctx, changes, &mut timer);
 
+    // Do this after fixing up all the state above.
+    bundle.map.simplify_edits(&mut timer);
+
+    // Agents are still drawn while editing, so they can't keep following deleted turns.
+    bundle.map.recalculate_pathfinding_after_edits(&mut timer);
+    bundle.sim.handle_live_edits(&bundle.map, &mut timer);
+}
+
+// Like Sim::step, but also redraws whatever scheduled edits switch on and off along the way.
+pub fn step_sim(bundle: &mut PerMapUI, cs: &ColorScheme, ctx: &mut EventCtx, dt: Duration) {
+    let target_time = bundle.sim.time() + dt;
+    apply_scheduled_map_edits(bundle, cs, ctx);
+    loop {
+        bundle
+            .sim
+            .step_until_scheduled_change(&bundle.map, target_time);
+        apply_scheduled_map_edits(bundle, cs, ctx);
+        if bundle.sim.time() == target_time {
+            break;
+        }
+    }
+}
+
+// Like Sim::timed_step
+pub fn timed_step_sim(
+    bundle: &mut PerMapUI,
+    cs: &ColorScheme,
+    ctx: &mut EventCtx,
+    dt: Duration,
+    timer: &mut Timer,
+) {
+    let orig_time = bundle.sim.time();
+    let chunks = (dt / Duration::seconds(10.0)).ceil() as usize;
+    timer.start_iter(&format!("advance simulation by {}", dt), chunks);
+    for i in 0..chunks {
+        timer.next();
+        let chunk_dt = if i == chunks - 1 {
+            orig_time + dt - bundle.sim.time()
+        } else {
+            dt * (1.0 / (chunks as f64))
+        };
+        step_sim(bundle, cs, ctx, chunk_dt);
+    }
+}
+
+// Like Sim::time_limited_step
+pub fn time_limited_step_sim(
+    bundle: &mut PerMapUI,
+    cs: &ColorScheme,
+    ctx: &mut EventCtx,
+    dt: Duration,
+    real_time_limit: Duration,
+) {
+    let started_at = Instant::now();
+    let goal_time = bundle.sim.time() + dt;
+    while Duration::seconds(elapsed_seconds(started_at)) <= real_time_limit
+        && bundle.sim.time() < goal_time
+    {
+        step_sim(bundle, cs, ctx, Duration::seconds(0.1));
+    }
+}
+
+// Scheduled edits switch on and off as the simulation runs. Also call after loading a savestate.
+pub fn apply_scheduled_map_edits(bundle: &mut PerMapUI, cs: &ColorScheme, ctx: &mut EventCtx) {
+    let now = bundle.sim.time();
+    if bundle
+        .map
+        .get_edits()
+        .same_scheduled_edits_active(bundle.map.get_edits_time(), now)
+    {
+        return;
+    }
+    let mut timer = Timer::new("apply scheduled map edits");
+
+    let changes = bundle.map.set_edits_time(now, &mut timer);
+    redraw_after_edits(bundle, cs, ctx, changes, &mut timer);
+    bundle.map.recalculate_pathfinding_after_edits(&mut timer);
+    bundle.sim.handle_live_edits(&bundle.map, &mut timer);
+}
+
+fn redraw_after_edits(
+    bundle: &mut PerMapUI,
+    cs: &ColorScheme,
+    ctx: &mut EventCtx,
+    (lanes_changed, turns_deleted, turns_added): (
+        BTreeSet<LaneID>,
+        BTreeSet<TurnID>,
+        BTreeSet<TurnID>,
+    ),
+    timer: &mut Timer,
+) {
+    if let Some(ID::Lane(l)) = bundle.current_selection {
+        if !bundle.map.lane_exists(l) {
+            bundle.current_selection = None;
+        }
+    }
     for l in lanes_changed {
-        bundle.draw_map.lanes[l.0] = DrawLane::new(
+        let draw = DrawLane::new(
             bundle.map.get_l(l),
             &bundle.map,
             !bundle.current_flags.dont_draw_lane_markings,
             cs,
-            &mut timer,
+            timer,
         )
         .finish(ctx.prerender);
+        // New lanes come in order, since lanes_changed is sorted.
+        if l.0 == bundle.draw_map.lanes.len() {
+            bundle.draw_map.add_lane(draw, &bundle.map);
+        } else {
+            bundle.draw_map.lanes[l.0] = draw;
+        }
     }
     let mut modified_intersections: BTreeSet<IntersectionID> = BTreeSet::new();
     let mut lanes_of_modified_turns: BTreeSet<LaneID> = BTreeSet::new();
@@ -462,17 +801,9 @@ pub fn apply_map_edits(
     }
 
     for i in modified_intersections {
-        bundle.draw_map.intersections[i.0] = DrawIntersection::new(
-            bundle.map.get_i(i),
-            &bundle.map,
-            cs,
-            ctx.prerender,
-            &mut timer,
-        );
+        bundle.draw_map.intersections[i.0] =
+            DrawIntersection::new(bundle.map.get_i(i), &bundle.map, cs, ctx.prerender, timer);
     }
-
-    // Do this after fixing up all the state above.
-    bundle.map.simplify_edits(&mut timer);
 }
 
 fn make_bulk_edit_lanes(road: RoadID) -> Box<dyn State> {
@@ -504,7 +835,7 @@ fn make_bulk_edit_lanes(road: RoadID) -> Box<dyn State> {
         let mut edits = map.get_edits().clone();
         let mut cnt = 0;
         for l in map.all_lanes() {
-            if l.lane_type != from {
+            if l.lane_type != from || !map.lane_exists(l.id) {
                 continue;
             }
             let parent = map.get_parent(l.id);
@@ -526,3 +857,374 @@ fn make_bulk_edit_lanes(road: RoadID) -> Box<dyn State> {
         Some(Transition::Pop)
     }))
 }
+
+// Like the values of OSM turn:lanes
+fn make_edit_allowed_turns(lane: LaneID) -> Box<dyn State> {
+    WizardState::new(Box::new(move |wiz, ctx, ui| {
+        let mut wizard = wiz.wrap(ctx);
+        let choice = wizard.choose_str(
+            "Vehicles in this lane may...",
+            vec![
+                "turn left",
+                "go straight",
+                "turn right",
+                "turn left or go straight",
+                "go straight or turn right",
+                "turn left or right",
+                "do anything",
+            ],
+        )?;
+        let mut turns = BTreeSet::new();
+        if choice.contains("left") || choice == "do anything" {
+            turns.insert(TurnType::Left);
+        }
+        if choice.contains("right") || choice == "do anything" {
+            turns.insert(TurnType::Right);
+        }
+        if choice.contains("straight") || choice == "do anything" {
+            turns.insert(TurnType::Straight);
+            turns.insert(TurnType::LaneChangeLeft);
+            turns.insert(TurnType::LaneChangeRight);
+        }
+        let mut edits = ui.primary.map.get_edits().clone();
+        edits.allowed_turns_overrides.insert(lane, turns);
+        apply_map_edits(&mut ui.primary, &ui.cs, ctx, edits);
+        Some(Transition::Pop)
+    }))
+}
+
+fn make_edit_lane_width(lane: LaneID) -> Box<dyn State> {
+    WizardState::new(Box::new(move |wiz, ctx, ui| {
+        let current = ui.primary.map.get_l(lane).width;
+        let meters = wiz.wrap(ctx).input_something(
+            "New width of this lane (meters)",
+            Some(format!("{:.2}", current.inner_meters())),
+            Box::new(|line| line.parse::<f64>().ok().filter(|x| *x >= 0.5 && *x <= 10.0)),
+        )?;
+        let mut edits = ui.primary.map.get_edits().clone();
+        edits
+            .lane_width_overrides
+            .insert(lane, Distance::meters(meters));
+        apply_map_edits(&mut ui.primary, &ui.cs, ctx, edits);
+        Some(Transition::Pop)
+    }))
+}
+
+fn make_edit_speed_limit(road: RoadID) -> Box<dyn State> {
+    WizardState::new(Box::new(move |wiz, ctx, ui| {
+        let current = ui.primary.map.get_r(road).get_speed_limit();
+        let mph = wiz.wrap(ctx).input_usize_prefilled(
+            "New speed limit for both directions (mph)",
+            format!("{}", (current.inner_meters_per_second() / 0.44704).round()),
+        )?;
+        let mut edits = ui.primary.map.get_edits().clone();
+        edits
+            .speed_limit_overrides
+            .insert(road, Speed::miles_per_hour(mph as f64));
+        apply_map_edits(&mut ui.primary, &ui.cs, ctx, edits);
+        Some(Transition::Pop)
+    }))
+}
+
+// For adding and removing lanes
+
+const PROTECTED_BIKE_LANE_BUFFER: Distance = Distance::const_meters(1.0);
+
+fn valid_cross_section(r: &Road, cs: &CrossSection, map: &Map) -> bool {
+    let fwd: Vec<LaneType> = cs.fwd.iter().map(|(lt, _)| *lt).collect();
+    let back: Vec<LaneType> = cs.back.iter().map(|(lt, _)| *lt).collect();
+    if !valid_side_of_road(&fwd) || !valid_side_of_road(&back) {
+        return false;
+    }
+    // Don't let players orphan a bus stop.
+    if !r.all_bus_stops(map).is_empty()
+        && !fwd
+            .iter()
+            .chain(back.iter())
+            .any(|lt| *lt == LaneType::Driving || *lt == LaneType::Bus)
+    {
+        return false;
+    }
+    !fwd.is_empty() || !back.is_empty()
+}
+
+// Per-lane edits on the road are already baked into the cross-section, and wouldn't line up with
+// the new lanes anyway. Lanes added to other roads might get new IDs.
+fn set_cross_section(edits: &mut MapEdits, r: &Road, cs: CrossSection, map: &Map) {
+    let before = edits.clone();
+    for l in r.all_lanes() {
+        edits.lane_overrides.remove(&l);
+        edits.allowed_turns_overrides.remove(&l);
+        edits.lane_width_overrides.remove(&l);
+    }
+    edits.cross_section_overrides.insert(r.id, cs);
+    map.renumber_added_lanes(&before, edits);
+}
+
+// Returns the road's cross-section without this lane. If give_to_sidewalk, the sidewalk on the
+// same side gets wider.
+fn without_lane(l: LaneID, give_to_sidewalk: bool, map: &Map) -> CrossSection {
+    let r = map.get_parent(l);
+    let (fwds, idx) = r.dir_and_offset(l);
+    let mut cs = r.get_cross_section(map);
+    let side = if fwds { &mut cs.fwd } else { &mut cs.back };
+    let (_, width) = side.remove(idx);
+    if give_to_sidewalk {
+        if let Some(sidewalk) = side.iter_mut().find(|(lt, _)| *lt == LaneType::Sidewalk) {
+            sidewalk.1 += width;
+        }
+    }
+    cs
+}
+
+fn can_remove_lane(l: LaneID, map: &Map) -> bool {
+    valid_cross_section(map.get_parent(l), &without_lane(l, false, map), map)
+}
+
+// The new lane goes just outside this one, or just inside a sidewalk.
+fn make_add_lane(lane: LaneID) -> Box<dyn State> {
+    WizardState::new(Box::new(move |wiz, ctx, ui| {
+        let map = &ui.primary.map;
+        let r = map.get_parent(lane);
+        let (fwds, idx) = r.dir_and_offset(lane);
+        let idx = if map.get_l(lane).is_sidewalk() {
+            idx
+        } else {
+            idx + 1
+        };
+        let choices: Vec<(&str, CrossSection)> = vec![
+            ("driving", LaneType::Driving, Distance::ZERO),
+            ("parking", LaneType::Parking, Distance::ZERO),
+            ("biking", LaneType::Biking, Distance::ZERO),
+            (
+                "protected biking, with a buffer",
+                LaneType::Biking,
+                PROTECTED_BIKE_LANE_BUFFER,
+            ),
+            ("bus", LaneType::Bus, Distance::ZERO),
+        ]
+        .into_iter()
+        .map(|(name, lt, buffer)| {
+            let mut cs = r.get_cross_section(map);
+            let side = if fwds { &mut cs.fwd } else { &mut cs.back };
+            side.insert(idx, (lt, lt.default_width() + buffer));
+            (name, cs)
+        })
+        .filter(|(_, cs)| valid_cross_section(r, cs, map))
+        .collect();
+        if choices.is_empty() {
+            println!("No kind of lane can be added next to {}", lane);
+            return Some(Transition::Pop);
+        }
+        let choice = wiz.wrap(ctx).choose_str(
+            "Add what kind of lane?",
+            choices.iter().map(|(name, _)| *name).collect(),
+        )?;
+        let cs = choices
+            .into_iter()
+            .find(|(name, _)| *name == choice)
+            .unwrap()
+            .1;
+
+        let mut edits = map.get_edits().clone();
+        set_cross_section(&mut edits, r, cs, map);
+        apply_map_edits(&mut ui.primary, &ui.cs, ctx, edits);
+        Some(Transition::Pop)
+    }))
+}
+
+fn make_remove_lane(lane: LaneID) -> Box<dyn State> {
+    WizardState::new(Box::new(move |wiz, ctx, ui| {
+        let map = &ui.primary.map;
+        let r = map.get_parent(lane);
+        let narrow = "narrow the road";
+        let widen = "widen the sidewalk";
+        let mut choices = vec![narrow];
+        if without_lane(lane, false, map) != without_lane(lane, true, map) {
+            choices.push(widen);
+        }
+        let choice = wiz
+            .wrap(ctx)
+            .choose_str("What happens to the space?", choices)?;
+        let cs = without_lane(lane, choice == widen, map);
+
+        let mut edits = map.get_edits().clone();
+        set_cross_section(&mut edits, r, cs, map);
+        apply_map_edits(&mut ui.primary, &ui.cs, ctx, edits);
+        Some(Transition::Pop)
+    }))
+}
+
+fn make_change_intersection_type(id: IntersectionID) -> Box<dyn State> {
+    WizardState::new(Box::new(move |wiz, ctx, ui| {
+        let current = ui.primary.map.get_i(id).intersection_type;
+        let choices: Vec<(&str, IntersectionType)> = vec![
+            ("stop sign", IntersectionType::StopSign),
+            ("traffic signal", IntersectionType::TrafficSignal),
+            ("uncontrolled", IntersectionType::Uncontrolled),
+            ("roundabout", IntersectionType::Roundabout),
+        ]
+        .into_iter()
+        .filter(|(_, it)| *it != current)
+        .collect();
+        let choice = wiz.wrap(ctx).choose_str(
+            &format!("Change {} from {:?} to what?", id, current),
+            choices.iter().map(|(name, _)| *name).collect(),
+        )?;
+        let new_type = choices
+            .into_iter()
+            .find(|(name, _)| *name == choice)
+            .unwrap()
+            .1;
+
+        // The old policy doesn't make sense anymore; start from the default for the new type.
+        let mut edits = ui.primary.map.get_edits().clone();
+        edits.stop_sign_overrides.remove(&id);
+        edits.traffic_signal_overrides.remove(&id);
+        edits.intersection_type_overrides.insert(id, new_type);
+        apply_map_edits(&mut ui.primary, &ui.cs, ctx, edits);
+        Some(Transition::Pop)
+    }))
+}
+
+#[derive(Clone, Copy)]
+enum ClosureTarget {
+    Lane(LaneID),
+    Road(RoadID),
+    Intersection(IntersectionID),
+}
+
+fn make_edit_closure(target: ClosureTarget) -> Box<dyn State> {
+    WizardState::new(Box::new(move |wiz, ctx, ui| {
+        let everyone = "closed to everyone";
+        let vehicles = "closed to vehicles, open to pedestrians";
+        let cars = "closed to cars, open to everyone else";
+        let pedestrians = "closed to pedestrians, open to vehicles";
+        let choice = wiz.wrap(ctx).choose_str(
+            "Close this to who?",
+            vec![everyone, vehicles, cars, pedestrians],
+        )?;
+        let closure = if choice == everyone {
+            Closure::everyone()
+        } else {
+            Closure {
+                cars: choice != pedestrians,
+                bikes: choice == vehicles,
+                buses: choice == vehicles,
+                pedestrians: choice == pedestrians,
+            }
+        };
+
+        let mut edits = ui.primary.map.get_edits().clone();
+        match target {
+            ClosureTarget::Lane(l) => {
+                edits.lane_closures.insert(l, closure);
+            }
+            ClosureTarget::Road(r) => {
+                edits.road_closures.insert(r, closure);
+            }
+            ClosureTarget::Intersection(i) => {
+                edits.intersection_closures.insert(i, closure);
+            }
+        }
+        apply_map_edits(&mut ui.primary, &ui.cs, ctx, edits);
+        Some(Transition::Pop)
+    }))
+}
+
+fn make_edit_turns(id: IntersectionID) -> Box<dyn State> {
+    WizardState::new(Box::new(move |wiz, ctx, ui| {
+        let no_left = "no left turns";
+        let right_only = "right turns only from one road";
+        let ban = "ban one turn";
+        let allow = "allow one turn";
+        let clear = "undo all turn edits here";
+        let mut wizard = wiz.wrap(ctx);
+        let choice = wizard.choose_str(
+            &format!("Ban or allow which turns at {}?", id),
+            vec![no_left, right_only, ban, allow, clear],
+        )?;
+
+        let map = &ui.primary.map;
+        let mut edits = map.get_edits().clone();
+        if choice == no_left {
+            for t in map.get_turns_in_intersection(id) {
+                if t.turn_type == TurnType::Left {
+                    edits.turn_overrides.insert(t.id, TurnOverride::Banned);
+                }
+            }
+        } else if choice == right_only {
+            let (_, road) = wizard.choose_something("Only turn right from which road?", || {
+                map.get_i(id)
+                    .roads
+                    .iter()
+                    .map(|r| (format!("{} ({})", map.get_r(*r).get_name(), r), *r))
+                    .collect()
+            })?;
+            for t in map.get_turns_in_intersection(id) {
+                if map.get_l(t.id.src).parent != road {
+                    continue;
+                }
+                match t.turn_type {
+                    TurnType::Straight
+                    | TurnType::LaneChangeLeft
+                    | TurnType::LaneChangeRight
+                    | TurnType::Left => {
+                        edits.turn_overrides.insert(t.id, TurnOverride::Banned);
+                    }
+                    _ => {}
+                }
+            }
+        } else if choice == ban || choice == allow {
+            let want_allowed = choice == allow;
+            let (_, turn) = wizard.choose_something(
+                if want_allowed {
+                    "Allow which turn?"
+                } else {
+                    "Ban which turn?"
+                },
+                || {
+                    map.get_turns_in_intersection(id)
+                        .into_iter()
+                        .filter(|t| {
+                            t.turn_type != TurnType::SharedSidewalkCorner
+                                && map.is_turn_allowed(t.id) != want_allowed
+                        })
+                        .map(|t| {
+                            (
+                                format!(
+                                    "{:?} from {} to {} ({})",
+                                    t.turn_type,
+                                    map.get_parent(t.id.src).get_name(),
+                                    map.get_parent(t.id.dst).get_name(),
+                                    t.id
+                                ),
+                                t.id,
+                            )
+                        })
+                        .collect()
+                },
+            )?;
+            edits.turn_overrides.insert(
+                turn,
+                if want_allowed {
+                    TurnOverride::Allowed
+                } else {
+                    TurnOverride::Banned
+                },
+            );
+        } else {
+            let remove: Vec<TurnID> = edits
+                .turn_overrides
+                .keys()
+                .filter(|t| t.parent == id)
+                .cloned()
+                .collect();
+            for t in remove {
+                edits.turn_overrides.remove(&t);
+            }
+        }
+        apply_map_edits(&mut ui.primary, &ui.cs, ctx, edits);
+        Some(Transition::Pop)
+    }))
+}
@@ -5,7 +5,7 @@ use crate::render::{DrawIntersection, DrawOptions};
 use crate::ui::{ShowEverything, UI};
 use ezgui::{hotkey, Color, EventCtx, GeomBatch, GfxCtx, Key, ModalMenu, Text};
 use geom::Polygon;
-use map_model::{IntersectionID, RoadID};
+use map_model::{ControlStopSign, IntersectionID, RoadID};
 use std::collections::HashMap;
 
 // TODO For now, individual turns can't be manipulated. Banning turns could be useful, but I'm not
@@ -51,7 +51,12 @@ impl StopSignEditor {
 
 impl State for StopSignEditor {
     fn event(&mut self, ctx: &mut EventCtx, ui: &mut UI) -> Transition {
-        self.menu.handle_event(ctx, None);
+        let mut txt = Text::prompt("Stop Sign Editor");
+        txt.add_line(format!(
+            "{:?} confidence in the default stop signs",
+            ControlStopSign::default_confidence(&ui.primary.map, self.id)
+        ));
+        self.menu.handle_event(ctx, Some(txt));
         ctx.canvas.handle_event(ctx.input);
 
         if ctx.redo_mouseover() {
@@ -68,7 +73,7 @@ impl State for StopSignEditor {
 
         if let Some(r) = self.selected_sign {
             if ctx.input.contextual_action(Key::Space, "toggle stop sign") {
-                let mut sign = ui.primary.map.get_stop_sign(self.id).clone();
+                let mut sign = ui.primary.map.get_unpatched_stop_sign(self.id).clone();
                 sign.flip_sign(r, &ui.primary.map);
                 let mut new_edits = ui.primary.map.get_edits().clone();
                 new_edits.stop_sign_overrides.insert(self.id, sign);
@@ -8,6 +8,7 @@ use abstutil::Timer;
 use ezgui::{hotkey, Color, EventCtx, GeomBatch, GfxCtx, Key, ModalMenu};
 use geom::Duration;
 use map_model::{ControlTrafficSignal, Cycle, IntersectionID, TurnID, TurnPriority, TurnType};
+use sim::SignalOptimizer;
 
 // TODO Warn if there are empty cycles or if some turn is completely absent from the signal.
 pub struct TrafficSignalEditor {
@@ -37,6 +38,7 @@ impl TrafficSignalEditor {
                 vec![
                     (hotkey(Key::R), "reset to original"),
                     (hotkey(Key::P), "choose a preset signal"),
+                    (hotkey(Key::O), "optimize timing for the simulation"),
                     (
                         hotkey(Key::B),
                         "convert to dedicated pedestrian scramble cycle",
@@ -76,7 +78,11 @@ impl State for TrafficSignalEditor {
             }
         }
 
-        let mut signal = ui.primary.map.get_traffic_signal(self.diagram.i).clone();
+        let mut signal = ui
+            .primary
+            .map
+            .get_unpatched_traffic_signal(self.diagram.i)
+            .clone();
 
         if let Some(id) = self.icon_selected {
             let cycle = &mut signal.cycles[self.diagram.current_cycle()];
@@ -122,11 +128,15 @@ impl State for TrafficSignalEditor {
         }
 
         if self.menu.action("change cycle duration") {
+            let cycle = &signal.cycles[self.diagram.current_cycle()];
             return Transition::Push(make_change_cycle_duration(
-                signal.cycles[self.diagram.current_cycle()].duration,
+                cycle.duration,
+                cycle.min_duration(&ui.primary.map),
             ));
         } else if self.menu.action("choose a preset signal") {
             return Transition::Push(make_change_preset(self.diagram.i));
+        } else if self.menu.action("optimize timing for the simulation") {
+            return Transition::Push(make_optimize_timing(self.diagram.i));
         } else if self.menu.action("reset to original") {
             signal = ControlTrafficSignal::get_possible_policies(&ui.primary.map, self.diagram.i)
                 .remove(0)
@@ -273,15 +283,31 @@ fn change_traffic_signal(
     apply_map_edits(&mut ui.primary, &ui.cs, ctx, new_edits);
 }
 
-fn make_change_cycle_duration(current_duration: Duration) -> Box<dyn State> {
+// Cycles have to give everyone crossing time to finish.
+fn make_change_cycle_duration(
+    current_duration: Duration,
+    min_duration: Duration,
+) -> Box<dyn State> {
     WizardState::new(Box::new(move |wiz, ctx, _| {
-        let new_duration = wiz.wrap(ctx).input_usize_prefilled(
-            "How long should this cycle be?",
-            format!("{}", current_duration.inner_seconds() as usize),
+        let new_duration = wiz.wrap(ctx).input_something(
+            &format!(
+                "How long should this cycle be? (At least {} seconds)",
+                min_duration.inner_seconds().ceil() as usize
+            ),
+            Some(format!("{}", current_duration.inner_seconds() as usize)),
+            Box::new(move |line| {
+                line.parse::<usize>()
+                    .ok()
+                    .filter(|secs| Duration::seconds(*secs as f64) >= min_duration)
+            }),
         )?;
         Some(Transition::PopWithData(Box::new(move |state, ui, ctx| {
             let mut editor = state.downcast_mut::<TrafficSignalEditor>().unwrap();
-            let mut signal = ui.primary.map.get_traffic_signal(editor.diagram.i).clone();
+            let mut signal = ui
+                .primary
+                .map
+                .get_unpatched_traffic_signal(editor.diagram.i)
+                .clone();
             let idx = editor.diagram.current_cycle();
             signal.cycles[idx].duration = Duration::seconds(new_duration as f64);
             change_traffic_signal(signal, editor.diagram.i, ui, ctx);
@@ -304,3 +330,39 @@ fn make_change_preset(i: IntersectionID) -> Box<dyn State> {
         })))
     }))
 }
+
+fn make_optimize_timing(i: IntersectionID) -> Box<dyn State> {
+    WizardState::new(Box::new(move |wiz, ctx, ui| {
+        let optimizer = SignalOptimizer::from_sim(&ui.primary.sim, i);
+        let mut wizard = wiz.wrap(ctx);
+        if optimizer.demand.is_empty() {
+            if wizard.acknowledge(
+                "Can't optimize this signal",
+                vec!["Run the simulation for a while first, so there's some demand"],
+            ) {
+                return Some(Transition::Pop);
+            }
+            return None;
+        }
+        let minutes = wizard.input_usize_prefilled(
+            "How many minutes should each candidate be simulated?",
+            "10".to_string(),
+        )?;
+
+        let savestate = ui.primary.sim.save();
+        let result = ctx.loading_screen("optimize traffic signal", |_, timer| {
+            optimizer.optimize(
+                &mut ui.primary.map,
+                &savestate,
+                Duration::minutes(minutes),
+                5,
+                timer,
+            )
+        });
+        Some(Transition::PopWithData(Box::new(move |state, ui, ctx| {
+            let mut editor = state.downcast_mut::<TrafficSignalEditor>().unwrap();
+            change_traffic_signal(result.signal, editor.diagram.i, ui, ctx);
+            editor.diagram = TrafficSignalDiagram::new(editor.diagram.i, 0, &ui.primary.map, ctx);
+        })))
+    }))
+}
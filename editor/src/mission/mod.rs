@@ -4,6 +4,7 @@ mod individ_trips;
 mod neighborhood;
 mod scenario;
 
+use crate::edit::step_sim;
 use crate::game::{State, Transition, WizardState};
 use crate::sandbox::SandboxMode;
 use crate::ui::UI;
@@ -60,7 +61,7 @@ impl State for MissionEditMode {
         } else if self.menu.action("visualize all PSRC trips") {
             return Transition::Push(Box::new(all_trips::TripsVisualizer::new(ctx, ui)));
         } else if self.menu.action("set up simulation with PSRC trips") {
-            ctx.loading_screen("setup PSRC scenario", |_, mut timer| {
+            ctx.loading_screen("setup PSRC scenario", |ctx, mut timer| {
                 let scenario = trips_to_scenario(
                     &ui.primary.map,
                     Duration::ZERO,
@@ -73,9 +74,7 @@ impl State for MissionEditMode {
                     &mut ui.primary.current_flags.sim_flags.make_rng(),
                     timer,
                 );
-                ui.primary
-                    .sim
-                    .step(&ui.primary.map, Duration::const_seconds(0.1));
+                step_sim(&mut ui.primary, &ui.cs, ctx, Duration::const_seconds(0.1));
             });
             return Transition::Replace(Box::new(SandboxMode::new(ctx)));
         } else if self.menu.action("create scenario from PSRC trips") {
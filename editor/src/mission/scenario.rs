@@ -1,4 +1,5 @@
 use crate::common::CommonState;
+use crate::edit::step_sim;
 use crate::game::{State, Transition, WizardState};
 use crate::helpers::ID;
 use crate::mission::pick_time_range;
@@ -98,14 +99,14 @@ impl State for ScenarioManager {
                 wizard: Wizard::new(),
             }));
         } else if self.menu.action("instantiate") {
-            ctx.loading_screen("instantiate scenario", |_, timer| {
+            ctx.loading_screen("instantiate scenario", |ctx, timer| {
                 self.scenario.instantiate(
                     &mut ui.primary.sim,
                     &ui.primary.map,
                     &mut ui.primary.current_flags.sim_flags.make_rng(),
                     timer,
                 );
-                ui.primary.sim.step(&ui.primary.map, Duration::seconds(0.1));
+                step_sim(&mut ui.primary, &ui.cs, ctx, Duration::seconds(0.1));
             });
             return Transition::Replace(Box::new(SandboxMode::new(ctx)));
         }
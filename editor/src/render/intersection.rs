@@ -38,7 +38,10 @@ impl DrawIntersection {
                 IntersectionType::Border => {
                     cs.get_def("border intersection", Color::rgb(50, 205, 50))
                 }
-                IntersectionType::StopSign | IntersectionType::TrafficSignal => {
+                IntersectionType::StopSign
+                | IntersectionType::TrafficSignal
+                | IntersectionType::Uncontrolled
+                | IntersectionType::Roundabout => {
                     cs.get_def("normal intersection", Color::grey(0.6))
                 }
             },
@@ -82,7 +85,9 @@ impl DrawIntersection {
                     }
                 }
             }
-            IntersectionType::TrafficSignal => {}
+            IntersectionType::TrafficSignal
+            | IntersectionType::Uncontrolled
+            | IntersectionType::Roundabout => {}
         }
 
         DrawIntersection {
@@ -187,13 +192,13 @@ pub fn calculate_corners(i: &Intersection, map: &Map, timer: &mut Timer) -> Vec<
             let l1 = map.get_l(turn.id.src);
             let l2 = map.get_l(turn.id.dst);
 
-            let src_line = l1.last_line().shift_left(LANE_THICKNESS / 2.0);
-            let dst_line = l2.first_line().shift_left(LANE_THICKNESS / 2.0);
+            let src_line = l1.last_line().shift_left(l1.width / 2.0);
+            let dst_line = l2.first_line().shift_left(l2.width / 2.0);
 
             let pt_maybe_in_intersection = src_line.infinite().intersection(&dst_line.infinite());
             // Now find all of the points on the intersection polygon between the two sidewalks.
-            let corner1 = l1.last_line().shift_right(LANE_THICKNESS / 2.0).pt2();
-            let corner2 = l2.first_line().shift_right(LANE_THICKNESS / 2.0).pt1();
+            let corner1 = l1.last_line().shift_right(l1.width / 2.0).pt2();
+            let corner2 = l2.first_line().shift_right(l2.width / 2.0).pt1();
             // Intersection polygons are constructed in clockwise order, so do corner2 to corner1.
             // TODO This threshold is higher than the 0.1 intersection polygons use to dedupe
             // because of jagged lane teeth from bad polyline shifting. Seemingly.
@@ -233,13 +238,13 @@ fn calculate_border_arrows(i: &Intersection, r: &Road, timer: &mut Timer) -> Vec
     if !i.outgoing_lanes.is_empty() {
         // The line starts at the border and points down the road
         let (line, width) = if r.dst_i == i.id {
-            let width = (r.children_forwards.len() as f64) * LANE_THICKNESS;
+            let width = r.back_width;
             (
                 r.center_pts.last_line().shift_left(width / 2.0).reverse(),
                 width,
             )
         } else {
-            let width = (r.children_forwards.len() as f64) * LANE_THICKNESS;
+            let width = r.fwd_width;
             (r.center_pts.first_line().shift_right(width / 2.0), width)
         };
         result.push(
@@ -257,13 +262,13 @@ fn calculate_border_arrows(i: &Intersection, r: &Road, timer: &mut Timer) -> Vec
     if !i.incoming_lanes.is_empty() {
         // The line starts at the border and points down the road
         let (line, width) = if r.dst_i == i.id {
-            let width = (r.children_forwards.len() as f64) * LANE_THICKNESS;
+            let width = r.fwd_width;
             (
                 r.center_pts.last_line().shift_right(width / 2.0).reverse(),
                 width,
             )
         } else {
-            let width = (r.children_backwards.len() as f64) * LANE_THICKNESS;
+            let width = r.back_width;
             (r.center_pts.first_line().shift_left(width / 2.0), width)
         };
         result.push(
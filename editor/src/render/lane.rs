@@ -42,9 +42,18 @@ impl DrawLane {
         timer: &mut Timer,
     ) -> AlmostDrawLane {
         let road = map.get_r(lane.parent);
-        let polygon = lane.lane_center_pts.make_polygons(LANE_THICKNESS);
+        let polygon = lane.lane_center_pts.make_polygons(lane.width);
 
         let mut draw = GeomBatch::new();
+        // Lanes removed by edits keep their ID, but there's nothing to draw.
+        if !map.lane_exists(lane.id) {
+            return AlmostDrawLane {
+                id: lane.id,
+                polygon,
+                zorder: road.get_zorder(),
+                draw_default: draw,
+            };
+        }
         draw.push(
             match lane.lane_type {
                 LaneType::Driving => cs.get_def("driving lane", Color::BLACK),
@@ -133,7 +142,7 @@ impl Renderable for DrawLane {
     fn get_outline(&self, map: &Map) -> Polygon {
         map.get_l(self.id)
             .lane_center_pts
-            .to_thick_boundary(LANE_THICKNESS, OUTLINE_THICKNESS)
+            .to_thick_boundary(map.get_l(self.id).width, OUTLINE_THICKNESS)
             .unwrap_or_else(|| self.polygon.clone())
     }
 
@@ -154,7 +163,7 @@ fn perp_line(l: Line, length: Distance) -> Line {
 }
 
 fn calculate_sidewalk_lines(lane: &Lane) -> Vec<Polygon> {
-    let tile_every = LANE_THICKNESS;
+    let tile_every = lane.width;
 
     let length = lane.length();
 
@@ -165,9 +174,8 @@ fn calculate_sidewalk_lines(lane: &Lane) -> Vec<Polygon> {
         let (pt, angle) = lane.dist_along(dist_along);
         // Reuse perp_line. Project away an arbitrary amount
         let pt2 = pt.project_away(Distance::meters(1.0), angle);
-        result.push(
-            perp_line(Line::new(pt, pt2), LANE_THICKNESS).make_polygons(Distance::meters(0.25)),
-        );
+        result
+            .push(perp_line(Line::new(pt, pt2), lane.width).make_polygons(Distance::meters(0.25)));
         dist_along += tile_every;
     }
 
@@ -186,7 +194,7 @@ fn calculate_parking_lines(lane: &Lane) -> Vec<Polygon> {
             let perp_angle = lane_angle.rotate_degs(270.0);
             // Find the outside of the lane. Actually, shift inside a little bit, since the line will
             // have thickness, but shouldn't really intersect the adjacent line when drawn.
-            let t_pt = pt.project_away(LANE_THICKNESS * 0.4, perp_angle);
+            let t_pt = pt.project_away(lane.width * 0.4, perp_angle);
             // The perp leg
             let p1 = t_pt.project_away(leg_length, perp_angle.opposite());
             result.push(Line::new(t_pt, p1).make_polygons(Distance::meters(0.25)));
@@ -211,10 +219,7 @@ fn calculate_driving_lines(lane: &Lane, parent: &Road, timer: &mut Timer) -> Vec
     let dash_separation = Distance::meters(1.5);
     let dash_len = Distance::meters(1.0);
 
-    let lane_edge_pts = lane
-        .lane_center_pts
-        .shift_left(LANE_THICKNESS / 2.0)
-        .get(timer);
+    let lane_edge_pts = lane.lane_center_pts.shift_left(lane.width / 2.0).get(timer);
     if lane_edge_pts.length() < dash_separation * 2.0 + EPSILON_DIST {
         return Vec::new();
     }
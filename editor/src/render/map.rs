@@ -14,8 +14,8 @@ use abstutil::{Cloneable, Timer};
 use ezgui::{Color, Drawable, GeomBatch, GfxCtx, Prerender};
 use geom::{Bounds, Circle, Distance, Duration, FindClosest};
 use map_model::{
-    AreaID, BuildingID, BusStopID, DirectedRoadID, IntersectionID, IntersectionType, Lane, LaneID,
-    Map, RoadID, Traversable, Turn, TurnID, TurnType, LANE_THICKNESS,
+    AreaID, BuildingID, BusStopID, DirectedRoadID, IntersectionID, Lane, LaneID, Map, RoadID,
+    Traversable, Turn, TurnID, TurnType, LANE_THICKNESS,
 };
 use sim::{CarStatus, DrawCarInput, DrawPedestrianInput, UnzoomedAgent, VehicleType};
 use std::borrow::Borrow;
@@ -115,7 +115,7 @@ impl DrawMap {
         for i in map.all_intersections() {
             timer.next();
             let draw_i = DrawIntersection::new(i, map, cs, prerender, timer);
-            if i.intersection_type == IntersectionType::StopSign {
+            if i.intersection_type.is_unsignalized() {
                 all_intersections.push(osm_rank_to_color(cs, i.get_rank(map)), i.polygon.clone());
                 all_intersections.push(cs.get("unzoomed outline"), draw_i.get_outline(map));
             } else {
@@ -143,14 +143,14 @@ impl DrawMap {
 
         let mut extra_shapes: Vec<DrawExtraShape> = Vec::new();
         if let Some(ref path) = flags.kml {
-            let raw_shapes = if path.ends_with(".kml") {
-                kml::load(&path, &map.get_gps_bounds(), timer)
-                    .expect("Couldn't load extra KML shapes")
-                    .shapes
-            } else {
+            let raw_shapes = if path.ends_with(".bin") {
                 let shapes: kml::ExtraShapes =
                     abstutil::read_binary(&path, timer).expect("Couldn't load ExtraShapes");
                 shapes.shapes
+            } else {
+                kml::load_any(&path, &map.get_gps_bounds(), None, timer)
+                    .expect("Couldn't load extra shapes")
+                    .shapes
             };
 
             let mut closest: FindClosest<DirectedRoadID> = FindClosest::new(&map.get_bounds());
@@ -317,6 +317,14 @@ impl DrawMap {
         &self.areas[id.0]
     }
 
+    // Map edits can add lanes to the end.
+    pub fn add_lane(&mut self, draw: DrawLane, map: &Map) {
+        assert_eq!(draw.id.0, self.lanes.len());
+        self.quadtree
+            .insert_with_box(draw.get_id(), draw.get_outline(map).get_bounds().as_bbox());
+        self.lanes.push(draw);
+    }
+
     // Unsorted, unexpanded, raw result.
     pub fn get_matching_objects(&self, bounds: Bounds) -> Vec<ID> {
         let mut results: Vec<ID> = Vec::new();
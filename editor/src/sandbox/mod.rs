@@ -6,7 +6,7 @@ use crate::common::{
     time_controls, AgentTools, CommonState, RouteExplorer, SpeedControls, TripExplorer,
 };
 use crate::debug::DebugMode;
-use crate::edit::EditMode;
+use crate::edit::{apply_scheduled_map_edits, time_limited_step_sim, EditMode};
 use crate::game::{State, Transition, WizardState};
 use crate::ui::{ShowEverything, UI};
 use ezgui::{hotkey, lctrl, EventCtx, EventLoopMode, GfxCtx, Key, ModalMenu, Text, Wizard};
@@ -115,15 +115,13 @@ impl State for SandboxMode {
             return Transition::Replace(Box::new(DebugMode::new(ctx, ui)));
         }
         if self.menu.action("edit mode") {
-            return Transition::Replace(Box::new(EditMode::new(ctx, ui)));
+            return Transition::Replace(Box::new(EditMode::new(ctx)));
         }
 
         if let Some(dt) = self.speed.event(ctx, &mut self.menu, ui.primary.sim.time()) {
             // If speed is too high, don't be unresponsive for too long.
             // TODO This should probably match the ezgui framerate.
-            ui.primary
-                .sim
-                .time_limited_step(&ui.primary.map, dt, Duration::seconds(0.1));
+            time_limited_step_sim(&mut ui.primary, &ui.cs, ctx, dt, Duration::seconds(0.1));
             ui.recalculate_current_selection(ctx);
         }
 
@@ -151,6 +149,7 @@ impl State for SandboxMode {
                     {
                         Some(new_sim) => {
                             ui.primary.sim = new_sim;
+                            apply_scheduled_map_edits(&mut ui.primary, &ui.cs, ctx);
                             ui.recalculate_current_selection(ctx);
                         }
                         None => println!("Couldn't load previous savestate {:?}", prev_state),
@@ -166,6 +165,7 @@ impl State for SandboxMode {
                     {
                         Some(new_sim) => {
                             ui.primary.sim = new_sim;
+                            apply_scheduled_map_edits(&mut ui.primary, &ui.cs, ctx);
                             ui.recalculate_current_selection(ctx);
                         }
                         None => println!("Couldn't load next savestate {:?}", next_state),
@@ -217,6 +217,7 @@ fn load_savestate(wiz: &mut Wizard, ctx: &mut EventCtx, ui: &mut UI) -> Option<T
 
     ctx.loading_screen("load savestate", |ctx, mut timer| {
         ui.primary.sim = Sim::load_savestate(ss, &mut timer).expect("Can't load savestate");
+        apply_scheduled_map_edits(&mut ui.primary, &ui.cs, ctx);
         ui.recalculate_current_selection(ctx);
     });
     Some(Transition::Pop)
@@ -1,4 +1,5 @@
 use crate::common::CommonState;
+use crate::edit::step_sim;
 use crate::game::{State, Transition, WizardState};
 use crate::helpers::ID;
 use crate::render::DrawOptions;
@@ -265,7 +266,7 @@ impl State for AgentSpawner {
                 }
             };
             sim.spawn_all_trips(map, &mut Timer::new("spawn trip"), false);
-            sim.step(map, SMALL_DT);
+            step_sim(&mut ui.primary, &ui.cs, ctx, SMALL_DT);
             ui.recalculate_current_selection(ctx);
             return Transition::Pop;
         }
@@ -295,7 +296,7 @@ impl State for AgentSpawner {
     }
 }
 
-fn spawn_agents_around(i: IntersectionID, ui: &mut UI, ctx: &EventCtx) {
+fn spawn_agents_around(i: IntersectionID, ui: &mut UI, ctx: &mut EventCtx) {
     let map = &ui.primary.map;
     let sim = &mut ui.primary.sim;
     let mut rng = ui.primary.current_flags.sim_flags.make_rng();
@@ -351,7 +352,7 @@ fn spawn_agents_around(i: IntersectionID, ui: &mut UI, ctx: &EventCtx) {
     }
 
     sim.spawn_all_trips(map, &mut Timer::throwaway(), false);
-    sim.step(map, SMALL_DT);
+    step_sim(&mut ui.primary, &ui.cs, ctx, SMALL_DT);
     ui.recalculate_current_selection(ctx);
 }
 
@@ -390,14 +391,14 @@ fn instantiate_scenario(wiz: &mut Wizard, ctx: &mut EventCtx, ui: &mut UI) -> Op
         )
         .unwrap()
     };
-    ctx.loading_screen("instantiate scenario", |_, timer| {
+    ctx.loading_screen("instantiate scenario", |ctx, timer| {
         scenario.instantiate(
             &mut ui.primary.sim,
             &ui.primary.map,
             &mut ui.primary.current_flags.sim_flags.make_rng(),
             timer,
         );
-        ui.primary.sim.step(&ui.primary.map, SMALL_DT);
+        step_sim(&mut ui.primary, &ui.cs, ctx, SMALL_DT);
     });
     Some(Transition::Pop)
 }
@@ -185,7 +185,7 @@ fn splash_screen(
                 None
             }
         }
-        x if x == edit => Some(Transition::Push(Box::new(EditMode::new(ctx, ui)))),
+        x if x == edit => Some(Transition::Push(Box::new(EditMode::new(ctx)))),
         x if x == tutorial => Some(Transition::Push(Box::new(TutorialMode::new(ctx, ui)))),
         x if x == debug => Some(Transition::Push(Box::new(DebugMode::new(ctx, ui)))),
         x if x == mission => Some(Transition::Push(Box::new(MissionEditMode::new(ctx, ui)))),
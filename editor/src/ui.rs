@@ -268,6 +268,9 @@ impl UI {
             match id {
                 ID::Area(id) => areas.push(draw_map.get_a(id)),
                 ID::Lane(id) => {
+                    if !map.lane_exists(id) {
+                        continue;
+                    }
                     lanes.push(draw_map.get_l(id));
                     agents_on.push(Traversable::Lane(id));
                     for bs in &map.get_l(id).bus_stops {
@@ -428,7 +431,7 @@ pub struct Flags {
     #[structopt(flatten)]
     pub sim_flags: SimFlags,
 
-    /// Extra KML or ExtraShapes to display
+    /// Extra shapes to display: KML, GeoJSON, a Shapefile, or serialized ExtraShapes (.bin)
     #[structopt(long = "kml")]
     pub kml: Option<String>,
 
@@ -59,6 +59,14 @@ impl Duration {
         }
     }
 
+    pub fn max(self, other: Duration) -> Duration {
+        if self >= other {
+            self
+        } else {
+            other
+        }
+    }
+
     // TODO Remove if possible.
     pub fn inner_seconds(self) -> f64 {
         self.0
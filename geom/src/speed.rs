@@ -31,6 +31,14 @@ impl Speed {
         Speed::meters_per_second(0.44704 * value)
     }
 
+    pub fn km_per_hour(value: f64) -> Speed {
+        Speed::meters_per_second(value / 3.6)
+    }
+
+    pub fn knots(value: f64) -> Speed {
+        Speed::meters_per_second(0.514_444 * value)
+    }
+
     pub fn from_dist_time(d: Distance, t: Duration) -> Speed {
         Speed::meters_per_second(d.inner_meters() / t.inner_seconds())
     }
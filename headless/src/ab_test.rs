@@ -0,0 +1,159 @@
+use abstutil::Timer;
+use geom::Duration;
+use serde_derive::Serialize;
+use sim::{compare_trips, ABTest, FinishedTrips, ModeDeltas, SimFlags, TripDelta, TripMode};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Error, Write};
+use std::path::PathBuf;
+
+#[derive(Serialize)]
+pub struct ABTestReport {
+    pub test: ABTest,
+    pub rng_seed: u8,
+    pub unfinished_trips1: usize,
+    pub unfinished_trips2: usize,
+    // Only trips that finished in both runs
+    pub trips: Vec<TripDelta>,
+    pub per_mode: BTreeMap<TripMode, ModeDeltas>,
+}
+
+// Runs the scenario to completion with both sets of edits, using the same RNG seed, so the same
+// TripIDs refer to the same trips.
+pub fn run(test: ABTest, rng_seed: u8, output: &str) {
+    let trips1 = run_side(&test, &test.edits1_name, rng_seed);
+    let trips2 = run_side(&test, &test.edits2_name, rng_seed);
+    let report = ABTestReport::new(test, rng_seed, trips1, trips2);
+
+    report.print();
+    abstutil::write_json(&format!("{}.json", output), &report).unwrap();
+    report
+        .write_trips_csv(&format!("{}_trips.csv", output))
+        .unwrap();
+    report
+        .write_modes_csv(&format!("{}_modes.csv", output))
+        .unwrap();
+    println!(
+        "Wrote {}.json, {}_trips.csv, and {}_modes.csv",
+        output, output, output
+    );
+}
+
+fn run_side(test: &ABTest, edits_name: &str, rng_seed: u8) -> FinishedTrips {
+    let mut timer = Timer::new(&format!("run {} with {}", test.test_name, edits_name));
+    let flags = SimFlags {
+        load: PathBuf::from(abstutil::path1_bin(
+            &test.map_name,
+            abstutil::SCENARIOS,
+            &test.scenario_name,
+        )),
+        rng_seed: Some(rng_seed),
+        edits_name: edits_name.to_string(),
+        run_name: Some(format!("{} with {}", test.test_name, edits_name)),
+        savestate_every: None,
+        freeform_policy: false,
+        disable_block_the_box: false,
+    };
+    let (mut map, mut sim, _) = flags.load(&mut timer);
+    timer.done();
+
+    sim.just_run_until_done(&mut map, None);
+    println!("{} done at {}", edits_name, sim.time());
+    sim.get_finished_trips()
+}
+
+impl ABTestReport {
+    fn new(
+        test: ABTest,
+        rng_seed: u8,
+        trips1: FinishedTrips,
+        trips2: FinishedTrips,
+    ) -> ABTestReport {
+        let unfinished_trips1 = trips1.unfinished_trips;
+        let unfinished_trips2 = trips2.unfinished_trips;
+        let (trips, per_mode) = compare_trips(trips1, trips2);
+
+        ABTestReport {
+            test,
+            rng_seed,
+            unfinished_trips1,
+            unfinished_trips2,
+            trips,
+            per_mode,
+        }
+    }
+
+    fn print(&self) {
+        println!(
+            "{}: {} vs {}",
+            self.test.test_name, self.test.edits1_name, self.test.edits2_name
+        );
+        println!(
+            "{} | {} unfinished trips",
+            abstutil::prettyprint_usize(self.unfinished_trips1),
+            abstutil::prettyprint_usize(self.unfinished_trips2)
+        );
+        for (mode, m) in &self.per_mode {
+            println!(
+                "{:?} trips: {} same, {} faster, {} slower, mean delta {}",
+                mode,
+                abstutil::prettyprint_usize(m.num_same),
+                abstutil::prettyprint_usize(m.num_faster),
+                abstutil::prettyprint_usize(m.num_slower),
+                handle_negative(m.mean_delta)
+            );
+        }
+    }
+
+    fn write_trips_csv(&self, path: &str) -> Result<(), Error> {
+        let mut f = File::create(path)?;
+        writeln!(f, "trip,mode,time1_seconds,time2_seconds,delta_seconds")?;
+        for t in &self.trips {
+            writeln!(
+                f,
+                "{},{:?},{},{},{}",
+                t.trip.0,
+                t.mode,
+                t.time1.inner_seconds(),
+                t.time2.inner_seconds(),
+                t.delta.inner_seconds()
+            )?;
+        }
+        Ok(())
+    }
+
+    fn write_modes_csv(&self, path: &str) -> Result<(), Error> {
+        let mut f = File::create(path)?;
+        writeln!(f, "mode,num_trips,num_same,num_faster,num_slower,total_time1_seconds,total_time2_seconds,mean_delta_seconds,delta_50_percentile_seconds,delta_90_percentile_seconds,delta_99_percentile_seconds")?;
+        let csv_opt = |x: Option<Duration>| {
+            x.map(|t| t.inner_seconds().to_string())
+                .unwrap_or_else(String::new)
+        };
+        for (mode, m) in &self.per_mode {
+            writeln!(
+                f,
+                "{:?},{},{},{},{},{},{},{},{},{},{}",
+                mode,
+                m.num_trips,
+                m.num_same,
+                m.num_faster,
+                m.num_slower,
+                m.total_time1.inner_seconds(),
+                m.total_time2.inner_seconds(),
+                m.mean_delta.inner_seconds(),
+                csv_opt(m.delta_50_percentile),
+                csv_opt(m.delta_90_percentile),
+                csv_opt(m.delta_99_percentile)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn handle_negative(x: Duration) -> String {
+    if x >= Duration::ZERO {
+        format!("+{}", x)
+    } else {
+        format!("-{}", -x)
+    }
+}
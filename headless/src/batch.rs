@@ -0,0 +1,237 @@
+use geom::Duration;
+use serde_derive::{Deserialize, Serialize};
+use sim::{confidence_interval, is_significant, FinishedTrips, TripMode};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::process::{Child, Command, Stdio};
+
+// Summarizes trip times over many runs of the same scenario that only differ by RNG seed. One
+// run can't distinguish the effect of some edits from noise.
+#[derive(Serialize, Deserialize)]
+pub struct BatchResults {
+    pub load: String,
+    pub edits_name: String,
+    pub seeds: Vec<u8>,
+    // These crashed or didn't produce results. They're excluded from everything else.
+    pub failed_seeds: Vec<u8>,
+    pub per_mode: BTreeMap<TripMode, ModeSummary>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ModeSummary {
+    // Indexed like BatchResults::seeds
+    pub finished_trips_per_seed: Vec<usize>,
+    // Only for runs where at least one trip of this mode finished
+    pub mean_trip_time_per_seed: Vec<Duration>,
+
+    // Across seeds
+    pub mean_trip_time: Duration,
+    pub ci95_low: Duration,
+    pub ci95_high: Duration,
+}
+
+impl ModeSummary {
+    fn new(
+        finished_trips_per_seed: Vec<usize>,
+        mean_trip_time_per_seed: Vec<Duration>,
+    ) -> ModeSummary {
+        let (mean, half_width) = confidence_interval(&mean_trip_time_per_seed);
+        ModeSummary {
+            finished_trips_per_seed,
+            mean_trip_time_per_seed,
+            mean_trip_time: mean,
+            ci95_low: mean - half_width,
+            ci95_high: mean + half_width,
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "mean trip time {} (95% CI {} to {}), {} finished trips per run on average",
+            self.mean_trip_time,
+            self.ci95_low,
+            self.ci95_high,
+            self.finished_trips_per_seed.iter().sum::<usize>()
+                / self.finished_trips_per_seed.len().max(1)
+        )
+    }
+}
+
+// base_args describe the scenario and edits; each run adds its own seed and output.
+pub fn run(
+    base_args: Vec<String>,
+    load: String,
+    edits_name: String,
+    num_seeds: u8,
+    parallel_processes: usize,
+    output: &str,
+    compare_to: Option<String>,
+) {
+    assert!(parallel_processes > 0);
+    let exe = std::env::current_exe().unwrap();
+    let scratch_dir = std::env::temp_dir();
+    let scratch_path = |seed: u8, ext: &str| {
+        scratch_dir
+            .join(format!(
+                "abst_batch_{}_seed_{}.{}",
+                std::process::id(),
+                seed,
+                ext
+            ))
+            .display()
+            .to_string()
+    };
+
+    // Each seed runs in its own process, so one crashing simulation doesn't take down the batch.
+    let mut running: Vec<(u8, Child)> = Vec::new();
+    let mut finished: BTreeMap<u8, Option<FinishedTrips>> = BTreeMap::new();
+    for seed in 0..num_seeds {
+        if running.len() == parallel_processes {
+            let (seed, child) = running.remove(0);
+            finished.insert(seed, wait_for(seed, child, &scratch_path));
+        }
+
+        println!("Starting run with RNG seed {}", seed);
+        let child = Command::new(&exe)
+            .args(&base_args)
+            .arg(format!("--rng_seed={}", seed))
+            .arg(format!("--run_name=batch_seed_{}", seed))
+            .arg(format!(
+                "--finished_trips_output={}",
+                scratch_path(seed, "json")
+            ))
+            .stdout(Stdio::from(
+                File::create(scratch_path(seed, "log")).unwrap(),
+            ))
+            .spawn()
+            .unwrap();
+        running.push((seed, child));
+    }
+    for (seed, child) in running {
+        finished.insert(seed, wait_for(seed, child, &scratch_path));
+    }
+
+    let mut results = BatchResults {
+        load,
+        edits_name,
+        seeds: Vec::new(),
+        failed_seeds: Vec::new(),
+        per_mode: BTreeMap::new(),
+    };
+    let mut per_mode: BTreeMap<TripMode, (Vec<usize>, Vec<Duration>)> = BTreeMap::new();
+    for (seed, maybe_trips) in finished {
+        let trips = if let Some(t) = maybe_trips {
+            t
+        } else {
+            results.failed_seeds.push(seed);
+            continue;
+        };
+        results.seeds.push(seed);
+
+        let mut totals: BTreeMap<TripMode, (usize, Duration)> = BTreeMap::new();
+        for mode in &[
+            TripMode::Walk,
+            TripMode::Bike,
+            TripMode::Transit,
+            TripMode::Drive,
+        ] {
+            totals.insert(*mode, (0, Duration::ZERO));
+        }
+        for (_, mode, dt) in trips.finished_trips {
+            let entry = totals.get_mut(&mode).unwrap();
+            entry.0 += 1;
+            entry.1 += dt;
+        }
+        for (mode, (count, total)) in totals {
+            let entry = per_mode
+                .entry(mode)
+                .or_insert_with(|| (Vec::new(), Vec::new()));
+            entry.0.push(count);
+            if count > 0 {
+                entry.1.push(total * (1.0 / (count as f64)));
+            }
+        }
+    }
+    for (mode, (counts, means)) in per_mode {
+        if means.is_empty() {
+            continue;
+        }
+        results
+            .per_mode
+            .insert(mode, ModeSummary::new(counts, means));
+    }
+
+    println!(
+        "{} runs finished, {} failed",
+        results.seeds.len(),
+        results.failed_seeds.len()
+    );
+    for (mode, summary) in &results.per_mode {
+        println!("{:?}: {}", mode, summary.describe());
+    }
+    abstutil::write_json(output, &results).unwrap();
+    println!("Wrote {}", output);
+
+    if let Some(path) = compare_to {
+        let baseline: BatchResults = abstutil::read_json(&path).unwrap();
+        compare(&baseline, &results);
+    }
+}
+
+fn wait_for<F: Fn(u8, &str) -> String>(
+    seed: u8,
+    mut child: Child,
+    scratch_path: F,
+) -> Option<FinishedTrips> {
+    let status = child.wait().unwrap();
+    let log = scratch_path(seed, "log");
+    if !status.success() {
+        println!("Run with RNG seed {} failed; see {}", seed, log);
+        return None;
+    }
+    let path = scratch_path(seed, "json");
+    let trips: FinishedTrips = abstutil::read_json(&path).ok()?;
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(&log).unwrap();
+    println!("Run with RNG seed {} done", seed);
+    Some(trips)
+}
+
+// Uses Welch's t-test on the per-seed means, since the two batches might have different
+// variance and numbers of successful runs.
+pub fn compare(baseline: &BatchResults, experiment: &BatchResults) {
+    println!(
+        "Comparing {} with {} against {} with {}",
+        experiment.load, experiment.edits_name, baseline.load, baseline.edits_name
+    );
+    for (mode, after) in &experiment.per_mode {
+        let before = if let Some(s) = baseline.per_mode.get(mode) {
+            s
+        } else {
+            println!("{:?}: no trips in the baseline", mode);
+            continue;
+        };
+        let delta = after.mean_trip_time - before.mean_trip_time;
+        let verdict = if is_significant(
+            &before.mean_trip_time_per_seed,
+            &after.mean_trip_time_per_seed,
+        ) {
+            "significant at 95%"
+        } else {
+            "NOT significant; could just be noise between seeds"
+        };
+        println!(
+            "{:?}: mean trip time {} -> {} ({}{}), {}",
+            mode,
+            before.mean_trip_time,
+            after.mean_trip_time,
+            if delta >= Duration::ZERO { "+" } else { "-" },
+            if delta >= Duration::ZERO {
+                delta
+            } else {
+                -delta
+            },
+            verdict
+        );
+    }
+}
@@ -1,3 +1,6 @@
+mod ab_test;
+mod batch;
+
 use abstutil::Timer;
 use geom::Duration;
 use sim::{GetDrawAgents, Scenario, SimFlags};
@@ -25,15 +28,83 @@ struct Flags {
     /// Every 0.1s, pretend to draw everything to make sure there are no bugs.
     #[structopt(long = "paranoia")]
     paranoia: bool,
+
+    /// When the simulation is done, write all finished trips as JSON here
+    #[structopt(long = "finished_trips_output")]
+    finished_trips_output: Option<String>,
+
+    /// When the simulation is done, write the map as GeoJSON here, with throughput and delay
+    #[structopt(long = "geojson_output")]
+    geojson_output: Option<String>,
+
+    /// Instead of one run, run once per RNG seed from 0 to num_seeds - 1 and summarize trip times
+    #[structopt(long = "num_seeds")]
+    num_seeds: Option<u8>,
+
+    /// When running multiple seeds, how many separate processes to run at once
+    #[structopt(long = "parallel_processes", default_value = "1")]
+    parallel_processes: usize,
+
+    /// When running multiple seeds, where to write the summary
+    #[structopt(long = "batch_output", default_value = "batch_results.json")]
+    batch_output: String,
+
+    /// When running multiple seeds, a previous summary to test for significant differences against
+    #[structopt(long = "compare_to")]
+    compare_to: Option<String>,
+
+    /// Instead of one run, run both sides of this A/B test (a JSON file from ../data/ab_tests/)
+    #[structopt(long = "ab_test")]
+    ab_test: Option<String>,
+
+    /// When running an A/B test, the prefix for the JSON and CSV reports
+    #[structopt(long = "ab_test_output", default_value = "ab_test_report")]
+    ab_test_output: String,
 }
 
 fn main() {
     let flags = Flags::from_args();
 
+    if let Some(ref path) = flags.ab_test {
+        let test: sim::ABTest = abstutil::read_json(path).unwrap();
+        ab_test::run(
+            test,
+            flags.sim_flags.rng_seed.unwrap_or(42),
+            &flags.ab_test_output,
+        );
+        return;
+    }
+
+    if let Some(num_seeds) = flags.num_seeds {
+        let mut base_args = vec![
+            flags.sim_flags.load.display().to_string(),
+            format!("--edits_name={}", flags.sim_flags.edits_name),
+        ];
+        if let Some(n) = flags.num_agents {
+            base_args.push(format!("--num_agents={}", n));
+        }
+        if flags.sim_flags.freeform_policy {
+            base_args.push("--freeform_policy".to_string());
+        }
+        if flags.sim_flags.disable_block_the_box {
+            base_args.push("--disable_block_the_box".to_string());
+        }
+        batch::run(
+            base_args,
+            flags.sim_flags.load.display().to_string(),
+            flags.sim_flags.edits_name.clone(),
+            num_seeds,
+            flags.parallel_processes,
+            &flags.batch_output,
+            flags.compare_to.clone(),
+        );
+        return;
+    }
+
     // TODO not the ideal way to distinguish what thing we loaded
     let load = flags.sim_flags.load.clone();
     let mut timer = Timer::new("setup headless");
-    let (map, mut sim, mut rng) = flags.sim_flags.load(&mut timer);
+    let (mut map, mut sim, mut rng) = flags.sim_flags.load(&mut timer);
 
     if load.starts_with(Path::new("../data/raw_maps/"))
         || load.starts_with(Path::new("../data/maps/"))
@@ -59,7 +130,7 @@ fn main() {
     let save_at = flags.save_at;
     let timer = Timer::new("run sim until done");
     sim.run_until_done(
-        &map,
+        &mut map,
         move |sim, map| {
             // TODO We want to savestate at the end of this time; this'll happen at the beginning.
             if Some(sim.time()) == save_at {
@@ -77,6 +148,15 @@ fn main() {
     );
     timer.done();
     println!("Done at {}", sim.time());
+    if let Some(path) = flags.finished_trips_output {
+        abstutil::write_json(&path, &sim.get_finished_trips()).unwrap();
+        println!("Wrote finished trips to {}", path);
+    }
+    if let Some(path) = flags.geojson_output {
+        map.export_geojson(&path, &sim.get_traffic_stats().to_feature_properties())
+            .unwrap();
+        println!("Wrote GeoJSON to {}", path);
+    }
     if flags.enable_profiler && save_at.is_none() {
         cpuprofiler::PROFILER.lock().unwrap().stop().unwrap();
     }
@@ -55,6 +55,13 @@ for poly in `ls data/polygons/`; do
 	fi
 done
 
+# Only the tests use this, to check PBF reading against the XML.
+if [ ! -f data/input/montlake.osm.pbf ]; then
+	osmosis \
+		--read-xml enableDateParsing=no file=data/input/montlake.osm \
+		--write-pbf data/input/montlake.osm.pbf
+fi
+
 if [ ! -f data/shapes/blockface.bin ]; then
 	# From http://data-seattlecitygis.opendata.arcgis.com/datasets/blockface
 	get_if_needed https://opendata.arcgis.com/datasets/a1458ad1abca41869b81f7c0db0cd777_0.kml data/input/blockface.kml;
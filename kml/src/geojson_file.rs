@@ -0,0 +1,100 @@
+use crate::{ExtraShape, ExtraShapes, ShapeBuilder};
+use abstutil::Timer;
+use geojson::{GeoJson, Geometry, Value};
+use std::collections::BTreeMap;
+use std::io;
+
+pub(crate) fn load(
+    path: &str,
+    builder: &mut ShapeBuilder,
+    timer: &mut Timer,
+) -> Result<ExtraShapes, io::Error> {
+    println!("Opening {}", path);
+    let document: GeoJson = abstutil::read_json(path)?;
+    let collection = match document {
+        GeoJson::FeatureCollection(c) => c,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} isn't a FeatureCollection", path),
+            ));
+        }
+    };
+
+    // The 2008 spec allowed naming a CRS. RFC 7946 removed it and says everything is WGS84, but
+    // plenty of exports still include it.
+    if builder.projection.is_none() {
+        if let Some(name) = collection
+            .foreign_members
+            .as_ref()
+            .and_then(|m| m.get("crs"))
+            .and_then(|crs| crs.pointer("/properties/name"))
+            .and_then(|name| name.as_str())
+        {
+            builder.projection = crate::parse_crs(name)?;
+        }
+    }
+
+    let mut shapes = Vec::new();
+    timer.start_iter("convert GeoJSON features", collection.features.len());
+    for f in collection.features {
+        timer.next();
+        let mut attributes = BTreeMap::new();
+        for (k, v) in f.properties.unwrap_or_default() {
+            let value = match v {
+                serde_json::Value::Null => continue,
+                serde_json::Value::String(s) => s,
+                x => x.to_string(),
+            };
+            attributes.insert(k, value);
+        }
+        if let Some(geom) = f.geometry {
+            convert(geom, &attributes, builder, &mut shapes);
+        }
+    }
+
+    println!(
+        "Got {} shapes from {} and skipped {} shapes",
+        shapes.len(),
+        path,
+        builder.skipped_count
+    );
+    Ok(ExtraShapes { shapes })
+}
+
+// Multi-part geometries become one shape per part, sharing attributes. Only the outer ring of
+// polygons is kept.
+fn convert(
+    geom: Geometry,
+    attributes: &BTreeMap<String, String>,
+    builder: &mut ShapeBuilder,
+    shapes: &mut Vec<ExtraShape>,
+) {
+    let parts: Vec<Vec<Vec<f64>>> = match geom.value {
+        Value::Point(pt) => vec![vec![pt]],
+        Value::MultiPoint(pts) => pts.into_iter().map(|pt| vec![pt]).collect(),
+        Value::LineString(pts) => vec![pts],
+        Value::MultiLineString(lines) => lines,
+        Value::Polygon(rings) => rings.into_iter().take(1).collect(),
+        Value::MultiPolygon(polygons) => polygons
+            .into_iter()
+            .filter_map(|rings| rings.into_iter().next())
+            .collect(),
+        Value::GeometryCollection(geoms) => {
+            for g in geoms {
+                convert(g, attributes, builder, shapes);
+            }
+            return;
+        }
+    };
+    for pts in parts {
+        let raw: Vec<(f64, f64)> = pts
+            .into_iter()
+            .filter(|pt| pt.len() >= 2)
+            .map(|pt| (pt[0], pt[1]))
+            .collect();
+        if let Some(shape) = builder.build(raw, attributes.clone()) {
+            shapes.push(shape);
+        }
+    }
+}
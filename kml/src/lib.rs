@@ -1,3 +1,8 @@
+mod geojson_file;
+mod projection;
+mod shapefile;
+
+pub use crate::projection::StatePlane;
 use abstutil::{FileWithProgress, Timer};
 use geom::{GPSBounds, LonLat};
 use quick_xml::events::Event;
@@ -19,6 +24,41 @@ pub struct ExtraShape {
     pub attributes: BTreeMap<String, String>,
 }
 
+// Picks the reader from the file extension. KML is assumed to be WGS84. For GeoJSON and
+// Shapefiles, the CRS is detected from the file when possible; pass an EPSG code to override it.
+pub fn load_any(
+    path: &str,
+    gps_bounds: &GPSBounds,
+    epsg: Option<usize>,
+    timer: &mut Timer,
+) -> Result<ExtraShapes, io::Error> {
+    let mut builder = ShapeBuilder {
+        gps_bounds,
+        projection: match epsg {
+            Some(4326) | None => None,
+            Some(code) => Some(StatePlane::from_epsg(code).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("EPSG:{} isn't a supported CRS", code),
+                )
+            })?),
+        },
+        skipped_count: 0,
+    };
+    if path.ends_with(".geojson") || path.ends_with(".json") {
+        geojson_file::load(path, &mut builder, timer)
+    } else if path.ends_with(".shp") {
+        shapefile::load(path, &mut builder, timer)
+    } else if path.ends_with(".kml") {
+        load(path, gps_bounds, timer)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Don't know how to read {}", path),
+        ))
+    }
+}
+
 pub fn load(
     path: &str,
     gps_bounds: &GPSBounds,
@@ -158,3 +198,57 @@ fn fix_field_names(orig_path: &str, shapes: &mut ExtraShapes) -> Option<()> {
 
     Some(())
 }
+
+// Reprojects raw coordinates and skips shapes that aren't entirely within the map.
+pub(crate) struct ShapeBuilder<'a> {
+    gps_bounds: &'a GPSBounds,
+    // None means the input is already WGS84
+    projection: Option<StatePlane>,
+    skipped_count: usize,
+}
+
+impl<'a> ShapeBuilder<'a> {
+    fn build(
+        &mut self,
+        raw_pts: Vec<(f64, f64)>,
+        attributes: BTreeMap<String, String>,
+    ) -> Option<ExtraShape> {
+        let mut points = Vec::new();
+        for (x, y) in raw_pts {
+            let pt = match self.projection {
+                Some(ref p) => p.to_gps(x, y),
+                None => LonLat::new(x, y),
+            };
+            if !self.gps_bounds.contains(pt) {
+                self.skipped_count += 1;
+                return None;
+            }
+            points.push(pt);
+        }
+        if points.is_empty() {
+            self.skipped_count += 1;
+            return None;
+        }
+        Some(ExtraShape { points, attributes })
+    }
+}
+
+// Understands EPSG codes, GeoJSON CRS names, and the WKT in .prj files. None means WGS84.
+fn parse_crs(name: &str) -> Result<Option<StatePlane>, io::Error> {
+    let upper = name.to_uppercase();
+    // NAD83 lon/lat is within a meter of WGS84, so treat any geographic CRS the same
+    if upper.contains("CRS84")
+        || upper.ends_with(":4326")
+        || upper.ends_with(":4269")
+        || upper.starts_with("GEOGCS")
+    {
+        return Ok(None);
+    }
+    match StatePlane::from_name(name) {
+        Some(p) => Ok(Some(p)),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unsupported CRS {}; pass the EPSG code explicitly", name),
+        )),
+    }
+}
@@ -4,10 +4,14 @@ use structopt::StructOpt;
 #[derive(StructOpt)]
 #[structopt(name = "kml")]
 struct Flags {
-    /// KML file to read
+    /// KML, GeoJSON, or Shapefile (.shp) to read
     #[structopt(long = "input")]
     pub input: String,
 
+    /// EPSG code of the input's CRS, if it isn't WGS84 and can't be detected from the file
+    #[structopt(long = "epsg")]
+    pub epsg: Option<usize>,
+
     /// Output (serialized ExtraShapes) to write
     #[structopt(long = "output")]
     pub output: String,
@@ -16,10 +20,11 @@ struct Flags {
 fn main() {
     let flags = Flags::from_args();
 
-    let shapes = kml::load(
+    let shapes = kml::load_any(
         &flags.input,
         &GPSBounds::seattle_bounds(),
-        &mut abstutil::Timer::new("extracting shapes from KML"),
+        flags.epsg,
+        &mut abstutil::Timer::new("extracting shapes"),
     )
     .unwrap();
 
@@ -0,0 +1,166 @@
+use geom::LonLat;
+use std::f64::consts::PI;
+
+const US_SURVEY_FOOT: f64 = 1200.0 / 3937.0;
+// GRS80, which NAD83 uses
+const SEMI_MAJOR_AXIS: f64 = 6_378_137.0;
+const FLATTENING: f64 = 1.0 / 298.257_222_101;
+
+// A state plane zone using the Lambert conformal conic projection. NAD83 and WGS84 differ by about
+// a meter, which doesn't matter for overlaying shapes, so the datum shift is skipped.
+#[derive(Clone, Debug)]
+pub struct StatePlane {
+    pub name: &'static str,
+    // Standard parallels, latitude of origin, and central meridian, all in degrees
+    lat1: f64,
+    lat2: f64,
+    lat0: f64,
+    lon0: f64,
+    // In meters
+    false_easting: f64,
+    false_northing: f64,
+    // Multiply coordinates by this to get meters
+    units: f64,
+}
+
+const WA_NORTH: StatePlane = StatePlane {
+    name: "NAD83 / Washington North",
+    lat1: 47.5,
+    lat2: 48.0 + 44.0 / 60.0,
+    lat0: 47.0,
+    lon0: -(120.0 + 50.0 / 60.0),
+    false_easting: 500_000.0,
+    false_northing: 0.0,
+    units: 1.0,
+};
+
+const WA_SOUTH: StatePlane = StatePlane {
+    name: "NAD83 / Washington South",
+    lat1: 45.0 + 50.0 / 60.0,
+    lat2: 47.0 + 20.0 / 60.0,
+    lat0: 45.0 + 20.0 / 60.0,
+    lon0: -120.5,
+    false_easting: 500_000.0,
+    false_northing: 0.0,
+    units: 1.0,
+};
+
+const OR_NORTH: StatePlane = StatePlane {
+    name: "NAD83 / Oregon North",
+    lat1: 44.0 + 20.0 / 60.0,
+    lat2: 46.0,
+    lat0: 43.0 + 40.0 / 60.0,
+    lon0: -120.5,
+    false_easting: 2_500_000.0,
+    false_northing: 0.0,
+    units: 1.0,
+};
+
+const CA_III: StatePlane = StatePlane {
+    name: "NAD83 / California zone 3",
+    lat1: 37.0 + 4.0 / 60.0,
+    lat2: 38.0 + 26.0 / 60.0,
+    lat0: 36.5,
+    lon0: -120.5,
+    false_easting: 2_000_000.0,
+    false_northing: 500_000.0,
+    units: 1.0,
+};
+
+impl StatePlane {
+    // Only a handful of zones are supported; add more here as needed.
+    pub fn from_epsg(code: usize) -> Option<StatePlane> {
+        match code {
+            32148 => Some(WA_NORTH),
+            2285 | 2926 => Some(WA_NORTH.in_feet("NAD83 / Washington North (ftUS)")),
+            32149 => Some(WA_SOUTH),
+            2286 | 2927 => Some(WA_SOUTH.in_feet("NAD83 / Washington South (ftUS)")),
+            32126 => Some(OR_NORTH),
+            2269 | 2913 => Some(OR_NORTH.in_feet("NAD83 / Oregon North (ft)")),
+            26943 => Some(CA_III),
+            2227 => Some(CA_III.in_feet("NAD83 / California zone 3 (ftUS)")),
+            _ => None,
+        }
+    }
+
+    // Recognizes the PROJCS names in .prj files and GeoJSON CRS names written by common tools.
+    // Returns None for WGS84 and anything unrecognized; the caller decides which is which.
+    pub fn from_name(name: &str) -> Option<StatePlane> {
+        if let Some(code) = parse_epsg(name) {
+            return StatePlane::from_epsg(code);
+        }
+        let name = name.to_lowercase().replace(' ', "_");
+        let feet = name.contains("feet") || name.contains("ft");
+        let zone = if name.contains("washington_north") {
+            WA_NORTH
+        } else if name.contains("washington_south") {
+            WA_SOUTH
+        } else if name.contains("oregon_north") {
+            OR_NORTH
+        } else if name.contains("california_iii") || name.contains("california_zone_3") {
+            CA_III
+        } else {
+            return None;
+        };
+        if feet {
+            Some(zone.in_feet(zone.name))
+        } else {
+            Some(zone)
+        }
+    }
+
+    fn in_feet(self, name: &'static str) -> StatePlane {
+        StatePlane {
+            name,
+            units: US_SURVEY_FOOT,
+            ..self
+        }
+    }
+
+    // The inverse projection from Snyder's "Map Projections: A Working Manual", equations 15-9
+    // through 15-11 and 7-9.
+    pub fn to_gps(&self, x: f64, y: f64) -> LonLat {
+        let e = (2.0 * FLATTENING - FLATTENING * FLATTENING).sqrt();
+        let m = |lat: f64| lat.cos() / (1.0 - e * e * lat.sin().powi(2)).sqrt();
+        let t = |lat: f64| {
+            (PI / 4.0 - lat / 2.0).tan()
+                / ((1.0 - e * lat.sin()) / (1.0 + e * lat.sin())).powf(e / 2.0)
+        };
+
+        let lat1 = self.lat1.to_radians();
+        let lat2 = self.lat2.to_radians();
+        let n = (m(lat1).ln() - m(lat2).ln()) / (t(lat1).ln() - t(lat2).ln());
+        let f = m(lat1) / (n * t(lat1).powf(n));
+        let rho0 = SEMI_MAJOR_AXIS * f * t(self.lat0.to_radians()).powf(n);
+
+        let x = x * self.units - self.false_easting;
+        let y = y * self.units - self.false_northing;
+        let rho = n.signum() * (x * x + (rho0 - y).powi(2)).sqrt();
+        let theta = (n.signum() * x).atan2(n.signum() * (rho0 - y));
+        let t_prime = (rho / (SEMI_MAJOR_AXIS * f)).powf(1.0 / n);
+
+        let lon = theta / n + self.lon0.to_radians();
+        let mut lat = PI / 2.0 - 2.0 * t_prime.atan();
+        for _ in 0..10 {
+            let next = PI / 2.0
+                - 2.0
+                    * (t_prime * ((1.0 - e * lat.sin()) / (1.0 + e * lat.sin())).powf(e / 2.0))
+                        .atan();
+            let done = (next - lat).abs() < 1e-12;
+            lat = next;
+            if done {
+                break;
+            }
+        }
+        LonLat::new(lon.to_degrees(), lat.to_degrees())
+    }
+}
+
+// Handles "EPSG:2926", "urn:ogc:def:crs:EPSG::2926", and friends
+fn parse_epsg(name: &str) -> Option<usize> {
+    let idx = name.to_uppercase().find("EPSG:")?;
+    name[idx + "EPSG:".len()..]
+        .trim_start_matches(':')
+        .parse::<usize>()
+        .ok()
+}
@@ -0,0 +1,232 @@
+use crate::{ExtraShapes, ShapeBuilder};
+use abstutil::Timer;
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::path::Path;
+use std::{fs, io};
+
+// A Shapefile is really a few files side-by-side: .shp has geometry, .dbf has attributes in the
+// same order, and .prj optionally describes the CRS. The formats are described in
+// https://www.esri.com/library/whitepapers/pdfs/shapefile.pdf and
+// http://www.dbase.com/Knowledgebase/INT/db7_file_fmt.htm.
+pub(crate) fn load(
+    path: &str,
+    builder: &mut ShapeBuilder,
+    timer: &mut Timer,
+) -> Result<ExtraShapes, io::Error> {
+    println!("Opening {}", path);
+    let geometry = read_shp(&fs::read(path)?)?;
+    let dbf_path = Path::new(path).with_extension("dbf");
+    let records = if dbf_path.exists() {
+        read_dbf(&fs::read(dbf_path)?)?
+    } else {
+        timer.warn(format!(
+            "{} has no .dbf file, so there are no attributes",
+            path
+        ));
+        Vec::new()
+    };
+    let prj_path = Path::new(path).with_extension("prj");
+    if builder.projection.is_none() && prj_path.exists() {
+        builder.projection = crate::parse_crs(fs::read_to_string(prj_path)?.trim())?;
+    }
+
+    let mut shapes = Vec::new();
+    timer.start_iter("convert shapefile records", geometry.len());
+    for (idx, parts) in geometry.into_iter().enumerate() {
+        timer.next();
+        let attributes = records.get(idx).cloned().unwrap_or_else(BTreeMap::new);
+        for pts in parts {
+            if let Some(shape) = builder.build(pts, attributes.clone()) {
+                shapes.push(shape);
+            }
+        }
+    }
+
+    println!(
+        "Got {} shapes from {} and skipped {} shapes",
+        shapes.len(),
+        path,
+        builder.skipped_count
+    );
+    Ok(ExtraShapes { shapes })
+}
+
+fn bad_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+struct Bytes<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Bytes<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], io::Error> {
+        if self.pos + n > self.data.len() {
+            return Err(bad_data("Shapefile ended early"));
+        }
+        let slice = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(slice)
+    }
+
+    fn i32_be(&mut self) -> Result<i32, io::Error> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i32_le(&mut self) -> Result<i32, io::Error> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn f64_le(&mut self) -> Result<f64, io::Error> {
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn count(&mut self) -> Result<usize, io::Error> {
+        let n = self.i32_le()?;
+        if n < 0 {
+            return Err(bad_data("Negative count in shapefile"));
+        }
+        Ok(n as usize)
+    }
+}
+
+// For every record, returns the parts, each a list of raw (x, y) points. The Z and M variants of
+// each shape type are read the same way, ignoring the extra values.
+fn read_shp(data: &[u8]) -> Result<Vec<Vec<Vec<(f64, f64)>>>, io::Error> {
+    let mut bytes = Bytes { data, pos: 0 };
+    if bytes.i32_be()? != 9994 {
+        return Err(bad_data("Not a shapefile"));
+    }
+    bytes.pos = 100;
+
+    let mut records = Vec::new();
+    while bytes.pos < data.len() {
+        let _record_number = bytes.i32_be()?;
+        // In 16-bit words
+        let length = bytes.i32_be()?;
+        if length < 0 || bytes.pos + 2 * (length as usize) > data.len() {
+            return Err(bad_data("Bad record length in shapefile"));
+        }
+        let end = bytes.pos + 2 * (length as usize);
+
+        let mut parts = Vec::new();
+        let shape_type = bytes.i32_le()?;
+        match shape_type {
+            // Null
+            0 => {}
+            // Point, PointZ, PointM
+            1 | 11 | 21 => {
+                parts.push(vec![(bytes.f64_le()?, bytes.f64_le()?)]);
+            }
+            // MultiPoint
+            8 | 18 | 28 => {
+                bytes.take(32)?;
+                let num_pts = bytes.count()?;
+                for _ in 0..num_pts {
+                    parts.push(vec![(bytes.f64_le()?, bytes.f64_le()?)]);
+                }
+            }
+            // PolyLine and Polygon
+            3 | 13 | 23 | 5 | 15 | 25 => {
+                bytes.take(32)?;
+                let num_parts = bytes.count()?;
+                let num_pts = bytes.count()?;
+                let mut starts = Vec::new();
+                for _ in 0..num_parts {
+                    starts.push(bytes.count()?);
+                }
+                starts.push(num_pts);
+                let mut pts = Vec::new();
+                for _ in 0..num_pts {
+                    pts.push((bytes.f64_le()?, bytes.f64_le()?));
+                }
+                for pair in starts.windows(2) {
+                    if pair[0] <= pair[1] && pair[1] <= pts.len() {
+                        parts.push(pts[pair[0]..pair[1]].to_vec());
+                    }
+                }
+                // Like GeoJSON, only keep the outer rings of polygons. Those go clockwise, and
+                // holes counter-clockwise. If nothing's clockwise, the writer ignored that, so
+                // fall back to the first ring.
+                if shape_type % 10 == 5 {
+                    let outer: Vec<Vec<(f64, f64)>> = parts
+                        .iter()
+                        .filter(|ring| signed_area(ring) < 0.0)
+                        .cloned()
+                        .collect();
+                    if outer.is_empty() {
+                        parts.truncate(1);
+                    } else {
+                        parts = outer;
+                    }
+                }
+            }
+            x => {
+                return Err(bad_data(&format!("Unsupported shape type {}", x)));
+            }
+        }
+        records.push(parts);
+        bytes.pos = end;
+    }
+    Ok(records)
+}
+
+// Positive for counter-clockwise rings
+fn signed_area(ring: &[(f64, f64)]) -> f64 {
+    let mut sum = 0.0;
+    for pair in ring.windows(2) {
+        sum += pair[0].0 * pair[1].1 - pair[1].0 * pair[0].1;
+    }
+    sum / 2.0
+}
+
+fn read_dbf(data: &[u8]) -> Result<Vec<BTreeMap<String, String>>, io::Error> {
+    if data.len() < 32 {
+        return Err(bad_data("dbf file too short"));
+    }
+    let num_records = u32::from_le_bytes(data[4..8].try_into().unwrap()) as usize;
+    let header_length = u16::from_le_bytes(data[8..10].try_into().unwrap()) as usize;
+    let record_length = u16::from_le_bytes(data[10..12].try_into().unwrap()) as usize;
+
+    // (name, width)
+    let mut fields: Vec<(String, usize)> = Vec::new();
+    let mut pos = 32;
+    while pos + 32 <= header_length && data[pos] != 0x0D {
+        let raw_name = &data[pos..pos + 11];
+        let name_len = raw_name.iter().position(|b| *b == 0).unwrap_or(11);
+        let name = String::from_utf8_lossy(&raw_name[..name_len])
+            .trim()
+            .to_string();
+        fields.push((name, data[pos + 16] as usize));
+        pos += 32;
+    }
+
+    let mut records = Vec::new();
+    for idx in 0..num_records {
+        let start = header_length + idx * record_length;
+        if start + record_length > data.len() {
+            return Err(bad_data("dbf file ended early"));
+        }
+        let record = &data[start..start + record_length];
+        let mut attributes = BTreeMap::new();
+        // The first byte marks deleted records, but those still have geometry in the .shp, so
+        // keep them to stay aligned.
+        let mut offset = 1;
+        for (name, width) in &fields {
+            if offset + width > record.len() {
+                break;
+            }
+            let value = String::from_utf8_lossy(&record[offset..offset + width])
+                .trim()
+                .to_string();
+            if !value.is_empty() {
+                attributes.insert(name.clone(), value);
+            }
+            offset += width;
+        }
+        records.push(attributes);
+    }
+    Ok(records)
+}
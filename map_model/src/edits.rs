@@ -1,15 +1,123 @@
-use crate::{ControlStopSign, ControlTrafficSignal, IntersectionID, LaneID, LaneType};
+use crate::{
+    ControlStopSign, ControlTrafficSignal, IntersectionID, IntersectionType, LaneID, LaneType, Map,
+    RoadID, TurnID, TurnType,
+};
+use abstutil::{deserialize_btreemap, serialize_btreemap};
+use geom::{Distance, Duration, Speed};
 use serde_derive::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MapEdits {
     pub(crate) map_name: String,
     pub edits_name: String,
     pub lane_overrides: BTreeMap<LaneID, LaneType>,
+    // What movements vehicles can make from the end of a lane, replacing OSM turn:lanes
+    #[serde(default)]
+    pub allowed_turns_overrides: BTreeMap<LaneID, BTreeSet<TurnType>>,
+    #[serde(default)]
+    pub lane_width_overrides: BTreeMap<LaneID, Distance>,
+    // Adds, removes, or reorders the lanes of a road. Applied before the per-lane overrides.
+    #[serde(default)]
+    pub cross_section_overrides: BTreeMap<RoadID, CrossSection>,
+    // Applies to both directions of the road
+    #[serde(default)]
+    pub speed_limit_overrides: BTreeMap<RoadID, Speed>,
     // TODO Storing the entire thing is maybe a bit dramatic, but works for now.
     pub stop_sign_overrides: BTreeMap<IntersectionID, ControlStopSign>,
     pub traffic_signal_overrides: BTreeMap<IntersectionID, ControlTrafficSignal>,
+    // Converting between stop signs, signals, etc. Borders can't be changed.
+    #[serde(default)]
+    pub intersection_type_overrides: BTreeMap<IntersectionID, IntersectionType>,
+    // Wins over the stop sign or traffic signal, and Allowed also lifts OSM turn restrictions
+    #[serde(
+        default,
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    pub turn_overrides: BTreeMap<TurnID, TurnOverride>,
+    // Layered on top of everything else, only while the time of day is inside their window
+    #[serde(default)]
+    pub scheduled: Vec<ScheduledEdits>,
+    // A road closure applies to all of its lanes
+    #[serde(default)]
+    pub lane_closures: BTreeMap<LaneID, Closure>,
+    #[serde(default)]
+    pub road_closures: BTreeMap<RoadID, Closure>,
+    #[serde(default)]
+    pub intersection_closures: BTreeMap<IntersectionID, Closure>,
+    // Who made each edit and why. Not every edit has this.
+    #[serde(
+        default,
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    pub provenance: BTreeMap<EditID, EditProvenance>,
+}
+
+// Like peak-hour bus lanes or an evening signal plan. Active from start (inclusive) to end
+// (exclusive), measured from midnight.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScheduledEdits {
+    pub start: Duration,
+    pub end: Duration,
+    pub lane_overrides: BTreeMap<LaneID, LaneType>,
+    pub traffic_signal_overrides: BTreeMap<IntersectionID, ControlTrafficSignal>,
+}
+
+impl ScheduledEdits {
+    pub fn is_active(&self, time: Duration) -> bool {
+        time >= self.start && time < self.end
+    }
+}
+
+// Every lane of a road and its width, from the center of the road outwards, for each direction.
+// Sidewalks can be moved and widened, but not added or removed, since buildings connect to them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CrossSection {
+    pub fwd: Vec<(LaneType, Distance)>,
+    pub back: Vec<(LaneType, Distance)>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum TurnOverride {
+    Banned,
+    Allowed,
+}
+
+// Keeps some modes out of a lane, road, or intersection, like for construction.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Closure {
+    pub cars: bool,
+    pub bikes: bool,
+    pub buses: bool,
+    pub pedestrians: bool,
+}
+
+impl Closure {
+    pub fn everyone() -> Closure {
+        Closure {
+            cars: true,
+            bikes: true,
+            buses: true,
+            pedestrians: true,
+        }
+    }
+
+    // lane_types are what the agent may use, like in VehiclePathfinder. Pedestrians use
+    // sidewalks.
+    pub fn applies_to(&self, lane_types: &[LaneType]) -> bool {
+        if lane_types.contains(&LaneType::Sidewalk) {
+            self.pedestrians
+        } else if lane_types.contains(&LaneType::Biking) {
+            self.bikes
+        } else if lane_types.contains(&LaneType::Bus) {
+            self.buses
+        } else {
+            self.cars
+        }
+    }
 }
 
 impl MapEdits {
@@ -19,8 +127,19 @@ impl MapEdits {
             // Something has to fill this out later
             edits_name: "no_edits".to_string(),
             lane_overrides: BTreeMap::new(),
+            allowed_turns_overrides: BTreeMap::new(),
+            lane_width_overrides: BTreeMap::new(),
+            cross_section_overrides: BTreeMap::new(),
+            speed_limit_overrides: BTreeMap::new(),
             stop_sign_overrides: BTreeMap::new(),
             traffic_signal_overrides: BTreeMap::new(),
+            intersection_type_overrides: BTreeMap::new(),
+            turn_overrides: BTreeMap::new(),
+            scheduled: Vec::new(),
+            lane_closures: BTreeMap::new(),
+            road_closures: BTreeMap::new(),
+            intersection_closures: BTreeMap::new(),
+            provenance: BTreeMap::new(),
         }
     }
 
@@ -34,4 +153,609 @@ impl MapEdits {
     pub fn save(&self) {
         abstutil::save_json_object(abstutil::EDITS, &self.map_name, &self.edits_name, self);
     }
+
+    // The overrides that actually hold at some time of day.
+    pub fn active_at(&self, time: Duration) -> MapEdits {
+        let mut edits = self.clone();
+        for window in &self.scheduled {
+            if window.is_active(time) {
+                edits.lane_overrides.extend(window.lane_overrides.clone());
+                edits
+                    .traffic_signal_overrides
+                    .extend(window.traffic_signal_overrides.clone());
+            }
+        }
+        edits
+    }
+
+    // When does the next scheduled window start or end, strictly after this time?
+    pub fn next_scheduled_change(&self, after: Duration) -> Option<Duration> {
+        self.scheduled
+            .iter()
+            .flat_map(|w| vec![w.start, w.end])
+            .filter(|t| *t > after)
+            .min()
+    }
+
+    pub fn same_scheduled_edits_active(&self, t1: Duration, t2: Duration) -> bool {
+        self.scheduled
+            .iter()
+            .all(|w| w.is_active(t1) == w.is_active(t2))
+    }
+
+    // A copy referring to the lanes that these cross-sections would produce, instead of the ones
+    // its own cross-sections produce
+    fn with_lanes_for(
+        &self,
+        map: &Map,
+        cross_sections: &BTreeMap<RoadID, CrossSection>,
+    ) -> MapEdits {
+        let mut copy = self.clone();
+        copy.renumber_lanes(
+            &map.added_lane_renumbering(&self.cross_section_overrides, cross_sections),
+        );
+        copy
+    }
+
+    // Points every edit of a lane at its new ID, or drops the edit if the lane has no new ID.
+    // Lanes that aren't mentioned keep their ID.
+    pub(crate) fn renumber_lanes(&mut self, renumber: &BTreeMap<LaneID, Option<LaneID>>) {
+        let lane = |l: LaneID| renumber.get(&l).cloned().unwrap_or(Some(l));
+        let turn = |t: TurnID| {
+            Some(TurnID {
+                parent: t.parent,
+                src: lane(t.src)?,
+                dst: lane(t.dst)?,
+            })
+        };
+        let renumber_signal = |ts: &mut ControlTrafficSignal| {
+            for cycle in &mut ts.cycles {
+                cycle.priority_turns = cycle
+                    .priority_turns
+                    .iter()
+                    .filter_map(|t| turn(*t))
+                    .collect();
+                cycle.yield_turns = cycle.yield_turns.iter().filter_map(|t| turn(*t)).collect();
+            }
+        };
+
+        self.lane_overrides = renumber_keys(&self.lane_overrides, lane);
+        self.allowed_turns_overrides = renumber_keys(&self.allowed_turns_overrides, lane);
+        self.lane_width_overrides = renumber_keys(&self.lane_width_overrides, lane);
+        self.lane_closures = renumber_keys(&self.lane_closures, lane);
+        self.turn_overrides = renumber_keys(&self.turn_overrides, turn);
+        for ss in self.stop_sign_overrides.values_mut() {
+            ss.turns = renumber_keys(&ss.turns, turn);
+            for road in ss.roads.values_mut() {
+                road.travel_lanes = road.travel_lanes.iter().filter_map(|l| lane(*l)).collect();
+            }
+        }
+        for ts in self.traffic_signal_overrides.values_mut() {
+            renumber_signal(ts);
+        }
+        for window in &mut self.scheduled {
+            window.lane_overrides = renumber_keys(&window.lane_overrides, lane);
+            for ts in window.traffic_signal_overrides.values_mut() {
+                renumber_signal(ts);
+            }
+        }
+        self.provenance = renumber_keys(&self.provenance, |id| match id {
+            EditID::LaneType(l) => lane(l).map(EditID::LaneType),
+            EditID::AllowedTurns(l) => lane(l).map(EditID::AllowedTurns),
+            EditID::LaneWidth(l) => lane(l).map(EditID::LaneWidth),
+            EditID::LaneClosure(l) => lane(l).map(EditID::LaneClosure),
+            EditID::Turn(t) => turn(t).map(EditID::Turn),
+            _ => Some(id),
+        });
+    }
+}
+
+// Names one edit, for diffing, merging, and provenance. Scheduled windows are compared whole
+// instead.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EditID {
+    LaneType(LaneID),
+    AllowedTurns(LaneID),
+    LaneWidth(LaneID),
+    CrossSection(RoadID),
+    SpeedLimit(RoadID),
+    StopSign(IntersectionID),
+    TrafficSignal(IntersectionID),
+    IntersectionType(IntersectionID),
+    Turn(TurnID),
+    LaneClosure(LaneID),
+    RoadClosure(RoadID),
+    IntersectionClosure(IntersectionID),
+}
+
+impl fmt::Display for EditID {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EditID::LaneType(l) => write!(f, "lane type of {}", l),
+            EditID::AllowedTurns(l) => write!(f, "allowed turns from {}", l),
+            EditID::LaneWidth(l) => write!(f, "width of {}", l),
+            EditID::CrossSection(r) => write!(f, "lanes of {}", r),
+            EditID::SpeedLimit(r) => write!(f, "speed limit of {}", r),
+            EditID::StopSign(i) => write!(f, "stop sign at {}", i),
+            EditID::TrafficSignal(i) => write!(f, "traffic signal at {}", i),
+            EditID::IntersectionType(i) => write!(f, "type of {}", i),
+            EditID::Turn(t) => write!(f, "{}", t),
+            EditID::LaneClosure(l) => write!(f, "closure of {}", l),
+            EditID::RoadClosure(r) => write!(f, "closure of {}", r),
+            EditID::IntersectionClosure(i) => write!(f, "closure of {}", i),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EditProvenance {
+    pub author: String,
+    // Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub rationale: String,
+}
+
+impl EditProvenance {
+    pub fn now(author: String, rationale: String) -> EditProvenance {
+        EditProvenance {
+            author,
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            rationale,
+        }
+    }
+}
+
+impl fmt::Display for EditProvenance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (at {})", self.author, self.timestamp)?;
+        if !self.rationale.is_empty() {
+            write!(f, ": {}", self.rationale)?;
+        }
+        Ok(())
+    }
+}
+
+// How to get from one set of edits to another
+#[derive(Debug)]
+pub struct EditsDiff {
+    pub added: Vec<EditID>,
+    pub removed: Vec<EditID>,
+    pub changed: Vec<EditID>,
+    pub scheduled_added: Vec<ScheduledEdits>,
+    pub scheduled_removed: Vec<ScheduledEdits>,
+}
+
+impl EditsDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.scheduled_added.is_empty()
+            && self.scheduled_removed.is_empty()
+    }
+
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for id in &self.added {
+            lines.push(format!("+ {}", id));
+        }
+        for id in &self.removed {
+            lines.push(format!("- {}", id));
+        }
+        for id in &self.changed {
+            lines.push(format!("~ {}", id));
+        }
+        for w in &self.scheduled_added {
+            lines.push(format!("+ edits scheduled from {} to {}", w.start, w.end));
+        }
+        for w in &self.scheduled_removed {
+            lines.push(format!("- edits scheduled from {} to {}", w.start, w.end));
+        }
+        lines
+    }
+}
+
+// Both sides changed the same edit differently. The merge keeps ours.
+#[derive(Debug)]
+pub struct MergeConflict {
+    pub id: EditID,
+    pub ours: Option<EditProvenance>,
+    pub theirs: Option<EditProvenance>,
+}
+
+impl MergeConflict {
+    pub fn describe(&self) -> String {
+        let who = |p: &Option<EditProvenance>| match p {
+            Some(p) => p.to_string(),
+            None => "unknown".to_string(),
+        };
+        format!(
+            "{} changed by both {} and {}",
+            self.id,
+            who(&self.ours),
+            who(&self.theirs)
+        )
+    }
+}
+
+impl MapEdits {
+    // What would have to happen to self to get other. Lanes are referred to by the IDs they have
+    // in other.
+    pub fn diff(&self, other: &MapEdits, map: &Map) -> EditsDiff {
+        let before = &self.with_lanes_for(map, &other.cross_section_overrides);
+        let after = other;
+        let mut diff = EditsDiff {
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+            scheduled_added: after
+                .scheduled
+                .iter()
+                .filter(|w| !before.scheduled.contains(w))
+                .cloned()
+                .collect(),
+            scheduled_removed: before
+                .scheduled
+                .iter()
+                .filter(|w| !after.scheduled.contains(w))
+                .cloned()
+                .collect(),
+        };
+        diff_field(
+            &before.lane_overrides,
+            &after.lane_overrides,
+            EditID::LaneType,
+            &mut diff,
+        );
+        diff_field(
+            &before.allowed_turns_overrides,
+            &after.allowed_turns_overrides,
+            EditID::AllowedTurns,
+            &mut diff,
+        );
+        diff_field(
+            &before.lane_width_overrides,
+            &after.lane_width_overrides,
+            EditID::LaneWidth,
+            &mut diff,
+        );
+        diff_field(
+            &before.cross_section_overrides,
+            &after.cross_section_overrides,
+            EditID::CrossSection,
+            &mut diff,
+        );
+        diff_field(
+            &before.speed_limit_overrides,
+            &after.speed_limit_overrides,
+            EditID::SpeedLimit,
+            &mut diff,
+        );
+        diff_field(
+            &before.stop_sign_overrides,
+            &after.stop_sign_overrides,
+            EditID::StopSign,
+            &mut diff,
+        );
+        diff_field(
+            &before.traffic_signal_overrides,
+            &after.traffic_signal_overrides,
+            EditID::TrafficSignal,
+            &mut diff,
+        );
+        diff_field(
+            &before.intersection_type_overrides,
+            &after.intersection_type_overrides,
+            EditID::IntersectionType,
+            &mut diff,
+        );
+        diff_field(
+            &before.turn_overrides,
+            &after.turn_overrides,
+            EditID::Turn,
+            &mut diff,
+        );
+        diff_field(
+            &before.lane_closures,
+            &after.lane_closures,
+            EditID::LaneClosure,
+            &mut diff,
+        );
+        diff_field(
+            &before.road_closures,
+            &after.road_closures,
+            EditID::RoadClosure,
+            &mut diff,
+        );
+        diff_field(
+            &before.intersection_closures,
+            &after.intersection_closures,
+            EditID::IntersectionClosure,
+            &mut diff,
+        );
+        diff
+    }
+
+    pub fn contains(&self, id: EditID) -> bool {
+        match id {
+            EditID::LaneType(l) => self.lane_overrides.contains_key(&l),
+            EditID::AllowedTurns(l) => self.allowed_turns_overrides.contains_key(&l),
+            EditID::LaneWidth(l) => self.lane_width_overrides.contains_key(&l),
+            EditID::CrossSection(r) => self.cross_section_overrides.contains_key(&r),
+            EditID::SpeedLimit(r) => self.speed_limit_overrides.contains_key(&r),
+            EditID::StopSign(i) => self.stop_sign_overrides.contains_key(&i),
+            EditID::TrafficSignal(i) => self.traffic_signal_overrides.contains_key(&i),
+            EditID::IntersectionType(i) => self.intersection_type_overrides.contains_key(&i),
+            EditID::Turn(t) => self.turn_overrides.contains_key(&t),
+            EditID::LaneClosure(l) => self.lane_closures.contains_key(&l),
+            EditID::RoadClosure(r) => self.road_closures.contains_key(&r),
+            EditID::IntersectionClosure(i) => self.intersection_closures.contains_key(&i),
+        }
+    }
+
+    // Credits everything that changed since before to this author, except for edits that already
+    // carry someone else's credit, like ones merged in from another set of edits.
+    pub fn record_provenance(
+        &mut self,
+        before: &MapEdits,
+        author: &str,
+        rationale: &str,
+        map: &Map,
+    ) {
+        let before = &before.with_lanes_for(map, &self.cross_section_overrides);
+        let diff = before.diff(self, map);
+        for id in diff.added.into_iter().chain(diff.changed.into_iter()) {
+            let current = self.provenance.get(&id);
+            if current.is_some() && current != before.provenance.get(&id) {
+                continue;
+            }
+            self.provenance.insert(
+                id,
+                EditProvenance::now(author.to_string(), rationale.to_string()),
+            );
+        }
+        for id in diff.removed {
+            self.provenance.remove(&id);
+        }
+    }
+
+    // Combines two sets of edits that both started from base. Changes only one side made are
+    // kept. When both sides changed the same edit differently, ours wins and the conflict is
+    // returned. Edits touching different things can still clash -- like theirs changing a lane
+    // that ours removed -- but Map::apply_edits warns about those.
+    pub fn merge(
+        base: &MapEdits,
+        ours: &MapEdits,
+        theirs: &MapEdits,
+        map: &Map,
+    ) -> (MapEdits, Vec<MergeConflict>) {
+        assert_eq!(ours.map_name, theirs.map_name);
+        // Lanes added by cross-section edits are numbered by the cross-sections, so the same
+        // LaneID can mean a different lane on each side. Line everything up with the merged
+        // cross-sections before comparing edits keyed by lane.
+        let mut cross_sections = ours.cross_section_overrides.clone();
+        merge_field(
+            &base.cross_section_overrides,
+            &ours.cross_section_overrides,
+            &theirs.cross_section_overrides,
+            &mut cross_sections,
+            EditID::CrossSection,
+            &mut Vec::new(),
+            &mut Vec::new(),
+        );
+        let base = &base.with_lanes_for(map, &cross_sections);
+        let ours = &ours.with_lanes_for(map, &cross_sections);
+        let theirs = &theirs.with_lanes_for(map, &cross_sections);
+
+        let mut merged = ours.clone();
+        let mut from_theirs = Vec::new();
+        let mut conflicts = Vec::new();
+
+        merge_field(
+            &base.lane_overrides,
+            &ours.lane_overrides,
+            &theirs.lane_overrides,
+            &mut merged.lane_overrides,
+            EditID::LaneType,
+            &mut from_theirs,
+            &mut conflicts,
+        );
+        merge_field(
+            &base.allowed_turns_overrides,
+            &ours.allowed_turns_overrides,
+            &theirs.allowed_turns_overrides,
+            &mut merged.allowed_turns_overrides,
+            EditID::AllowedTurns,
+            &mut from_theirs,
+            &mut conflicts,
+        );
+        merge_field(
+            &base.lane_width_overrides,
+            &ours.lane_width_overrides,
+            &theirs.lane_width_overrides,
+            &mut merged.lane_width_overrides,
+            EditID::LaneWidth,
+            &mut from_theirs,
+            &mut conflicts,
+        );
+        merge_field(
+            &base.cross_section_overrides,
+            &ours.cross_section_overrides,
+            &theirs.cross_section_overrides,
+            &mut merged.cross_section_overrides,
+            EditID::CrossSection,
+            &mut from_theirs,
+            &mut conflicts,
+        );
+        merge_field(
+            &base.speed_limit_overrides,
+            &ours.speed_limit_overrides,
+            &theirs.speed_limit_overrides,
+            &mut merged.speed_limit_overrides,
+            EditID::SpeedLimit,
+            &mut from_theirs,
+            &mut conflicts,
+        );
+        merge_field(
+            &base.stop_sign_overrides,
+            &ours.stop_sign_overrides,
+            &theirs.stop_sign_overrides,
+            &mut merged.stop_sign_overrides,
+            EditID::StopSign,
+            &mut from_theirs,
+            &mut conflicts,
+        );
+        merge_field(
+            &base.traffic_signal_overrides,
+            &ours.traffic_signal_overrides,
+            &theirs.traffic_signal_overrides,
+            &mut merged.traffic_signal_overrides,
+            EditID::TrafficSignal,
+            &mut from_theirs,
+            &mut conflicts,
+        );
+        merge_field(
+            &base.intersection_type_overrides,
+            &ours.intersection_type_overrides,
+            &theirs.intersection_type_overrides,
+            &mut merged.intersection_type_overrides,
+            EditID::IntersectionType,
+            &mut from_theirs,
+            &mut conflicts,
+        );
+        merge_field(
+            &base.turn_overrides,
+            &ours.turn_overrides,
+            &theirs.turn_overrides,
+            &mut merged.turn_overrides,
+            EditID::Turn,
+            &mut from_theirs,
+            &mut conflicts,
+        );
+        merge_field(
+            &base.lane_closures,
+            &ours.lane_closures,
+            &theirs.lane_closures,
+            &mut merged.lane_closures,
+            EditID::LaneClosure,
+            &mut from_theirs,
+            &mut conflicts,
+        );
+        merge_field(
+            &base.road_closures,
+            &ours.road_closures,
+            &theirs.road_closures,
+            &mut merged.road_closures,
+            EditID::RoadClosure,
+            &mut from_theirs,
+            &mut conflicts,
+        );
+        merge_field(
+            &base.intersection_closures,
+            &ours.intersection_closures,
+            &theirs.intersection_closures,
+            &mut merged.intersection_closures,
+            EditID::IntersectionClosure,
+            &mut from_theirs,
+            &mut conflicts,
+        );
+
+        // Windows only overlap in time, so there's nothing to conflict.
+        merged.scheduled = ours
+            .scheduled
+            .iter()
+            .filter(|w| !base.scheduled.contains(w) || theirs.scheduled.contains(w))
+            .cloned()
+            .collect();
+        for w in &theirs.scheduled {
+            if !base.scheduled.contains(w) && !merged.scheduled.contains(w) {
+                merged.scheduled.push(w.clone());
+            }
+        }
+
+        for id in from_theirs {
+            merged.provenance.remove(&id);
+            if let Some(p) = theirs.provenance.get(&id) {
+                merged.provenance.insert(id, p.clone());
+            }
+        }
+        let conflicts = conflicts
+            .into_iter()
+            .map(|id| MergeConflict {
+                id,
+                ours: ours.provenance.get(&id).cloned(),
+                theirs: theirs.provenance.get(&id).cloned(),
+            })
+            .collect();
+        (merged, conflicts)
+    }
+}
+
+fn diff_field<K: Copy + Ord, V: PartialEq>(
+    before: &BTreeMap<K, V>,
+    after: &BTreeMap<K, V>,
+    id: fn(K) -> EditID,
+    diff: &mut EditsDiff,
+) {
+    for (k, v) in before {
+        match after.get(k) {
+            Some(v2) if v2 != v => diff.changed.push(id(*k)),
+            Some(_) => {}
+            None => diff.removed.push(id(*k)),
+        }
+    }
+    for k in after.keys() {
+        if !before.contains_key(k) {
+            diff.added.push(id(*k));
+        }
+    }
+}
+
+// merged starts as a copy of ours.
+fn merge_field<K: Copy + Ord, V: Clone + PartialEq>(
+    base: &BTreeMap<K, V>,
+    ours: &BTreeMap<K, V>,
+    theirs: &BTreeMap<K, V>,
+    merged: &mut BTreeMap<K, V>,
+    id: fn(K) -> EditID,
+    from_theirs: &mut Vec<EditID>,
+    conflicts: &mut Vec<EditID>,
+) {
+    let keys: BTreeSet<K> = base
+        .keys()
+        .chain(ours.keys())
+        .chain(theirs.keys())
+        .cloned()
+        .collect();
+    for k in keys {
+        let (b, o, t) = (base.get(&k), ours.get(&k), theirs.get(&k));
+        if o == t || t == b {
+            continue;
+        }
+        if o == b {
+            match t {
+                Some(v) => {
+                    merged.insert(k, v.clone());
+                }
+                None => {
+                    merged.remove(&k);
+                }
+            }
+            from_theirs.push(id(k));
+        } else {
+            conflicts.push(id(k));
+        }
+    }
+}
+
+fn renumber_keys<K: Ord + Copy, V: Clone, F: Fn(K) -> Option<K>>(
+    map: &BTreeMap<K, V>,
+    f: F,
+) -> BTreeMap<K, V> {
+    map.iter()
+        .filter_map(|(k, v)| Some((f(*k)?, v.clone())))
+        .collect()
 }
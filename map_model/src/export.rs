@@ -0,0 +1,149 @@
+use crate::{IntersectionID, LaneID, LaneType, Map, RoadID};
+use geom::{GPSBounds, Pt2D};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::Error;
+
+// Numeric properties to attach to features, like throughput from a simulation. The caller picks
+// the property names.
+#[derive(Default)]
+pub struct FeatureProperties {
+    pub roads: BTreeMap<RoadID, BTreeMap<String, f64>>,
+    pub lanes: BTreeMap<LaneID, BTreeMap<String, f64>>,
+    pub intersections: BTreeMap<IntersectionID, BTreeMap<String, f64>>,
+}
+
+impl Map {
+    // A FeatureCollection in WGS84. Every feature has a "kind" property (road, lane,
+    // intersection, building, bus_stop) to filter on.
+    pub fn to_geojson(&self, extra: &FeatureProperties) -> Value {
+        let gps = self.get_gps_bounds();
+        let mut features = Vec::new();
+
+        for r in self.all_roads() {
+            let lane_types = |children: &Vec<(LaneID, LaneType)>| -> Vec<String> {
+                children.iter().map(|(_, lt)| format!("{:?}", lt)).collect()
+            };
+            let mut props = json!({
+                "kind": "road",
+                "id": r.id.0,
+                "osm_way_id": r.osm_way_id,
+                "name": r.get_name(),
+                "lanes_forwards": lane_types(&r.children_forwards),
+                "lanes_backwards": lane_types(&r.children_backwards),
+            });
+            add_extra(&mut props, extra.roads.get(&r.id));
+            features.push(feature(linestring(r.center_pts.points(), gps), props));
+        }
+
+        for l in self.all_lanes() {
+            if !self.lane_exists(l.id) {
+                continue;
+            }
+            let mut props = json!({
+                "kind": "lane",
+                "id": l.id.0,
+                "road": l.parent.0,
+                "lane_type": format!("{:?}", l.lane_type),
+                "src_i": l.src_i.0,
+                "dst_i": l.dst_i.0,
+            });
+            add_extra(&mut props, extra.lanes.get(&l.id));
+            features.push(feature(linestring(l.lane_center_pts.points(), gps), props));
+        }
+
+        for i in self.all_intersections() {
+            let mut props = json!({
+                "kind": "intersection",
+                "id": i.id.0,
+                "intersection_type": format!("{:?}", i.intersection_type),
+                "label": i.label,
+            });
+            add_extra(&mut props, extra.intersections.get(&i.id));
+            features.push(feature(polygon(i.polygon.points(), gps), props));
+        }
+
+        for b in self.all_buildings() {
+            let props = json!({
+                "kind": "building",
+                "id": b.id.0,
+                "osm_way_id": b.osm_way_id,
+                "name": b.get_name(),
+            });
+            features.push(feature(polygon(b.polygon.points(), gps), props));
+        }
+
+        for (id, bs) in self.all_bus_stops() {
+            let routes: Vec<String> = self
+                .get_routes_serving_stop(*id)
+                .into_iter()
+                .map(|r| r.name.clone())
+                .collect();
+            let props = json!({
+                "kind": "bus_stop",
+                "id": id.to_string(),
+                "sidewalk": id.sidewalk.0,
+                "routes": routes,
+            });
+            let pt = gps.must_convert_back(&vec![bs.sidewalk_pos.pt(self)])[0];
+            features.push(feature(
+                json!({
+                    "type": "Point",
+                    "coordinates": [pt.longitude, pt.latitude],
+                }),
+                props,
+            ));
+        }
+
+        json!({
+            "type": "FeatureCollection",
+            "features": features,
+        })
+    }
+
+    pub fn export_geojson(&self, path: &str, extra: &FeatureProperties) -> Result<(), Error> {
+        abstutil::write_json(path, &self.to_geojson(extra))
+    }
+}
+
+fn feature(geometry: Value, properties: Value) -> Value {
+    json!({
+        "type": "Feature",
+        "geometry": geometry,
+        "properties": properties,
+    })
+}
+
+fn coordinates(pts: &Vec<Pt2D>, gps: &GPSBounds) -> Vec<[f64; 2]> {
+    gps.must_convert_back(pts)
+        .into_iter()
+        .map(|pt| [pt.longitude, pt.latitude])
+        .collect()
+}
+
+fn linestring(pts: &Vec<Pt2D>, gps: &GPSBounds) -> Value {
+    json!({
+        "type": "LineString",
+        "coordinates": coordinates(pts, gps),
+    })
+}
+
+fn polygon(pts: &Vec<Pt2D>, gps: &GPSBounds) -> Value {
+    let mut ring = coordinates(pts, gps);
+    // GeoJSON rings must be closed
+    if ring.first() != ring.last() {
+        ring.push(ring[0]);
+    }
+    json!({
+        "type": "Polygon",
+        "coordinates": [ring],
+    })
+}
+
+fn add_extra(props: &mut Value, extra: Option<&BTreeMap<String, f64>>) {
+    if let (Some(obj), Some(extra)) = (props.as_object_mut(), extra) {
+        for (k, v) in extra {
+            obj.insert(k.clone(), json!(v));
+        }
+    }
+}
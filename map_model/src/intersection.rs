@@ -20,6 +20,22 @@ pub enum IntersectionType {
     StopSign,
     TrafficSignal,
     Border,
+    // Nobody has a sign or a signal; everybody yields.
+    Uncontrolled,
+    // Entering traffic yields, including to pedestrians on the crosswalks.
+    Roundabout,
+}
+
+impl IntersectionType {
+    // These are all controlled by a ControlStopSign, even if nobody actually has to stop.
+    pub fn is_unsignalized(self) -> bool {
+        match self {
+            IntersectionType::StopSign
+            | IntersectionType::Uncontrolled
+            | IntersectionType::Roundabout => true,
+            IntersectionType::TrafficSignal | IntersectionType::Border => false,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -32,6 +48,8 @@ pub struct Intersection {
     pub intersection_type: IntersectionType,
     pub label: Option<String>,
     pub stable_id: raw_data::StableIntersectionID,
+    // Survives re-importing the map, unlike id and stable_id
+    pub orig_id: raw_data::OriginalIntersection,
 
     // Note that a lane may belong to both incoming_lanes and outgoing_lanes.
     // TODO narrow down when and why. is it just sidewalks in weird cases?
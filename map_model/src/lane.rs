@@ -1,8 +1,8 @@
-use crate::{BuildingID, BusStopID, DirectedRoadID, IntersectionID, Map, Road, RoadID, TurnType};
+use crate::{BuildingID, BusStopID, DirectedRoadID, IntersectionID, Map, RoadID, TurnType};
 use abstutil;
 use geom::{Angle, Distance, Line, PolyLine, Pt2D};
 use serde_derive::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 
 // Bit longer than the longest car.
@@ -37,6 +37,17 @@ impl LaneType {
             LaneType::Sidewalk => false,
         }
     }
+
+    // Used when OSM doesn't say otherwise.
+    pub fn default_width(self) -> Distance {
+        match self {
+            LaneType::Driving => Distance::meters(3.0),
+            LaneType::Bus => Distance::meters(3.25),
+            LaneType::Parking => Distance::meters(2.5),
+            LaneType::Biking => Distance::meters(1.75),
+            LaneType::Sidewalk => Distance::meters(2.0),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -45,6 +56,7 @@ pub struct Lane {
     pub parent: RoadID,
     pub lane_type: LaneType,
     pub lane_center_pts: PolyLine,
+    pub width: Distance,
 
     pub src_i: IntersectionID,
     pub dst_i: IntersectionID,
@@ -56,6 +68,10 @@ pub struct Lane {
     // If set, cars trying to park near here should actually start their search at this other lane.
     // Only populated for driving lanes inevitably leading to borders.
     pub parking_blackhole: Option<LaneID>,
+
+    // Which movements vehicles may make from the end of this lane, usually from OSM turn:lanes.
+    // None means to guess from the lane's position.
+    pub allowed_turns: Option<BTreeSet<TurnType>>,
 }
 
 impl Lane {
@@ -163,51 +179,86 @@ impl Lane {
             r.id.backwards()
         }
     }
+}
 
-    pub fn get_turn_restrictions(&self, road: &Road) -> Option<HashSet<TurnType>> {
-        if !self.is_driving() {
-            return None;
-        }
+// Interprets turn:lanes for one side of a road. lane_types go outwards from the center line, like
+// Road::children_forwards, and offset picks one of them. Bike lanes, parking, and sidewalks aren't
+// counted in turn:lanes.
+pub(crate) fn parse_turn_lanes(
+    tags: &BTreeMap<String, String>,
+    fwds: bool,
+    lane_types: &[LaneType],
+    offset: usize,
+) -> Option<BTreeSet<TurnType>> {
+    let is_vehicle_lane = |lt: &LaneType| *lt == LaneType::Driving || *lt == LaneType::Bus;
+    if !is_vehicle_lane(&lane_types[offset]) {
+        return None;
+    }
 
-        let (dir, offset) = road.dir_and_offset(self.id);
-        let all = if dir && road.osm_tags.contains_key("abst:endpt_fwd") {
-            road.osm_tags
-                .get("turn:lanes:forward")
-                .or_else(|| road.osm_tags.get("turn:lanes"))?
-        } else if !dir && road.osm_tags.contains_key("abst:endpt_back") {
-            road.osm_tags.get("turn:lanes:backward")?
-        } else {
-            return None;
-        };
-        let parts: Vec<&str> = all.split('|').collect();
-        // TODO Verify the number of lanes matches up
-        let part = parts.get(offset)?;
-        if part == &"none" {
-            return None;
+    // OSM ways get split, but turn:lanes only describes the end of the original way.
+    let all = if fwds && tags.contains_key("abst:endpt_fwd") {
+        tags.get("turn:lanes:forward")
+            .or_else(|| tags.get("turn:lanes"))?
+    } else if !fwds && tags.contains_key("abst:endpt_back") {
+        tags.get("turn:lanes:backward")?
+    } else {
+        return None;
+    };
+    let parts: Vec<&str> = all.split('|').collect();
+    // If the lanes don't match up, the tags are stale or we misparsed the lanes. Either way,
+    // guessing is better than trusting them.
+    if parts.len() != lane_types.iter().filter(|lt| is_vehicle_lane(lt)).count() {
+        return None;
+    }
+    let part = parts[lane_types[0..offset]
+        .iter()
+        .filter(|lt| is_vehicle_lane(lt))
+        .count()];
+    if part == "none" {
+        return None;
+    }
+
+    let mut turns = BTreeSet::new();
+    for movement in part.split(';') {
+        match movement.trim() {
+            "left" | "sharp_left" => {
+                turns.insert(TurnType::Left);
+            }
+            "right" | "sharp_right" => {
+                turns.insert(TurnType::Right);
+            }
+            // TODO What is blank supposed to mean? From few observed cases, same as through
+            "through" | "" | "none" => {
+                turns.extend(vec![
+                    TurnType::Straight,
+                    TurnType::LaneChangeLeft,
+                    TurnType::LaneChangeRight,
+                ]);
+            }
+            // TODO Check this more carefully
+            "slight_right" | "slight right" | "merge_to_right" => {
+                turns.extend(vec![
+                    TurnType::Straight,
+                    TurnType::LaneChangeRight,
+                    TurnType::Right,
+                ]);
+            }
+            "slight_left" | "slight left" | "merge_to_left" => {
+                turns.extend(vec![
+                    TurnType::Straight,
+                    TurnType::LaneChangeLeft,
+                    TurnType::Left,
+                ]);
+            }
+            // U-turns aren't modeled.
+            "reverse" => {}
+            _ => {
+                return None;
+            }
         }
-        Some(
-            part.split(';')
-                .flat_map(|s| match s {
-                    "left" => vec![TurnType::Left],
-                    "right" => vec![TurnType::Right],
-                    // TODO What is blank supposed to mean? From few observed cases, same as through
-                    "through" | "" => vec![
-                        TurnType::Straight,
-                        TurnType::LaneChangeLeft,
-                        TurnType::LaneChangeRight,
-                    ],
-                    // TODO Check this more carefully
-                    "slight_right" | "slight right" | "merge_to_right" => vec![
-                        TurnType::Straight,
-                        TurnType::LaneChangeRight,
-                        TurnType::Right,
-                    ],
-                    "slight_left" | "slight left" | "merge_to_left" => {
-                        vec![TurnType::Straight, TurnType::LaneChangeLeft, TurnType::Left]
-                    }
-                    _ => panic!("What's turn restriction {}?", s),
-                })
-                .collect(),
-        )
     }
+    if turns.is_empty() {
+        return None;
+    }
+    Some(turns)
 }
@@ -2,10 +2,12 @@ mod area;
 mod building;
 mod bus_stop;
 mod edits;
+mod export;
 mod intersection;
 mod lane;
 mod make;
 mod map;
+mod migrate;
 mod neighborhood;
 mod pathfind;
 pub mod raw_data;
@@ -14,22 +16,31 @@ mod stop_signs;
 mod traffic_signals;
 mod traversable;
 mod turn;
+mod turn_restrictions;
+mod validate;
 
 pub use crate::area::{Area, AreaID, AreaType};
 pub use crate::building::{Building, BuildingID, FrontPath, OffstreetParking};
 pub use crate::bus_stop::{BusRoute, BusRouteID, BusStop, BusStopID};
-pub use crate::edits::MapEdits;
+pub use crate::edits::{
+    Closure, CrossSection, EditID, EditProvenance, EditsDiff, MapEdits, MergeConflict,
+    ScheduledEdits, TurnOverride,
+};
+pub use crate::export::FeatureProperties;
 pub use crate::intersection::{Intersection, IntersectionID, IntersectionType};
 pub use crate::lane::{Lane, LaneID, LaneType, PARKING_SPOT_LENGTH};
-pub use crate::make::RoadSpec;
+pub use crate::make::{get_lane_types, get_lane_widths, RoadSpec};
 pub use crate::map::Map;
+pub use crate::migrate::{MapMigration, StableLaneID};
 pub use crate::neighborhood::{FullNeighborhoodInfo, Neighborhood, NeighborhoodBuilder};
 pub use crate::pathfind::{Path, PathRequest, PathStep};
-pub use crate::road::{DirectedRoadID, Road, RoadID};
-pub use crate::stop_signs::{ControlStopSign, RoadWithStopSign};
+pub use crate::road::{parse_maxspeed, DirectedRoadID, Road, RoadID};
+pub use crate::stop_signs::{ControlStopSign, RoadWithStopSign, StopSignConfidence};
 pub use crate::traffic_signals::{ControlTrafficSignal, Cycle};
 pub use crate::traversable::{Position, Traversable};
 pub use crate::turn::{Turn, TurnID, TurnPriority, TurnType};
+pub use crate::turn_restrictions::{RestrictionType, TimeCondition, TurnRestriction};
+pub use crate::validate::{IssueType, MapValidationReport, ValidationIssue};
 use abstutil::Cloneable;
 use geom::Distance;
 
@@ -42,3 +53,5 @@ impl Cloneable for LaneType {}
 impl Cloneable for MapEdits {}
 impl Cloneable for Neighborhood {}
 impl Cloneable for NeighborhoodBuilder {}
+impl Cloneable for RoadID {}
+impl Cloneable for TurnID {}
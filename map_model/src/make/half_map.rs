@@ -1,9 +1,10 @@
 use crate::{
-    make, raw_data, Area, AreaID, Building, Intersection, IntersectionID, IntersectionType, Lane,
-    LaneID, Road, RoadID, Turn, TurnID, LANE_THICKNESS,
+    lane, make, raw_data, road, Area, AreaID, Building, Intersection, IntersectionID,
+    IntersectionType, IssueType, Lane, LaneID, LaneType, MapValidationReport, Road, RoadID, Turn,
+    TurnID, TurnRestriction,
 };
 use abstutil::Timer;
-use geom::{Bounds, Polygon};
+use geom::{Bounds, Distance, Polygon};
 use std::collections::BTreeMap;
 
 pub struct HalfMap {
@@ -15,6 +16,7 @@ pub struct HalfMap {
     pub areas: Vec<Area>,
 
     pub turn_lookup: Vec<TurnID>,
+    pub validation: MapValidationReport,
 }
 
 pub fn make_half_map(
@@ -31,6 +33,7 @@ pub fn make_half_map(
         buildings: Vec::new(),
         areas: Vec::new(),
         turn_lookup: Vec::new(),
+        validation: MapValidationReport::default(),
     };
 
     let road_id_mapping: BTreeMap<raw_data::StableRoadID, RoadID> = initial_map
@@ -55,6 +58,7 @@ pub fn make_half_map(
             intersection_type: i.intersection_type,
             label: raw_i.label.clone(),
             stable_id: i.id,
+            orig_id: raw_i.orig_id,
             incoming_lanes: Vec::new(),
             outgoing_lanes: Vec::new(),
             roads: i.roads.iter().map(|id| road_id_mapping[id]).collect(),
@@ -71,12 +75,15 @@ pub fn make_half_map(
         let i1 = intersection_id_mapping[&r.src_i];
         let i2 = intersection_id_mapping[&r.dst_i];
 
+        let (speed_limit_forwards, speed_limit_backwards) =
+            road::speed_limits_from_osm(&r.osm_tags);
         let mut road = Road {
             id: road_id,
             osm_tags: r.osm_tags.clone(),
             turn_restrictions: Vec::new(),
             osm_way_id,
             stable_id: r.id,
+            segment_id: data.roads[&r.id].segment_id,
             children_forwards: Vec::new(),
             children_backwards: Vec::new(),
             center_pts: r.trimmed_center_pts.clone(),
@@ -85,12 +92,26 @@ pub fn make_half_map(
             dst_i: i2,
             parking_lane_fwd: r.parking_lane_fwd,
             parking_lane_back: r.parking_lane_back,
+            speed_limit_forwards,
+            speed_limit_backwards,
+            fwd_width: r.fwd_width,
+            back_width: r.back_width,
         };
         for stable_id in &r.override_turn_restrictions_to {
-            road.turn_restrictions
-                .push(("no_anything".to_string(), road_id_mapping[stable_id]));
+            let to = &initial_map.roads[stable_id];
+            let at = if to.src_i == r.src_i || to.dst_i == r.src_i {
+                i1
+            } else {
+                i2
+            };
+            road.turn_restrictions.push(TurnRestriction::ban_everything(
+                at,
+                road_id_mapping[stable_id],
+            ));
         }
 
+        let mut fwd_offset = Distance::ZERO;
+        let mut back_offset = Distance::ZERO;
         for lane in &r.lane_specs {
             let id = LaneID(half_map.lanes.len());
 
@@ -98,27 +119,27 @@ pub fn make_half_map(
             half_map.intersections[src_i.0].outgoing_lanes.push(id);
             half_map.intersections[dst_i.0].incoming_lanes.push(id);
 
+            // How far the lane's center is from the road's center line
             let (unshifted_pts, offset) = if lane.reverse_pts {
                 road.children_backwards.push((id, lane.lane_type));
-                (
-                    road.center_pts.reversed(),
-                    road.children_backwards.len() - 1,
-                )
+                back_offset += lane.width;
+                (road.center_pts.reversed(), back_offset - lane.width / 2.0)
             } else {
                 road.children_forwards.push((id, lane.lane_type));
-                (road.center_pts.clone(), road.children_forwards.len() - 1)
+                fwd_offset += lane.width;
+                (road.center_pts.clone(), fwd_offset - lane.width / 2.0)
             };
             // TODO probably different behavior for oneways
             // TODO need to factor in yellow center lines (but what's the right thing to even do?
             // Reverse points for British-style driving on the left
-            let width = LANE_THICKNESS * (0.5 + (offset as f64));
             let lane_center_pts = unshifted_pts
-                .shift_right(width)
+                .shift_right(offset)
                 .with_context(timer, format!("shift for {}", id));
 
             half_map.lanes.push(Lane {
                 id,
                 lane_center_pts,
+                width: lane.width,
                 src_i,
                 dst_i,
                 lane_type: lane.lane_type,
@@ -126,8 +147,19 @@ pub fn make_half_map(
                 building_paths: Vec::new(),
                 bus_stops: Vec::new(),
                 parking_blackhole: None,
+                allowed_turns: None,
             });
         }
+        for (fwds, children) in vec![
+            (true, &road.children_forwards),
+            (false, &road.children_backwards),
+        ] {
+            let lane_types: Vec<LaneType> = children.iter().map(|(_, lt)| *lt).collect();
+            for (offset, (id, _)) in children.iter().enumerate() {
+                half_map.lanes[id.0].allowed_turns =
+                    lane::parse_turn_lanes(&road.osm_tags, fwds, &lane_types, offset);
+            }
+        }
         if road.get_name() == "???" {
             timer.warn(format!(
                 "{} has no name. Tags: {:?}",
@@ -140,23 +172,31 @@ pub fn make_half_map(
     let mut filtered_restrictions = Vec::new();
     for r in &half_map.roads {
         if let Some(restrictions) = data.turn_restrictions.get(&r.osm_way_id) {
-            for (restriction, to) in restrictions {
-                // Make sure the restriction actually applies to this road.
-                if let Some(to_road) = half_map.intersections[r.src_i.0]
-                    .roads
-                    .iter()
-                    .chain(half_map.intersections[r.dst_i.0].roads.iter())
-                    .find(|r| half_map.roads[r.0].osm_way_id == *to)
-                {
-                    filtered_restrictions.push((r.id, restriction, to_road));
+            for raw in restrictions {
+                // Make sure the restriction actually applies to this road. OSM ways get split
+                // into many roads, and only the one touching the via node or ways counts.
+                if let Some((at, via, to)) = resolve_restriction(r.id, raw, &half_map) {
+                    match TurnRestriction::new(raw, at, via, to) {
+                        Ok(tr) => filtered_restrictions.push((r.id, tr)),
+                        Err(err) => {
+                            timer.warn(format!("Skipping restriction from {}: {}", r.id, err));
+                            let issue = half_map.validation.add(
+                                IssueType::SkippedTurnRestriction,
+                                format!("Skipping restriction from {}: {}", r.id, err),
+                                half_map.intersections[at.0].polygon.center(),
+                                &data.gps_bounds,
+                            );
+                            issue.roads.push(r.id);
+                            issue.intersections.push(at);
+                            issue.osm_way_ids.push(r.osm_way_id);
+                        }
+                    }
                 }
             }
         }
     }
-    for (from, restriction, to) in filtered_restrictions {
-        half_map.roads[from.0]
-            .turn_restrictions
-            .push((restriction.to_string(), *to));
+    for (from, restriction) in filtered_restrictions {
+        half_map.roads[from.0].turn_restrictions.push(restriction);
     }
 
     for i in half_map.intersections.iter_mut() {
@@ -167,6 +207,24 @@ pub fn make_half_map(
 
         if i.incoming_lanes.is_empty() || i.outgoing_lanes.is_empty() {
             timer.warn(format!("{:?} is orphaned!", i));
+            let issue = half_map.validation.add(
+                IssueType::OrphanedIntersection,
+                format!(
+                    "{} has {} incoming and {} outgoing lanes",
+                    i.id,
+                    i.incoming_lanes.len(),
+                    i.outgoing_lanes.len()
+                ),
+                i.polygon.center(),
+                &data.gps_bounds,
+            );
+            issue.intersections.push(i.id);
+            issue.roads = i.roads.iter().cloned().collect();
+            issue.osm_way_ids = i
+                .roads
+                .iter()
+                .map(|r| half_map.roads[r.0].osm_way_id)
+                .collect();
             continue;
         }
 
@@ -237,3 +295,67 @@ fn is_border(intersection: &Intersection, lanes: &Vec<Lane>) -> bool {
         .any(|l| lanes[l.0].is_driving());
     has_driving_in != has_driving_out
 }
+
+// Finds the roads corresponding to the via ways, in order, and then the to road. None if the
+// restriction doesn't start from this road.
+fn resolve_restriction(
+    from: RoadID,
+    raw: &raw_data::TurnRestriction,
+    half_map: &HalfMap,
+) -> Option<(IntersectionID, Vec<RoadID>, RoadID)> {
+    let neighbors = |r: RoadID| {
+        let road = &half_map.roads[r.0];
+        half_map.intersections[road.src_i.0]
+            .roads
+            .iter()
+            .chain(half_map.intersections[road.dst_i.0].roads.iter())
+            .cloned()
+            .filter(move |other| *other != r)
+            .collect::<Vec<RoadID>>()
+    };
+
+    if raw.via_ways.is_empty() {
+        let to = neighbors(from)
+            .into_iter()
+            .find(|r| half_map.roads[r.0].osm_way_id == raw.to_way)?;
+        return Some((shared_intersection(from, to, half_map)?, Vec::new(), to));
+    }
+
+    // Via ways might be split into several roads, and OSM doesn't order them. Search outwards from
+    // the from road through roads belonging to any via way, until reaching the to way.
+    let mut queue: Vec<Vec<RoadID>> = vec![vec![from]];
+    while let Some(path) = queue.pop() {
+        let last = *path.last().unwrap();
+        for next in neighbors(last) {
+            if path.contains(&next) {
+                continue;
+            }
+            let way = half_map.roads[next.0].osm_way_id;
+            if way == raw.to_way && path.len() > 1 {
+                return Some((
+                    shared_intersection(from, path[1], half_map)?,
+                    path[1..].to_vec(),
+                    next,
+                ));
+            }
+            // Don't wander far; via ways are usually short.
+            if raw.via_ways.contains(&way) && path.len() < 10 {
+                let mut longer = path.clone();
+                longer.push(next);
+                queue.push(longer);
+            }
+        }
+    }
+    None
+}
+
+fn shared_intersection(r1: RoadID, r2: RoadID, half_map: &HalfMap) -> Option<IntersectionID> {
+    let (r1, r2) = (&half_map.roads[r1.0], &half_map.roads[r2.0]);
+    if r1.src_i == r2.src_i || r1.src_i == r2.dst_i {
+        Some(r1.src_i)
+    } else if r1.dst_i == r2.src_i || r1.dst_i == r2.dst_i {
+        Some(r1.dst_i)
+    } else {
+        None
+    }
+}
@@ -1,4 +1,5 @@
 use crate::LaneType;
+use geom::Distance;
 use serde_derive::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::iter;
@@ -22,14 +23,32 @@ pub fn get_lane_types(
     if osm_tags.get("junction") == Some(&"roundabout".to_string()) {
         return (vec![LaneType::Driving, LaneType::Sidewalk], Vec::new());
     }
-    if osm_tags.get("highway") == Some(&"footway".to_string()) {
-        return (vec![LaneType::Sidewalk], Vec::new());
-    }
 
     // TODO Reversible roads should be handled differently?
     let oneway = osm_tags.get("oneway") == Some(&"yes".to_string())
         || osm_tags.get("oneway") == Some(&"reversible".to_string());
 
+    // Paths that aren't for cars. One sidewalk is enough for pedestrians going both ways.
+    let allows = |mode: &str| match osm_tags.get(mode).map(|s| s.as_str()) {
+        Some("yes") | Some("designated") | Some("permissive") => true,
+        _ => false,
+    };
+    match osm_tags.get("highway").map(|s| s.as_str()) {
+        Some("footway") | Some("pedestrian") | Some("path") => {
+            if allows("bicycle") {
+                return bike_path(oneway, true);
+            }
+            return (vec![LaneType::Sidewalk], Vec::new());
+        }
+        Some("steps") => {
+            return (vec![LaneType::Sidewalk], Vec::new());
+        }
+        Some("cycleway") => {
+            return bike_path(oneway, osm_tags.get("foot") != Some(&"no".to_string()));
+        }
+        _ => {}
+    }
+
     // How many driving lanes in each direction?
     let num_driving_fwd = if let Some(n) = osm_tags
         .get("lanes:forward")
@@ -135,6 +154,130 @@ pub fn get_lane_types(
     (fwd_side, back_side)
 }
 
+// The width of each lane returned by get_lane_types, in the same order. Falls back to
+// LaneType::default_width when OSM doesn't say.
+pub fn get_lane_widths(
+    osm_tags: &BTreeMap<String, String>,
+    fwd_side: &Vec<LaneType>,
+    back_side: &Vec<LaneType>,
+) -> (Vec<Distance>, Vec<Distance>) {
+    let tag = |key: &str| osm_tags.get(key).and_then(|x| parse_width(x));
+    // width:lanes lists lanes left to right in the direction of travel, which is also
+    // center-to-curb for us. Without a direction, it only makes sense for one-ways.
+    let per_lane = |key: &str| -> Vec<Option<Distance>> {
+        osm_tags
+            .get(key)
+            .map(|x| x.split('|').map(parse_width).collect())
+            .unwrap_or_else(Vec::new)
+    };
+    let fwd_lanes = if osm_tags.contains_key("width:lanes:forward") || !back_side.is_empty() {
+        per_lane("width:lanes:forward")
+    } else {
+        per_lane("width:lanes")
+    };
+    let back_lanes = per_lane("width:lanes:backward");
+
+    let side = |types: &Vec<LaneType>, tagged_lanes: Vec<Option<Distance>>| -> Vec<Distance> {
+        let mut roadway_idx = 0;
+        types
+            .iter()
+            .map(|lt| {
+                let tagged = match lt {
+                    LaneType::Driving | LaneType::Bus => {
+                        roadway_idx += 1;
+                        tagged_lanes.get(roadway_idx - 1).and_then(|x| *x)
+                    }
+                    LaneType::Biking => tag("cycleway:width"),
+                    LaneType::Sidewalk => tag("sidewalk:width"),
+                    LaneType::Parking => None,
+                };
+                tagged.unwrap_or_else(|| lt.default_width())
+            })
+            .collect()
+    };
+    let mut fwd_widths = side(fwd_side, fwd_lanes);
+    let mut back_widths = side(back_side, back_lanes);
+
+    // The plain width tag is the whole carriageway, curb to curb. When nothing more specific is
+    // tagged, stretch the defaults to fit. Some mappers include sidewalks or shoulders, so ignore
+    // values wildly different from the defaults.
+    let per_lane_tagged = osm_tags.keys().any(|k| k.starts_with("width:lanes"));
+    if let Some(width) = tag("width").filter(|_| !per_lane_tagged) {
+        let mut total = Distance::ZERO;
+        for (lt, w) in fwd_side
+            .iter()
+            .zip(fwd_widths.iter())
+            .chain(back_side.iter().zip(back_widths.iter()))
+        {
+            if *lt != LaneType::Sidewalk {
+                total += *w;
+            }
+        }
+        if total > Distance::ZERO {
+            let ratio = width / total;
+            if ratio >= 0.5 && ratio <= 2.0 {
+                for (lt, w) in fwd_side
+                    .iter()
+                    .zip(fwd_widths.iter_mut())
+                    .chain(back_side.iter().zip(back_widths.iter_mut()))
+                {
+                    if *lt != LaneType::Sidewalk {
+                        *w = *w * ratio;
+                    }
+                }
+            }
+        }
+    }
+
+    (fwd_widths, back_widths)
+}
+
+// Handles "3.5", "3.5 m", "12 ft", and feet and inches like 12'6". A bare number is meters, per
+// OSM convention. Anything implausible for a lane or street is ignored.
+pub fn parse_width(value: &str) -> Option<Distance> {
+    let value = value.split(';').next()?.trim();
+    let meters = if let Some(idx) = value.find('\'') {
+        let feet = value[..idx].trim().parse::<f64>().ok()?;
+        let rest = value[idx + 1..].trim().trim_end_matches('"').trim();
+        let inches = if rest.is_empty() {
+            0.0
+        } else {
+            rest.parse::<f64>().ok()?
+        };
+        (feet + inches / 12.0) * 0.3048
+    } else {
+        let split = value
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or_else(|| value.len());
+        let number = value[..split].parse::<f64>().ok()?;
+        match value[split..].trim() {
+            "" | "m" => number,
+            "ft" => number * 0.3048,
+            _ => {
+                return None;
+            }
+        }
+    };
+    if meters >= 0.5 && meters <= 100.0 {
+        Some(Distance::meters(meters))
+    } else {
+        None
+    }
+}
+
+fn bike_path(oneway: bool, with_sidewalk: bool) -> (Vec<LaneType>, Vec<LaneType>) {
+    let mut fwd_side = vec![LaneType::Biking];
+    let back_side = if oneway {
+        Vec::new()
+    } else {
+        vec![LaneType::Biking]
+    };
+    if with_sidewalk {
+        fwd_side.push(LaneType::Sidewalk);
+    }
+    (fwd_side, back_side)
+}
+
 // This is a convenient way for the synthetic map editor to plumb instructions here.
 #[derive(Serialize, Deserialize)]
 pub struct RoadSpec {
@@ -3,8 +3,10 @@ mod geometry;
 pub mod lane_specs;
 mod merge;
 
+pub use self::geometry::DEGENERATE_INTERSECTION_HALF_LENGTH;
+
 use crate::raw_data::{StableIntersectionID, StableRoadID};
-use crate::{raw_data, IntersectionType, LaneType, LANE_THICKNESS};
+use crate::{raw_data, IntersectionType, LaneType};
 use abstutil::{deserialize_btreemap, serialize_btreemap, Timer};
 use geom::{Bounds, Distance, PolyLine, Pt2D};
 use serde_derive::{Deserialize, Serialize};
@@ -131,9 +133,9 @@ impl InitialMap {
             let mut back_width = Distance::ZERO;
             for l in &lane_specs {
                 if l.reverse_pts {
-                    back_width += LANE_THICKNESS;
+                    back_width += l.width;
                 } else {
-                    fwd_width += LANE_THICKNESS;
+                    fwd_width += l.width;
                 }
             }
 
@@ -291,9 +293,9 @@ impl InitialMap {
             let mut back_width = Distance::ZERO;
             for l in &lane_specs {
                 if l.reverse_pts {
-                    back_width += LANE_THICKNESS;
+                    back_width += l.width;
                 } else {
-                    fwd_width += LANE_THICKNESS;
+                    fwd_width += l.width;
                 }
             }
             road.lane_specs = lane_specs;
@@ -413,6 +415,7 @@ pub enum Hint {
 pub struct LaneSpec {
     pub lane_type: LaneType,
     pub reverse_pts: bool,
+    pub width: Distance,
 }
 
 fn get_lane_specs(
@@ -423,18 +426,22 @@ fn get_lane_specs(
 ) -> Vec<LaneSpec> {
     let (side1_types, side2_types) =
         lane_specs::get_lane_types(osm_tags, parking_lane_fwd, parking_lane_back);
+    let (side1_widths, side2_widths) =
+        lane_specs::get_lane_widths(osm_tags, &side1_types, &side2_types);
 
     let mut specs: Vec<LaneSpec> = Vec::new();
-    for lane_type in side1_types {
+    for (lane_type, width) in side1_types.into_iter().zip(side1_widths) {
         specs.push(LaneSpec {
             lane_type,
             reverse_pts: false,
+            width,
         });
     }
-    for lane_type in side2_types {
+    for (lane_type, width) in side2_types.into_iter().zip(side2_widths) {
         specs.push(LaneSpec {
             lane_type,
             reverse_pts: true,
+            width,
         });
     }
     if specs.is_empty() {
@@ -9,7 +9,7 @@ mod turns;
 pub use self::buildings::make_all_buildings;
 pub use self::bus_stops::{make_bus_stops, verify_bus_routes};
 pub use self::half_map::make_half_map;
-pub use self::initial::lane_specs::{get_lane_types, RoadSpec};
-pub use self::initial::{Hint, Hints, InitialMap};
-pub use self::parking_blackholes::redirect_parking_blackholes;
+pub use self::initial::lane_specs::{get_lane_types, get_lane_widths, RoadSpec};
+pub use self::initial::{Hint, Hints, InitialMap, DEGENERATE_INTERSECTION_HALF_LENGTH};
+pub use self::parking_blackholes::{largest_driving_scc, redirect_parking_blackholes};
 pub use self::turns::make_all_turns;
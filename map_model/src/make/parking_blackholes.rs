@@ -8,25 +8,13 @@ use std::collections::{HashSet, VecDeque};
 // It's a bit weird to never attempt parking on roads not part of the largest SCC of the graph.
 // This is acceptable, because there shouldn't be too many roads outside of that SCC anyway.
 pub fn redirect_parking_blackholes(map: &Map, timer: &mut Timer) -> Vec<(LaneID, LaneID)> {
-    let mut graph = DiGraphMap::new();
-    for turn in map.all_turns().values() {
-        if map.is_turn_allowed(turn.id) && !turn.between_sidewalks() {
-            graph.add_edge(turn.id.src, turn.id.dst, 1);
-        }
-    }
-    let components = petgraph::algo::kosaraju_scc(&graph);
-    let largest_group: HashSet<LaneID> = components
-        .into_iter()
-        .max_by_key(|c| c.len())
-        .unwrap()
-        .into_iter()
-        .collect();
+    let largest_group = largest_driving_scc(map);
 
     let mut redirects = Vec::new();
     timer.start_iter("find parking blackhole redirects", map.all_lanes().len());
     for l in map.all_lanes() {
         timer.next();
-        if !l.is_driving() || largest_group.contains(&l.id) {
+        if !l.is_driving() || largest_group.contains(&l.id) || !map.lane_exists(l.id) {
             continue;
         }
 
@@ -49,6 +37,23 @@ pub fn redirect_parking_blackholes(map: &Map, timer: &mut Timer) -> Vec<(LaneID,
     redirects
 }
 
+// The lanes in the largest strongly-connected component of the graph of vehicle lanes
+pub fn largest_driving_scc(map: &Map) -> HashSet<LaneID> {
+    let mut graph = DiGraphMap::new();
+    for turn in map.all_turns().values() {
+        if map.is_turn_allowed(turn.id) && !turn.between_sidewalks() {
+            graph.add_edge(turn.id.src, turn.id.dst, 1);
+        }
+    }
+    let components = petgraph::algo::kosaraju_scc(&graph);
+    components
+        .into_iter()
+        .max_by_key(|c| c.len())
+        .unwrap()
+        .into_iter()
+        .collect()
+}
+
 fn bidi_flood(map: &Map, start: LaneID, largest_group: &HashSet<LaneID>) -> Option<LaneID> {
     let mut queue = VecDeque::new();
     queue.push_back(start);
@@ -1,11 +1,11 @@
 use crate::{
-    Intersection, IntersectionID, IntersectionType, Lane, LaneID, LaneType, Road, RoadID, Turn,
-    TurnID, TurnType, LANE_THICKNESS,
+    Intersection, IntersectionID, IntersectionType, Lane, LaneID, LaneType, RestrictionType, Road,
+    RoadID, Turn, TurnID, TurnType,
 };
 use abstutil::{Timer, Warn};
 use geom::{Distance, Line, PolyLine, Pt2D};
 use nbez::{Bez3o, BezCurve, Point2d};
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeSet, HashSet};
 
 // TODO Add proper warnings when the geometry is too small to handle.
 
@@ -22,22 +22,10 @@ pub fn make_all_turns(
     raw_turns.extend(make_walking_turns(i, roads, lanes, timer));
     let unique_turns = ensure_unique(raw_turns);
 
-    let mut final_turns: Vec<Turn> = Vec::new();
-    let mut filtered_turns: HashMap<LaneID, Vec<Turn>> = HashMap::new();
-    for turn in unique_turns {
-        if !does_turn_pass_restrictions(&turn, &i.roads, roads, lanes) {
-            continue;
-        }
-
-        if is_turn_allowed(&turn, roads, lanes) {
-            final_turns.push(turn);
-        } else {
-            filtered_turns
-                .entry(turn.id.src)
-                .or_insert_with(Vec::new)
-                .push(turn);
-        }
-    }
+    let final_turns: Vec<Turn> = unique_turns
+        .into_iter()
+        .filter(|t| does_turn_pass_restrictions(t, roads, lanes))
+        .collect();
 
     // Make sure every incoming lane has a turn originating from it, and every outgoing lane has a
     // turn leading to it. Except for parking lanes, of course.
@@ -50,17 +38,6 @@ pub fn make_all_turns(
     for t in &final_turns {
         incoming_missing.remove(&t.id.src);
     }
-    // Turn restrictions are buggy. If they orphan a lane, restore the filtered turns.
-    for (l, turns) in filtered_turns {
-        if incoming_missing.contains(&l) {
-            timer.warn(format!(
-                "Turn restrictions broke {} outbound, so restoring turns",
-                l
-            ));
-            final_turns.extend(turns);
-            incoming_missing.remove(&l);
-        }
-    }
 
     let mut outgoing_missing: HashSet<LaneID> = HashSet::new();
     for l in &i.outgoing_lanes {
@@ -130,6 +107,7 @@ fn make_vehicle_turns(
                 continue;
             }
 
+            let mut r1_turns = Vec::new();
             let mut maybe_add_turns = Vec::new();
             let mut all_incoming_lanes_covered = false;
 
@@ -193,7 +171,7 @@ fn make_vehicle_turns(
                                     } else if idx1 > idx2 {
                                         t.turn_type = TurnType::LaneChangeLeft;
                                     }
-                                    result.push(Some(t));
+                                    r1_turns.push(Some(t));
                                 }
                             }
                         }
@@ -204,7 +182,7 @@ fn make_vehicle_turns(
                             for l2 in &outgoing {
                                 let turn = make_vehicle_turn(lanes, i.id, *l1, *l2);
                                 if idx == incoming.len() - 1 {
-                                    result.push(turn);
+                                    r1_turns.push(turn);
                                 } else {
                                     maybe_add_turns.push(turn);
                                 }
@@ -216,7 +194,7 @@ fn make_vehicle_turns(
                             for l2 in &outgoing {
                                 let turn = make_vehicle_turn(lanes, i.id, *l1, *l2);
                                 if idx == 0 {
-                                    result.push(turn);
+                                    r1_turns.push(turn);
                                 } else {
                                     maybe_add_turns.push(turn);
                                 }
@@ -228,7 +206,21 @@ fn make_vehicle_turns(
             }
 
             if !all_incoming_lanes_covered {
-                result.extend(maybe_add_turns);
+                r1_turns.extend(maybe_add_turns);
+            }
+
+            if let Some(turns) =
+                make_vehicle_turns_from_tags(i, r1, &roads, &incoming, lanes, lane_type)
+            {
+                result.extend(turns);
+            } else {
+                if incoming.iter().any(|l| lanes[l.0].allowed_turns.is_some()) {
+                    timer.warn(format!(
+                        "Ignoring allowed turns for {} at {}; they'd strand some lanes",
+                        r1.id, i.id
+                    ));
+                }
+                result.extend(r1_turns);
             }
         }
     }
@@ -236,6 +228,85 @@ fn make_vehicle_turns(
     result.into_iter().filter_map(|x| x).collect()
 }
 
+// Uses the movements each lane allows, usually from OSM turn:lanes. None if some incoming lane
+// doesn't say, or if following them would leave a lane with nowhere to go.
+fn make_vehicle_turns_from_tags(
+    i: &Intersection,
+    r1: &Road,
+    roads: &Vec<&Road>,
+    incoming: &Vec<LaneID>,
+    lanes: &Vec<Lane>,
+    lane_type: LaneType,
+) -> Option<Vec<Option<Turn>>> {
+    if incoming.iter().any(|l| lanes[l.0].allowed_turns.is_none()) {
+        return None;
+    }
+    // Lane-changing movements use indices among all travel lanes, like the heuristics.
+    let all_incoming: Vec<LaneID> = r1
+        .incoming_lanes(i.id)
+        .iter()
+        .filter(|(_, lt)| lt.is_for_moving_vehicles())
+        .map(|(id, _)| *id)
+        .collect();
+
+    let mut result = Vec::new();
+    let mut covered: HashSet<LaneID> = HashSet::new();
+    for r2 in roads {
+        if r1.id == r2.id {
+            continue;
+        }
+        let outgoing = filter_vehicle_lanes(r2.outgoing_lanes(i.id), lane_type);
+        if outgoing.is_empty() {
+            continue;
+        }
+        // Same deduplication as make_vehicle_turns
+        if lanes[incoming[0].0].lane_type != lane_type
+            && lanes[outgoing[0].0].lane_type != lane_type
+        {
+            continue;
+        }
+        let all_outgoing: Vec<LaneID> = r2
+            .outgoing_lanes(i.id)
+            .iter()
+            .filter(|(_, lt)| lt.is_for_moving_vehicles())
+            .map(|(id, _)| *id)
+            .collect();
+
+        let angle1 = lanes[incoming[0].0].last_line().angle();
+        let angle2 = lanes[outgoing[0].0].first_line().angle();
+        let turn_type = TurnType::from_angles(angle1, angle2);
+
+        for l1 in incoming {
+            let allowed = lanes[l1.0].allowed_turns.as_ref().unwrap();
+            for l2 in &outgoing {
+                let mut t = if let Some(t) = make_vehicle_turn(lanes, i.id, *l1, *l2) {
+                    t
+                } else {
+                    continue;
+                };
+                if turn_type == TurnType::Straight {
+                    let idx1 = all_incoming.iter().position(|l| l == l1).unwrap();
+                    let idx2 = all_outgoing.iter().position(|l| l == l2).unwrap();
+                    if idx1 < idx2 {
+                        t.turn_type = TurnType::LaneChangeRight;
+                    } else if idx1 > idx2 {
+                        t.turn_type = TurnType::LaneChangeLeft;
+                    }
+                }
+                if allowed.contains(&t.turn_type) {
+                    covered.insert(*l1);
+                    result.push(Some(t));
+                }
+            }
+        }
+    }
+
+    if incoming.iter().any(|l| !covered.contains(l)) {
+        return None;
+    }
+    Some(result)
+}
+
 fn make_vehicle_turns_for_dead_end(
     i: &Intersection,
     roads: &Vec<Road>,
@@ -324,7 +395,7 @@ fn make_crosswalks(i: IntersectionID, l1: &Lane, l2: &Lane) -> Vec<Turn> {
     }
 
     // Jut out a bit into the intersection, cross over, then jut back in.
-    let line = Line::new(l1.last_pt(), l2.first_pt()).shift_right(LANE_THICKNESS / 2.0);
+    let line = Line::new(l1.last_pt(), l2.first_pt()).shift_right(l1.width / 2.0);
     let geom_fwds = PolyLine::new(vec![l1.last_pt(), line.pt1(), line.pt2(), l2.first_pt()]);
 
     vec![
@@ -352,8 +423,8 @@ fn make_shared_sidewalk_corner(
     let baseline = PolyLine::new(vec![l1.last_pt(), l2.first_pt()]);
 
     // Find all of the points on the intersection polygon between the two sidewalks.
-    let corner1 = l1.last_line().shift_right(LANE_THICKNESS / 2.0).pt2();
-    let corner2 = l2.first_line().shift_right(LANE_THICKNESS / 2.0).pt1();
+    let corner1 = l1.last_line().shift_right(l1.width / 2.0).pt2();
+    let corner2 = l2.first_line().shift_right(l2.width / 2.0).pt1();
 
     // The order of the points here seems backwards, but it's because we scan from corner2
     // to corner1 below.
@@ -371,7 +442,7 @@ fn make_shared_sidewalk_corner(
 
             pts_between.extend(
                 PolyLine::new(deduped)
-                    .shift_right(LANE_THICKNESS / 2.0)
+                    .shift_right(l1.width / 2.0)
                     .with_context(
                         timer,
                         format!("SharedSidewalkCorner between {} and {}", l1.id, l2.id),
@@ -492,22 +563,7 @@ fn from_pt(pt: Point2d<f64>) -> Pt2D {
     Pt2D::new(pt.x, pt.y)
 }
 
-fn is_turn_allowed(turn: &Turn, roads: &Vec<Road>, lanes: &Vec<Lane>) -> bool {
-    let l = &lanes[turn.id.src.0];
-    let r = &roads[l.parent.0];
-    if let Some(types) = l.get_turn_restrictions(r) {
-        types.contains(&turn.turn_type)
-    } else {
-        true
-    }
-}
-
-fn does_turn_pass_restrictions(
-    turn: &Turn,
-    intersection_roads: &BTreeSet<RoadID>,
-    roads: &Vec<Road>,
-    lanes: &Vec<Lane>,
-) -> bool {
+fn does_turn_pass_restrictions(turn: &Turn, roads: &Vec<Road>, lanes: &Vec<Lane>) -> bool {
     if turn.between_sidewalks() {
         return true;
     }
@@ -515,30 +571,28 @@ fn does_turn_pass_restrictions(
     let src = lanes[turn.id.src.0].parent;
     let dst = lanes[turn.id.dst.0].parent;
 
-    for (restriction, to) in &roads[src.0].turn_restrictions {
+    // Ignore the TurnType. Between two roads, there's only one category of TurnType (treating
+    // Straight/LaneChangeLeft/LaneChangeRight as the same).
+    //
+    // Restrictions with exemptions, time conditions, or via roads can't be baked into the turns;
+    // pathfinding handles those.
+    let mut only_allowed: Vec<RoadID> = Vec::new();
+    for tr in &roads[src.0].turn_restrictions {
         // The restriction only applies to one direction of the road.
-        if !intersection_roads.contains(to) {
+        if !tr.is_permanent() || tr.at != turn.id.parent {
             continue;
         }
-
-        // Ignore the TurnType. Between two roads, there's only one category of TurnType (treating
-        // Straight/LaneChangeLeft/LaneChangeRight as the same).
-        //
-        // Strip off time restrictions (like " @ (Mo-Fr 06:00-09:00, 15:00-18:30)")
-        match restriction.split(" @ ").next().unwrap() {
-            "no_left_turn" | "no_right_turn" | "no_straight_on" | "no_u_turn" | "no_anything" => {
-                if dst == *to {
+        match tr.restriction_type {
+            RestrictionType::BanTurns => {
+                if dst == tr.to {
                     return false;
                 }
             }
-            "only_left_turn" | "only_right_turn" | "only_straight_on" => {
-                if dst != *to {
-                    return false;
-                }
+            RestrictionType::OnlyAllowTurns => {
+                only_allowed.push(tr.to);
             }
-            _ => panic!("Unknown restriction {}", restriction),
         }
     }
 
-    true
+    only_allowed.is_empty() || only_allowed.contains(&dst)
 }
@@ -1,14 +1,15 @@
-use crate::make::get_lane_types;
+use crate::make::{get_lane_types, get_lane_widths};
 use crate::pathfind::Pathfinder;
 use crate::{
-    make, raw_data, Area, AreaID, Building, BuildingID, BusRoute, BusRouteID, BusStop, BusStopID,
-    ControlStopSign, ControlTrafficSignal, Intersection, IntersectionID, IntersectionType, Lane,
-    LaneID, LaneType, MapEdits, Path, PathRequest, Position, Road, RoadID, Turn, TurnID,
-    TurnPriority,
+    lane, make, raw_data, road, Area, AreaID, Building, BuildingID, BusRoute, BusRouteID, BusStop,
+    BusStopID, ControlStopSign, ControlTrafficSignal, CrossSection, EditID, Intersection,
+    IntersectionID, IntersectionType, IssueType, Lane, LaneID, LaneType, MapEdits,
+    MapValidationReport, Path, PathRequest, Position, Road, RoadID, Turn, TurnID, TurnOverride,
+    TurnPriority, TurnType,
 };
 use abstutil;
 use abstutil::{deserialize_btreemap, serialize_btreemap, Error, Timer};
-use geom::{Bounds, GPSBounds, Polygon, Pt2D};
+use geom::{Bounds, Distance, Duration, GPSBounds, Line, Polygon, Pt2D, Speed};
 use serde_derive::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
 use std::io;
@@ -36,6 +37,9 @@ pub struct Map {
     // Note that border nodes belong in neither!
     stop_signs: BTreeMap<IntersectionID, ControlStopSign>,
     traffic_signals: BTreeMap<IntersectionID, ControlTrafficSignal>,
+    // The policies from before turn overrides patched them, only where they did
+    unpatched_stop_signs: BTreeMap<IntersectionID, ControlStopSign>,
+    unpatched_traffic_signals: BTreeMap<IntersectionID, ControlTrafficSignal>,
 
     gps_bounds: GPSBounds,
     bounds: Bounds,
@@ -47,6 +51,16 @@ pub struct Map {
 
     name: String,
     edits: MapEdits,
+    // Which scheduled edits are active depends on the time of day
+    edits_time: Duration,
+    // Filled out the first time an intersection's type is edited, so it can be reverted
+    original_intersection_types: BTreeMap<IntersectionID, IntersectionType>,
+    // The lanes of each road before its cross-section was first edited, per direction. Removed
+    // lanes stay in `lanes` so that IDs don't shift.
+    original_lanes: BTreeMap<RoadID, (Vec<LaneID>, Vec<LaneID>)>,
+    // Lanes added by cross-section edits get IDs from here on; see plan_lanes.
+    first_added_lane: usize,
+    validation: MapValidationReport,
 }
 
 impl Map {
@@ -73,6 +87,8 @@ impl Map {
             ]),
             stop_signs: BTreeMap::new(),
             traffic_signals: BTreeMap::new(),
+            unpatched_stop_signs: BTreeMap::new(),
+            unpatched_traffic_signals: BTreeMap::new(),
             gps_bounds: GPSBounds::new(),
             bounds: Bounds::new(),
             turn_lookup: Vec::new(),
@@ -80,6 +96,11 @@ impl Map {
             pathfinder_dirty: false,
             name: "blank".to_string(),
             edits: MapEdits::new("blank".to_string()),
+            edits_time: Duration::ZERO,
+            original_intersection_types: BTreeMap::new(),
+            original_lanes: BTreeMap::new(),
+            first_added_lane: 0,
+            validation: MapValidationReport::default(),
         }
     }
 
@@ -97,6 +118,7 @@ impl Map {
         timer.stop("InitialMap to HalfMap");
 
         timer.start("finalize Map");
+        let first_added_lane = half_map.lanes.len();
         let mut m = Map {
             roads: half_map.roads,
             lanes: half_map.lanes,
@@ -109,6 +131,8 @@ impl Map {
             boundary_polygon: data.boundary_polygon.clone(),
             stop_signs: BTreeMap::new(),
             traffic_signals: BTreeMap::new(),
+            unpatched_stop_signs: BTreeMap::new(),
+            unpatched_traffic_signals: BTreeMap::new(),
             gps_bounds,
             bounds,
             turn_lookup: half_map.turn_lookup,
@@ -116,6 +140,11 @@ impl Map {
             pathfinder_dirty: false,
             name: name.clone(),
             edits: MapEdits::new(name),
+            edits_time: Duration::ZERO,
+            original_intersection_types: BTreeMap::new(),
+            original_lanes: BTreeMap::new(),
+            first_added_lane,
+            validation: half_map.validation,
         };
 
         // Extra setup that's annoying to do as HalfMap, since we want to pass around a Map.
@@ -124,15 +153,11 @@ impl Map {
             let mut traffic_signals: BTreeMap<IntersectionID, ControlTrafficSignal> =
                 BTreeMap::new();
             for i in &m.intersections {
-                match i.intersection_type {
-                    IntersectionType::StopSign => {
-                        stop_signs.insert(i.id, ControlStopSign::new(&m, i.id, timer));
-                    }
-                    IntersectionType::TrafficSignal => {
-                        traffic_signals.insert(i.id, ControlTrafficSignal::new(&m, i.id, timer));
-                    }
-                    IntersectionType::Border => {}
-                };
+                if i.intersection_type == IntersectionType::TrafficSignal {
+                    traffic_signals.insert(i.id, ControlTrafficSignal::new(&m, i.id, timer));
+                } else if i.intersection_type.is_unsignalized() {
+                    stop_signs.insert(i.id, m.default_stop_sign(i.id, timer));
+                }
             }
             m.stop_signs = stop_signs;
             m.traffic_signals = traffic_signals;
@@ -154,7 +179,31 @@ impl Map {
                 m.lanes[id.sidewalk.0].bus_stops.push(*id);
             }
 
+            let made_routes: BTreeSet<String> = routes.iter().map(|r| r.name.clone()).collect();
             m.bus_routes = make::verify_bus_routes(&m, routes, timer);
+            for route in &data.bus_routes {
+                if m.bus_routes.iter().any(|r| r.name == route.name) {
+                    continue;
+                }
+                // Only complain about routes that actually pass through the map
+                if let Some(pt) = route
+                    .stops
+                    .iter()
+                    .find_map(|gps| Pt2D::from_gps(*gps, &m.gps_bounds))
+                {
+                    let why = if made_routes.contains(&route.name) {
+                        "some of its stops aren't connected"
+                    } else {
+                        "it doesn't have 2 stops next to driving lanes in the map"
+                    };
+                    m.validation.add(
+                        IssueType::DroppedBusRoute,
+                        format!("Dropped bus route {}, because {}", route.name, why),
+                        pt,
+                        &m.gps_bounds,
+                    );
+                }
+            }
 
             // Remove orphaned bus stops
             let mut remove_stops = HashSet::new();
@@ -183,6 +232,15 @@ impl Map {
         }
         timer.stop("find parking blackholes");
 
+        timer.start("validate map");
+        let more_issues = MapValidationReport::check_map(&m).issues;
+        m.validation.issues.extend(more_issues);
+        timer.note(format!(
+            "{} map validation issues",
+            m.validation.issues.len()
+        ));
+        timer.stop("validate map");
+
         timer.stop("finalize Map");
         m
     }
@@ -216,7 +274,19 @@ impl Map {
     }
 
     pub fn maybe_get_l(&self, id: LaneID) -> Option<&Lane> {
-        self.lanes.get(id.0)
+        if self.lane_exists(id) {
+            Some(&self.lanes[id.0])
+        } else {
+            None
+        }
+    }
+
+    // all_lanes() includes lanes that edits have removed from their road; this doesn't.
+    pub fn lane_exists(&self, id: LaneID) -> bool {
+        self.lanes
+            .get(id.0)
+            .map(|l| self.roads[l.parent.0].all_lanes().contains(&id))
+            .unwrap_or(false)
     }
 
     pub fn maybe_get_i(&self, id: IntersectionID) -> Option<&Intersection> {
@@ -279,6 +349,20 @@ impl Map {
         &self.traffic_signals[&id]
     }
 
+    // Without the turn overrides. Edits to the policy should start from this, since the overrides
+    // get applied on top of whatever's stored.
+    pub fn get_unpatched_stop_sign(&self, id: IntersectionID) -> &ControlStopSign {
+        self.unpatched_stop_signs
+            .get(&id)
+            .unwrap_or_else(|| self.get_stop_sign(id))
+    }
+
+    pub fn get_unpatched_traffic_signal(&self, id: IntersectionID) -> &ControlTrafficSignal {
+        self.unpatched_traffic_signals
+            .get(&id)
+            .unwrap_or_else(|| self.get_traffic_signal(id))
+    }
+
     pub fn lookup_turn_by_idx(&self, idx: usize) -> Option<TurnID> {
         self.turn_lookup.get(idx).cloned()
     }
@@ -386,6 +470,10 @@ impl Map {
         self.get_r(l.parent)
     }
 
+    pub fn get_validation_report(&self) -> &MapValidationReport {
+        &self.validation
+    }
+
     pub fn get_gps_bounds(&self) -> &GPSBounds {
         &self.gps_bounds
     }
@@ -546,6 +634,35 @@ impl Map {
         }
     }
 
+    // Explicitly allowing any turn between two roads lifts the OSM restrictions on that movement
+    // at just that intersection.
+    pub fn is_restriction_lifted(&self, at: IntersectionID, from: RoadID, to: RoadID) -> bool {
+        self.edits.turn_overrides.iter().any(|(t, o)| {
+            *o == TurnOverride::Allowed
+                && t.parent == at
+                && self.get_l(t.src).parent == from
+                && self.get_l(t.dst).parent == to
+        })
+    }
+
+    // lane_types are what the agent may use, like in VehiclePathfinder. Pedestrians use sidewalks.
+    pub fn is_lane_closed(&self, l: LaneID, lane_types: &[LaneType]) -> bool {
+        self.edits
+            .lane_closures
+            .get(&l)
+            .into_iter()
+            .chain(self.edits.road_closures.get(&self.get_l(l).parent))
+            .any(|c| c.applies_to(lane_types))
+    }
+
+    pub fn is_intersection_closed(&self, i: IntersectionID, lane_types: &[LaneType]) -> bool {
+        self.edits
+            .intersection_closures
+            .get(&i)
+            .map(|c| c.applies_to(lane_types))
+            .unwrap_or(false)
+    }
+
     // Cars trying to park near this building should head for the driving lane returned here, then
     // start their search. Some parking lanes are connected to driving lanes that're "parking
     // blackholes" -- if there are no free spots on that lane, then the roads force cars to a
@@ -602,6 +719,16 @@ impl Map {
         self.pathfinder.as_ref().unwrap().pathfind(req, self)
     }
 
+    // Like pathfind, but honors turn restrictions that're only active at some times or span
+    // several roads.
+    pub fn pathfind_at_time(&self, req: PathRequest, time: Duration) -> Option<Path> {
+        assert!(!self.pathfinder_dirty);
+        self.pathfinder
+            .as_ref()
+            .unwrap()
+            .pathfind_at_time(req, self, time)
+    }
+
     pub fn should_use_transit(
         &self,
         start: Position,
@@ -625,55 +752,225 @@ impl Map {
         new_edits: MapEdits,
         timer: &mut Timer,
     ) -> (BTreeSet<LaneID>, BTreeSet<TurnID>, BTreeSet<TurnID>) {
+        let old_edits = self.edits.active_at(self.edits_time);
+        let active_edits = new_edits.active_at(self.edits_time);
+        self.edits = new_edits;
+        self.apply_active_edits(old_edits, active_edits, timer)
+    }
+
+    // Scheduled edits whose window starts or ends by this time of day take effect. Same results
+    // as apply_edits.
+    pub fn set_edits_time(
+        &mut self,
+        time: Duration,
+        timer: &mut Timer,
+    ) -> (BTreeSet<LaneID>, BTreeSet<TurnID>, BTreeSet<TurnID>) {
+        let old_edits = self.edits.active_at(self.edits_time);
+        let active_edits = self.edits.active_at(time);
+        self.edits_time = time;
+        self.apply_active_edits(old_edits, active_edits, timer)
+    }
+
+    pub fn get_edits_time(&self) -> Duration {
+        self.edits_time
+    }
+
+    // Both sets of edits already have the scheduled edits for the current time merged in.
+    fn apply_active_edits(
+        &mut self,
+        old_edits: MapEdits,
+        new_edits: MapEdits,
+        timer: &mut Timer,
+    ) -> (BTreeSet<LaneID>, BTreeSet<TurnID>, BTreeSet<TurnID>) {
+        let mut changed_lanes = BTreeSet::new();
+        let mut changed_intersections = BTreeSet::new();
+        let mut changed_roads = BTreeSet::new();
+
+        // Lanes come and go first, so the per-lane overrides below apply to the new lanes.
+        let mut relaid_roads = BTreeSet::new();
+        for (id, cs) in &new_edits.cross_section_overrides {
+            if old_edits.cross_section_overrides.get(id) != Some(cs) {
+                if let Err(err) = self.check_cross_section(*id, cs) {
+                    timer.warn(format!("Can't change the lanes of {}: {}", id, err));
+                }
+                relaid_roads.insert(*id);
+            }
+        }
+        for id in old_edits.cross_section_overrides.keys() {
+            if !new_edits.cross_section_overrides.contains_key(id) {
+                relaid_roads.insert(*id);
+            }
+        }
+        let mut relabeled_lanes = BTreeSet::new();
+        let mut reshaped_intersections = BTreeSet::new();
+        if !relaid_roads.is_empty() {
+            let cross_sections = self.valid_cross_sections(&new_edits.cross_section_overrides);
+            let plan = self.plan_lanes(&cross_sections);
+            // Lanes added to one road can renumber the added lanes of later roads.
+            for id in plan.keys().chain(self.original_lanes.keys()) {
+                let planned = plan
+                    .get(id)
+                    .cloned()
+                    .unwrap_or_else(|| self.get_original_lanes(*id));
+                let r = &self.roads[id.0];
+                let current: (Vec<LaneID>, Vec<LaneID>) = (
+                    r.children_forwards.iter().map(|(l, _)| *l).collect(),
+                    r.children_backwards.iter().map(|(l, _)| *l).collect(),
+                );
+                if planned != current {
+                    relaid_roads.insert(*id);
+                }
+            }
+            for id in &relaid_roads {
+                changed_lanes.extend(self.roads[id.0].all_lanes());
+            }
+            self.relayout_lanes(&relaid_roads, &cross_sections, &plan, timer);
+            for id in &relaid_roads {
+                changed_lanes.extend(self.roads[id.0].all_lanes());
+                relabeled_lanes.extend(self.roads[id.0].all_lanes());
+                changed_intersections.insert(self.roads[id.0].src_i);
+                changed_intersections.insert(self.roads[id.0].dst_i);
+                reshaped_intersections.insert(self.roads[id.0].src_i);
+                reshaped_intersections.insert(self.roads[id.0].dst_i);
+                changed_roads.insert(*id);
+            }
+        }
+
         // Ignore if there's no change from current
         let mut all_lane_edits: BTreeMap<LaneID, LaneType> = BTreeMap::new();
+        let mut all_allowed_turns_edits: BTreeMap<LaneID, Option<BTreeSet<TurnType>>> =
+            BTreeMap::new();
+        let mut all_width_edits: BTreeMap<LaneID, Distance> = BTreeMap::new();
+        // None means revert to the original limits
+        let mut all_speed_limit_edits: BTreeMap<RoadID, Option<Speed>> = BTreeMap::new();
         let mut all_stop_sign_edits: BTreeMap<IntersectionID, ControlStopSign> = BTreeMap::new();
         let mut all_traffic_signals: BTreeMap<IntersectionID, ControlTrafficSignal> =
             BTreeMap::new();
+        let mut all_intersection_type_edits: BTreeMap<IntersectionID, IntersectionType> =
+            BTreeMap::new();
         for (id, lt) in &new_edits.lane_overrides {
-            if self.edits.lane_overrides.get(id) != Some(lt) {
+            if old_edits.lane_overrides.get(id) != Some(lt) {
                 all_lane_edits.insert(*id, *lt);
             }
         }
+        for (id, turns) in &new_edits.allowed_turns_overrides {
+            if old_edits.allowed_turns_overrides.get(id) != Some(turns) {
+                all_allowed_turns_edits.insert(*id, Some(turns.clone()));
+            }
+        }
+        for (id, width) in &new_edits.lane_width_overrides {
+            if old_edits.lane_width_overrides.get(id) != Some(width) {
+                all_width_edits.insert(*id, *width);
+            }
+        }
+        for (id, speed) in &new_edits.speed_limit_overrides {
+            if old_edits.speed_limit_overrides.get(id) != Some(speed) {
+                all_speed_limit_edits.insert(*id, Some(*speed));
+            }
+        }
         for (id, ss) in &new_edits.stop_sign_overrides {
-            if self.edits.stop_sign_overrides.get(id) != Some(ss) {
+            if old_edits.stop_sign_overrides.get(id) != Some(ss) {
                 all_stop_sign_edits.insert(*id, ss.clone());
             }
         }
         for (id, ts) in &new_edits.traffic_signal_overrides {
-            if self.edits.traffic_signal_overrides.get(id) != Some(ts) {
+            if old_edits.traffic_signal_overrides.get(id) != Some(ts) {
                 all_traffic_signals.insert(*id, ts.clone());
             }
         }
+        // Lanes of roads with a new cross-section have lost their per-lane edits.
+        for id in &relabeled_lanes {
+            if let Some(lt) = new_edits.lane_overrides.get(id) {
+                all_lane_edits.insert(*id, *lt);
+            }
+            if let Some(turns) = new_edits.allowed_turns_overrides.get(id) {
+                all_allowed_turns_edits.insert(*id, Some(turns.clone()));
+            }
+            if let Some(width) = new_edits.lane_width_overrides.get(id) {
+                all_width_edits.insert(*id, *width);
+            }
+        }
+        for (id, it) in &new_edits.intersection_type_overrides {
+            if old_edits.intersection_type_overrides.get(id) != Some(it) {
+                all_intersection_type_edits.insert(*id, *it);
+            }
+        }
+        let mut turn_override_intersections = BTreeSet::new();
+        for (id, o) in &new_edits.turn_overrides {
+            if old_edits.turn_overrides.get(id) != Some(o) {
+                turn_override_intersections.insert(id.parent);
+            }
+        }
+        for id in old_edits.turn_overrides.keys() {
+            if !new_edits.turn_overrides.contains_key(id) {
+                turn_override_intersections.insert(id.parent);
+            }
+        }
 
         // May need to revert some previous changes
-        for id in self.edits.lane_overrides.keys() {
+        for id in old_edits.lane_overrides.keys() {
             if !new_edits.lane_overrides.contains_key(id) {
-                all_lane_edits.insert(*id, self.get_original_lt(*id));
+                if let Some(lt) = self.get_original_lt(*id) {
+                    all_lane_edits.insert(*id, lt);
+                }
+            }
+        }
+        for id in old_edits.allowed_turns_overrides.keys() {
+            if !new_edits.allowed_turns_overrides.contains_key(id) && self.lane_exists(*id) {
+                all_allowed_turns_edits.insert(*id, self.get_original_allowed_turns(*id));
+            }
+        }
+        for id in old_edits.lane_width_overrides.keys() {
+            if !new_edits.lane_width_overrides.contains_key(id) {
+                if let Some(width) = self.get_original_width(*id) {
+                    all_width_edits.insert(*id, width);
+                }
             }
         }
-        for id in self.edits.stop_sign_overrides.keys() {
-            if !new_edits.stop_sign_overrides.contains_key(id) {
-                all_stop_sign_edits.insert(*id, ControlStopSign::new(self, *id, timer));
+        for id in old_edits.speed_limit_overrides.keys() {
+            if !new_edits.speed_limit_overrides.contains_key(id) {
+                all_speed_limit_edits.insert(*id, None);
             }
         }
-        for id in self.edits.traffic_signal_overrides.keys() {
-            if !new_edits.traffic_signal_overrides.contains_key(id) {
+        for id in old_edits.intersection_type_overrides.keys() {
+            if !new_edits.intersection_type_overrides.contains_key(id) {
+                all_intersection_type_edits.insert(*id, self.get_original_intersection_type(*id));
+            }
+        }
+        // Changing the type recreates the default policy anyway.
+        for id in old_edits.stop_sign_overrides.keys() {
+            if !new_edits.stop_sign_overrides.contains_key(id)
+                && !all_intersection_type_edits.contains_key(id)
+            {
+                all_stop_sign_edits.insert(*id, self.default_stop_sign(*id, timer));
+            }
+        }
+        for id in old_edits.traffic_signal_overrides.keys() {
+            if !new_edits.traffic_signal_overrides.contains_key(id)
+                && !all_intersection_type_edits.contains_key(id)
+            {
                 all_traffic_signals.insert(*id, ControlTrafficSignal::new(self, *id, timer));
             }
         }
 
         timer.note(format!(
-            "Total diff: {} lanes, {} stop signs, {} traffic signals",
+            "Total diff: {} roads with different lanes, {} lanes, {} lanes with different turns, {} lane widths, {} speed limits, {} stop signs, {} traffic signals, {} intersection types, {} intersections with turn overrides",
+            relaid_roads.len(),
             all_lane_edits.len(),
+            all_allowed_turns_edits.len(),
+            all_width_edits.len(),
+            all_speed_limit_edits.len(),
             all_stop_sign_edits.len(),
-            all_traffic_signals.len()
+            all_traffic_signals.len(),
+            all_intersection_type_edits.len(),
+            turn_override_intersections.len()
         ));
 
-        let mut changed_lanes = BTreeSet::new();
-        let mut changed_intersections = BTreeSet::new();
-        let mut changed_roads = BTreeSet::new();
         for (id, lt) in all_lane_edits {
+            // Edits to lanes that have been removed don't apply.
+            if !self.lane_exists(id) {
+                continue;
+            }
             changed_lanes.insert(id);
 
             let l = &mut self.lanes[id.0];
@@ -691,6 +988,69 @@ impl Map {
             changed_intersections.insert(l.dst_i);
             changed_roads.insert(l.parent);
         }
+        // Only the turns at the end of the lane change.
+        for (id, turns) in all_allowed_turns_edits {
+            if !self.lane_exists(id) {
+                continue;
+            }
+            changed_lanes.insert(id);
+            let l = &mut self.lanes[id.0];
+            l.allowed_turns = turns;
+            changed_intersections.insert(l.dst_i);
+        }
+
+        // Resizing one lane shifts every lane further from the center.
+        let mut resized_roads = BTreeSet::new();
+        for (id, width) in all_width_edits {
+            if !self.lane_exists(id) {
+                continue;
+            }
+            self.lanes[id.0].width = width;
+            resized_roads.insert(self.lanes[id.0].parent);
+        }
+        for id in resized_roads {
+            self.recalculate_lane_geometry(id, timer);
+            let r = &self.roads[id.0];
+            changed_lanes.extend(r.all_lanes());
+            changed_intersections.insert(r.src_i);
+            changed_intersections.insert(r.dst_i);
+            reshaped_intersections.insert(r.src_i);
+            reshaped_intersections.insert(r.dst_i);
+            changed_roads.insert(id);
+        }
+        // The roads now end at a different spot, so the turns below need the new shape.
+        for id in reshaped_intersections {
+            self.recalculate_intersection_polygon(id, timer);
+        }
+
+        // Agents look up the speed limit as they go, so nothing else needs to be recalculated.
+        // The turns don't change, just who controls them. The new policy gets created below.
+        for (id, it) in all_intersection_type_edits {
+            let i = &mut self.intersections[id.0];
+            if i.intersection_type == IntersectionType::Border || it == IntersectionType::Border {
+                timer.warn(format!("Can't change {} to or from a border", id));
+                continue;
+            }
+            self.original_intersection_types
+                .entry(id)
+                .or_insert(i.intersection_type);
+            i.intersection_type = it;
+            self.stop_signs.remove(&id);
+            self.traffic_signals.remove(&id);
+            changed_intersections.insert(id);
+            changed_lanes.extend(i.incoming_lanes.clone());
+        }
+
+        for (id, speed) in all_speed_limit_edits {
+            let r = &mut self.roads[id.0];
+            let (fwds, back) = match speed {
+                Some(s) => (s, s),
+                None => road::speed_limits_from_osm(&r.osm_tags),
+            };
+            r.speed_limit_forwards = fwds;
+            r.speed_limit_backwards = back;
+            changed_lanes.extend(r.all_lanes());
+        }
 
         for id in changed_roads {
             let stops = self.get_r(id).all_bus_stops(self);
@@ -706,9 +1066,27 @@ impl Map {
             }
         }
 
+        for id in turn_override_intersections {
+            changed_intersections.insert(id);
+            changed_lanes.extend(self.get_i(id).incoming_lanes.clone());
+        }
+        // Every changed intersection gets a default policy below, so any policy edits have to be
+        // reapplied on top of it.
+        for id in &changed_intersections {
+            if let Some(ss) = new_edits.stop_sign_overrides.get(id) {
+                all_stop_sign_edits.insert(*id, ss.clone());
+            }
+            if let Some(ts) = new_edits.traffic_signal_overrides.get(id) {
+                all_traffic_signals.insert(*id, ts.clone());
+            }
+        }
+
         // Recompute turns and intersection policy
         let mut delete_turns = BTreeSet::new();
         let mut add_turns = BTreeSet::new();
+        let mut patch_policies = changed_intersections.clone();
+        patch_policies.extend(all_stop_sign_edits.keys().cloned());
+        patch_policies.extend(all_traffic_signals.keys().cloned());
         for id in changed_intersections {
             let i = &mut self.intersections[id.0];
 
@@ -736,22 +1114,31 @@ impl Map {
             // TODO Deal with turn_lookup
 
             // Do this before applying intersection policy edits.
-            match i.intersection_type {
-                IntersectionType::StopSign => {
-                    self.stop_signs
-                        .insert(id, ControlStopSign::new(self, id, timer));
-                }
-                IntersectionType::TrafficSignal => {
-                    self.traffic_signals
-                        .insert(id, ControlTrafficSignal::new(self, id, timer));
-                }
-                IntersectionType::Border => unreachable!(),
+            if i.intersection_type == IntersectionType::TrafficSignal {
+                self.traffic_signals
+                    .insert(id, ControlTrafficSignal::new(self, id, timer));
+            } else {
+                let ss = self.default_stop_sign(id, timer);
+                self.stop_signs.insert(id, ss);
             }
         }
 
         // Make sure all of the turns of modified intersections are re-added in the pathfinder;
         // they might've become banned. Lane markings may also change based on turn priorities.
         for (id, ss) in all_stop_sign_edits {
+            // A policy for the old type of intersection no longer applies.
+            if !self.get_i(id).intersection_type.is_unsignalized() {
+                continue;
+            }
+            // Lane edits might've changed the turns since the policy was made.
+            let turns: BTreeSet<TurnID> = ss.turns.keys().cloned().collect();
+            if turns != self.get_i(id).turns.iter().cloned().collect() {
+                timer.warn(format!(
+                    "Stop sign edits for {} don't match its turns anymore",
+                    id
+                ));
+                continue;
+            }
             self.stop_signs.insert(id, ss);
             for t in &self.get_i(id).turns {
                 add_turns.insert(*t);
@@ -761,6 +1148,20 @@ impl Map {
             }
         }
         for (id, ts) in all_traffic_signals {
+            if self.get_i(id).intersection_type != IntersectionType::TrafficSignal {
+                continue;
+            }
+            let mut turns: BTreeSet<TurnID> = BTreeSet::new();
+            for cycle in &ts.cycles {
+                turns.extend(cycle.priority_turns.iter().chain(cycle.yield_turns.iter()));
+            }
+            if turns != self.get_i(id).turns.iter().cloned().collect() {
+                timer.warn(format!(
+                    "Traffic signal edits for {} don't match its turns anymore",
+                    id
+                ));
+                continue;
+            }
             self.traffic_signals.insert(id, ts);
             for t in &self.get_i(id).turns {
                 add_turns.insert(*t);
@@ -769,12 +1170,184 @@ impl Map {
                 changed_lanes.insert(*l);
             }
         }
+        for id in patch_policies {
+            self.apply_turn_overrides(id, &new_edits.turn_overrides);
+        }
 
-        self.edits = new_edits;
         self.pathfinder_dirty = true;
         (changed_lanes, delete_turns, add_turns)
     }
 
+    // Turn overrides win over the stop sign or traffic signal. Allowing a turn the policy bans
+    // makes everyone stop or yield for it.
+    fn apply_turn_overrides(
+        &mut self,
+        id: IntersectionID,
+        overrides: &BTreeMap<TurnID, TurnOverride>,
+    ) {
+        // The policy was just replaced, so anything saved from before is stale.
+        self.unpatched_stop_signs.remove(&id);
+        self.unpatched_traffic_signals.remove(&id);
+        // The lanes of an overridden turn might be gone or not connect anymore.
+        let overrides: Vec<(TurnID, TurnOverride)> = overrides
+            .iter()
+            .filter(|(t, _)| t.parent == id && self.turns.contains_key(t))
+            .map(|(t, o)| (*t, *o))
+            .collect();
+        if overrides.is_empty() {
+            return;
+        }
+
+        if let Some(mut ss) = self.stop_signs.remove(&id) {
+            self.unpatched_stop_signs.insert(id, ss.clone());
+            for (t, o) in overrides {
+                match o {
+                    TurnOverride::Banned => ss.change(t, TurnPriority::Banned, self),
+                    TurnOverride::Allowed => {
+                        if ss.turns.get(&t) == Some(&TurnPriority::Banned) {
+                            ss.change(t, TurnPriority::Stop, self);
+                        }
+                    }
+                }
+            }
+            self.stop_signs.insert(id, ss);
+        } else if let Some(mut ts) = self.traffic_signals.remove(&id) {
+            self.unpatched_traffic_signals.insert(id, ts.clone());
+            for (t, o) in overrides {
+                let turn = &self.turns[&t];
+                match o {
+                    TurnOverride::Banned => {
+                        for cycle in ts.cycles.iter_mut() {
+                            cycle.edit_turn(turn, TurnPriority::Banned);
+                        }
+                    }
+                    TurnOverride::Allowed => {
+                        if ts
+                            .cycles
+                            .iter()
+                            .all(|c| c.get_priority(t) == TurnPriority::Banned)
+                        {
+                            for cycle in ts.cycles.iter_mut() {
+                                cycle.edit_turn(turn, TurnPriority::Yield);
+                            }
+                        }
+                    }
+                }
+            }
+            self.traffic_signals.insert(id, ts);
+        }
+    }
+
+    // The roads aren't trimmed again, so this just wraps a new polygon around their ends. Like
+    // the initial geometry, dead-ends get a stub past the end of the road.
+    fn recalculate_intersection_polygon(&mut self, id: IntersectionID, timer: &mut Timer) {
+        // The right and left corners of every road, oriented to point into the intersection
+        let mut corners: Vec<(Line, Pt2D, Pt2D)> = Vec::new();
+        for r in &self.intersections[id.0].roads {
+            let r = &self.roads[r.0];
+            let (center, width_right, width_left) = if r.dst_i == id {
+                (r.center_pts.clone(), r.fwd_width, r.back_width)
+            } else {
+                (r.center_pts.reversed(), r.back_width, r.fwd_width)
+            };
+            let right = if width_right == Distance::ZERO {
+                center.last_pt()
+            } else {
+                center
+                    .shift_right(width_right)
+                    .with_context(timer, format!("polygon corners from {}", r.id))
+                    .last_pt()
+            };
+            let left = if width_left == Distance::ZERO {
+                center.last_pt()
+            } else {
+                center
+                    .shift_left(width_left)
+                    .with_context(timer, format!("polygon corners from {}", r.id))
+                    .last_pt()
+            };
+            corners.push((center.last_line(), right, left));
+        }
+
+        let mut pts = if corners.len() == 1 {
+            let (line, right, left) = corners.pop().unwrap();
+            let len = make::DEGENERATE_INTERSECTION_HALF_LENGTH * 2.0;
+            vec![
+                right,
+                left,
+                left.project_away(len, line.angle()),
+                right.project_away(len, line.angle()),
+            ]
+        } else {
+            let mut pts: Vec<Pt2D> = corners
+                .into_iter()
+                .flat_map(|(_, right, left)| vec![right, left])
+                .collect();
+            pts.sort_by_key(|pt| pt.to_hashable());
+            pts = Pt2D::approx_dedupe(pts, Distance::meters(0.1));
+            let center = Pt2D::center(&pts);
+            pts.sort_by_key(|pt| pt.angle_to(center).normalized_degrees() as i64);
+            pts
+        };
+        if pts.len() < 3 {
+            timer.warn(format!(
+                "Can't make a new polygon for {}, keeping the old one",
+                id
+            ));
+            return;
+        }
+        pts.push(pts[0]);
+        self.intersections[id.0].polygon = Polygon::new(&pts);
+    }
+
+    fn recalculate_lane_geometry(&mut self, id: RoadID, timer: &mut Timer) {
+        let r = &mut self.roads[id.0];
+        for (fwds, children) in vec![(true, &r.children_forwards), (false, &r.children_backwards)] {
+            let unshifted_pts = if fwds {
+                r.center_pts.clone()
+            } else {
+                r.center_pts.reversed()
+            };
+            let mut offset = Distance::ZERO;
+            for (l, _) in children {
+                let lane = &mut self.lanes[l.0];
+                lane.lane_center_pts = unshifted_pts
+                    .shift_right(offset + lane.width / 2.0)
+                    .with_context(timer, format!("shift for {}", l));
+                offset += lane.width;
+            }
+            if fwds {
+                r.fwd_width = offset;
+            } else {
+                r.back_width = offset;
+            }
+        }
+
+        // The lanes got a bit longer or shorter, so keep everything positioned along them in
+        // bounds.
+        for l in r.all_lanes() {
+            let length = self.lanes[l.0].length();
+            for b in self.lanes[l.0].building_paths.clone() {
+                let pos = self.buildings[b.0].front_path.sidewalk;
+                if pos.dist_along() > length {
+                    let pos = Position::new(l, length);
+                    let bldg = &mut self.buildings[b.0];
+                    bldg.front_path.sidewalk = pos;
+                    bldg.front_path.line = Line::new(
+                        bldg.front_path.line.pt1(),
+                        self.lanes[l.0].dist_along(length).0,
+                    );
+                }
+            }
+            for bs in self.lanes[l.0].bus_stops.clone() {
+                let stop = self.bus_stops.get_mut(&bs).unwrap();
+                if stop.sidewalk_pos.dist_along() > length {
+                    stop.sidewalk_pos = Position::new(l, length);
+                }
+            }
+        }
+    }
+
     pub fn recalculate_pathfinding_after_edits(&mut self, timer: &mut Timer) {
         if !self.pathfinder_dirty {
             return;
@@ -800,7 +1373,7 @@ impl Map {
     pub fn simplify_edits(&mut self, timer: &mut Timer) {
         let mut delete_lanes = Vec::new();
         for (id, lt) in &self.edits.lane_overrides {
-            if *lt == self.get_original_lt(*id) {
+            if self.get_original_lt(*id) == Some(*lt) {
                 delete_lanes.push(*id);
             }
         }
@@ -808,9 +1381,49 @@ impl Map {
             self.edits.lane_overrides.remove(&id);
         }
 
+        let mut delete_allowed_turns = Vec::new();
+        for (id, turns) in &self.edits.allowed_turns_overrides {
+            if Some(turns) == self.get_original_allowed_turns(*id).as_ref() {
+                delete_allowed_turns.push(*id);
+            }
+        }
+        for id in delete_allowed_turns {
+            self.edits.allowed_turns_overrides.remove(&id);
+        }
+
+        let mut delete_widths = Vec::new();
+        for (id, width) in &self.edits.lane_width_overrides {
+            if self.get_original_width(*id) == Some(*width) {
+                delete_widths.push(*id);
+            }
+        }
+        for id in delete_widths {
+            self.edits.lane_width_overrides.remove(&id);
+        }
+
+        let mut delete_cross_sections = Vec::new();
+        for (id, cs) in &self.edits.cross_section_overrides {
+            if *cs == self.get_original_cross_section(*id) {
+                delete_cross_sections.push(*id);
+            }
+        }
+        for id in delete_cross_sections {
+            self.edits.cross_section_overrides.remove(&id);
+        }
+
+        let mut delete_speed_limits = Vec::new();
+        for (id, speed) in &self.edits.speed_limit_overrides {
+            if road::speed_limits_from_osm(&self.get_r(*id).osm_tags) == (*speed, *speed) {
+                delete_speed_limits.push(*id);
+            }
+        }
+        for id in delete_speed_limits {
+            self.edits.speed_limit_overrides.remove(&id);
+        }
+
         let mut delete_stop_signs = Vec::new();
         for (id, ss) in &self.edits.stop_sign_overrides {
-            if *ss == ControlStopSign::new(self, *id, timer) {
+            if *ss == self.default_stop_sign(*id, timer) {
                 delete_stop_signs.push(*id);
             }
         }
@@ -827,20 +1440,383 @@ impl Map {
         for id in delete_signals {
             self.edits.traffic_signal_overrides.remove(&id);
         }
+
+        let mut delete_intersection_types = Vec::new();
+        for (id, it) in &self.edits.intersection_type_overrides {
+            if *it == self.get_original_intersection_type(*id) {
+                delete_intersection_types.push(*id);
+            }
+        }
+        for id in delete_intersection_types {
+            self.edits.intersection_type_overrides.remove(&id);
+        }
+
+        // Forget who made edits that are gone now.
+        let delete_provenance: Vec<EditID> = self
+            .edits
+            .provenance
+            .keys()
+            .filter(|id| !self.edits.contains(**id))
+            .cloned()
+            .collect();
+        for id in delete_provenance {
+            self.edits.provenance.remove(&id);
+        }
+    }
+
+    fn get_original_cross_section(&self, id: RoadID) -> CrossSection {
+        let r = self.get_r(id);
+        let (side1, side2) = get_lane_types(&r.osm_tags, r.parking_lane_fwd, r.parking_lane_back);
+        let (widths1, widths2) = get_lane_widths(&r.osm_tags, &side1, &side2);
+        CrossSection {
+            fwd: side1.into_iter().zip(widths1.into_iter()).collect(),
+            back: side2.into_iter().zip(widths2.into_iter()).collect(),
+        }
+    }
+
+    fn check_cross_section(&self, id: RoadID, cs: &CrossSection) -> Result<(), String> {
+        let r = self.get_r(id);
+        let (fwd, back) = r.get_lane_types();
+        let num_sidewalks = |lts: Vec<LaneType>| {
+            lts.into_iter()
+                .filter(|lt| *lt == LaneType::Sidewalk)
+                .count()
+        };
+        if num_sidewalks(fwd) != num_sidewalks(cs.fwd.iter().map(|(lt, _)| *lt).collect())
+            || num_sidewalks(back) != num_sidewalks(cs.back.iter().map(|(lt, _)| *lt).collect())
+        {
+            return Err("sidewalks can't be added or removed".to_string());
+        }
+        if cs.fwd.is_empty() && cs.back.is_empty() {
+            return Err("no lanes left".to_string());
+        }
+        if cs
+            .fwd
+            .iter()
+            .chain(cs.back.iter())
+            .any(|(_, width)| *width <= Distance::ZERO)
+        {
+            return Err("every lane needs some width".to_string());
+        }
+        if !r.all_bus_stops(self).is_empty()
+            && !cs
+                .fwd
+                .iter()
+                .chain(cs.back.iter())
+                .any(|(lt, _)| *lt == LaneType::Driving || *lt == LaneType::Bus)
+        {
+            return Err("buses couldn't reach the bus stops".to_string());
+        }
+        Ok(())
+    }
+
+    // The cross-section edits that pass check_cross_section
+    fn valid_cross_sections(
+        &self,
+        cross_sections: &BTreeMap<RoadID, CrossSection>,
+    ) -> BTreeMap<RoadID, CrossSection> {
+        cross_sections
+            .iter()
+            .filter(|(id, cs)| self.check_cross_section(**id, cs).is_ok())
+            .map(|(id, cs)| (*id, cs.clone()))
+            .collect()
+    }
+
+    // Which lanes each road gets for these cross-sections, per direction. A road's original lanes
+    // are reused in order, sidewalks only for sidewalks. Lanes beyond those are numbered in RoadID
+    // order, so the IDs only depend on the cross-sections, not the order they were edited in.
+    fn plan_lanes(
+        &self,
+        cross_sections: &BTreeMap<RoadID, CrossSection>,
+    ) -> BTreeMap<RoadID, (Vec<LaneID>, Vec<LaneID>)> {
+        let mut next_id = self.first_added_lane;
+        let mut plan = BTreeMap::new();
+        for (id, cs) in cross_sections {
+            let (orig_fwd, orig_back) = self.get_original_lanes(*id);
+            let orig_cs = self.get_original_cross_section(*id);
+            let fwd = plan_side(&orig_fwd, &orig_cs.fwd, &cs.fwd, &mut next_id);
+            let back = plan_side(&orig_back, &orig_cs.back, &cs.back, &mut next_id);
+            plan.insert(*id, (fwd, back));
+        }
+        plan
     }
 
-    fn get_original_lt(&self, id: LaneID) -> LaneType {
+    fn get_original_lanes(&self, id: RoadID) -> (Vec<LaneID>, Vec<LaneID>) {
+        if let Some(lanes) = self.original_lanes.get(&id) {
+            return lanes.clone();
+        }
+        let r = &self.roads[id.0];
+        (
+            r.children_forwards.iter().map(|(l, _)| *l).collect(),
+            r.children_backwards.iter().map(|(l, _)| *l).collect(),
+        )
+    }
+
+    // Where each lane added by these cross-sections sits: its road, direction, and offset from the
+    // center.
+    pub(crate) fn added_lane_positions(
+        &self,
+        cross_sections: &BTreeMap<RoadID, CrossSection>,
+    ) -> BTreeMap<(RoadID, bool, usize), LaneID> {
+        let mut positions = BTreeMap::new();
+        for (id, (fwd, back)) in self.plan_lanes(&self.valid_cross_sections(cross_sections)) {
+            for (fwds, lanes) in vec![(true, fwd), (false, back)] {
+                for (idx, l) in lanes.into_iter().enumerate() {
+                    if l.0 >= self.first_added_lane {
+                        positions.insert((id, fwds, idx), l);
+                    }
+                }
+            }
+        }
+        positions
+    }
+
+    // How the added lanes get renumbered when one set of cross-sections is swapped for another.
+    // Lanes that don't exist afterwards map to None.
+    pub(crate) fn added_lane_renumbering(
+        &self,
+        before: &BTreeMap<RoadID, CrossSection>,
+        after: &BTreeMap<RoadID, CrossSection>,
+    ) -> BTreeMap<LaneID, Option<LaneID>> {
+        let new_positions = self.added_lane_positions(after);
+        let mut renumber = BTreeMap::new();
+        for (pos, l) in self.added_lane_positions(before) {
+            let new_id = new_positions.get(&pos).cloned();
+            if new_id != Some(l) {
+                renumber.insert(l, new_id);
+            }
+        }
+        renumber
+    }
+
+    // Lanes added by cross-section edits are numbered in RoadID order, so changing the lanes of
+    // one road can renumber the added lanes of later roads. Call after changing the
+    // cross-sections in edits, so that the other edits still refer to the same lanes.
+    pub fn renumber_added_lanes(&self, before: &MapEdits, after: &mut MapEdits) {
+        let renumber = self.added_lane_renumbering(
+            &before.cross_section_overrides,
+            &after.cross_section_overrides,
+        );
+        if !renumber.is_empty() {
+            after.renumber_lanes(&renumber);
+        }
+    }
+
+    // Added lanes can move between roads, so unhook every old lane before hooking up new ones.
+    fn relayout_lanes(
+        &mut self,
+        roads: &BTreeSet<RoadID>,
+        cross_sections: &BTreeMap<RoadID, CrossSection>,
+        plan: &BTreeMap<RoadID, (Vec<LaneID>, Vec<LaneID>)>,
+        timer: &mut Timer,
+    ) {
+        for id in roads {
+            if !self.original_lanes.contains_key(id) {
+                let lanes = self.get_original_lanes(*id);
+                self.original_lanes.insert(*id, lanes);
+            }
+            for l in self.roads[id.0].all_lanes() {
+                let (src_i, dst_i) = (self.lanes[l.0].src_i, self.lanes[l.0].dst_i);
+                self.intersections[src_i.0]
+                    .outgoing_lanes
+                    .retain(|x| *x != l);
+                self.intersections[dst_i.0]
+                    .incoming_lanes
+                    .retain(|x| *x != l);
+            }
+        }
+
+        for id in roads {
+            let cs = cross_sections
+                .get(id)
+                .cloned()
+                .unwrap_or_else(|| self.get_original_cross_section(*id));
+            let (fwd, back) = plan
+                .get(id)
+                .cloned()
+                .unwrap_or_else(|| self.original_lanes[id].clone());
+            let children_forwards = self.place_lanes(*id, true, &fwd, &cs.fwd);
+            let children_backwards = self.place_lanes(*id, false, &back, &cs.back);
+            let r = &mut self.roads[id.0];
+            r.children_forwards = children_forwards;
+            r.children_backwards = children_backwards;
+
+            for l in self.roads[id.0].all_lanes() {
+                let (src_i, dst_i) = (self.lanes[l.0].src_i, self.lanes[l.0].dst_i);
+                self.intersections[src_i.0].outgoing_lanes.push(l);
+                self.intersections[dst_i.0].incoming_lanes.push(l);
+                self.lanes[l.0].allowed_turns = self.get_original_allowed_turns(l);
+            }
+            self.recalculate_lane_geometry(*id, timer);
+        }
+    }
+
+    // Returns the new children. Lanes are created as their IDs come up, and added lanes that
+    // belonged to another road move over. The real geometry gets calculated once all of the
+    // lanes are known.
+    fn place_lanes(
+        &mut self,
+        id: RoadID,
+        fwds: bool,
+        lanes: &[LaneID],
+        specs: &[(LaneType, Distance)],
+    ) -> Vec<(LaneID, LaneType)> {
+        let r = &self.roads[id.0];
+        let (src_i, dst_i, lane_center_pts) = if fwds {
+            (r.src_i, r.dst_i, r.center_pts.clone())
+        } else {
+            (r.dst_i, r.src_i, r.center_pts.reversed())
+        };
+
+        let mut children = Vec::new();
+        for (l, (lt, width)) in lanes.iter().zip(specs.iter()) {
+            if l.0 == self.lanes.len() {
+                self.lanes.push(Lane {
+                    id: *l,
+                    parent: id,
+                    lane_type: *lt,
+                    lane_center_pts: lane_center_pts.clone(),
+                    width: *width,
+                    src_i,
+                    dst_i,
+                    building_paths: Vec::new(),
+                    bus_stops: Vec::new(),
+                    parking_blackhole: None,
+                    allowed_turns: None,
+                });
+            } else if l.0 >= self.first_added_lane {
+                let lane = &mut self.lanes[l.0];
+                lane.parent = id;
+                lane.src_i = src_i;
+                lane.dst_i = dst_i;
+                lane.lane_center_pts = lane_center_pts.clone();
+                lane.parking_blackhole = None;
+            }
+            let lane = &mut self.lanes[l.0];
+            lane.lane_type = *lt;
+            lane.width = *width;
+            children.push((*l, *lt));
+        }
+        children
+    }
+
+    fn get_original_intersection_type(&self, id: IntersectionID) -> IntersectionType {
+        self.original_intersection_types
+            .get(&id)
+            .cloned()
+            .unwrap_or(self.get_i(id).intersection_type)
+    }
+
+    // The starting policy for any intersection controlled by a ControlStopSign
+    fn default_stop_sign(&self, id: IntersectionID, timer: &mut Timer) -> ControlStopSign {
+        match self.get_i(id).intersection_type {
+            IntersectionType::Uncontrolled => ControlStopSign::uncontrolled(self, id),
+            IntersectionType::Roundabout => ControlStopSign::roundabout(self, id, timer),
+            _ => ControlStopSign::new(self, id, timer),
+        }
+    }
+
+    // None for lanes that aren't part of any road
+    fn get_original_lt(&self, id: LaneID) -> Option<LaneType> {
+        if !self.lane_exists(id) {
+            return None;
+        }
         let parent = self.get_parent(id);
+        let (fwds, idx) = parent.dir_and_offset(id);
+        if let Some(cs) = self.edits.cross_section_overrides.get(&parent.id) {
+            let side = if fwds { &cs.fwd } else { &cs.back };
+            if let Some((lt, _)) = side.get(idx) {
+                return Some(*lt);
+            }
+        }
         let (side1, side2) = get_lane_types(
             &parent.osm_tags,
             parent.parking_lane_fwd,
             parent.parking_lane_back,
         );
+        if fwds {
+            Some(side1[idx])
+        } else {
+            Some(side2[idx])
+        }
+    }
+
+    // None for lanes that aren't part of any road
+    fn get_original_width(&self, id: LaneID) -> Option<Distance> {
+        if !self.lane_exists(id) {
+            return None;
+        }
+        let parent = self.get_parent(id);
         let (fwds, idx) = parent.dir_and_offset(id);
+        if let Some(cs) = self.edits.cross_section_overrides.get(&parent.id) {
+            let side = if fwds { &cs.fwd } else { &cs.back };
+            if let Some((_, width)) = side.get(idx) {
+                return Some(*width);
+            }
+        }
+        let (side1, side2) = get_lane_types(
+            &parent.osm_tags,
+            parent.parking_lane_fwd,
+            parent.parking_lane_back,
+        );
+        let (widths1, widths2) = get_lane_widths(&parent.osm_tags, &side1, &side2);
         if fwds {
-            side1[idx]
+            Some(widths1[idx])
         } else {
-            side2[idx]
+            Some(widths2[idx])
         }
     }
+
+    fn get_original_allowed_turns(&self, id: LaneID) -> Option<BTreeSet<TurnType>> {
+        if !self.lane_exists(id) {
+            return None;
+        }
+        let parent = self.get_parent(id);
+        // turn:lanes describes the lanes from OSM, not the edited ones
+        if self.edits.cross_section_overrides.contains_key(&parent.id) {
+            return None;
+        }
+        let (side1, side2) = get_lane_types(
+            &parent.osm_tags,
+            parent.parking_lane_fwd,
+            parent.parking_lane_back,
+        );
+        let (fwds, idx) = parent.dir_and_offset(id);
+        lane::parse_turn_lanes(
+            &parent.osm_tags,
+            fwds,
+            if fwds { &side1 } else { &side2 },
+            idx,
+        )
+    }
+}
+
+// Reuses the original lanes on one side of a road for specs, then hands out new IDs.
+fn plan_side(
+    orig: &[LaneID],
+    orig_specs: &[(LaneType, Distance)],
+    specs: &[(LaneType, Distance)],
+    next_id: &mut usize,
+) -> Vec<LaneID> {
+    let (sidewalks, others): (Vec<_>, Vec<_>) = orig
+        .iter()
+        .zip(orig_specs.iter())
+        .partition(|(_, (lt, _))| *lt == LaneType::Sidewalk);
+    let mut sidewalks = sidewalks.into_iter().map(|(l, _)| *l);
+    let mut others = others.into_iter().map(|(l, _)| *l);
+    specs
+        .iter()
+        .map(|(lt, _)| {
+            let reuse = if *lt == LaneType::Sidewalk {
+                sidewalks.next()
+            } else {
+                others.next()
+            };
+            reuse.unwrap_or_else(|| {
+                let l = LaneID(*next_id);
+                *next_id += 1;
+                l
+            })
+        })
+        .collect()
 }
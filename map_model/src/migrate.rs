@@ -0,0 +1,433 @@
+use crate::raw_data::{OriginalIntersection, OriginalRoad};
+use crate::{
+    BuildingID, BusRouteID, BusStopID, ControlStopSign, ControlTrafficSignal, CrossSection, Cycle,
+    EditID, IntersectionID, LaneID, Map, MapEdits, Position, RoadID, RoadWithStopSign,
+    ScheduledEdits, TurnID,
+};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+// RoadIDs, LaneIDs, and friends are just indices, so they change whenever a map is re-imported.
+// Anything saved against an old version of a map (edits, scenarios) has to be translated through
+// the original OSM geometry and IDs, which survive.
+
+// A way to refer to lanes across many maps.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct StableLaneID {
+    pub road: OriginalRoad,
+    pub fwds: bool,
+    // Counting from the center of the road, like Road::children_forwards and children_backwards
+    pub offset: usize,
+}
+
+pub struct MapMigration<'a> {
+    old: &'a Map,
+    new: &'a Map,
+    roads: BTreeMap<OriginalRoad, RoadID>,
+    intersections: BTreeMap<OriginalIntersection, IntersectionID>,
+    buildings: BTreeMap<i64, BuildingID>,
+    // Lanes that only exist because of the cross-sections in the edits being migrated
+    added_lanes: BTreeMap<LaneID, LaneID>,
+    // Deduplicated, since many trips might refer to the same missing building
+    pub problems: BTreeSet<String>,
+}
+
+impl<'a> MapMigration<'a> {
+    pub fn new(old: &'a Map, new: &'a Map) -> MapMigration<'a> {
+        let (roads, mut ambiguous_roads) =
+            index_unique(new.all_roads().iter().map(|r| (r.segment_id, r.id)));
+        ambiguous_roads
+            .extend(index_unique(old.all_roads().iter().map(|r| (r.segment_id, r.id))).1);
+        let (intersections, mut ambiguous_intersections) =
+            index_unique(new.all_intersections().iter().map(|i| (i.orig_id, i.id)));
+        ambiguous_intersections
+            .extend(index_unique(old.all_intersections().iter().map(|i| (i.orig_id, i.id))).1);
+
+        let mut m = MapMigration {
+            old,
+            new,
+            roads,
+            intersections,
+            buildings: new
+                .all_buildings()
+                .iter()
+                .map(|b| (b.osm_way_id, b.id))
+                .collect(),
+            added_lanes: BTreeMap::new(),
+            problems: BTreeSet::new(),
+        };
+        // Matching these up would silently pick one of the roads, so leave them out instead.
+        for orig in ambiguous_roads {
+            m.roads.remove(&orig);
+            m.problem(format!(
+                "Several roads go from {} to {}, so they can't be matched",
+                orig.pt1, orig.pt2
+            ));
+        }
+        for orig in ambiguous_intersections {
+            m.intersections.remove(&orig);
+            m.problem(format!(
+                "Several intersections are at {}, so they can't be matched",
+                orig.point
+            ));
+        }
+        m
+    }
+
+    pub fn old_map(&self) -> &'a Map {
+        self.old
+    }
+
+    pub fn new_map(&self) -> &'a Map {
+        self.new
+    }
+
+    pub fn problem(&mut self, msg: String) {
+        self.problems.insert(msg);
+    }
+
+    pub fn road(&mut self, id: RoadID) -> Option<RoadID> {
+        let orig = self.old.get_r(id).segment_id;
+        let result = self.roads.get(&orig).cloned();
+        if result.is_none() {
+            self.problem(format!(
+                "{} (OSM way {}) doesn't exist anymore",
+                id,
+                self.old.get_r(id).osm_way_id
+            ));
+        }
+        result
+    }
+
+    pub fn intersection(&mut self, id: IntersectionID) -> Option<IntersectionID> {
+        let orig = self.old.get_i(id).orig_id;
+        let result = self.intersections.get(&orig).cloned();
+        if result.is_none() {
+            self.problem(format!("{} at {} doesn't exist anymore", id, orig.point));
+        }
+        result
+    }
+
+    pub fn stable_lane(&self, id: LaneID) -> StableLaneID {
+        let r = self.old.get_parent(id);
+        let (fwds, offset) = r.dir_and_offset(id);
+        StableLaneID {
+            road: r.segment_id,
+            fwds,
+            offset,
+        }
+    }
+
+    // Lanes added by cross-section edits are numbered by the cross-sections, not by anything
+    // stable, so match them up by their position on the migrated road.
+    fn match_added_lanes(
+        &mut self,
+        old_cross_sections: &BTreeMap<RoadID, CrossSection>,
+        new_cross_sections: &BTreeMap<RoadID, CrossSection>,
+    ) {
+        let new_positions = self.new.added_lane_positions(new_cross_sections);
+        self.added_lanes.clear();
+        for ((r, fwds, idx), l) in self.old.added_lane_positions(old_cross_sections) {
+            if let Some(new_r) = self.roads.get(&self.old.get_r(r).segment_id) {
+                if let Some(new_l) = new_positions.get(&(*new_r, fwds, idx)) {
+                    self.added_lanes.insert(l, *new_l);
+                }
+            }
+        }
+    }
+
+    // Only matches if the road still has the same number of lanes in that direction. Otherwise
+    // there's no good way to tell which lane was meant.
+    pub fn lane(&mut self, id: LaneID) -> Option<LaneID> {
+        if let Some(new_id) = self.added_lanes.get(&id) {
+            return Some(*new_id);
+        }
+        // Lanes removed by edits can't be matched up; migrating the cross-section takes care of
+        // them.
+        if !self.old.lane_exists(id) {
+            self.problem(format!(
+                "{} isn't part of any road, so it can't be matched",
+                id
+            ));
+            return None;
+        }
+        let stable = self.stable_lane(id);
+        let new_r = self.new.get_r(self.road(self.old.get_l(id).parent)?);
+        let old_r = self.old.get_parent(id);
+        let (old_children, new_children) = if stable.fwds {
+            (&old_r.children_forwards, &new_r.children_forwards)
+        } else {
+            (&old_r.children_backwards, &new_r.children_backwards)
+        };
+        if old_children.len() != new_children.len() {
+            self.problem(format!(
+                "{} changed from {} to {} lanes in one direction, so {} can't be matched",
+                old_r.id,
+                old_children.len(),
+                new_children.len(),
+                id
+            ));
+            return None;
+        }
+        Some(new_children[stable.offset].0)
+    }
+
+    pub fn turn(&mut self, id: TurnID) -> Option<TurnID> {
+        let new_id = TurnID {
+            parent: self.intersection(id.parent)?,
+            src: self.lane(id.src)?,
+            dst: self.lane(id.dst)?,
+        };
+        // Turns to and from added lanes only exist once the edits are applied.
+        let both_exist = self.new.lane_exists(new_id.src) && self.new.lane_exists(new_id.dst);
+        if both_exist && self.new.maybe_get_t(new_id).is_none() {
+            self.problem(format!("{} isn't possible anymore", id));
+            return None;
+        }
+        Some(new_id)
+    }
+
+    pub fn building(&mut self, id: BuildingID) -> Option<BuildingID> {
+        let osm_way_id = self.old.get_b(id).osm_way_id;
+        let result = self.buildings.get(&osm_way_id).cloned();
+        if result.is_none() {
+            self.problem(format!(
+                "{} (OSM way {}) doesn't exist anymore",
+                id, osm_way_id
+            ));
+        }
+        result
+    }
+
+    // The lane's length may have changed, so the distance is clamped.
+    pub fn position(&mut self, pos: Position) -> Option<Position> {
+        let lane = self.lane(pos.lane())?;
+        let dist = pos.dist_along().min(self.new.get_l(lane).length());
+        Some(Position::new(lane, dist))
+    }
+
+    pub fn bus_stop(&mut self, id: BusStopID) -> Option<BusStopID> {
+        let new_id = BusStopID {
+            sidewalk: self.lane(id.sidewalk)?,
+            idx: id.idx,
+        };
+        if self.new.maybe_get_bs(new_id).is_none() {
+            self.problem(format!("{} doesn't exist anymore", id));
+            return None;
+        }
+        Some(new_id)
+    }
+
+    pub fn bus_route(&mut self, id: BusRouteID) -> Option<BusRouteID> {
+        let name = &self.old.get_br(id).name;
+        let result = self.new.get_bus_route(name).map(|r| r.id);
+        if result.is_none() {
+            self.problem(format!("Bus route {} doesn't exist anymore", name));
+        }
+        result
+    }
+
+    fn stop_sign(&mut self, ss: &ControlStopSign) -> Option<ControlStopSign> {
+        let id = self.intersection(ss.id)?;
+        let mut turns = BTreeMap::new();
+        for (t, pri) in &ss.turns {
+            turns.insert(self.turn(*t)?, *pri);
+        }
+        if self
+            .new
+            .get_turns_in_intersection(id)
+            .iter()
+            .any(|t| !turns.contains_key(&t.id))
+        {
+            self.problem(format!(
+                "{} has new turns, so the stop sign edits don't apply",
+                ss.id
+            ));
+            return None;
+        }
+        let mut roads = BTreeMap::new();
+        for (r, road) in &ss.roads {
+            let mut travel_lanes = Vec::new();
+            for l in &road.travel_lanes {
+                travel_lanes.push(self.lane(*l)?);
+            }
+            roads.insert(
+                self.road(*r)?,
+                RoadWithStopSign {
+                    travel_lanes,
+                    enabled: road.enabled,
+                },
+            );
+        }
+        Some(ControlStopSign { id, turns, roads })
+    }
+
+    fn traffic_signal(&mut self, ts: &ControlTrafficSignal) -> Option<ControlTrafficSignal> {
+        let id = self.intersection(ts.id)?;
+        let mut cycles = Vec::new();
+        for cycle in &ts.cycles {
+            let mut priority_turns = BTreeSet::new();
+            for t in &cycle.priority_turns {
+                priority_turns.insert(self.turn(*t)?);
+            }
+            let mut yield_turns = BTreeSet::new();
+            for t in &cycle.yield_turns {
+                yield_turns.insert(self.turn(*t)?);
+            }
+            cycles.push(Cycle {
+                parent: id,
+                priority_turns,
+                yield_turns,
+                duration: cycle.duration,
+            });
+        }
+        Some(ControlTrafficSignal { id, cycles })
+    }
+}
+
+impl MapEdits {
+    // Anything that can't be matched is dropped and recorded in the migration's problems.
+    pub fn migrate(&self, m: &mut MapMigration) -> MapEdits {
+        let mut edits = MapEdits::new(m.new_map().get_name().to_string());
+        edits.edits_name = self.edits_name.clone();
+
+        for (r, cs) in &self.cross_section_overrides {
+            if let Some(id) = m.road(*r) {
+                edits.cross_section_overrides.insert(id, cs.clone());
+            }
+        }
+        m.match_added_lanes(
+            &self.cross_section_overrides,
+            &edits.cross_section_overrides,
+        );
+        for (l, lt) in &self.lane_overrides {
+            if let Some(id) = m.lane(*l) {
+                edits.lane_overrides.insert(id, *lt);
+            }
+        }
+        for (l, turns) in &self.allowed_turns_overrides {
+            if let Some(id) = m.lane(*l) {
+                edits.allowed_turns_overrides.insert(id, turns.clone());
+            }
+        }
+        for (l, width) in &self.lane_width_overrides {
+            if let Some(id) = m.lane(*l) {
+                edits.lane_width_overrides.insert(id, *width);
+            }
+        }
+        for (r, speed) in &self.speed_limit_overrides {
+            if let Some(id) = m.road(*r) {
+                edits.speed_limit_overrides.insert(id, *speed);
+            }
+        }
+        for (i, ss) in &self.stop_sign_overrides {
+            match m.stop_sign(ss) {
+                Some(new_ss) => {
+                    edits.stop_sign_overrides.insert(new_ss.id, new_ss);
+                }
+                None => m.problem(format!("Dropping the stop sign edits at {}", i)),
+            }
+        }
+        for (i, ts) in &self.traffic_signal_overrides {
+            match m.traffic_signal(ts) {
+                Some(new_ts) => {
+                    edits.traffic_signal_overrides.insert(new_ts.id, new_ts);
+                }
+                None => m.problem(format!("Dropping the traffic signal edits at {}", i)),
+            }
+        }
+        for (i, it) in &self.intersection_type_overrides {
+            if let Some(id) = m.intersection(*i) {
+                edits.intersection_type_overrides.insert(id, *it);
+            }
+        }
+        for (t, o) in &self.turn_overrides {
+            if let Some(id) = m.turn(*t) {
+                edits.turn_overrides.insert(id, *o);
+            }
+        }
+        for window in &self.scheduled {
+            let mut new_window = ScheduledEdits {
+                start: window.start,
+                end: window.end,
+                lane_overrides: BTreeMap::new(),
+                traffic_signal_overrides: BTreeMap::new(),
+            };
+            for (l, lt) in &window.lane_overrides {
+                if let Some(id) = m.lane(*l) {
+                    new_window.lane_overrides.insert(id, *lt);
+                }
+            }
+            for (i, ts) in &window.traffic_signal_overrides {
+                match m.traffic_signal(ts) {
+                    Some(new_ts) => {
+                        new_window
+                            .traffic_signal_overrides
+                            .insert(new_ts.id, new_ts);
+                    }
+                    None => m.problem(format!(
+                        "Dropping the traffic signal edits at {} from {} to {}",
+                        i, window.start, window.end
+                    )),
+                }
+            }
+            edits.scheduled.push(new_window);
+        }
+        for (l, closure) in &self.lane_closures {
+            if let Some(id) = m.lane(*l) {
+                edits.lane_closures.insert(id, *closure);
+            }
+        }
+        for (r, closure) in &self.road_closures {
+            if let Some(id) = m.road(*r) {
+                edits.road_closures.insert(id, *closure);
+            }
+        }
+        for (i, closure) in &self.intersection_closures {
+            if let Some(id) = m.intersection(*i) {
+                edits.intersection_closures.insert(id, *closure);
+            }
+        }
+        for (id, p) in &self.provenance {
+            let new_id = match id {
+                EditID::LaneType(l) => m.lane(*l).map(EditID::LaneType),
+                EditID::AllowedTurns(l) => m.lane(*l).map(EditID::AllowedTurns),
+                EditID::LaneWidth(l) => m.lane(*l).map(EditID::LaneWidth),
+                EditID::CrossSection(r) => m.road(*r).map(EditID::CrossSection),
+                EditID::SpeedLimit(r) => m.road(*r).map(EditID::SpeedLimit),
+                EditID::StopSign(i) => m.intersection(*i).map(EditID::StopSign),
+                EditID::TrafficSignal(i) => m.intersection(*i).map(EditID::TrafficSignal),
+                EditID::IntersectionType(i) => m.intersection(*i).map(EditID::IntersectionType),
+                EditID::Turn(t) => m.turn(*t).map(EditID::Turn),
+                EditID::LaneClosure(l) => m.lane(*l).map(EditID::LaneClosure),
+                EditID::RoadClosure(r) => m.road(*r).map(EditID::RoadClosure),
+                EditID::IntersectionClosure(i) => {
+                    m.intersection(*i).map(EditID::IntersectionClosure)
+                }
+            };
+            // Edits that didn't survive the migration have already been dropped.
+            if let Some(new_id) = new_id {
+                if edits.contains(new_id) {
+                    edits.provenance.insert(new_id, p.clone());
+                }
+            }
+        }
+        // Scenarios and other edits number the added lanes differently.
+        m.added_lanes.clear();
+        edits
+    }
+}
+
+// Also returns the keys that appear more than once.
+fn index_unique<K: Ord + Copy, V>(
+    pairs: impl Iterator<Item = (K, V)>,
+) -> (BTreeMap<K, V>, BTreeSet<K>) {
+    let mut map = BTreeMap::new();
+    let mut dupes = BTreeSet::new();
+    for (k, v) in pairs {
+        if map.insert(k, v).is_some() {
+            dupes.insert(k);
+        }
+    }
+    (map, dupes)
+}
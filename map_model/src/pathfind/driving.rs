@@ -79,9 +79,16 @@ impl VehiclePathfinder {
         ))
     }
 
+    // Edits can add lanes, in which case the graph has to be built from scratch.
+    pub fn has_all_lanes(&self, map: &Map) -> bool {
+        self.nodes
+            .maybe_get(LaneID(map.all_lanes().len() - 1))
+            .is_some()
+    }
+
     pub fn apply_edits(&mut self, map: &Map) {
-        // The NodeMap is just all lanes -- it won't change. So we can also reuse the node
-        // ordering.
+        // The NodeMap is just all lanes -- it won't change, unless has_all_lanes is false. So we
+        // can also reuse the node ordering.
         // TODO Make sure the result of this is deterministic and equivalent to computing from
         // scratch.
         let input_graph = make_input_graph(map, &self.nodes, &self.lane_types);
@@ -100,6 +107,22 @@ fn make_input_graph(map: &Map, nodes: &NodeMap<LaneID>, lane_types: &Vec<LaneTyp
             if !map.is_turn_allowed(turn.id) || !lane_types.contains(&next.lane_type) {
                 continue;
             }
+            if map.is_lane_closed(l.id, lane_types)
+                || map.is_lane_closed(next.id, lane_types)
+                || map.is_intersection_closed(turn.id.parent, lane_types)
+            {
+                continue;
+            }
+            // Restrictions at just this intersection that always apply, except maybe to some
+            // vehicles. The rest are checked in pathfind_at_time.
+            if map.get_r(l.parent).turn_restrictions.iter().any(|tr| {
+                tr.is_static()
+                    && tr.applies_to(lane_types)
+                    && tr.forbids(turn.id.parent, &[l.parent, next.parent])
+            }) && !map.is_restriction_lifted(turn.id.parent, l.parent, next.parent)
+            {
+                continue;
+            }
             any = true;
             // TODO Speed limit or some other cost
             let length = l.length() + turn.geom.length();
@@ -1,12 +1,13 @@
 mod driving;
 mod node_map;
+mod restrictions;
 mod walking;
 
 use self::driving::VehiclePathfinder;
 use self::walking::SidewalkPathfinder;
 use crate::{BusRouteID, BusStopID, LaneID, LaneType, Map, Position, Traversable, TurnID};
 use abstutil::Timer;
-use geom::{Distance, PolyLine};
+use geom::{Distance, Duration, PolyLine};
 use serde_derive::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::fmt;
@@ -159,6 +160,12 @@ impl Path {
         self.steps.push_back(step);
     }
 
+    // For rerouting an agent partway through the first step.
+    pub fn prepend(&mut self, step: PathStep, map: &Map) {
+        self.total_length += step.as_traversable().length(map);
+        self.steps.push_front(step);
+    }
+
     pub fn current_step(&self) -> PathStep {
         self.steps[0]
     }
@@ -406,6 +413,26 @@ impl Pathfinder {
         }
     }
 
+    // Also respects turn restrictions that depend on the time or span several roads.
+    pub fn pathfind_at_time(&self, req: PathRequest, map: &Map, time: Duration) -> Option<Path> {
+        if map.get_l(req.start.lane()).is_sidewalk() {
+            return self.walking_graph.pathfind(&req, map);
+        }
+        let lane_types = if req.can_use_bus_lanes {
+            vec![LaneType::Driving, LaneType::Bus]
+        } else if req.can_use_bike_lanes {
+            vec![LaneType::Driving, LaneType::Biking]
+        } else {
+            vec![LaneType::Driving]
+        };
+        let path = self.pathfind(req.clone(), map)?;
+        if restrictions::breaks_restrictions(map, &path, &lane_types, time) {
+            restrictions::pathfind_with_restrictions(&req, map, &lane_types, time)
+        } else {
+            Some(path)
+        }
+    }
+
     pub fn should_use_transit(
         &self,
         map: &Map,
@@ -419,19 +446,41 @@ impl Pathfinder {
     }
 
     pub fn apply_edits(&mut self, map: &Map, timer: &mut Timer) {
-        timer.start("apply edits to car pathfinding");
-        self.car_graph.apply_edits(map);
-        timer.stop("apply edits to car pathfinding");
+        if !self.car_graph.has_all_lanes(map) {
+            timer.start("rebuild vehicle pathfinding for new lanes");
+            self.car_graph = VehiclePathfinder::new(map, vec![LaneType::Driving], None);
+            self.bike_graph = VehiclePathfinder::new(
+                map,
+                vec![LaneType::Driving, LaneType::Biking],
+                Some(&self.car_graph),
+            );
+            self.bus_graph = VehiclePathfinder::new(
+                map,
+                vec![LaneType::Driving, LaneType::Bus],
+                Some(&self.car_graph),
+            );
+            timer.stop("rebuild vehicle pathfinding for new lanes");
+        } else {
+            timer.start("apply edits to car pathfinding");
+            self.car_graph.apply_edits(map);
+            timer.stop("apply edits to car pathfinding");
 
-        timer.start("apply edits to bike pathfinding");
-        self.bike_graph.apply_edits(map);
-        timer.stop("apply edits to bike pathfinding");
+            timer.start("apply edits to bike pathfinding");
+            self.bike_graph.apply_edits(map);
+            timer.stop("apply edits to bike pathfinding");
 
-        timer.start("apply edits to bus pathfinding");
-        self.bus_graph.apply_edits(map);
-        timer.stop("apply edits to bus pathfinding");
+            timer.start("apply edits to bus pathfinding");
+            self.bus_graph.apply_edits(map);
+            timer.stop("apply edits to bus pathfinding");
+        }
 
-        // TODO Can edits ever affect walking or walking+transit? If a crosswalk is entirely
-        // banned, then yes... but actually that sounds like a bad edit to allow.
+        // Closures can cut off sidewalks. The walking graphs aren't seeded with a node ordering,
+        // so just rebuild them.
+        timer.start("apply edits to walking pathfinding");
+        self.walking_graph = SidewalkPathfinder::new(map, false);
+        if self.walking_with_transit_graph.is_some() {
+            self.walking_with_transit_graph = Some(SidewalkPathfinder::new(map, true));
+        }
+        timer.stop("apply edits to walking pathfinding");
     }
 }
@@ -0,0 +1,155 @@
+use crate::{IntersectionID, LaneID, LaneType, Map, Path, PathRequest, PathStep, RoadID, TurnID};
+use geom::{Distance, Duration};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+// Turn restrictions with via roads or time conditions can't be baked into the contraction
+// hierarchies, so paths get checked afterwards. The rare path that breaks one is recalculated with
+// a much slower search that remembers the last few roads crossed.
+
+// Only checks the restrictions that the vehicle pathfinding graphs don't already handle.
+pub fn breaks_restrictions(
+    map: &Map,
+    path: &Path,
+    lane_types: &[LaneType],
+    time: Duration,
+) -> bool {
+    let mut roads: Vec<RoadID> = Vec::new();
+    let mut crossed: Vec<IntersectionID> = Vec::new();
+    for step in path.get_steps() {
+        if let PathStep::Turn(t) = step {
+            if roads.is_empty() {
+                roads.push(map.get_l(t.src).parent);
+            }
+            roads.push(map.get_l(t.dst).parent);
+            crossed.push(t.parent);
+        }
+    }
+    (0..crossed.len()).any(|idx| {
+        map.get_r(roads[idx]).turn_restrictions.iter().any(|tr| {
+            !tr.is_static()
+                && tr.applies_to(lane_types)
+                && tr.active_at(time)
+                && tr.forbids(crossed[idx], &roads[idx..])
+                && !(tr.via.is_empty()
+                    && map.is_restriction_lifted(crossed[idx], roads[idx], roads[idx + 1]))
+        })
+    })
+}
+
+// A vehicle's position in the search, plus enough history to evaluate the longest restriction.
+#[derive(PartialEq, Eq, Hash, Clone)]
+struct State {
+    lane: LaneID,
+    roads: Vec<RoadID>,
+    // crossed[i] is the intersection between roads[i] and roads[i + 1]
+    crossed: Vec<IntersectionID>,
+}
+
+// Dijkstra's over states, with the same edge costs and closures as the contraction hierarchies.
+pub fn pathfind_with_restrictions(
+    req: &PathRequest,
+    map: &Map,
+    lane_types: &[LaneType],
+    time: Duration,
+) -> Option<Path> {
+    let history = 1 + map
+        .all_roads()
+        .iter()
+        .flat_map(|r| r.turn_restrictions.iter().map(|tr| tr.via.len()))
+        .max()
+        .unwrap_or(0);
+
+    let start = State {
+        lane: req.start.lane(),
+        roads: vec![map.get_l(req.start.lane()).parent],
+        crossed: Vec::new(),
+    };
+    // The index of each state's predecessor, and the turn taken from it
+    let mut states: Vec<(State, Option<(usize, TurnID)>)> = vec![(start.clone(), None)];
+    let mut best_cost: HashMap<State, usize> = HashMap::new();
+    best_cost.insert(start, 0);
+    let mut done: HashSet<State> = HashSet::new();
+    let mut queue: BinaryHeap<Reverse<(usize, usize)>> = BinaryHeap::new();
+    queue.push(Reverse((0, 0)));
+
+    while let Some(Reverse((cost, idx))) = queue.pop() {
+        let current = states[idx].0.clone();
+        if !done.insert(current.clone()) {
+            continue;
+        }
+        if current.lane == req.end.lane() {
+            let mut turns = Vec::new();
+            let mut at = idx;
+            while let Some((prev, turn)) = states[at].1 {
+                turns.push(turn);
+                at = prev;
+            }
+            turns.reverse();
+            let mut steps = Vec::new();
+            for t in turns {
+                steps.push(PathStep::Lane(t.src));
+                steps.push(PathStep::Turn(t));
+            }
+            steps.push(PathStep::Lane(req.end.lane()));
+            return Some(Path::new(
+                map,
+                steps,
+                req.end.dist_along(),
+                Distance::centimeters(cost),
+            ));
+        }
+
+        let l = map.get_l(current.lane);
+        for (turn, next) in map.get_next_turns_and_lanes(l.id, l.dst_i) {
+            if !map.is_turn_allowed(turn.id) || !lane_types.contains(&next.lane_type) {
+                continue;
+            }
+            // Same as the vehicle pathfinding graphs
+            if map.is_lane_closed(l.id, lane_types)
+                || map.is_lane_closed(next.id, lane_types)
+                || map.is_intersection_closed(turn.id.parent, lane_types)
+            {
+                continue;
+            }
+            let mut roads = current.roads.clone();
+            roads.push(next.parent);
+            let mut crossed = current.crossed.clone();
+            crossed.push(turn.id.parent);
+            let forbidden = (0..crossed.len()).any(|i| {
+                map.get_r(roads[i]).turn_restrictions.iter().any(|tr| {
+                    tr.applies_to(lane_types)
+                        && tr.active_at(time)
+                        && tr.forbids(crossed[i], &roads[i..])
+                        && !(tr.via.is_empty()
+                            && map.is_restriction_lifted(crossed[i], roads[i], roads[i + 1]))
+                })
+            });
+            if forbidden {
+                continue;
+            }
+            if roads.len() > history {
+                roads.remove(0);
+                crossed.remove(0);
+            }
+
+            let length = l.length() + turn.geom.length();
+            let next_cost = cost + (length.inner_meters() * 100.0).round() as usize;
+            let state = State {
+                lane: next.id,
+                roads,
+                crossed,
+            };
+            if best_cost
+                .get(&state)
+                .map(|c| next_cost < *c)
+                .unwrap_or(true)
+            {
+                best_cost.insert(state.clone(), next_cost);
+                states.push((state, Some((idx, turn.id))));
+                queue.push(Reverse((next_cost, states.len() - 1)));
+            }
+        }
+    }
+    None
+}
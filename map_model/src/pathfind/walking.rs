@@ -35,8 +35,16 @@ impl SidewalkPathfinder {
             if !t.between_sidewalks() || !map.is_turn_allowed(t.id) {
                 continue;
             }
+            let sidewalk = [LaneType::Sidewalk];
+            if map.is_lane_closed(t.id.src, &sidewalk)
+                || map.is_lane_closed(t.id.dst, &sidewalk)
+                || map.is_intersection_closed(t.id.parent, &sidewalk)
+            {
+                continue;
+            }
             // Duplicate edges in InputGraph will be removed.
-            let length = map.get_l(t.id.src).length() + t.geom.length();
+            let length = map.get_l(t.id.src).length() * map.get_parent(t.id.src).walking_slowdown()
+                + t.geom.length();
             let length_cm = (length.inner_meters() * 100.0).round() as usize;
 
             input_graph.add_edge(
@@ -31,13 +31,29 @@ pub struct Map {
     pub buildings: Vec<Building>,
     pub bus_routes: Vec<Route>,
     pub areas: Vec<Area>,
-    // from OSM way => [(restriction, to OSM way)]
-    pub turn_restrictions: BTreeMap<i64, Vec<(String, i64)>>,
+    // from OSM way => restrictions starting there
+    pub turn_restrictions: BTreeMap<i64, Vec<TurnRestriction>>,
 
     pub boundary_polygon: Polygon,
     pub gps_bounds: GPSBounds,
 }
 
+// Straight from an OSM restriction relation; map_model resolves this to a
+// crate::TurnRestriction.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TurnRestriction {
+    // Like "no_left_turn" or "only_straight_on"
+    pub restriction: String,
+    // OSM ways in between the from and to ways. Empty when the via member is a node. Not
+    // necessarily in order.
+    pub via_ways: Vec<i64>,
+    pub to_way: i64,
+    // From except=*, like "bicycle" or "psv"
+    pub except: Vec<String>,
+    // From restriction:conditional, like "Mo-Fr 07:00-09:00"
+    pub conditions: Option<String>,
+}
+
 impl Map {
     pub fn blank() -> Map {
         Map {
@@ -97,6 +113,9 @@ pub struct Road {
     // cul-de-sac roads for roundabout handling.
     pub center_points: Vec<Pt2D>,
     pub orig_id: OriginalRoad,
+    // Where this piece of the OSM way starts and ends. A way gets split wherever it crosses
+    // another road, and orig_id covers the whole way, so this tells the pieces apart.
+    pub segment_id: OriginalRoad,
     pub osm_tags: BTreeMap<String, String>,
     pub osm_way_id: i64,
     pub parking_lane_fwd: bool,
@@ -187,3 +206,26 @@ impl Ord for OriginalRoad {
 pub struct OriginalIntersection {
     pub point: LonLat,
 }
+
+impl PartialOrd for OriginalIntersection {
+    fn partial_cmp(&self, other: &OriginalIntersection) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Eq for OriginalIntersection {}
+impl Ord for OriginalIntersection {
+    fn cmp(&self, other: &OriginalIntersection) -> std::cmp::Ordering {
+        let ord = self
+            .point
+            .longitude
+            .partial_cmp(&other.point.longitude)
+            .unwrap();
+        if ord != std::cmp::Ordering::Equal {
+            return ord;
+        }
+        self.point
+            .latitude
+            .partial_cmp(&other.point.latitude)
+            .unwrap()
+    }
+}
@@ -1,10 +1,15 @@
-use crate::{raw_data, BusStopID, IntersectionID, LaneID, LaneType, Map, LANE_THICKNESS};
+use crate::{
+    raw_data, BusStopID, CrossSection, IntersectionID, LaneID, LaneType, Map, TurnRestriction,
+};
 use abstutil::{Error, Warn};
 use geom::{Distance, PolyLine, Polygon, Speed};
 use serde_derive::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashSet};
 use std::fmt;
 
+// Going up or down stairs is much slower than walking on flat ground.
+const STAIRS_WALKING_SLOWDOWN: f64 = 3.0;
+
 // TODO reconsider pub usize. maybe outside world shouldnt know.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct RoadID(pub usize);
@@ -61,9 +66,11 @@ pub struct Road {
     // complexity was annoying.
     pub osm_tags: BTreeMap<String, String>,
     // self is 'from'
-    pub turn_restrictions: Vec<(String, RoadID)>,
+    pub turn_restrictions: Vec<TurnRestriction>,
     pub osm_way_id: i64,
     pub stable_id: raw_data::StableRoadID,
+    // Survives re-importing the map, unlike id and stable_id
+    pub segment_id: raw_data::OriginalRoad,
 
     // Invariant: A road must contain at least one child
     // These are ordered from left-most lane (closest to center lane) to rightmost (sidewalk)
@@ -82,6 +89,14 @@ pub struct Road {
     // Need to retain for map editing.
     pub parking_lane_fwd: bool,
     pub parking_lane_back: bool,
+
+    // From the OSM maxspeed tags, unless edited
+    pub speed_limit_forwards: Speed,
+    pub speed_limit_backwards: Speed,
+
+    // The sum of the lane widths on each side of center_pts
+    pub fwd_width: Distance,
+    pub back_width: Distance,
 }
 
 impl Road {
@@ -92,6 +107,19 @@ impl Road {
         )
     }
 
+    pub fn get_cross_section(&self, map: &Map) -> CrossSection {
+        let spec = |children: &Vec<(LaneID, LaneType)>| {
+            children
+                .iter()
+                .map(|(id, lt)| (*lt, map.get_l(*id).width))
+                .collect()
+        };
+        CrossSection {
+            fwd: spec(&self.children_forwards),
+            back: spec(&self.children_backwards),
+        }
+    }
+
     pub fn is_forwards(&self, lane: LaneID) -> bool {
         self.dir_and_offset(lane).0
     }
@@ -185,23 +213,26 @@ impl Road {
         lane == self.children_backwards[0].0
     }
 
+    // The faster direction, for when the direction doesn't matter
     pub fn get_speed_limit(&self) -> Speed {
-        // TODO Should probably cache this
-        if let Some(limit) = self.osm_tags.get("maxspeed") {
-            // TODO handle other units
-            if limit.ends_with(" mph") {
-                if let Ok(mph) = limit[0..limit.len() - 4].parse::<f64>() {
-                    return Speed::miles_per_hour(mph);
-                }
-            }
+        self.speed_limit_forwards.max(self.speed_limit_backwards)
+    }
+
+    pub fn speed_limit_for(&self, lane: LaneID) -> Speed {
+        if self.is_forwards(lane) {
+            self.speed_limit_forwards
+        } else {
+            self.speed_limit_backwards
         }
+    }
 
-        if self.osm_tags.get("highway") == Some(&"primary".to_string())
-            || self.osm_tags.get("highway") == Some(&"secondary".to_string())
-        {
-            return Speed::miles_per_hour(40.0);
+    // Pedestrians take this many times longer to cross the road than its length suggests.
+    pub fn walking_slowdown(&self) -> f64 {
+        if self.osm_tags.get("highway") == Some(&"steps".to_string()) {
+            STAIRS_WALKING_SLOWDOWN
+        } else {
+            1.0
         }
-        Speed::miles_per_hour(20.0)
     }
 
     pub fn get_zorder(&self) -> isize {
@@ -300,8 +331,8 @@ impl Road {
     }
 
     pub fn get_thick_polyline(&self, orig_pts: bool) -> Warn<(PolyLine, Distance)> {
-        let width_right = (self.children_forwards.len() as f64) * LANE_THICKNESS;
-        let width_left = (self.children_backwards.len() as f64) * LANE_THICKNESS;
+        let width_right = self.fwd_width;
+        let width_left = self.back_width;
         let total_width = width_right + width_left;
         let pts = if orig_pts {
             &self.original_center_pts
@@ -329,7 +360,7 @@ impl Road {
             if self.children_forwards.is_empty() {
                 return None;
             }
-            let width = LANE_THICKNESS * (self.children_forwards.len() as f64);
+            let width = self.fwd_width;
             Some(
                 self.center_pts
                     .shift_right(width / 2.0)
@@ -339,7 +370,7 @@ impl Road {
             if self.children_backwards.is_empty() {
                 return None;
             }
-            let width = LANE_THICKNESS * (self.children_backwards.len() as f64);
+            let width = self.back_width;
             Some(
                 self.center_pts
                     .shift_left(width / 2.0)
@@ -348,6 +379,17 @@ impl Road {
         }
     }
 
+    // What OSM says about a stop sign for traffic entering this intersection from this road:
+    // "yes" if it always stops, or "minor" if it stops only when the other roads are bigger.
+    pub fn osm_stop_sign(&self, i: IntersectionID) -> Option<&str> {
+        let key = if self.dst_i == i {
+            "abst:stop_fwd"
+        } else {
+            "abst:stop_back"
+        };
+        self.osm_tags.get(key).map(|s| s.as_str())
+    }
+
     pub fn get_name(&self) -> String {
         if let Some(name) = self.osm_tags.get("name") {
             return name.to_string();
@@ -415,3 +457,44 @@ impl Road {
         stops
     }
 }
+
+// Returns the (forwards, backwards) speed limits. maxspeed:forward and maxspeed:backward win over
+// maxspeed; without any, guess from the highway type.
+pub fn speed_limits_from_osm(tags: &BTreeMap<String, String>) -> (Speed, Speed) {
+    let default = if let Some(limit) = tags.get("maxspeed").and_then(|x| parse_maxspeed(x)) {
+        limit
+    } else {
+        match tags.get("highway").map(|x| x.as_str()) {
+            Some("primary") | Some("secondary") => Speed::miles_per_hour(40.0),
+            // Alleys
+            Some("service") => Speed::miles_per_hour(10.0),
+            _ => Speed::miles_per_hour(20.0),
+        }
+    };
+    let fwds = tags
+        .get("maxspeed:forward")
+        .and_then(|x| parse_maxspeed(x))
+        .unwrap_or(default);
+    let back = tags
+        .get("maxspeed:backward")
+        .and_then(|x| parse_maxspeed(x))
+        .unwrap_or(default);
+    (fwds, back)
+}
+
+// Handles "25 mph", "25mph", "50", "50 km/h", and "10 knots". A bare number is km/h, per OSM
+// convention. Values like "none", "walk", or "US:urban" aren't understood.
+pub fn parse_maxspeed(value: &str) -> Option<Speed> {
+    // Multiple values are sometimes separated by semicolons; just take the first
+    let value = value.split(';').next()?.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or_else(|| value.len());
+    let number = value[..split].parse::<f64>().ok()?;
+    match value[split..].trim() {
+        "" | "km/h" | "kmh" | "kph" => Some(Speed::km_per_hour(number)),
+        "mph" => Some(Speed::miles_per_hour(number)),
+        "knots" => Some(Speed::knots(number)),
+        _ => None,
+    }
+}
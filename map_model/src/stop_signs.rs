@@ -1,7 +1,7 @@
 use crate::{IntersectionID, LaneID, Map, RoadID, TurnID, TurnPriority, TurnType};
 use abstutil::{deserialize_btreemap, serialize_btreemap, Error, Timer, Warn};
 use serde_derive::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet};
 
 // TODO Some of these are probably old notes:
 // 1) Pedestrians always have right-of-way. (for now -- should be toggleable later)
@@ -42,6 +42,17 @@ pub struct ControlStopSign {
     pub roads: BTreeMap<RoadID, RoadWithStopSign>,
 }
 
+// How much to trust the default stop signs at an intersection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StopSignConfidence {
+    // Roads of the same rank and no OSM stop signs, so everyone stopping is just a guess
+    Low,
+    // The smaller roads stop for the biggest one
+    Medium,
+    // From OSM stop signs, or there's only one sensible choice
+    High,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct RoadWithStopSign {
     pub travel_lanes: Vec<LaneID>,
@@ -50,9 +61,53 @@ pub struct RoadWithStopSign {
 
 impl ControlStopSign {
     pub fn new(map: &Map, id: IntersectionID, timer: &mut Timer) -> ControlStopSign {
-        let mut ss = smart_assignment(map, id).get(timer);
+        let ss = smart_assignment(map, id).0.get(timer);
         ss.validate(map).unwrap().get(timer);
+        ss.with_roads(map)
+    }
+
+    pub fn default_confidence(map: &Map, id: IntersectionID) -> StopSignConfidence {
+        smart_assignment(map, id).1
+    }
 
+    // Everyone yields to whoever's already in the intersection.
+    pub fn uncontrolled(map: &Map, id: IntersectionID) -> ControlStopSign {
+        let mut ss = ControlStopSign {
+            id,
+            turns: BTreeMap::new(),
+            roads: BTreeMap::new(),
+        };
+        for t in &map.get_i(id).turns {
+            if map.get_t(*t).turn_type == TurnType::SharedSidewalkCorner {
+                ss.turns.insert(*t, TurnPriority::Priority);
+            } else {
+                ss.turns.insert(*t, TurnPriority::Yield);
+            }
+        }
+        ss.with_roads(map)
+    }
+
+    // Like uncontrolled, except pedestrians on the crosswalks have right-of-way over entering
+    // traffic.
+    pub fn roundabout(map: &Map, id: IntersectionID, timer: &mut Timer) -> ControlStopSign {
+        let mut ss = ControlStopSign::uncontrolled(map, id);
+        for t in &map.get_i(id).turns {
+            if map.get_t(*t).turn_type == TurnType::Crosswalk {
+                ss.turns.insert(*t, TurnPriority::Priority);
+            }
+        }
+        if let Err(err) = ss.validate(map) {
+            timer.warn(format!(
+                "Giving up on roundabout crosswalk priority for {}: {}",
+                id, err
+            ));
+            return ControlStopSign::uncontrolled(map, id);
+        }
+        ss
+    }
+
+    fn with_roads(mut self, map: &Map) -> ControlStopSign {
+        let id = self.id;
         for r in &map.get_i(id).roads {
             let travel_lanes: Vec<LaneID> = map
                 .get_r(*r)
@@ -67,7 +122,7 @@ impl ControlStopSign {
                 })
                 .collect();
             if !travel_lanes.is_empty() {
-                ss.roads.insert(
+                self.roads.insert(
                     *r,
                     RoadWithStopSign {
                         travel_lanes,
@@ -76,9 +131,9 @@ impl ControlStopSign {
                 );
             }
         }
-        ss.recalculate_stop_signs(map);
+        self.recalculate_stop_signs(map);
 
-        ss
+        self
     }
 
     pub fn get_priority(&self, turn: TurnID) -> TurnPriority {
@@ -207,38 +262,59 @@ impl ControlStopSign {
     }
 }
 
-fn smart_assignment(map: &Map, id: IntersectionID) -> Warn<ControlStopSign> {
+fn smart_assignment(map: &Map, id: IntersectionID) -> (Warn<ControlStopSign>, StopSignConfidence) {
     // Count the number of roads with incoming lanes to determine degenerate/deadends. Might have
     // one incoming road to two outgoing. Don't count sidewalks as incoming; crosswalks always
     // yield anyway.
-    let mut incoming_roads: HashSet<RoadID> = HashSet::new();
+    let mut incoming_roads: BTreeSet<RoadID> = BTreeSet::new();
     for l in &map.get_i(id).incoming_lanes {
         if map.get_l(*l).lane_type.is_for_moving_vehicles() {
             incoming_roads.insert(map.get_l(*l).parent);
         }
     }
-    if incoming_roads.len() <= 2 {
-        return for_degenerate_and_deadend(map, id);
+    let osm_stops = |r: &RoadID| map.get_r(*r).osm_stop_sign(id);
+    if incoming_roads.len() <= 2 && !incoming_roads.iter().any(|r| osm_stops(r) == Some("yes")) {
+        return (
+            for_degenerate_and_deadend(map, id),
+            StopSignConfidence::High,
+        );
     }
 
     // Higher numbers are higher rank roads
-    let mut rank_per_incoming_lane: HashMap<LaneID, usize> = HashMap::new();
-    let mut ranks: HashSet<usize> = HashSet::new();
-    let mut highest_rank = 0;
-    // TODO should just be incoming, but because of weirdness with sidewalks...
-    for l in map
-        .get_i(id)
-        .incoming_lanes
+    let highest_rank = incoming_roads
         .iter()
-        .chain(map.get_i(id).outgoing_lanes.iter())
-    {
-        let rank = map.get_parent(*l).get_rank();
-        rank_per_incoming_lane.insert(*l, rank);
-        highest_rank = highest_rank.max(rank);
-        ranks.insert(rank);
-    }
-    if ranks.len() == 1 {
-        return Warn::ok(all_way_stop(map, id));
+        .map(|r| map.get_r(*r).get_rank())
+        .max()
+        .unwrap_or(0);
+    let minor = |r: &RoadID| map.get_r(*r).get_rank() < highest_rank;
+
+    // Trust OSM when it says who stops. A stop sign on the way applies no matter what;
+    // stop=minor at the intersection needs the road hierarchy to mean anything. Smaller roads stop
+    // for the biggest one either way, since a missing tag doesn't mean there's no sign.
+    let tagged: BTreeSet<RoadID> = incoming_roads
+        .iter()
+        .filter(|r| match osm_stops(*r) {
+            Some("yes") => true,
+            Some("minor") => minor(*r),
+            _ => false,
+        })
+        .cloned()
+        .collect();
+    let mut stopping: BTreeSet<RoadID> = incoming_roads
+        .iter()
+        .filter(|r| tagged.contains(*r) || minor(*r))
+        .cloned()
+        .collect();
+    let confidence = if !tagged.is_empty() {
+        StopSignConfidence::High
+    } else if !stopping.is_empty() {
+        StopSignConfidence::Medium
+    } else {
+        stopping = incoming_roads.clone();
+        StopSignConfidence::Low
+    };
+    if stopping == incoming_roads {
+        return (Warn::ok(all_way_stop(map, id)), confidence);
     }
 
     let mut ss = ControlStopSign {
@@ -247,10 +323,17 @@ fn smart_assignment(map: &Map, id: IntersectionID) -> Warn<ControlStopSign> {
         roads: BTreeMap::new(),
     };
     for t in &map.get_i(id).turns {
+        // Crosswalks aren't on an incoming road, so go by the rank of the road they cross.
+        let src = map.get_l(t.src).parent;
+        let stops = if incoming_roads.contains(&src) {
+            stopping.contains(&src)
+        } else {
+            minor(&src)
+        };
         if map.get_t(*t).turn_type == TurnType::SharedSidewalkCorner {
             ss.turns.insert(*t, TurnPriority::Priority);
-        } else if rank_per_incoming_lane[&t.src] == highest_rank {
-            // If it's the highest rank road, prioritize main turns and make others yield.
+        } else if !stops {
+            // Roads without a stop sign prioritize main turns and make others yield.
             ss.turns.insert(*t, TurnPriority::Yield);
             if ss.could_be_priority_turn(*t, map) {
                 match map.get_t(*t).turn_type {
@@ -261,11 +344,10 @@ fn smart_assignment(map: &Map, id: IntersectionID) -> Warn<ControlStopSign> {
                 }
             }
         } else {
-            // Lower rank roads have to stop.
             ss.turns.insert(*t, TurnPriority::Stop);
         }
     }
-    Warn::ok(ss)
+    (Warn::ok(ss), confidence)
 }
 
 fn all_way_stop(map: &Map, id: IntersectionID) -> ControlStopSign {
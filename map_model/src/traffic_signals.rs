@@ -1,11 +1,20 @@
-use crate::{IntersectionID, Map, RoadID, Turn, TurnID, TurnPriority, TurnType};
+use crate::{IntersectionID, LaneID, Map, RoadID, Turn, TurnID, TurnPriority, TurnType};
 use abstutil::{Error, Timer};
-use geom::Duration;
+use geom::{Duration, Speed};
 use serde_derive::{Deserialize, Serialize};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 const CYCLE_DURATION: Duration = Duration::const_seconds(30.0);
 
+// For Webster's method. How many vehicles per hour one lane gets through on green, and how much
+// of each cycle is lost to starting up and clearing out.
+const SATURATION_FLOW_PER_HOUR: f64 = 1800.0;
+const LOST_TIME_PER_CYCLE: Duration = Duration::const_seconds(4.0);
+const MAX_SIGNAL_PERIOD: Duration = Duration::const_seconds(180.0);
+// Every cycle has to be long enough to matter, and to let slow pedestrians finish crossing.
+const MIN_CYCLE_DURATION: Duration = Duration::const_seconds(10.0);
+const SLOW_WALKING_SPEED: Speed = Speed::const_meters_per_second(1.0);
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ControlTrafficSignal {
     pub id: IntersectionID,
@@ -49,12 +58,86 @@ impl ControlTrafficSignal {
     }
 
     pub fn current_cycle_and_remaining_time(&self, now: Duration) -> (usize, &Cycle, Duration) {
-        let cycle_idx = (now / CYCLE_DURATION).floor() as usize;
-        let normalized_cycle_idx = cycle_idx % self.cycles.len();
-        let cycle = &self.cycles[normalized_cycle_idx];
-        let next_cycle_time = CYCLE_DURATION * (cycle_idx + 1) as f64;
-        let remaining_cycle_time = next_cycle_time - now;
-        (normalized_cycle_idx, cycle, remaining_cycle_time)
+        let period = self.period();
+        let mut offset = now - period * (now / period).floor();
+        for (idx, cycle) in self.cycles.iter().enumerate() {
+            if offset < cycle.duration {
+                return (idx, cycle, cycle.duration - offset);
+            }
+            offset -= cycle.duration;
+        }
+        // Only reachable through floating point error right at the end of the period
+        let idx = self.cycles.len() - 1;
+        (idx, &self.cycles[idx], Duration::EPSILON)
+    }
+
+    // How long it takes to go through every cycle once
+    pub fn period(&self) -> Duration {
+        self.cycles
+            .iter()
+            .fold(Duration::ZERO, |sum, c| sum + c.duration)
+    }
+
+    // Webster's method: the period comes from how saturated the intersection is, then each cycle
+    // gets green time in proportion to its busiest lane. demand counts how many agents made each
+    // turn over the observed duration. The cycles themselves don't change.
+    pub fn with_webster_timing(
+        &self,
+        map: &Map,
+        demand: &BTreeMap<TurnID, usize>,
+        observed: Duration,
+    ) -> ControlTrafficSignal {
+        let hours = observed.inner_seconds() / 3600.0;
+        let mut flow_ratios = Vec::new();
+        for cycle in &self.cycles {
+            let mut per_lane: BTreeMap<LaneID, f64> = BTreeMap::new();
+            for (t, count) in demand {
+                // Edits since the demand was observed might've removed some turns.
+                if t.parent != self.id
+                    || map
+                        .maybe_get_t(*t)
+                        .map(|t| t.between_sidewalks())
+                        .unwrap_or(true)
+                    || cycle.get_priority(*t) == TurnPriority::Banned
+                {
+                    continue;
+                }
+                // Turns allowed in several cycles split their demand between them.
+                let num_cycles = self
+                    .cycles
+                    .iter()
+                    .filter(|c| c.get_priority(*t) != TurnPriority::Banned)
+                    .count();
+                *per_lane.entry(t.src).or_insert(0.0) +=
+                    (*count as f64) / (num_cycles as f64) / hours;
+            }
+            flow_ratios
+                .push(per_lane.values().cloned().fold(0.0, f64::max) / SATURATION_FLOW_PER_HOUR);
+        }
+        let total_ratio: f64 = flow_ratios.iter().sum();
+
+        let lost = LOST_TIME_PER_CYCLE * (self.cycles.len() as f64);
+        let min_period = MIN_CYCLE_DURATION * (self.cycles.len() as f64);
+        // The formula blows up near saturation.
+        let period = if total_ratio < 0.95 {
+            Duration::seconds((1.5 * lost.inner_seconds() + 5.0) / (1.0 - total_ratio))
+                .min(MAX_SIGNAL_PERIOD)
+        } else {
+            MAX_SIGNAL_PERIOD
+        }
+        .max(min_period);
+        let green = period - lost;
+
+        let mut signal = self.clone();
+        for (cycle, ratio) in signal.cycles.iter_mut().zip(flow_ratios) {
+            let share = if total_ratio > 0.0 {
+                ratio / total_ratio
+            } else {
+                1.0 / (self.cycles.len() as f64)
+            };
+            cycle.duration = (LOST_TIME_PER_CYCLE + green * share).max(cycle.min_duration(map));
+        }
+        signal
     }
 
     fn validate(&self, map: &Map) -> Result<(), Error> {
@@ -72,6 +155,15 @@ impl ControlTrafficSignal {
         }
 
         for cycle in &self.cycles {
+            // Is there time for everyone crossing to finish?
+            let min = cycle.min_duration(map);
+            if cycle.duration < min {
+                return Err(Error::new(format!(
+                    "Traffic signal at {} has a cycle lasting {}, but it needs at least {}",
+                    self.id, cycle.duration, min
+                )));
+            }
+
             // Do any of the priority turns in one cycle conflict?
             for t1 in cycle.priority_turns.iter().map(|t| map.get_t(*t)) {
                 for t2 in cycle.priority_turns.iter().map(|t| map.get_t(*t)) {
@@ -101,6 +193,14 @@ impl ControlTrafficSignal {
         Ok(())
     }
 
+    // Every cycle lasts long enough for its crosswalks.
+    fn from_cycles(map: &Map, id: IntersectionID, mut cycles: Vec<Cycle>) -> ControlTrafficSignal {
+        for cycle in cycles.iter_mut() {
+            cycle.duration = cycle.duration.max(cycle.min_duration(map));
+        }
+        ControlTrafficSignal { id, cycles }
+    }
+
     fn greedy_assignment(map: &Map, intersection: IntersectionID) -> ControlTrafficSignal {
         if map.get_turns_in_intersection(intersection).is_empty() {
             panic!("{} has no turns", intersection);
@@ -137,10 +237,7 @@ impl ControlTrafficSignal {
 
         expand_all_cycles(&mut cycles, map, intersection);
 
-        let ts = ControlTrafficSignal {
-            id: intersection,
-            cycles,
-        };
+        let ts = ControlTrafficSignal::from_cycles(map, intersection, cycles);
         // This must succeed
         ts.validate(map).unwrap();
         ts
@@ -168,7 +265,7 @@ impl ControlTrafficSignal {
 
         let cycles = make_cycles(map, i, phases);
 
-        let ts = ControlTrafficSignal { id: i, cycles };
+        let ts = ControlTrafficSignal::from_cycles(map, i, cycles);
         if ts.validate(map).is_ok() {
             Some(ts)
         } else {
@@ -221,7 +318,7 @@ impl ControlTrafficSignal {
             ],
         );
 
-        let ts = ControlTrafficSignal { id: i, cycles };
+        let ts = ControlTrafficSignal::from_cycles(map, i, cycles);
         if ts.validate(map).is_ok() {
             Some(ts)
         } else {
@@ -267,7 +364,7 @@ impl ControlTrafficSignal {
             ],
         );
 
-        let ts = ControlTrafficSignal { id: i, cycles };
+        let ts = ControlTrafficSignal::from_cycles(map, i, cycles);
         if ts.validate(map).is_ok() {
             Some(ts)
         } else {
@@ -312,7 +409,7 @@ impl ControlTrafficSignal {
             ],
         );
 
-        let ts = ControlTrafficSignal { id: i, cycles };
+        let ts = ControlTrafficSignal::from_cycles(map, i, cycles);
         if ts.validate(map).is_ok() {
             Some(ts)
         } else {
@@ -368,7 +465,7 @@ impl ControlTrafficSignal {
             ],
         );
 
-        let ts = ControlTrafficSignal { id: i, cycles };
+        let ts = ControlTrafficSignal::from_cycles(map, i, cycles);
         if ts.validate(map).is_ok() {
             Some(ts)
         } else {
@@ -438,6 +535,17 @@ impl Cycle {
         true
     }
 
+    // Long enough for everyone crossing to finish
+    pub fn min_duration(&self, map: &Map) -> Duration {
+        self.priority_turns
+            .iter()
+            .map(|t| map.get_t(*t))
+            .filter(|t| t.turn_type == TurnType::Crosswalk)
+            .fold(MIN_CYCLE_DURATION, |min, t| {
+                min.max(t.geom.length() / SLOW_WALKING_SPEED)
+            })
+    }
+
     pub fn get_priority(&self, t: TurnID) -> TurnPriority {
         if self.priority_turns.contains(&t) {
             TurnPriority::Priority
@@ -145,8 +145,8 @@ impl Traversable {
 
     pub fn speed_limit(&self, map: &Map) -> Speed {
         match *self {
-            Traversable::Lane(id) => map.get_parent(id).get_speed_limit(),
-            Traversable::Turn(id) => map.get_parent(id.dst).get_speed_limit(),
+            Traversable::Lane(id) => map.get_parent(id).speed_limit_for(id),
+            Traversable::Turn(id) => map.get_parent(id.dst).speed_limit_for(id.dst),
         }
     }
 
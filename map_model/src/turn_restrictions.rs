@@ -0,0 +1,231 @@
+use crate::{raw_data, IntersectionID, LaneType, RoadID};
+use geom::Duration;
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestrictionType {
+    BanTurns,
+    OnlyAllowTurns,
+}
+
+// Lives in the from road.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TurnRestriction {
+    pub restriction_type: RestrictionType,
+    // Where the from road meets the first via road or the to road
+    pub at: IntersectionID,
+    // Roads between the from and to road, in order. Empty when the restriction applies at one
+    // intersection.
+    pub via: Vec<RoadID>,
+    pub to: RoadID,
+    pub except_bikes: bool,
+    pub except_buses: bool,
+    // None means always
+    pub condition: Option<TimeCondition>,
+    // The original OSM value, like "no_left_turn"
+    pub osm_restriction: String,
+}
+
+impl TurnRestriction {
+    // The via roads and to road must already be resolved.
+    pub fn new(
+        raw: &raw_data::TurnRestriction,
+        at: IntersectionID,
+        via: Vec<RoadID>,
+        to: RoadID,
+    ) -> Result<TurnRestriction, String> {
+        let restriction_type = match raw.restriction.as_ref() {
+            "no_left_turn" | "no_right_turn" | "no_straight_on" | "no_u_turn" | "no_entry"
+            | "no_exit" | "no_anything" => RestrictionType::BanTurns,
+            "only_left_turn" | "only_right_turn" | "only_straight_on" | "only_u_turn" => {
+                RestrictionType::OnlyAllowTurns
+            }
+            x => {
+                return Err(format!("Unknown restriction {}", x));
+            }
+        };
+        let condition = if let Some(ref c) = raw.conditions {
+            Some(TimeCondition::parse(c).ok_or_else(|| format!("Can't parse condition {}", c))?)
+        } else {
+            None
+        };
+        Ok(TurnRestriction {
+            restriction_type,
+            at,
+            via,
+            to,
+            except_bikes: raw.except.iter().any(|e| e == "bicycle"),
+            except_buses: raw.except.iter().any(|e| e == "psv" || e == "bus"),
+            condition,
+            osm_restriction: raw.restriction.clone(),
+        })
+    }
+
+    pub fn ban_everything(at: IntersectionID, to: RoadID) -> TurnRestriction {
+        TurnRestriction {
+            restriction_type: RestrictionType::BanTurns,
+            at,
+            via: Vec::new(),
+            to,
+            except_bikes: false,
+            except_buses: false,
+            condition: None,
+            osm_restriction: "no_anything".to_string(),
+        }
+    }
+
+    // Applies to everyone all the time at one intersection, so the affected turns can just be
+    // removed from the map.
+    pub fn is_permanent(&self) -> bool {
+        self.via.is_empty() && !self.except_bikes && !self.except_buses && self.condition.is_none()
+    }
+
+    // Pathfinding graphs can encode restrictions at one intersection that hold all the time. The
+    // rest have to be checked per path.
+    pub fn is_static(&self) -> bool {
+        self.via.is_empty() && self.condition.is_none()
+    }
+
+    // lane_types are what the vehicle may use, like in VehiclePathfinder.
+    pub fn applies_to(&self, lane_types: &[LaneType]) -> bool {
+        if self.except_bikes && lane_types.contains(&LaneType::Biking) {
+            return false;
+        }
+        if self.except_buses && lane_types.contains(&LaneType::Bus) {
+            return false;
+        }
+        true
+    }
+
+    pub fn active_at(&self, time: Duration) -> bool {
+        self.condition
+            .as_ref()
+            .map(|c| c.active_at(time))
+            .unwrap_or(true)
+    }
+
+    // roads starts with the from road, and the first turn happens at the given intersection.
+    // Returns true if this is a prefix that the restriction forbids. Sequences that don't make it
+    // through all of the via roads are fine.
+    pub fn forbids(&self, at: IntersectionID, roads: &[RoadID]) -> bool {
+        if at != self.at
+            || roads.len() < self.via.len() + 2
+            || roads[1..=self.via.len()] != self.via[..]
+        {
+            return false;
+        }
+        let next = roads[self.via.len() + 1];
+        match self.restriction_type {
+            RestrictionType::BanTurns => next == self.to,
+            RestrictionType::OnlyAllowTurns => next != self.to,
+        }
+    }
+}
+
+impl fmt::Display for TurnRestriction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} to {}", self.osm_restriction, self.to)?;
+        if !self.via.is_empty() {
+            write!(f, " via {:?}", self.via)?;
+        }
+        if self.except_bikes {
+            write!(f, ", except bikes")?;
+        }
+        if self.except_buses {
+            write!(f, ", except buses")?;
+        }
+        if let Some(ref c) = self.condition {
+            write!(f, " @ ({})", c.osm)?;
+        }
+        Ok(())
+    }
+}
+
+// A subset of the OSM opening_hours syntax, like "Mo-Fr 07:00-09:00,16:00-18:00; Sa 10:00-14:00".
+// Simulations don't have a calendar, so day 0 is a Monday.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TimeCondition {
+    // (Days of the week starting from Monday, start and end time within the day)
+    rules: Vec<([bool; 7], Duration, Duration)>,
+    osm: String,
+}
+
+const DAYS: [&str; 7] = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"];
+
+impl TimeCondition {
+    pub fn parse(osm: &str) -> Option<TimeCondition> {
+        let mut rules = Vec::new();
+        for rule in osm.split(';') {
+            let rule = rule.trim();
+            if rule.is_empty() {
+                continue;
+            }
+            let (days, times) = if rule.starts_with(|c: char| c.is_ascii_digit()) {
+                ([true; 7], rule)
+            } else {
+                let mut parts = rule.splitn(2, ' ');
+                (parse_days(parts.next()?)?, parts.next()?.trim())
+            };
+            for span in times.split(',') {
+                let mut parts = span.trim().split('-');
+                let start = parse_time(parts.next()?)?;
+                let end = parse_time(parts.next()?)?;
+                rules.push((days, start, end));
+            }
+        }
+        if rules.is_empty() {
+            return None;
+        }
+        Some(TimeCondition {
+            rules,
+            osm: osm.to_string(),
+        })
+    }
+
+    pub fn active_at(&self, time: Duration) -> bool {
+        let day_length = Duration::minutes(24 * 60);
+        let day = ((time / day_length).floor() as usize) % 7;
+        let time_of_day = time - day_length * (time / day_length).floor();
+        self.rules.iter().any(|(days, start, end)| {
+            if start <= end {
+                days[day] && time_of_day >= *start && time_of_day < *end
+            } else {
+                // Overnight, like 22:00-06:00. The early morning part belongs to the day before.
+                (days[day] && time_of_day >= *start) || (days[(day + 6) % 7] && time_of_day < *end)
+            }
+        })
+    }
+}
+
+// Like "Mo-Fr" or "Sa,Su"
+fn parse_days(spec: &str) -> Option<[bool; 7]> {
+    let mut days = [false; 7];
+    for part in spec.split(',') {
+        let mut range = part.split('-');
+        let first = range.next()?;
+        let start = DAYS.iter().position(|d| *d == first)?;
+        let end = if let Some(d) = range.next() {
+            DAYS.iter().position(|x| *x == d)?
+        } else {
+            start
+        };
+        let mut idx = start;
+        loop {
+            days[idx] = true;
+            if idx == end {
+                break;
+            }
+            idx = (idx + 1) % 7;
+        }
+    }
+    Some(days)
+}
+
+// Like "07:30"
+fn parse_time(time: &str) -> Option<Duration> {
+    let mut parts = time.trim().split(':');
+    let hours = parts.next()?.parse::<f64>().ok()?;
+    let minutes = parts.next()?.parse::<f64>().ok()?;
+    Some(Duration::seconds(hours * 3600.0 + minutes * 60.0))
+}
@@ -0,0 +1,224 @@
+use crate::make::largest_driving_scc;
+use crate::raw_data::OriginalRoad;
+use crate::{
+    ControlStopSign, IntersectionID, IntersectionType, LaneID, Map, RoadID, StopSignConfidence,
+};
+use geom::{GPSBounds, LonLat, Pt2D};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+// Problems found while importing a map, usually pointing at OSM data that should be fixed
+// upstream or covered by a hint.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MapValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IssueType {
+    // Exactly two roads with the same lanes meet, so the split is probably pointless.
+    DegenerateIntersection,
+    // No incoming or outgoing lanes, so no turns were made
+    OrphanedIntersection,
+    // Driving lanes that can't reach or can't be reached from most of the map
+    DisconnectedRoad,
+    // A disconnected driving lane with no nearby connected lane to redirect parking to
+    ParkingBlackhole,
+    DroppedBusRoute,
+    SkippedTurnRestriction,
+    // Roads of the same rank with no OSM stop signs, so it's an all-way stop by default
+    GuessedStopSign,
+    // Several roads share the same endpoints, so edits on them can't be migrated to a new import
+    DuplicateOriginalRoad,
+}
+
+impl fmt::Display for IssueType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ValidationIssue {
+    pub issue_type: IssueType,
+    pub description: String,
+    pub roads: Vec<RoadID>,
+    pub lanes: Vec<LaneID>,
+    pub intersections: Vec<IntersectionID>,
+    pub osm_way_ids: Vec<i64>,
+    // Where to look
+    pub pt: Pt2D,
+    pub gps: Option<LonLat>,
+}
+
+impl MapValidationReport {
+    pub(crate) fn add(
+        &mut self,
+        issue_type: IssueType,
+        description: String,
+        pt: Pt2D,
+        gps_bounds: &GPSBounds,
+    ) -> &mut ValidationIssue {
+        self.issues.push(ValidationIssue {
+            issue_type,
+            description,
+            roads: Vec::new(),
+            lanes: Vec::new(),
+            intersections: Vec::new(),
+            osm_way_ids: Vec::new(),
+            pt,
+            gps: pt.to_gps(gps_bounds),
+        });
+        self.issues.last_mut().unwrap()
+    }
+
+    // Checks that only need the finished map.
+    pub(crate) fn check_map(map: &Map) -> MapValidationReport {
+        let mut report = MapValidationReport::default();
+        let gps_bounds = map.get_gps_bounds();
+
+        for i in map.all_intersections() {
+            if !i.is_degenerate() {
+                continue;
+            }
+            let roads: Vec<RoadID> = i.roads.iter().cloned().collect();
+            let (r1, r2) = (map.get_r(roads[0]), map.get_r(roads[1]));
+            let mut types1 = r1.get_lane_types();
+            let types2 = r2.get_lane_types();
+            // The roads might point different directions
+            if (r1.src_i == i.id) == (r2.src_i == i.id) {
+                types1 = (types1.1, types1.0);
+            }
+            if types1 != types2 {
+                continue;
+            }
+            let issue = report.add(
+                IssueType::DegenerateIntersection,
+                format!(
+                    "{} joins {} and {}, which have the same lanes",
+                    i.id, r1.id, r2.id
+                ),
+                i.polygon.center(),
+                gps_bounds,
+            );
+            issue.intersections.push(i.id);
+            issue.roads = roads;
+            issue.osm_way_ids = vec![r1.osm_way_id, r2.osm_way_id];
+        }
+
+        for i in map.all_intersections() {
+            if i.intersection_type != IntersectionType::StopSign
+                || ControlStopSign::default_confidence(map, i.id) != StopSignConfidence::Low
+            {
+                continue;
+            }
+            let issue = report.add(
+                IssueType::GuessedStopSign,
+                format!(
+                    "{} is guessed to be an all-way stop; tag highway=stop in OSM to fix",
+                    i.id
+                ),
+                i.polygon.center(),
+                gps_bounds,
+            );
+            issue.intersections.push(i.id);
+            for r in &i.roads {
+                issue.roads.push(*r);
+                issue.osm_way_ids.push(map.get_r(*r).osm_way_id);
+            }
+        }
+
+        let mut roads_per_segment_id: BTreeMap<OriginalRoad, Vec<RoadID>> = BTreeMap::new();
+        for r in map.all_roads() {
+            roads_per_segment_id
+                .entry(r.segment_id)
+                .or_insert_with(Vec::new)
+                .push(r.id);
+        }
+        for (orig, roads) in roads_per_segment_id {
+            if roads.len() < 2 {
+                continue;
+            }
+            let issue = report.add(
+                IssueType::DuplicateOriginalRoad,
+                format!(
+                    "{} roads go from {} to {}, so edits to them won't survive re-importing",
+                    roads.len(),
+                    orig.pt1,
+                    orig.pt2
+                ),
+                map.get_r(roads[0]).center_pts.middle(),
+                gps_bounds,
+            );
+            for r in &roads {
+                issue.osm_way_ids.push(map.get_r(*r).osm_way_id);
+            }
+            issue.roads = roads;
+        }
+
+        let largest_group = largest_driving_scc(map);
+        let mut disconnected: BTreeSet<RoadID> = BTreeSet::new();
+        for l in map.all_lanes() {
+            if !l.is_driving() || largest_group.contains(&l.id) {
+                continue;
+            }
+            disconnected.insert(l.parent);
+            if l.parking_blackhole.is_none() {
+                let r = map.get_r(l.parent);
+                let issue = report.add(
+                    IssueType::ParkingBlackhole,
+                    format!(
+                        "{} is a parking blackhole with no reasonable redirect",
+                        l.id
+                    ),
+                    l.lane_center_pts.middle(),
+                    gps_bounds,
+                );
+                issue.lanes.push(l.id);
+                issue.roads.push(r.id);
+                issue.osm_way_ids.push(r.osm_way_id);
+            }
+        }
+        for id in disconnected {
+            let r = map.get_r(id);
+            let lanes: Vec<LaneID> = r
+                .all_lanes()
+                .into_iter()
+                .filter(|l| map.get_l(*l).is_driving() && !largest_group.contains(l))
+                .collect();
+            let issue = report.add(
+                IssueType::DisconnectedRoad,
+                format!(
+                    "{} ({}) has {} driving lanes cut off from the rest of the map",
+                    r.id,
+                    r.get_name(),
+                    lanes.len()
+                ),
+                r.center_pts.middle(),
+                gps_bounds,
+            );
+            issue.roads.push(r.id);
+            issue.lanes = lanes;
+            issue.osm_way_ids.push(r.osm_way_id);
+        }
+        report
+    }
+
+    pub fn count(&self, issue_type: IssueType) -> usize {
+        self.issues
+            .iter()
+            .filter(|i| i.issue_type == issue_type)
+            .count()
+    }
+
+    pub fn save(&self, map_name: &str) {
+        let path = abstutil::path_validation_report(map_name);
+        abstutil::write_json(&path, self).expect(&format!("Saving {} failed", path));
+        println!(
+            "Saved {} map validation issues to {}",
+            self.issues.len(),
+            path
+        );
+    }
+}
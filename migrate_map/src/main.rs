@@ -0,0 +1,62 @@
+use abstutil::Timer;
+use map_model::{Map, MapEdits, MapMigration};
+use sim::Scenario;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(name = "migrate_map")]
+struct Flags {
+    /// The map that edits and scenarios were made against. Keep a copy of the .bin before
+    /// re-importing.
+    #[structopt(long = "old_map")]
+    old_map: String,
+
+    /// The re-imported map. Its name determines which edits and scenarios are migrated.
+    #[structopt(long = "new_map")]
+    new_map: String,
+
+    /// Just report what can't be matched, without overwriting anything
+    #[structopt(long = "dry_run")]
+    dry_run: bool,
+}
+
+fn main() {
+    let flags = Flags::from_args();
+    let mut timer = Timer::new("migrate edits and scenarios");
+
+    let old_map: Map = abstutil::read_binary(&flags.old_map, &mut timer)
+        .expect(&format!("Couldn't load {}", flags.old_map));
+    let new_map: Map = abstutil::read_binary(&flags.new_map, &mut timer)
+        .expect(&format!("Couldn't load {}", flags.new_map));
+    let map_name = new_map.get_name().to_string();
+    let mut migration = MapMigration::new(&old_map, &new_map);
+
+    for (name, edits) in abstutil::load_all_objects::<MapEdits>(abstutil::EDITS, &map_name) {
+        timer.start(&format!("migrate edits {}", name));
+        let edits = edits.migrate(&mut migration);
+        if !flags.dry_run {
+            edits.save();
+        }
+        timer.stop(&format!("migrate edits {}", name));
+    }
+
+    for (name, scenario) in abstutil::load_all_objects::<Scenario>(abstutil::SCENARIOS, &map_name) {
+        timer.start(&format!("migrate scenario {}", name));
+        let scenario = scenario.migrate(&mut migration);
+        if !flags.dry_run {
+            scenario.save();
+        }
+        timer.stop(&format!("migrate scenario {}", name));
+    }
+
+    // Savestates capture every agent mid-trip, so there's no sensible way to carry them over.
+    println!("Savestates for {} can't be migrated; delete them", map_name);
+    if migration.problems.is_empty() {
+        println!("Everything matched");
+    } else {
+        println!("{} problems:", migration.problems.len());
+        for problem in &migration.problems {
+            println!("- {}", problem);
+        }
+    }
+}
@@ -31,6 +31,7 @@ fn main() {
     let map = Map::new(&raw_map_path, &mut timer).unwrap();
     timer.start("save map");
     map.save();
+    map.get_validation_report().save(map.get_name());
     timer.stop("save map");
 
     if !flags.disable_psrc_scenarios {
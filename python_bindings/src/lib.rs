@@ -0,0 +1,33 @@
+// A thin Python wrapper over the map and simulation, for poking at things from Jupyter. pyo3 needs
+// nightly, so build with `cargo +nightly build --release` from this crate, then copy
+// target/release/libabstreet.so to abstreet.so somewhere on the Python path.
+// Paths are relative to a crate directory, like everywhere else, so run Python from one of those.
+
+mod map;
+mod simulation;
+
+use geom::{PolyLine, Pt2D};
+use pyo3::exceptions;
+use pyo3::prelude::*;
+
+#[pymodule]
+fn abstreet(_: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<map::Map>()?;
+    m.add_class::<map::MapEdits>()?;
+    m.add_class::<simulation::Scenario>()?;
+    m.add_class::<simulation::Sim>()?;
+    Ok(())
+}
+
+// Geometry is in map-space meters. Map.to_lonlat converts.
+fn pts(pts: &[Pt2D]) -> Vec<(f64, f64)> {
+    pts.iter().map(|pt| (pt.x(), pt.y())).collect()
+}
+
+fn polyline(pl: &PolyLine) -> Vec<(f64, f64)> {
+    pts(pl.points())
+}
+
+fn value_error<T: std::fmt::Display>(err: T) -> PyErr {
+    PyErr::new::<exceptions::ValueError, _>(err.to_string())
+}
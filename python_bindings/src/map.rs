@@ -0,0 +1,183 @@
+use crate::{polyline, pts, value_error};
+use abstutil::Timer;
+use geom::Pt2D;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::HashMap;
+
+#[pyclass]
+pub struct Map {
+    // Every Sim loads its own copy, since running it changes the map for scheduled edits.
+    pub map: map_model::Map,
+}
+
+#[pymethods]
+impl Map {
+    // Loads ../data/maps/{name}.bin
+    #[new]
+    fn new(obj: &PyRawObject, name: String) -> PyResult<()> {
+        let map = load_map(&name, &mut Timer::new(&format!("load {}", name)))?;
+        obj.init(Map { map });
+        Ok(())
+    }
+
+    fn name(&self) -> String {
+        self.map.get_name().to_string()
+    }
+
+    fn edits_name(&self) -> String {
+        self.map.get_edits().edits_name.clone()
+    }
+
+    // One dict per road
+    fn roads(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        let mut result = Vec::new();
+        for r in self.map.all_roads() {
+            let d = PyDict::new(py);
+            d.set_item("id", r.id.0)?;
+            d.set_item("name", r.get_name())?;
+            d.set_item("osm_way_id", r.osm_way_id)?;
+            d.set_item("src_i", r.src_i.0)?;
+            d.set_item("dst_i", r.dst_i.0)?;
+            d.set_item(
+                "lanes_forwards",
+                r.children_forwards
+                    .iter()
+                    .map(|(l, _)| l.0)
+                    .collect::<Vec<_>>(),
+            )?;
+            d.set_item(
+                "lanes_backwards",
+                r.children_backwards
+                    .iter()
+                    .map(|(l, _)| l.0)
+                    .collect::<Vec<_>>(),
+            )?;
+            d.set_item(
+                "speed_limit_mps",
+                r.get_speed_limit().inner_meters_per_second(),
+            )?;
+            d.set_item("length_m", r.center_pts.length().inner_meters())?;
+            d.set_item("center_pts", polyline(&r.center_pts))?;
+            d.set_item("osm_tags", r.osm_tags.iter().collect::<HashMap<_, _>>())?;
+            result.push(d.to_object(py));
+        }
+        Ok(result)
+    }
+
+    // One dict per lane
+    fn lanes(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        let mut result = Vec::new();
+        for l in self.map.all_lanes() {
+            if !self.map.lane_exists(l.id) {
+                continue;
+            }
+            let d = PyDict::new(py);
+            d.set_item("id", l.id.0)?;
+            d.set_item("road", l.parent.0)?;
+            d.set_item("lane_type", format!("{:?}", l.lane_type))?;
+            d.set_item("src_i", l.src_i.0)?;
+            d.set_item("dst_i", l.dst_i.0)?;
+            d.set_item("length_m", l.length().inner_meters())?;
+            d.set_item("center_pts", polyline(&l.lane_center_pts))?;
+            // None when the turns are guessed
+            d.set_item(
+                "allowed_turns",
+                l.allowed_turns
+                    .as_ref()
+                    .map(|turns| turns.iter().map(|t| format!("{:?}", t)).collect::<Vec<_>>()),
+            )?;
+            result.push(d.to_object(py));
+        }
+        Ok(result)
+    }
+
+    // One dict per intersection
+    fn intersections(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        let mut result = Vec::new();
+        for i in self.map.all_intersections() {
+            let d = PyDict::new(py);
+            d.set_item("id", i.id.0)?;
+            d.set_item("intersection_type", format!("{:?}", i.intersection_type))?;
+            d.set_item("roads", i.roads.iter().map(|r| r.0).collect::<Vec<_>>())?;
+            d.set_item(
+                "incoming_lanes",
+                i.incoming_lanes.iter().map(|l| l.0).collect::<Vec<_>>(),
+            )?;
+            d.set_item(
+                "outgoing_lanes",
+                i.outgoing_lanes.iter().map(|l| l.0).collect::<Vec<_>>(),
+            )?;
+            d.set_item("polygon", pts(i.polygon.points()))?;
+            result.push(d.to_object(py));
+        }
+        Ok(result)
+    }
+
+    // Returns None for points outside the map
+    fn to_lonlat(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        Pt2D::new(x, y)
+            .to_gps(self.map.get_gps_bounds())
+            .map(|gps| (gps.longitude, gps.latitude))
+    }
+
+    // Sims made from this map earlier keep using the old version of it; use Sim.apply_edits to
+    // change those.
+    fn apply_edits(&mut self, edits: &MapEdits) {
+        let mut timer = Timer::new("apply edits");
+        self.map.apply_edits(edits.edits.clone(), &mut timer);
+        self.map.recalculate_pathfinding_after_edits(&mut timer);
+    }
+}
+
+// Loads ../data/maps/{name}.bin
+pub fn load_map(name: &str, timer: &mut Timer) -> PyResult<map_model::Map> {
+    abstutil::read_binary(&abstutil::path_map(name), timer).map_err(value_error)
+}
+
+#[pyclass]
+pub struct MapEdits {
+    pub edits: map_model::MapEdits,
+}
+
+#[pymethods]
+impl MapEdits {
+    // Loads ../data/edits/{map_name}/{edits_name}.json
+    #[staticmethod]
+    fn load(map_name: &str, edits_name: &str) -> PyResult<MapEdits> {
+        if edits_name == "no_edits" {
+            return Ok(MapEdits {
+                edits: map_model::MapEdits::new(map_name.to_string()),
+            });
+        }
+        let edits =
+            abstutil::read_json(&abstutil::path1_json(map_name, abstutil::EDITS, edits_name))
+                .map_err(value_error)?;
+        Ok(MapEdits { edits })
+    }
+
+    // The same format as the files in ../data/edits, so scripts can build edits as dicts
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<MapEdits> {
+        let edits = serde_json::from_str(json).map_err(value_error)?;
+        Ok(MapEdits { edits })
+    }
+
+    fn to_json(&self) -> String {
+        abstutil::to_json(&self.edits)
+    }
+
+    fn edits_name(&self) -> String {
+        self.edits.edits_name.clone()
+    }
+
+    // One line per edit that differs, like "+ lane type of Lane #12". The map is needed to line
+    // up lanes added by cross-section edits.
+    fn diff(&self, other: &MapEdits, map: &Map) -> Vec<String> {
+        self.edits.diff(&other.edits, &map.map).describe()
+    }
+
+    fn save(&self) {
+        self.edits.save();
+    }
+}
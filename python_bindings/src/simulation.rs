@@ -0,0 +1,166 @@
+use crate::map::{load_map, Map, MapEdits};
+use crate::value_error;
+use abstutil::Timer;
+use geom::Duration;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use sim::SimOptions;
+
+#[pyclass]
+pub struct Scenario {
+    pub scenario: sim::Scenario,
+}
+
+#[pymethods]
+impl Scenario {
+    // Loads ../data/scenarios/{map_name}/{scenario_name}.bin
+    #[staticmethod]
+    fn load(map_name: &str, scenario_name: &str) -> PyResult<Scenario> {
+        let scenario = abstutil::read_binary(
+            &abstutil::path1_bin(map_name, abstutil::SCENARIOS, scenario_name),
+            &mut Timer::throwaway(),
+        )
+        .map_err(value_error)?;
+        Ok(Scenario { scenario })
+    }
+
+    #[staticmethod]
+    fn small_run(map: &Map) -> Scenario {
+        Scenario {
+            scenario: sim::Scenario::small_run(&map.map),
+        }
+    }
+
+    #[staticmethod]
+    fn scaled_run(map: &Map, num_agents: usize) -> Scenario {
+        Scenario {
+            scenario: sim::Scenario::scaled_run(&map.map, num_agents),
+        }
+    }
+
+    fn scenario_name(&self) -> String {
+        self.scenario.scenario_name.clone()
+    }
+
+    fn map_name(&self) -> String {
+        self.scenario.map_name.clone()
+    }
+
+    fn to_json(&self) -> String {
+        abstutil::to_json(&self.scenario)
+    }
+}
+
+#[pyclass]
+pub struct Sim {
+    // A copy of the Map it was created from, with the same edits
+    map: map_model::Map,
+    sim: sim::Sim,
+}
+
+#[pymethods]
+impl Sim {
+    #[new]
+    #[args(rng_seed = "42", run_name = "None")]
+    fn new(
+        obj: &PyRawObject,
+        map: &Map,
+        scenario: &Scenario,
+        rng_seed: u8,
+        run_name: Option<String>,
+    ) -> PyResult<()> {
+        if map.map.get_name() != &scenario.scenario.map_name {
+            return Err(value_error(format!(
+                "Scenario {} is for {}, not {}",
+                scenario.scenario.scenario_name,
+                scenario.scenario.map_name,
+                map.map.get_name()
+            )));
+        }
+        let mut timer = Timer::new("setup sim");
+        let mut own_map = load_map(map.map.get_name(), &mut timer)?;
+        own_map.apply_edits(map.map.get_edits().clone(), &mut timer);
+        own_map.recalculate_pathfinding_after_edits(&mut timer);
+        let mut sim = sim::Sim::new(
+            &own_map,
+            SimOptions::new(&run_name.unwrap_or_else(|| scenario.scenario.scenario_name.clone())),
+        );
+        let mut rng = XorShiftRng::from_seed([rng_seed; 16]);
+        scenario
+            .scenario
+            .instantiate(&mut sim, &own_map, &mut rng, &mut timer);
+        obj.init(Sim { map: own_map, sim });
+        Ok(())
+    }
+
+    // Changes the map partway through the simulation. Agents whose paths are broken get
+    // rerouted or cancelled.
+    fn apply_edits(&mut self, edits: &MapEdits) {
+        let mut timer = Timer::new("apply edits to sim");
+        self.map.apply_edits(edits.edits.clone(), &mut timer);
+        self.map.recalculate_pathfinding_after_edits(&mut timer);
+        self.sim.handle_live_edits(&self.map, &mut timer);
+    }
+
+    // In seconds
+    fn time(&self) -> f64 {
+        self.sim.time().inner_seconds()
+    }
+
+    fn step(&mut self, seconds: f64) -> PyResult<()> {
+        if seconds <= 0.0 {
+            return Err(value_error("seconds must be positive"));
+        }
+        self.sim.step(&mut self.map, Duration::seconds(seconds));
+        Ok(())
+    }
+
+    fn run_until_done(&mut self) {
+        self.sim.just_run_until_done(&mut self.map, None);
+    }
+
+    fn is_done(&self) -> bool {
+        self.sim.is_done()
+    }
+
+    fn summary(&self) -> String {
+        self.sim.summary()
+    }
+
+    fn unfinished_trips(&self) -> usize {
+        self.sim.get_finished_trips().unfinished_trips
+    }
+
+    // A pandas DataFrame with columns trip, mode, and duration (in seconds)
+    fn finished_trips(&self, py: Python) -> PyResult<PyObject> {
+        let trips = self.sim.get_finished_trips().finished_trips;
+        let columns = PyDict::new(py);
+        columns.set_item(
+            "trip",
+            trips.iter().map(|(t, _, _)| t.0).collect::<Vec<_>>(),
+        )?;
+        columns.set_item(
+            "mode",
+            trips
+                .iter()
+                .map(|(_, m, _)| format!("{:?}", m))
+                .collect::<Vec<_>>(),
+        )?;
+        columns.set_item(
+            "duration",
+            trips
+                .iter()
+                .map(|(_, _, dt)| dt.inner_seconds())
+                .collect::<Vec<_>>(),
+        )?;
+        let pandas = py.import("pandas")?;
+        Ok(pandas.call1("DataFrame", (columns,))?.to_object(py))
+    }
+
+    // Returns the path
+    fn save(&self) -> String {
+        self.sim.save()
+    }
+}
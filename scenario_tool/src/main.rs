@@ -0,0 +1,181 @@
+use abstutil::Timer;
+use geom::Duration;
+use map_model::Map;
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use sim::{Scenario, TripMode};
+use std::collections::BTreeSet;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(name = "scenario_tool")]
+struct Flags {
+    /// Write the result here, instead of to the usual scenarios directory
+    #[structopt(long = "output")]
+    output: Option<String>,
+
+    #[structopt(subcommand)]
+    cmd: Command,
+}
+
+#[derive(StructOpt)]
+enum Command {
+    /// Combine all demand from two scenarios on the same map
+    #[structopt(name = "merge")]
+    Merge {
+        #[structopt(long = "input1")]
+        input1: String,
+        #[structopt(long = "input2")]
+        input2: String,
+        #[structopt(long = "name")]
+        name: String,
+    },
+
+    /// Scale the number of trips by some factor
+    #[structopt(name = "scale")]
+    Scale {
+        #[structopt(long = "input")]
+        input: String,
+        /// 1.2 means 20% more trips
+        #[structopt(long = "factor")]
+        factor: f64,
+        /// Determines which individual trips are sampled
+        #[structopt(long = "rng_seed", default_value = "42")]
+        rng_seed: u8,
+        #[structopt(long = "name")]
+        name: String,
+    },
+
+    /// Move all trips later by some amount
+    #[structopt(name = "shift")]
+    Shift {
+        #[structopt(long = "input")]
+        input: String,
+        #[structopt(long = "offset")]
+        offset: Duration,
+        /// Move trips earlier instead
+        #[structopt(long = "earlier")]
+        earlier: bool,
+        #[structopt(long = "name")]
+        name: String,
+    },
+
+    /// Only keep trips starting in a time window, optionally squeezing them into a new window
+    #[structopt(name = "window")]
+    Window {
+        #[structopt(long = "input")]
+        input: String,
+        #[structopt(long = "start")]
+        start: Duration,
+        #[structopt(long = "end")]
+        end: Duration,
+        #[structopt(long = "new_start")]
+        new_start: Option<Duration>,
+        #[structopt(long = "new_end")]
+        new_end: Option<Duration>,
+        #[structopt(long = "name")]
+        name: String,
+    },
+
+    /// Only keep trips using some modes
+    #[structopt(name = "filter_modes")]
+    FilterModes {
+        #[structopt(long = "input")]
+        input: String,
+        /// Comma-separated list from walk, bike, transit, drive
+        #[structopt(long = "modes")]
+        modes: String,
+        #[structopt(long = "name")]
+        name: String,
+    },
+
+    /// Only keep trips starting in a neighborhood
+    #[structopt(name = "filter_neighborhood")]
+    FilterNeighborhood {
+        #[structopt(long = "input")]
+        input: String,
+        #[structopt(long = "neighborhood")]
+        neighborhood: String,
+        #[structopt(long = "name")]
+        name: String,
+    },
+}
+
+fn main() {
+    let flags = Flags::from_args();
+    let mut timer = Timer::new("transform scenario");
+
+    let result = match flags.cmd {
+        Command::Merge {
+            input1,
+            input2,
+            name,
+        } => load(&input1, &mut timer).merge(&load(&input2, &mut timer), &name),
+        Command::Scale {
+            input,
+            factor,
+            rng_seed,
+            name,
+        } => load(&input, &mut timer).scale(
+            factor,
+            &mut XorShiftRng::from_seed([rng_seed; 16]),
+            &name,
+        ),
+        Command::Shift {
+            input,
+            offset,
+            earlier,
+            name,
+        } => load(&input, &mut timer).shift_time(if earlier { -offset } else { offset }, &name),
+        Command::Window {
+            input,
+            start,
+            end,
+            new_start,
+            new_end,
+            name,
+        } => {
+            let new_start = new_start.unwrap_or(start);
+            let new_end = new_end.unwrap_or(new_start + (end - start));
+            load(&input, &mut timer).remap_time_window(start, end, new_start, new_end, &name)
+        }
+        Command::FilterModes { input, modes, name } => {
+            let modes: BTreeSet<TripMode> = modes
+                .split(',')
+                .map(|m| match m {
+                    "walk" => TripMode::Walk,
+                    "bike" => TripMode::Bike,
+                    "transit" => TripMode::Transit,
+                    "drive" => TripMode::Drive,
+                    _ => panic!("Unknown mode {}", m),
+                })
+                .collect();
+            load(&input, &mut timer).filter_modes(&modes, &name)
+        }
+        Command::FilterNeighborhood {
+            input,
+            neighborhood,
+            name,
+        } => {
+            let scenario = load(&input, &mut timer);
+            let map: Map =
+                abstutil::read_binary(&abstutil::path_map(&scenario.map_name), &mut timer)
+                    .expect(&format!("Couldn't load map {}", scenario.map_name));
+            scenario.filter_neighborhood(&neighborhood, &map, &name)
+        }
+    };
+
+    for line in result.describe() {
+        println!("{}", line);
+    }
+    if let Some(path) = flags.output {
+        abstutil::write_binary(&path, &result).unwrap();
+        println!("Wrote {}", path);
+    } else {
+        result.save();
+    }
+}
+
+fn load(path: &str, timer: &mut Timer) -> Scenario {
+    abstutil::read_binary(path, timer).expect(&format!("Couldn't load scenario {}", path))
+}
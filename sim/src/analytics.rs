@@ -0,0 +1,238 @@
+use crate::{FinishedTrips, TripID, TripMode};
+use abstutil::{deserialize_btreemap, serialize_btreemap};
+use geom::Duration;
+use map_model::{FeatureProperties, IntersectionID, Map, RoadID, TurnID};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Accumulated over the whole simulation so far.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct TrafficStats {
+    // How many agents (including pedestrians) have entered each road from an intersection
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    pub thruput_roads: BTreeMap<RoadID, usize>,
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    pub thruput_intersections: BTreeMap<IntersectionID, usize>,
+    // Total time agents spent waiting at each intersection before starting their turn
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    pub delay_intersections: BTreeMap<IntersectionID, Duration>,
+    // How many agents have started each turn. This is the demand for signal timing.
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    pub thruput_turns: BTreeMap<TurnID, usize>,
+}
+
+impl TrafficStats {
+    pub(crate) fn turn_started(&mut self, turn: TurnID, delay: Duration, map: &Map) {
+        *self
+            .thruput_roads
+            .entry(map.get_l(turn.dst).parent)
+            .or_insert(0) += 1;
+        *self.thruput_intersections.entry(turn.parent).or_insert(0) += 1;
+        *self.thruput_turns.entry(turn).or_insert(0) += 1;
+        *self
+            .delay_intersections
+            .entry(turn.parent)
+            .or_insert(Duration::ZERO) += delay;
+    }
+
+    // For Map::to_geojson. Delay is in seconds, both total and averaged per agent.
+    pub fn to_feature_properties(&self) -> FeatureProperties {
+        let mut props = FeatureProperties::default();
+        for (r, count) in &self.thruput_roads {
+            props
+                .roads
+                .entry(*r)
+                .or_insert_with(BTreeMap::new)
+                .insert("thruput".to_string(), *count as f64);
+        }
+        for (i, count) in &self.thruput_intersections {
+            let delay = self.delay_intersections[i].inner_seconds();
+            let entry = props.intersections.entry(*i).or_insert_with(BTreeMap::new);
+            entry.insert("thruput".to_string(), *count as f64);
+            entry.insert("total_delay_seconds".to_string(), delay);
+            entry.insert("avg_delay_seconds".to_string(), delay / (*count as f64));
+        }
+        props
+    }
+}
+
+#[derive(Serialize)]
+pub struct TripDelta {
+    pub trip: TripID,
+    pub mode: TripMode,
+    pub time1: Duration,
+    pub time2: Duration,
+    // time2 - time1, so negative means the trip is faster with edits2
+    pub delta: Duration,
+}
+
+#[derive(Serialize)]
+pub struct ModeDeltas {
+    pub num_trips: usize,
+    pub num_same: usize,
+    pub num_faster: usize,
+    pub num_slower: usize,
+    pub total_time1: Duration,
+    pub total_time2: Duration,
+    pub mean_delta: Duration,
+    // Over the trips that changed at all
+    pub delta_50_percentile: Option<Duration>,
+    pub delta_90_percentile: Option<Duration>,
+    pub delta_99_percentile: Option<Duration>,
+}
+
+// Compares two runs of the same scenario with the same RNG seed, so the same TripIDs refer to the
+// same trips. Only trips that finished in both runs are included.
+pub fn compare_trips(
+    trips1: FinishedTrips,
+    trips2: FinishedTrips,
+) -> (Vec<TripDelta>, BTreeMap<TripMode, ModeDeltas>) {
+    let trips: Vec<TripDelta> = trips1
+        .pair_with(trips2)
+        .into_iter()
+        .map(|(trip, mode, time1, time2)| TripDelta {
+            trip,
+            mode,
+            time1,
+            time2,
+            delta: time2 - time1,
+        })
+        .collect();
+
+    let mut per_mode = BTreeMap::new();
+    for mode in &[
+        TripMode::Walk,
+        TripMode::Bike,
+        TripMode::Transit,
+        TripMode::Drive,
+    ] {
+        let these: Vec<&TripDelta> = trips.iter().filter(|t| t.mode == *mode).collect();
+        if these.is_empty() {
+            continue;
+        }
+        per_mode.insert(*mode, ModeDeltas::new(these));
+    }
+    (trips, per_mode)
+}
+
+impl ModeDeltas {
+    fn new(trips: Vec<&TripDelta>) -> ModeDeltas {
+        let mut m = ModeDeltas {
+            num_trips: trips.len(),
+            num_same: 0,
+            num_faster: 0,
+            num_slower: 0,
+            total_time1: Duration::ZERO,
+            total_time2: Duration::ZERO,
+            mean_delta: Duration::ZERO,
+            delta_50_percentile: None,
+            delta_90_percentile: None,
+            delta_99_percentile: None,
+        };
+        // Same as the scoreboard in the editor: DurationHistogram doesn't handle negative deltas
+        let mut deltas = Vec::new();
+        for t in trips {
+            m.total_time1 += t.time1;
+            m.total_time2 += t.time2;
+            if t.delta == Duration::ZERO {
+                m.num_same += 1;
+            } else {
+                if t.delta < Duration::ZERO {
+                    m.num_faster += 1;
+                } else {
+                    m.num_slower += 1;
+                }
+                deltas.push(t.delta);
+            }
+        }
+        m.mean_delta = (m.total_time2 - m.total_time1) * (1.0 / (m.num_trips as f64));
+        deltas.sort();
+        if !deltas.is_empty() {
+            let len = deltas.len() as f64;
+            m.delta_50_percentile = Some(deltas[(0.5 * len).floor() as usize]);
+            m.delta_90_percentile = Some(deltas[(0.9 * len).floor() as usize]);
+            m.delta_99_percentile = Some(deltas[(0.99 * len).floor() as usize]);
+        }
+        m
+    }
+}
+
+// Returns (mean, half-width of the 95% confidence interval). With a single sample, there's no
+// estimate of the spread, so the interval is empty.
+pub fn confidence_interval(samples: &[Duration]) -> (Duration, Duration) {
+    let (mean, variance) = mean_and_variance(samples);
+    if samples.len() < 2 {
+        return (Duration::seconds(mean), Duration::ZERO);
+    }
+    let n = samples.len() as f64;
+    (
+        Duration::seconds(mean),
+        Duration::seconds(t_critical_95(samples.len() - 1) * (variance / n).sqrt()),
+    )
+}
+
+pub fn is_significant(samples1: &[Duration], samples2: &[Duration]) -> bool {
+    if samples1.len() < 2 || samples2.len() < 2 {
+        return false;
+    }
+    let (mean1, var1) = mean_and_variance(samples1);
+    let (mean2, var2) = mean_and_variance(samples2);
+    let (n1, n2) = (samples1.len() as f64, samples2.len() as f64);
+    let se1 = var1 / n1;
+    let se2 = var2 / n2;
+    if se1 + se2 == 0.0 {
+        // No variation at all; any difference is real.
+        return mean1 != mean2;
+    }
+    let t = (mean1 - mean2).abs() / (se1 + se2).sqrt();
+    // Welch-Satterthwaite approximation. Rounding down is conservative.
+    let df = (se1 + se2).powi(2) / (se1.powi(2) / (n1 - 1.0) + se2.powi(2) / (n2 - 1.0));
+    t > t_critical_95((df.floor() as usize).max(1))
+}
+
+// In seconds. Uses the sample variance.
+fn mean_and_variance(samples: &[Duration]) -> (f64, f64) {
+    if samples.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = samples.len() as f64;
+    let mean = samples.iter().map(|t| t.inner_seconds()).sum::<f64>() / n;
+    if samples.len() < 2 {
+        return (mean, 0.0);
+    }
+    let variance = samples
+        .iter()
+        .map(|t| (t.inner_seconds() - mean).powi(2))
+        .sum::<f64>()
+        / (n - 1.0);
+    (mean, variance)
+}
+
+// Two-sided critical values of Student's t distribution at 95% confidence
+fn t_critical_95(degrees_of_freedom: usize) -> f64 {
+    const TABLE: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+        2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+        2.052, 2.048, 2.045, 2.042,
+    ];
+    match degrees_of_freedom {
+        0 => panic!("t_critical_95 needs at least 1 degree of freedom"),
+        1..=30 => TABLE[degrees_of_freedom - 1],
+        31..=40 => 2.021,
+        41..=60 => 2.000,
+        61..=120 => 1.980,
+        _ => 1.960,
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{Scenario, Sim, SimOptions, TripMode};
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Distance, Duration};
+use map_model::{LaneType, Map, PathRequest, PathStep, Position, RoadID};
+use rand_xorshift::XorShiftRng;
+use std::collections::{BTreeMap, BTreeSet};
+
+// A quick guess at how map edits affect a scenario, just by pathfinding every trip before and
+// after. Congestion, parking, and waiting for buses aren't captured; only the full simulation
+// does that.
+pub struct ImpactPreview {
+    // Indexed by trip, in the order the scenario schedules them. Each request also says whether
+    // it's for walking, since edits can change the type of the lane it starts on.
+    trips: Vec<(Duration, TripMode, Vec<(PathRequest, bool)>)>,
+    before: Vec<Option<TripRoute>>,
+}
+
+// All of the legs of one trip, stitched together
+struct TripRoute {
+    steps: Vec<PathStep>,
+    length: Distance,
+    roads: BTreeSet<RoadID>,
+}
+
+pub struct ImpactSummary {
+    pub num_trips: usize,
+    // These index trips in the order the scenario schedules them.
+    pub changed_routes: Vec<usize>,
+    pub newly_impossible: Vec<usize>,
+    pub newly_possible: Vec<usize>,
+    // Summed over the trips that're possible
+    pub length_before: BTreeMap<TripMode, Distance>,
+    pub length_after: BTreeMap<TripMode, Distance>,
+    // How many trips cross each road
+    pub road_trips_before: BTreeMap<RoadID, usize>,
+    pub road_trips_after: BTreeMap<RoadID, usize>,
+}
+
+impl ImpactPreview {
+    // Pathfinds on the map as it is now, before the edits to compare against. The rng should be
+    // seeded like the real run, to get the same trips.
+    pub fn new(
+        scenario: &Scenario,
+        map: &Map,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) -> ImpactPreview {
+        timer.start(&format!("schedule trips for {}", scenario.scenario_name));
+        let mut sim = Sim::new(map, SimOptions::new("impact preview"));
+        scenario.schedule_trips(&mut sim, map, rng, timer);
+        let trips = sim
+            .scheduled_trip_requests(map)
+            .into_iter()
+            .map(|(time, mode, reqs)| {
+                let reqs = reqs
+                    .into_iter()
+                    .map(|req| {
+                        let walking = map.get_l(req.start.lane()).is_sidewalk();
+                        (req, walking)
+                    })
+                    .collect();
+                (time, mode, reqs)
+            })
+            .collect();
+        timer.stop(&format!("schedule trips for {}", scenario.scenario_name));
+
+        let before = route_all(&trips, map, timer);
+        ImpactPreview { trips, before }
+    }
+
+    // The map's pathfinding has to be up-to-date with the edits.
+    pub fn compare(&self, map: &Map, timer: &mut Timer) -> ImpactSummary {
+        let after = route_all(&self.trips, map, timer);
+
+        let mut summary = ImpactSummary {
+            num_trips: self.trips.len(),
+            changed_routes: Vec::new(),
+            newly_impossible: Vec::new(),
+            newly_possible: Vec::new(),
+            length_before: BTreeMap::new(),
+            length_after: BTreeMap::new(),
+            road_trips_before: BTreeMap::new(),
+            road_trips_after: BTreeMap::new(),
+        };
+        for (idx, ((_, mode, _), (before, after))) in self
+            .trips
+            .iter()
+            .zip(self.before.iter().zip(after.iter()))
+            .enumerate()
+        {
+            match (before, after) {
+                (Some(b), Some(a)) => {
+                    if b.steps != a.steps {
+                        summary.changed_routes.push(idx);
+                    }
+                }
+                (Some(_), None) => summary.newly_impossible.push(idx),
+                (None, Some(_)) => summary.newly_possible.push(idx),
+                (None, None) => {}
+            }
+            if let Some(b) = before {
+                *summary.length_before.entry(*mode).or_insert(Distance::ZERO) += b.length;
+                for r in &b.roads {
+                    *summary.road_trips_before.entry(*r).or_insert(0) += 1;
+                }
+            }
+            if let Some(a) = after {
+                *summary.length_after.entry(*mode).or_insert(Distance::ZERO) += a.length;
+                for r in &a.roads {
+                    *summary.road_trips_after.entry(*r).or_insert(0) += 1;
+                }
+            }
+        }
+        summary
+    }
+}
+
+impl ImpactSummary {
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("{} trips", prettyprint_usize(self.num_trips)),
+            format!(
+                "{} change routes",
+                prettyprint_usize(self.changed_routes.len())
+            ),
+            format!(
+                "{} become impossible",
+                prettyprint_usize(self.newly_impossible.len())
+            ),
+            format!(
+                "{} become possible",
+                prettyprint_usize(self.newly_possible.len())
+            ),
+        ];
+        let modes: BTreeSet<TripMode> = self
+            .length_before
+            .keys()
+            .chain(self.length_after.keys())
+            .cloned()
+            .collect();
+        for mode in modes {
+            lines.push(format!(
+                "{:?}: {} total before, {} after",
+                mode,
+                self.length_before
+                    .get(&mode)
+                    .cloned()
+                    .unwrap_or(Distance::ZERO),
+                self.length_after
+                    .get(&mode)
+                    .cloned()
+                    .unwrap_or(Distance::ZERO)
+            ));
+        }
+        lines
+    }
+
+    // Positive when more trips use the road after the edits
+    pub fn road_trips_change(&self, r: RoadID) -> isize {
+        (self.road_trips_after.get(&r).cloned().unwrap_or(0) as isize)
+            - (self.road_trips_before.get(&r).cloned().unwrap_or(0) as isize)
+    }
+}
+
+fn route_all(
+    trips: &[(Duration, TripMode, Vec<(PathRequest, bool)>)],
+    map: &Map,
+    timer: &mut Timer,
+) -> Vec<Option<TripRoute>> {
+    timer.parallelize(
+        "calculate paths",
+        trips
+            .iter()
+            .map(|(time, _, reqs)| (*time, reqs.clone()))
+            .collect(),
+        |(time, reqs)| route(time, reqs, map),
+    )
+}
+
+fn route(time: Duration, reqs: Vec<(PathRequest, bool)>, map: &Map) -> Option<TripRoute> {
+    let mut route = TripRoute {
+        steps: Vec::new(),
+        length: Distance::ZERO,
+        roads: BTreeSet::new(),
+    };
+    for (req, walking) in reqs {
+        let req = PathRequest {
+            start: still_valid(req.start, walking, &req, map)?,
+            end: still_valid(req.end, walking, &req, map)?,
+            ..req
+        };
+        let path = map.pathfind_at_time(req, time)?;
+        route.length += path.total_length();
+        for step in path.get_steps() {
+            match step {
+                PathStep::Lane(l) | PathStep::ContraflowLane(l) => {
+                    route.roads.insert(map.get_l(*l).parent);
+                }
+                PathStep::Turn(_) => {}
+            }
+            route.steps.push(*step);
+        }
+    }
+    Some(route)
+}
+
+// Edits can remove lanes, change their type, or change their length slightly. A sidewalk has to
+// stay a sidewalk, and a vehicle has to be allowed on the lane it starts or ends on.
+fn still_valid(pos: Position, walking: bool, req: &PathRequest, map: &Map) -> Option<Position> {
+    let lane = map.maybe_get_l(pos.lane())?;
+    let usable = match lane.lane_type {
+        LaneType::Sidewalk => walking,
+        LaneType::Driving => !walking,
+        LaneType::Biking => !walking && req.can_use_bike_lanes,
+        LaneType::Bus => !walking && req.can_use_bus_lanes,
+        LaneType::Parking => false,
+    };
+    if !usable {
+        return None;
+    }
+    Some(Position::new(
+        pos.lane(),
+        pos.dist_along().min(lane.length()),
+    ))
+}
@@ -1,23 +1,31 @@
+mod analytics;
 mod events;
+mod impact;
 mod make;
 mod mechanics;
 mod render;
 mod router;
 mod scheduler;
+mod signal_optimizer;
 mod sim;
 mod transit;
 mod trips;
 
+pub use self::analytics::{
+    compare_trips, confidence_interval, is_significant, ModeDeltas, TrafficStats, TripDelta,
+};
 pub use self::events::Event;
+pub use self::impact::{ImpactPreview, ImpactSummary};
 pub use self::make::{
     ABTest, BorderSpawnOverTime, OriginDestination, Scenario, SeedParkedCars, SimFlags,
     SpawnOverTime, SpawnTrip, TripSpawner, TripSpec,
 };
 pub(crate) use self::mechanics::{
-    DrivingSimState, IntersectionSimState, ParkingSimState, WalkingSimState,
+    is_walking_path_open, DrivingSimState, IntersectionSimState, ParkingSimState, WalkingSimState,
 };
 pub(crate) use self::router::{ActionAtEnd, Router};
 pub(crate) use self::scheduler::{Command, Scheduler};
+pub use self::signal_optimizer::{OptimizedSignal, SignalOptimizer};
 pub use self::sim::{Sim, SimOptions, TripResult};
 pub(crate) use self::transit::TransitSimState;
 pub use self::trips::{FinishedTrips, TripEnd, TripMode, TripStart, TripStatus};
@@ -114,6 +122,27 @@ pub enum VehicleType {
     Bike,
 }
 
+impl VehicleType {
+    // Matches the lanes the pathfinder considers for each type.
+    pub fn can_use_lane(self, lt: LaneType) -> bool {
+        match lt {
+            LaneType::Driving => true,
+            LaneType::Bus => self == VehicleType::Bus,
+            LaneType::Biking => self == VehicleType::Bike,
+            LaneType::Parking | LaneType::Sidewalk => false,
+        }
+    }
+
+    // Like the lane_types in VehiclePathfinder
+    pub fn lane_types(self) -> Vec<LaneType> {
+        match self {
+            VehicleType::Car => vec![LaneType::Driving],
+            VehicleType::Bus => vec![LaneType::Driving, LaneType::Bus],
+            VehicleType::Bike => vec![LaneType::Driving, LaneType::Biking],
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Vehicle {
     pub id: CarID,
@@ -238,6 +267,17 @@ impl SidewalkSpot {
         }
     }
 
+    // Live map edits can shorten the sidewalk. Returns true if the position changed.
+    pub fn clamp_to_sidewalk(&mut self, map: &Map) -> bool {
+        let lane = self.sidewalk_pos.lane();
+        let len = map.get_l(lane).length();
+        if self.sidewalk_pos.dist_along() > len {
+            self.sidewalk_pos = Position::new(lane, len);
+            return true;
+        }
+        false
+    }
+
     pub fn building(bldg: BuildingID, map: &Map) -> SidewalkSpot {
         let front_path = &map.get_b(bldg).front_path;
         SidewalkSpot {
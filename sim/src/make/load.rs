@@ -22,6 +22,10 @@ pub struct SimFlags {
     #[structopt(long = "rng_seed")]
     pub rng_seed: Option<u8>,
 
+    /// Map edits to apply before starting the simulation. Ignored when resuming a savestate.
+    #[structopt(long = "edits_name", default_value = "no_edits")]
+    pub edits_name: String,
+
     /// Run name for savestating
     #[structopt(long = "run_name")]
     pub run_name: Option<String>,
@@ -49,6 +53,7 @@ impl SimFlags {
         SimFlags {
             load: PathBuf::from(abstutil::path_map(map)),
             rng_seed: Some(42),
+            edits_name: "no_edits".to_string(),
             run_name: Some(run_name.to_string()),
             savestate_every: None,
             freeform_policy: false,
@@ -81,13 +86,14 @@ impl SimFlags {
         if self.load.starts_with(Path::new("../data/save/")) {
             timer.note(format!("Resuming from {}", self.load.display()));
 
-            let sim: Sim = abstutil::read_binary(self.load.to_str().unwrap(), timer)
+            let mut sim: Sim = abstutil::read_binary(self.load.to_str().unwrap(), timer)
                 .expect("loading sim state failed");
 
             let mut map: Map =
                 abstutil::read_binary(&abstutil::path_map(&sim.map_name), timer).unwrap();
             map.apply_edits(MapEdits::load(map.get_name(), &sim.edits_name), timer);
             map.recalculate_pathfinding_after_edits(timer);
+            sim.apply_scheduled_edits(&mut map, timer);
 
             (map, sim, rng)
         } else if self.load.starts_with(Path::new("../data/scenarios/")) {
@@ -99,8 +105,9 @@ impl SimFlags {
             let scenario: Scenario = abstutil::read_binary(self.load.to_str().unwrap(), timer)
                 .expect("loading scenario failed");
 
-            let map: Map =
+            let mut map: Map =
                 abstutil::read_binary(&abstutil::path_map(&scenario.map_name), timer).unwrap();
+            self.apply_edits(&mut map, timer);
 
             opts.run_name = self
                 .run_name
@@ -113,8 +120,9 @@ impl SimFlags {
         } else if self.load.starts_with(Path::new("../data/raw_maps/")) {
             timer.note(format!("Loading map {}", self.load.display()));
 
-            let map = Map::new(self.load.to_str().unwrap(), timer)
+            let mut map = Map::new(self.load.to_str().unwrap(), timer)
                 .expect(&format!("Couldn't load map from {}", self.load.display()));
+            self.apply_edits(&mut map, timer);
 
             timer.start("create sim");
             let sim = Sim::new(&map, opts);
@@ -124,8 +132,9 @@ impl SimFlags {
         } else if self.load.starts_with(Path::new("../data/maps/")) {
             timer.note(format!("Loading map {}", self.load.display()));
 
-            let map: Map = abstutil::read_binary(self.load.to_str().unwrap(), timer)
+            let mut map: Map = abstutil::read_binary(self.load.to_str().unwrap(), timer)
                 .expect(&format!("Couldn't load map from {}", self.load.display()));
+            self.apply_edits(&mut map, timer);
 
             timer.start("create sim");
             let sim = Sim::new(&map, opts);
@@ -136,4 +145,12 @@ impl SimFlags {
             panic!("Don't know how to load {}", self.load.display());
         }
     }
+
+    fn apply_edits(&self, map: &mut Map, timer: &mut abstutil::Timer) {
+        if self.edits_name == "no_edits" {
+            return;
+        }
+        map.apply_edits(MapEdits::load(map.get_name(), &self.edits_name), timer);
+        map.recalculate_pathfinding_after_edits(timer);
+    }
 }
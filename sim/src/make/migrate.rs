@@ -0,0 +1,143 @@
+use crate::{
+    DrivingGoal, OriginDestination, ParkingSpot, Scenario, SidewalkPOI, SidewalkSpot, SpawnTrip,
+};
+use map_model::MapMigration;
+
+// Neighborhoods are referred to by name and polygon, so they don't need any translation.
+impl Scenario {
+    // Trips referring to something that can't be matched are dropped, and the reasons recorded in
+    // the migration's problems.
+    pub fn migrate(&self, m: &mut MapMigration) -> Scenario {
+        let mut s = self.clone();
+        s.map_name = m.new_map().get_name().to_string();
+
+        s.spawn_over_time.clear();
+        for spawn in &self.spawn_over_time {
+            let mut spawn = spawn.clone();
+            if let Some(goal) = migrate_od(&spawn.goal, m) {
+                spawn.goal = goal;
+                s.spawn_over_time.push(spawn);
+            }
+        }
+
+        s.border_spawn_over_time.clear();
+        for spawn in &self.border_spawn_over_time {
+            let mut spawn = spawn.clone();
+            if let (Some(start), Some(goal)) = (
+                m.intersection(spawn.start_from_border),
+                migrate_od(&spawn.goal, m),
+            ) {
+                spawn.start_from_border = start;
+                spawn.goal = goal;
+                s.border_spawn_over_time.push(spawn);
+            }
+        }
+
+        s.individ_trips.clear();
+        let mut dropped = 0;
+        for trip in &self.individ_trips {
+            match migrate_trip(trip, m) {
+                Some(t) => s.individ_trips.push(t),
+                None => {
+                    dropped += 1;
+                }
+            }
+        }
+        if dropped > 0 {
+            m.problem(format!(
+                "Dropped {} of the {} individual trips in {}",
+                dropped,
+                self.individ_trips.len(),
+                self.scenario_name
+            ));
+        }
+
+        s
+    }
+}
+
+fn migrate_od(od: &OriginDestination, m: &mut MapMigration) -> Option<OriginDestination> {
+    match od {
+        OriginDestination::Neighborhood(ref n) => Some(OriginDestination::Neighborhood(n.clone())),
+        OriginDestination::Border(i) => Some(OriginDestination::Border(m.intersection(*i)?)),
+    }
+}
+
+fn migrate_trip(trip: &SpawnTrip, m: &mut MapMigration) -> Option<SpawnTrip> {
+    Some(match trip {
+        SpawnTrip::CarAppearing {
+            depart,
+            start,
+            goal,
+            is_bike,
+        } => SpawnTrip::CarAppearing {
+            depart: *depart,
+            start: m.position(*start)?,
+            goal: migrate_driving_goal(goal, m)?,
+            is_bike: *is_bike,
+        },
+        SpawnTrip::UsingBike(depart, start, goal) => SpawnTrip::UsingBike(
+            *depart,
+            migrate_sidewalk_spot(start, m)?,
+            migrate_driving_goal(goal, m)?,
+        ),
+        SpawnTrip::JustWalking(depart, start, goal) => SpawnTrip::JustWalking(
+            *depart,
+            migrate_sidewalk_spot(start, m)?,
+            migrate_sidewalk_spot(goal, m)?,
+        ),
+        SpawnTrip::UsingTransit(depart, start, goal, route, stop1, stop2) => {
+            SpawnTrip::UsingTransit(
+                *depart,
+                migrate_sidewalk_spot(start, m)?,
+                migrate_sidewalk_spot(goal, m)?,
+                m.bus_route(*route)?,
+                m.bus_stop(*stop1)?,
+                m.bus_stop(*stop2)?,
+            )
+        }
+    })
+}
+
+fn migrate_driving_goal(goal: &DrivingGoal, m: &mut MapMigration) -> Option<DrivingGoal> {
+    match goal {
+        DrivingGoal::ParkNear(b) => Some(DrivingGoal::ParkNear(m.building(*b)?)),
+        DrivingGoal::Border(i, l) => Some(DrivingGoal::Border(m.intersection(*i)?, m.lane(*l)?)),
+    }
+}
+
+fn migrate_sidewalk_spot(spot: &SidewalkSpot, m: &mut MapMigration) -> Option<SidewalkSpot> {
+    let map = m.new_map();
+    // Buildings and bus stops might connect to the sidewalk somewhere else now, so ask the new map
+    match spot.connection {
+        SidewalkPOI::Building(b) => {
+            return Some(SidewalkSpot::building(m.building(b)?, map));
+        }
+        SidewalkPOI::BusStop(bs) => {
+            return Some(SidewalkSpot::bus_stop(m.bus_stop(bs)?, map));
+        }
+        _ => {}
+    }
+
+    let connection = match spot.connection {
+        SidewalkPOI::ParkingSpot(ref ps) => {
+            let lane = m.lane(ps.lane)?;
+            if ps.idx >= map.get_l(lane).number_parking_spots() {
+                m.problem(format!(
+                    "{} doesn't have parking spot {} anymore",
+                    ps.lane, ps.idx
+                ));
+                return None;
+            }
+            SidewalkPOI::ParkingSpot(ParkingSpot::new(lane, ps.idx))
+        }
+        SidewalkPOI::Border(i) => SidewalkPOI::Border(m.intersection(i)?),
+        SidewalkPOI::BikeRack(pos) => SidewalkPOI::BikeRack(m.position(pos)?),
+        SidewalkPOI::SuddenlyAppear => SidewalkPOI::SuddenlyAppear,
+        SidewalkPOI::Building(_) | SidewalkPOI::BusStop(_) => unreachable!(),
+    };
+    Some(SidewalkSpot {
+        connection,
+        sidewalk_pos: m.position(spot.sidewalk_pos)?,
+    })
+}
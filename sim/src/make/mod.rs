@@ -1,6 +1,8 @@
 mod a_b_test;
 mod load;
+mod migrate;
 mod scenario;
+mod scenario_ops;
 mod spawner;
 
 pub use self::a_b_test::ABTest;
@@ -1,6 +1,6 @@
 use crate::{
-    CarID, DrivingGoal, ParkingSpot, SidewalkSpot, Sim, TripSpec, VehicleSpec, VehicleType,
-    BIKE_LENGTH, MAX_CAR_LENGTH, MIN_CAR_LENGTH,
+    CarID, DrivingGoal, ParkingSpot, PedestrianID, SidewalkSpot, Sim, TripSpec, VehicleSpec,
+    VehicleType, BIKE_LENGTH, MAX_CAR_LENGTH, MIN_CAR_LENGTH,
 };
 use abstutil;
 use abstutil::{fork_rng, prettyprint_usize, Timer, WeightedUsizeChoice};
@@ -91,6 +91,20 @@ impl Scenario {
             sim.seed_bus_route(route, map, timer);
         }
 
+        self.schedule_trips(sim, map, rng, timer);
+
+        sim.spawn_all_trips(map, timer, true);
+        timer.stop(&format!("Instantiating {}", self.scenario_name));
+    }
+
+    // Seeds parked cars and schedules every trip, without spawning anything yet.
+    pub(crate) fn schedule_trips(
+        &self,
+        sim: &mut Sim,
+        map: &Map,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) {
         timer.start("load full neighborhood info");
         let neighborhoods = FullNeighborhoodInfo::load_all(map);
         timer.stop("load full neighborhood info");
@@ -136,71 +150,9 @@ impl Scenario {
 
         timer.start_iter("SpawnTrip", self.individ_trips.len());
         for t in &self.individ_trips {
-            match t.clone() {
-                SpawnTrip::CarAppearing {
-                    depart,
-                    start,
-                    goal,
-                    is_bike,
-                } => {
-                    sim.schedule_trip(
-                        depart,
-                        TripSpec::CarAppearing {
-                            start_pos: start,
-                            goal,
-                            vehicle_spec: if is_bike {
-                                Scenario::rand_bike(rng)
-                            } else {
-                                Scenario::rand_car(rng)
-                            },
-                            ped_speed: Scenario::rand_ped_speed(rng),
-                        },
-                        map,
-                    );
-                }
-                SpawnTrip::UsingBike(depart, start, goal) => {
-                    sim.schedule_trip(
-                        depart,
-                        TripSpec::UsingBike {
-                            start,
-                            goal,
-                            vehicle: Scenario::rand_bike(rng),
-                            ped_speed: Scenario::rand_ped_speed(rng),
-                        },
-                        map,
-                    );
-                }
-                SpawnTrip::JustWalking(depart, start, goal) => {
-                    sim.schedule_trip(
-                        depart,
-                        TripSpec::JustWalking {
-                            start,
-                            goal,
-                            ped_speed: Scenario::rand_ped_speed(rng),
-                        },
-                        map,
-                    );
-                }
-                SpawnTrip::UsingTransit(depart, start, goal, route, stop1, stop2) => {
-                    sim.schedule_trip(
-                        depart,
-                        TripSpec::UsingTransit {
-                            start,
-                            goal,
-                            route,
-                            stop1,
-                            stop2,
-                            ped_speed: Scenario::rand_ped_speed(rng),
-                        },
-                        map,
-                    );
-                }
-            }
+            t.clone().schedule(sim, map, rng);
             timer.next();
         }
-
-        sim.spawn_all_trips(map, timer, true);
-        timer.stop(&format!("Instantiating {}", self.scenario_name));
     }
 
     pub fn save(&self) {
@@ -843,3 +795,67 @@ pub enum SpawnTrip {
         BusStopID,
     ),
 }
+
+impl SpawnTrip {
+    // Only schedules the trip; the caller must call sim.spawn_all_trips afterwards. The departure
+    // time can't be before the current time in the simulation.
+    pub fn schedule(
+        self,
+        sim: &mut Sim,
+        map: &Map,
+        rng: &mut XorShiftRng,
+    ) -> (Option<PedestrianID>, Option<CarID>) {
+        match self {
+            SpawnTrip::CarAppearing {
+                depart,
+                start,
+                goal,
+                is_bike,
+            } => sim.schedule_trip(
+                depart,
+                TripSpec::CarAppearing {
+                    start_pos: start,
+                    goal,
+                    vehicle_spec: if is_bike {
+                        Scenario::rand_bike(rng)
+                    } else {
+                        Scenario::rand_car(rng)
+                    },
+                    ped_speed: Scenario::rand_ped_speed(rng),
+                },
+                map,
+            ),
+            SpawnTrip::UsingBike(depart, start, goal) => sim.schedule_trip(
+                depart,
+                TripSpec::UsingBike {
+                    start,
+                    goal,
+                    vehicle: Scenario::rand_bike(rng),
+                    ped_speed: Scenario::rand_ped_speed(rng),
+                },
+                map,
+            ),
+            SpawnTrip::JustWalking(depart, start, goal) => sim.schedule_trip(
+                depart,
+                TripSpec::JustWalking {
+                    start,
+                    goal,
+                    ped_speed: Scenario::rand_ped_speed(rng),
+                },
+                map,
+            ),
+            SpawnTrip::UsingTransit(depart, start, goal, route, stop1, stop2) => sim.schedule_trip(
+                depart,
+                TripSpec::UsingTransit {
+                    start,
+                    goal,
+                    route,
+                    stop1,
+                    stop2,
+                    ped_speed: Scenario::rand_ped_speed(rng),
+                },
+                map,
+            ),
+        }
+    }
+}
@@ -0,0 +1,301 @@
+use crate::{Scenario, SpawnTrip, TripMode};
+use geom::Duration;
+use map_model::{FullNeighborhoodInfo, LaneID, Map};
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use std::collections::BTreeSet;
+
+// Operations to derive variants of a scenario (more demand, just the AM peak, only bikes...)
+// without regenerating everything from scratch. None of these modify the original.
+impl Scenario {
+    // Everything is just concatenated, so merging a scenario with itself doubles the demand.
+    pub fn merge(&self, other: &Scenario, new_name: &str) -> Scenario {
+        if self.map_name != other.map_name {
+            panic!(
+                "Can't merge {} on {} with {} on {}",
+                self.scenario_name, self.map_name, other.scenario_name, other.map_name
+            );
+        }
+        let mut s = self.clone();
+        s.scenario_name = new_name.to_string();
+        s.seed_parked_cars
+            .extend(other.seed_parked_cars.iter().cloned());
+        s.spawn_over_time
+            .extend(other.spawn_over_time.iter().cloned());
+        s.border_spawn_over_time
+            .extend(other.border_spawn_over_time.iter().cloned());
+        s.individ_trips.extend(other.individ_trips.iter().cloned());
+        s
+    }
+
+    // Scales the number of trips. Aggregate counts are rounded. Each individual trip is repeated
+    // floor(factor) times, plus once more with probability equal to the fractional part, so the
+    // same RNG seed always picks the same trips. Parked cars describe supply, not demand, so
+    // they're untouched.
+    pub fn scale(&self, factor: f64, rng: &mut XorShiftRng, new_name: &str) -> Scenario {
+        assert!(factor >= 0.0);
+        let mut s = self.clone();
+        s.scenario_name = new_name.to_string();
+        for spawn in s.spawn_over_time.iter_mut() {
+            spawn.num_agents = scale_count(spawn.num_agents, factor);
+        }
+        for spawn in s.border_spawn_over_time.iter_mut() {
+            spawn.num_peds = scale_count(spawn.num_peds, factor);
+            spawn.num_cars = scale_count(spawn.num_cars, factor);
+            spawn.num_bikes = scale_count(spawn.num_bikes, factor);
+        }
+
+        let whole = factor.floor() as usize;
+        let fraction = factor - factor.floor();
+        s.individ_trips.clear();
+        for trip in &self.individ_trips {
+            // Always sample, even when fraction is 0, so the choices for one trip don't depend on
+            // the factor used for previous ones.
+            let extra = rng.gen_bool(fraction);
+            for _ in 0..whole + if extra { 1 } else { 0 } {
+                s.individ_trips.push(trip.clone());
+            }
+        }
+        s
+    }
+
+    // Moves everything later (or earlier, for a negative offset). Anything that would start
+    // before midnight is dropped; spawning windows straddling midnight are trimmed.
+    pub fn shift_time(&self, offset: Duration, new_name: &str) -> Scenario {
+        let mut s = self.clone();
+        s.scenario_name = new_name.to_string();
+        s.spawn_over_time = self
+            .spawn_over_time
+            .iter()
+            .filter_map(|spawn| {
+                let (start, stop, pct) =
+                    clip_window(spawn.start_time + offset, spawn.stop_time + offset, None)?;
+                let mut spawn = spawn.clone();
+                spawn.start_time = start;
+                spawn.stop_time = stop;
+                spawn.num_agents = scale_count(spawn.num_agents, pct);
+                Some(spawn)
+            })
+            .collect();
+        s.border_spawn_over_time = self
+            .border_spawn_over_time
+            .iter()
+            .filter_map(|spawn| {
+                let (start, stop, pct) =
+                    clip_window(spawn.start_time + offset, spawn.stop_time + offset, None)?;
+                let mut spawn = spawn.clone();
+                spawn.start_time = start;
+                spawn.stop_time = stop;
+                spawn.num_peds = scale_count(spawn.num_peds, pct);
+                spawn.num_cars = scale_count(spawn.num_cars, pct);
+                spawn.num_bikes = scale_count(spawn.num_bikes, pct);
+                Some(spawn)
+            })
+            .collect();
+        s.individ_trips = self
+            .individ_trips
+            .iter()
+            .filter_map(|trip| {
+                let depart = trip.depart() + offset;
+                if depart < Duration::ZERO {
+                    return None;
+                }
+                let mut trip = trip.clone();
+                trip.set_depart(depart);
+                Some(trip)
+            })
+            .collect();
+        s
+    }
+
+    // Only keeps demand starting in [start, end), then linearly squeezes or stretches that window
+    // to [new_start, new_end). Passing the same window twice just filters, like "AM peak only".
+    // Spawning windows partly overlapping [start, end) keep the proportional number of agents.
+    pub fn remap_time_window(
+        &self,
+        start: Duration,
+        end: Duration,
+        new_start: Duration,
+        new_end: Duration,
+        new_name: &str,
+    ) -> Scenario {
+        assert!(end > start);
+        assert!(new_end > new_start);
+        let remap = |t: Duration| new_start + ((t - start) / (end - start)) * (new_end - new_start);
+
+        let mut s = self.clone();
+        s.scenario_name = new_name.to_string();
+        s.spawn_over_time = self
+            .spawn_over_time
+            .iter()
+            .filter_map(|spawn| {
+                let (t1, t2, pct) =
+                    clip_window(spawn.start_time, spawn.stop_time, Some((start, end)))?;
+                let mut spawn = spawn.clone();
+                spawn.start_time = remap(t1);
+                spawn.stop_time = remap(t2);
+                spawn.num_agents = scale_count(spawn.num_agents, pct);
+                Some(spawn)
+            })
+            .collect();
+        s.border_spawn_over_time = self
+            .border_spawn_over_time
+            .iter()
+            .filter_map(|spawn| {
+                let (t1, t2, pct) =
+                    clip_window(spawn.start_time, spawn.stop_time, Some((start, end)))?;
+                let mut spawn = spawn.clone();
+                spawn.start_time = remap(t1);
+                spawn.stop_time = remap(t2);
+                spawn.num_peds = scale_count(spawn.num_peds, pct);
+                spawn.num_cars = scale_count(spawn.num_cars, pct);
+                spawn.num_bikes = scale_count(spawn.num_bikes, pct);
+                Some(spawn)
+            })
+            .collect();
+        s.individ_trips = self
+            .individ_trips
+            .iter()
+            .filter_map(|trip| {
+                let depart = trip.depart();
+                if depart < start || depart >= end {
+                    return None;
+                }
+                let mut trip = trip.clone();
+                trip.set_depart(remap(depart));
+                Some(trip)
+            })
+            .collect();
+        s
+    }
+
+    // SpawnOverTime agents pick their mode during instantiation, so the best we can do is stop
+    // them from biking or using transit. Walking and driving are fallbacks there, so they can't
+    // be excluded.
+    pub fn filter_modes(&self, modes: &BTreeSet<TripMode>, new_name: &str) -> Scenario {
+        let mut s = self.clone();
+        s.scenario_name = new_name.to_string();
+        for spawn in s.spawn_over_time.iter_mut() {
+            if !modes.contains(&TripMode::Bike) {
+                spawn.percent_biking = 0.0;
+            }
+            if !modes.contains(&TripMode::Transit) {
+                spawn.percent_use_transit = 0.0;
+            }
+        }
+        for spawn in s.border_spawn_over_time.iter_mut() {
+            if !modes.contains(&TripMode::Walk) && !modes.contains(&TripMode::Transit) {
+                spawn.num_peds = 0;
+            } else if !modes.contains(&TripMode::Transit) {
+                spawn.percent_use_transit = 0.0;
+            }
+            if !modes.contains(&TripMode::Drive) {
+                spawn.num_cars = 0;
+            }
+            if !modes.contains(&TripMode::Bike) {
+                spawn.num_bikes = 0;
+            }
+        }
+        s.border_spawn_over_time
+            .retain(|spawn| spawn.num_peds + spawn.num_cars + spawn.num_bikes > 0);
+        s.individ_trips.retain(|trip| modes.contains(&trip.mode()));
+        s
+    }
+
+    // Only keeps demand starting inside a neighborhood. SpawnOverTime is matched by name, since
+    // the buildings it picks from aren't known until instantiation.
+    pub fn filter_neighborhood(&self, neighborhood: &str, map: &Map, new_name: &str) -> Scenario {
+        let neighborhoods = FullNeighborhoodInfo::load_all(map);
+        let roads = &neighborhoods
+            .get(neighborhood)
+            .unwrap_or_else(|| panic!("Neighborhood {} isn't defined", neighborhood))
+            .roads;
+        let in_neighborhood = |l: LaneID| roads.contains(&map.get_l(l).parent);
+
+        let mut s = self.clone();
+        s.scenario_name = new_name.to_string();
+        s.seed_parked_cars
+            .retain(|spawn| spawn.neighborhood == neighborhood);
+        s.spawn_over_time
+            .retain(|spawn| spawn.start_from_neighborhood == neighborhood);
+        s.border_spawn_over_time.retain(|spawn| {
+            map.get_i(spawn.start_from_border)
+                .roads
+                .iter()
+                .any(|r| roads.contains(r))
+        });
+        s.individ_trips
+            .retain(|trip| in_neighborhood(trip.start_lane()));
+        s
+    }
+}
+
+impl SpawnTrip {
+    pub fn depart(&self) -> Duration {
+        match self {
+            SpawnTrip::CarAppearing { depart, .. } => *depart,
+            SpawnTrip::UsingBike(depart, _, _)
+            | SpawnTrip::JustWalking(depart, _, _)
+            | SpawnTrip::UsingTransit(depart, _, _, _, _, _) => *depart,
+        }
+    }
+
+    fn set_depart(&mut self, time: Duration) {
+        match self {
+            SpawnTrip::CarAppearing { ref mut depart, .. } => *depart = time,
+            SpawnTrip::UsingBike(ref mut depart, _, _)
+            | SpawnTrip::JustWalking(ref mut depart, _, _)
+            | SpawnTrip::UsingTransit(ref mut depart, _, _, _, _, _) => *depart = time,
+        }
+    }
+
+    pub fn mode(&self) -> TripMode {
+        match self {
+            SpawnTrip::CarAppearing { is_bike, .. } => {
+                if *is_bike {
+                    TripMode::Bike
+                } else {
+                    TripMode::Drive
+                }
+            }
+            SpawnTrip::UsingBike(_, _, _) => TripMode::Bike,
+            SpawnTrip::JustWalking(_, _, _) => TripMode::Walk,
+            SpawnTrip::UsingTransit(_, _, _, _, _, _) => TripMode::Transit,
+        }
+    }
+
+    pub fn start_lane(&self) -> LaneID {
+        match self {
+            SpawnTrip::CarAppearing { start, .. } => start.lane(),
+            SpawnTrip::UsingBike(_, ref spot, _)
+            | SpawnTrip::JustWalking(_, ref spot, _)
+            | SpawnTrip::UsingTransit(_, ref spot, _, _, _, _) => spot.sidewalk_pos.lane(),
+        }
+    }
+}
+
+fn scale_count(count: usize, factor: f64) -> usize {
+    ((count as f64) * factor).round() as usize
+}
+
+// Restricts [start, stop] to be after midnight and optionally inside a window. Returns the new
+// range and the fraction of the original range that's left, or None if nothing is left. Scenario
+// instantiation needs a nonempty range.
+fn clip_window(
+    start: Duration,
+    stop: Duration,
+    window: Option<(Duration, Duration)>,
+) -> Option<(Duration, Duration, f64)> {
+    let (low, high) = window.unwrap_or((Duration::ZERO, stop.max(Duration::ZERO)));
+    let new_start = start.max(low).max(Duration::ZERO);
+    let new_stop = stop.min(high);
+    if new_stop <= new_start {
+        return None;
+    }
+    let pct = if stop > start {
+        (new_stop - new_start) / (stop - start)
+    } else {
+        1.0
+    };
+    Some((new_start, new_stop, pct))
+}
@@ -1,6 +1,6 @@
 use crate::{
     CarID, Command, CreateCar, CreatePedestrian, DrivingGoal, ParkingSimState, ParkingSpot,
-    PedestrianID, Scheduler, SidewalkPOI, SidewalkSpot, TripLeg, TripManager, TripStart,
+    PedestrianID, Scheduler, SidewalkPOI, SidewalkSpot, TripLeg, TripManager, TripMode, TripStart,
     VehicleSpec, VehicleType, MAX_CAR_LENGTH,
 };
 use abstutil::Timer;
@@ -160,7 +160,7 @@ impl TripSpawner {
             std::mem::replace(&mut self.trips, Vec::new()),
             |tuple| {
                 let req = tuple.3.get_pathfinding_request(map, parking);
-                (tuple, req.clone(), map.pathfind(req))
+                (tuple, req.clone(), map.pathfind_at_time(req, tuple.0))
             },
         );
         timer.start_iter("spawn trips", paths.len());
@@ -367,6 +367,21 @@ impl TripSpawner {
     pub fn is_done(&self) -> bool {
         self.trips.is_empty()
     }
+
+    // Per trip not spawned yet: when it starts, its mode, and the legs that can be known up front
+    pub(crate) fn static_requests(
+        &self,
+        map: &Map,
+        parking: &ParkingSimState,
+    ) -> Vec<(Duration, TripMode, Vec<PathRequest>)> {
+        self.trips
+            .iter()
+            .map(|(start_time, _, _, spec)| {
+                let (mode, reqs) = spec.static_requests(map, parking);
+                (*start_time, mode, reqs)
+            })
+            .collect()
+    }
 }
 
 impl TripSpec {
@@ -387,6 +402,78 @@ impl TripSpec {
         }
     }
 
+    // The walk after parking depends on where the car winds up, so only the earlier legs are
+    // included.
+    fn static_requests(
+        &self,
+        map: &Map,
+        parking: &ParkingSimState,
+    ) -> (TripMode, Vec<PathRequest>) {
+        let first = self.get_pathfinding_request(map, parking);
+        match self {
+            TripSpec::CarAppearing { vehicle_spec, .. } => {
+                let mode = if vehicle_spec.vehicle_type == VehicleType::Bike {
+                    TripMode::Bike
+                } else {
+                    TripMode::Drive
+                };
+                (mode, vec![first])
+            }
+            TripSpec::UsingParkedCar { spot, goal, .. } => {
+                let start = parking
+                    .get_car_at_spot(*spot)
+                    .unwrap()
+                    .get_driving_pos(parking, map);
+                (
+                    TripMode::Drive,
+                    vec![
+                        first,
+                        PathRequest {
+                            start,
+                            end: goal.goal_pos(map),
+                            can_use_bus_lanes: false,
+                            can_use_bike_lanes: false,
+                        },
+                    ],
+                )
+            }
+            TripSpec::JustWalking { .. } => (TripMode::Walk, vec![first]),
+            TripSpec::UsingBike { start, goal, .. } => {
+                let rack = SidewalkSpot::bike_from_bike_rack(start.sidewalk_pos.lane(), map)
+                    .unwrap()
+                    .connection;
+                let start = match rack {
+                    SidewalkPOI::BikeRack(pos) => pos,
+                    _ => unreachable!(),
+                };
+                (
+                    TripMode::Bike,
+                    vec![
+                        first,
+                        PathRequest {
+                            start,
+                            end: goal.goal_pos(map),
+                            can_use_bus_lanes: false,
+                            can_use_bike_lanes: true,
+                        },
+                    ],
+                )
+            }
+            TripSpec::UsingTransit { stop2, goal, .. } => (
+                TripMode::Transit,
+                vec![
+                    first,
+                    PathRequest {
+                        start: SidewalkSpot::bus_stop(*stop2, map).sidewalk_pos,
+                        end: goal.sidewalk_pos,
+                        can_use_bus_lanes: false,
+                        can_use_bike_lanes: false,
+                    },
+                ],
+            ),
+        }
+    }
+
     fn get_pathfinding_request(&self, map: &Map, parking: &ParkingSimState) -> PathRequest {
         match self {
             TripSpec::CarAppearing {
@@ -10,7 +10,8 @@ use geom::{Distance, Duration, PolyLine};
 use map_model::{BuildingID, IntersectionID, LaneID, Map, Path, Traversable};
 use petgraph::graph::{Graph, NodeIndex};
 use serde_derive::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::iter;
 
 const TIME_TO_UNPARK: Duration = Duration::const_seconds(10.0);
 const TIME_TO_PARK: Duration = Duration::const_seconds(15.0);
@@ -42,7 +43,7 @@ impl DrivingSimState {
         };
 
         for l in map.all_lanes() {
-            if l.lane_type.is_for_moving_vehicles() {
+            if l.lane_type.is_for_moving_vehicles() && map.lane_exists(l.id) {
                 let q = Queue::new(Traversable::Lane(l.id), map);
                 sim.queues.insert(q.id, q);
             }
@@ -640,6 +641,200 @@ impl DrivingSimState {
         }
     }
 
+    // Brings the queues in line with the map after live edits. Cars touching a lane or turn
+    // that's gone or changed shape are evicted, and so are cars that can't be rerouted around
+    // something ahead that they can't use anymore. Returns all of the evicted cars.
+    pub fn handle_live_edits(
+        &mut self,
+        now: Duration,
+        map: &Map,
+        also_evict: Vec<CarID>,
+        parking: &mut ParkingSimState,
+        intersections: &mut IntersectionSimState,
+        scheduler: &mut Scheduler,
+    ) -> Vec<CarID> {
+        let mut stale: BTreeSet<Traversable> = BTreeSet::new();
+        for (id, queue) in &self.queues {
+            let still_exists = match id {
+                Traversable::Lane(l) => map
+                    .maybe_get_l(*l)
+                    .map(|l| l.lane_type.is_for_moving_vehicles())
+                    .unwrap_or(false),
+                Traversable::Turn(t) => map.maybe_get_t(*t).is_some(),
+            };
+            if !still_exists || queue.geom_len != id.length(map) {
+                stale.insert(*id);
+            }
+        }
+
+        let mut evict: BTreeSet<CarID> = also_evict.into_iter().collect();
+        for car in self.cars.values_mut() {
+            // A car can't change where it stops partway through the last step.
+            if car.router.refresh_goal(parking, map) && car.router.last_step() {
+                evict.insert(car.vehicle.id);
+            }
+            // The lane a car in a turn is headed to has space reserved for it.
+            let reserved = match car.router.head() {
+                Traversable::Turn(t) => Traversable::Lane(t.dst),
+                on => on,
+            };
+            if stale.contains(&car.router.head())
+                || stale.contains(&reserved)
+                || car.last_steps.iter().any(|on| stale.contains(on))
+            {
+                evict.insert(car.vehicle.id);
+            }
+        }
+        let mut touched: BTreeSet<Traversable> = BTreeSet::new();
+        for id in &evict {
+            self.evict_car(
+                *id,
+                now,
+                map,
+                parking,
+                intersections,
+                scheduler,
+                &mut touched,
+            );
+        }
+
+        for id in &stale {
+            self.queues.remove(id);
+            touched.remove(id);
+        }
+        for l in map.all_lanes() {
+            let id = Traversable::Lane(l.id);
+            if l.lane_type.is_for_moving_vehicles()
+                && map.lane_exists(l.id)
+                && !self.queues.contains_key(&id)
+            {
+                self.queues.insert(id, Queue::new(id, map));
+            }
+        }
+        for t in map.all_turns().values() {
+            let id = Traversable::Turn(t.id);
+            if !t.between_sidewalks() && !self.queues.contains_key(&id) {
+                self.queues.insert(id, Queue::new(id, map));
+            }
+        }
+
+        let mut unreachable = Vec::new();
+        let reroute: Vec<CarID> = self
+            .cars
+            .values()
+            .filter(|car| {
+                !car.router
+                    .is_rest_of_path_valid(car.vehicle.vehicle_type, map)
+            })
+            .map(|car| car.vehicle.id)
+            .collect();
+        for id in reroute {
+            let head = self.cars[&id].router.head();
+            let front = match head {
+                Traversable::Lane(_) => {
+                    self.queues[&head]
+                        .get_car_positions(now, &self.cars, &self.queues)
+                        .into_iter()
+                        .find(|(c, _)| *c == id)
+                        .unwrap()
+                        .1
+                }
+                Traversable::Turn(_) => Distance::ZERO,
+            };
+            let car = self.cars.get_mut(&id).unwrap();
+            if !car.router.reroute(front, &car.vehicle, now, map) || car.router.last_step() {
+                unreachable.push(id);
+                continue;
+            }
+            // The car might've been waiting for a turn that's gone now.
+            if let CarState::WaitingToAdvance = car.state {
+                scheduler.update(now, Command::UpdateCar(id));
+            }
+        }
+        for id in &unreachable {
+            self.evict_car(
+                *id,
+                now,
+                map,
+                parking,
+                intersections,
+                scheduler,
+                &mut touched,
+            );
+        }
+        evict.extend(unreachable);
+
+        // Cars stuck behind somebody who vanished need to start moving again.
+        for id in touched {
+            let dists = self.queues[&id].get_car_positions(now, &self.cars, &self.queues);
+            for (car_id, dist) in dists {
+                let car = self.cars.get_mut(&car_id).unwrap();
+                if let CarState::Queued = car.state {
+                    if !car.router.last_step() {
+                        car.state = car.crossing_state(dist, now, map);
+                        car.blocked_since = None;
+                        scheduler.update(car.state.get_end_time(), Command::UpdateCar(car_id));
+                    }
+                }
+            }
+        }
+
+        evict.into_iter().collect()
+    }
+
+    fn evict_car(
+        &mut self,
+        id: CarID,
+        now: Duration,
+        map: &Map,
+        parking: &mut ParkingSimState,
+        intersections: &mut IntersectionSimState,
+        scheduler: &mut Scheduler,
+        touched: &mut BTreeSet<Traversable>,
+    ) {
+        let car = self.cars.remove(&id).unwrap();
+        let head = car.router.head();
+        for on in iter::once(head).chain(car.last_steps.iter().cloned()) {
+            let queue = self.queues.get_mut(&on).unwrap();
+            if let Some(idx) = queue.cars.iter().position(|c| *c == id) {
+                queue.cars.remove(idx);
+            }
+            if queue.laggy_head == Some(id) {
+                queue.laggy_head = None;
+            }
+            touched.insert(on);
+        }
+
+        // Same as when the car leaves normally: space is reserved on the lane it's on or headed
+        // to, and on every lane it's still partly in.
+        let mut reserved: Vec<LaneID> = car
+            .last_steps
+            .iter()
+            .filter_map(|on| match on {
+                Traversable::Lane(l) => Some(*l),
+                Traversable::Turn(_) => None,
+            })
+            .collect();
+        reserved.push(match head {
+            Traversable::Lane(l) => l,
+            Traversable::Turn(t) => t.dst,
+        });
+        for l in reserved {
+            self.queues
+                .get_mut(&Traversable::Lane(l))
+                .unwrap()
+                .free_reserved_space(&car);
+            intersections.space_freed(now, map.get_l(l).src_i, scheduler);
+        }
+
+        if let CarState::Parking(_, spot, _) = car.state {
+            parking.unreserve_spot(spot);
+        }
+        scheduler.cancel(Command::UpdateCar(id));
+        scheduler.cancel(Command::UpdateLaggyHead(id));
+        intersections.agent_evicted(now, AgentID::Car(id), scheduler);
+    }
+
     pub fn get_unzoomed_agents(&self, now: Duration, map: &Map) -> Vec<UnzoomedAgent> {
         let mut result = Vec::new();
 
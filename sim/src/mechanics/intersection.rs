@@ -1,6 +1,6 @@
 use crate::mechanics::car::Car;
 use crate::mechanics::queue::Queue;
-use crate::{AgentID, Command, Scheduler, Speed};
+use crate::{AgentID, Command, Scheduler, Speed, TrafficStats};
 use abstutil::{deserialize_btreemap, serialize_btreemap};
 use geom::Duration;
 use map_model::{
@@ -17,6 +17,7 @@ pub struct IntersectionSimState {
     state: BTreeMap<IntersectionID, State>,
     use_freeform_policy_everywhere: bool,
     force_queue_entry: bool,
+    stats: TrafficStats,
 }
 
 #[derive(Serialize, Deserialize, PartialEq)]
@@ -42,6 +43,7 @@ impl IntersectionSimState {
             state: BTreeMap::new(),
             use_freeform_policy_everywhere,
             force_queue_entry: disable_block_the_box,
+            stats: TrafficStats::default(),
         };
         for i in map.all_intersections() {
             sim.state.insert(
@@ -163,11 +165,64 @@ impl IntersectionSimState {
         }
 
         assert!(!state.any_accepted_conflict_with(turn, map));
-        state.waiting.remove(&req).unwrap();
+        let started_waiting = state.waiting.remove(&req).unwrap();
         state.accepted.insert(req);
+        self.stats.turn_started(turn, now - started_waiting, map);
         true
     }
 
+    // Forget about requests for turns that live map edits deleted. The agents making them are
+    // rerouted or evicted separately.
+    pub fn handle_live_edits(&mut self, now: Duration, map: &Map, scheduler: &mut Scheduler) {
+        for state in self.state.values_mut() {
+            state.accepted = state
+                .accepted
+                .iter()
+                .filter(|req| map.maybe_get_t(req.turn).is_some())
+                .cloned()
+                .collect();
+            state.waiting = state
+                .waiting
+                .iter()
+                .filter(|(req, _)| map.maybe_get_t(req.turn).is_some())
+                .map(|(req, t)| (req.clone(), *t))
+                .collect();
+        }
+
+        // An intersection may have changed type, or a signal's timing may be different.
+        for i in map.all_intersections() {
+            scheduler.cancel(Command::UpdateIntersection(i.id));
+            if i.intersection_type == IntersectionType::TrafficSignal {
+                self.update_intersection(now, i.id, map, scheduler);
+            }
+        }
+    }
+
+    // The agent was removed from the simulation without finishing its turns. This is rare, so
+    // don't bother tracking which intersections to look at.
+    pub fn agent_evicted(&mut self, now: Duration, agent: AgentID, scheduler: &mut Scheduler) {
+        for state in self.state.values_mut() {
+            let num_accepted = state.accepted.len();
+            state.accepted = state
+                .accepted
+                .iter()
+                .filter(|req| req.agent != agent)
+                .cloned()
+                .collect();
+            state.waiting = state
+                .waiting
+                .iter()
+                .filter(|(req, _)| req.agent != agent)
+                .map(|(req, t)| (req.clone(), *t))
+                .collect();
+            if state.accepted.len() != num_accepted {
+                for req in state.waiting.keys() {
+                    scheduler.update(now, Command::update_agent(req.agent));
+                }
+            }
+        }
+    }
+
     pub fn debug(&self, id: IntersectionID, map: &Map) {
         println!("{}", abstutil::to_json(&self.state[&id]));
         if let Some(ref sign) = map.maybe_get_stop_sign(id) {
@@ -179,6 +234,22 @@ impl IntersectionSimState {
         }
     }
 
+    pub fn get_stats(&self) -> &TrafficStats {
+        &self.stats
+    }
+
+    // Everyone who's waited at the intersection so far, including those still waiting
+    pub fn total_delay(&self, now: Duration, id: IntersectionID) -> Duration {
+        self.state[&id].waiting.values().fold(
+            self.stats
+                .delay_intersections
+                .get(&id)
+                .cloned()
+                .unwrap_or(Duration::ZERO),
+            |sum, started| sum + (now - *started),
+        )
+    }
+
     pub fn get_accepted_agents(&self, id: IntersectionID) -> HashSet<AgentID> {
         self.state[&id]
             .accepted
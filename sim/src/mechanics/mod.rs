@@ -8,4 +8,4 @@ mod walking;
 pub use self::driving::DrivingSimState;
 pub use self::intersection::IntersectionSimState;
 pub use self::parking::ParkingSimState;
-pub use self::walking::WalkingSimState;
+pub use self::walking::{is_walking_path_open, WalkingSimState};
@@ -46,6 +46,56 @@ impl ParkingSimState {
         sim
     }
 
+    // Rebuilds parking lanes to match the map after live edits. Cars parked in spots that no
+    // longer exist are removed and returned.
+    pub fn handle_live_edits(&mut self, map: &Map) -> Vec<ParkedCar> {
+        let mut evicted = Vec::new();
+        let old_lanes = std::mem::replace(&mut self.lanes, BTreeMap::new());
+        self.driving_to_parking_lane.clear();
+        for l in map.all_lanes() {
+            if let Some(mut lane) = ParkingLane::new(l, map) {
+                if let Some(old) = old_lanes.get(&l.id) {
+                    for (idx, occupant) in old.occupants.iter().enumerate() {
+                        if idx < lane.occupants.len() {
+                            lane.occupants[idx] = *occupant;
+                        }
+                    }
+                }
+                self.driving_to_parking_lane.insert(lane.driving_lane, l.id);
+                self.lanes.insert(lane.id, lane);
+            }
+        }
+
+        for (id, old) in old_lanes {
+            let num_spots = self.lanes.get(&id).map(|l| l.occupants.len()).unwrap_or(0);
+            for car in old.occupants.into_iter().skip(num_spots).flatten() {
+                let p = self.cars.remove(&car).unwrap();
+                if let Some(b) = p.vehicle.owner {
+                    self.cars_per_building.remove(b, car);
+                }
+                evicted.push(p);
+            }
+        }
+        self.reserved_spots = self
+            .reserved_spots
+            .iter()
+            .filter(|spot| self.spot_exists(**spot))
+            .cloned()
+            .collect();
+        evicted
+    }
+
+    pub fn spot_exists(&self, spot: ParkingSpot) -> bool {
+        self.lanes
+            .get(&spot.lane)
+            .map(|l| spot.idx < l.occupants.len())
+            .unwrap_or(false)
+    }
+
+    pub fn unreserve_spot(&mut self, spot: ParkingSpot) {
+        self.reserved_spots.remove(&spot);
+    }
+
     pub fn get_free_spots(&self, l: LaneID) -> Vec<ParkingSpot> {
         let lane = &self.lanes[&l];
         // Don't seed cars here, because if somebody tries to drive from here, they can't reach most
@@ -210,7 +260,8 @@ struct ParkingLane {
 
 impl ParkingLane {
     fn new(l: &Lane, map: &Map) -> Option<ParkingLane> {
-        if l.lane_type != LaneType::Parking {
+        // Edits may have removed the lane from its road.
+        if l.lane_type != LaneType::Parking || !map.lane_exists(l.id) {
             return None;
         }
 
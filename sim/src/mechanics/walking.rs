@@ -5,7 +5,9 @@ use crate::{
 };
 use abstutil::{deserialize_multimap, serialize_multimap, MultiMap};
 use geom::{Distance, Duration, Line, PolyLine, Speed};
-use map_model::{BuildingID, Map, Path, PathStep, Traversable, LANE_THICKNESS};
+use map_model::{
+    BuildingID, LaneType, Map, Path, PathRequest, PathStep, Position, Traversable, LANE_THICKNESS,
+};
 use serde_derive::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
@@ -218,6 +220,98 @@ impl WalkingSimState {
         };
     }
 
+    // Fixes up pedestrians after live map edits. Returns the ones that had to be evicted, because
+    // they were in a turn that's gone or can't reach their goal anymore.
+    pub fn handle_live_edits(
+        &mut self,
+        now: Duration,
+        map: &Map,
+        intersections: &mut IntersectionSimState,
+        scheduler: &mut Scheduler,
+    ) -> Vec<PedestrianID> {
+        let mut evict = Vec::new();
+        for ped in self.peds.values_mut() {
+            let on = ped.path.current_step().as_traversable();
+            if let Traversable::Turn(t) = on {
+                if map.maybe_get_t(t).is_none() {
+                    evict.push(ped.id);
+                    continue;
+                }
+            }
+            let len = on.length(map);
+            let dist = match ped.state {
+                PedState::Crossing(ref dist_int, ref time_int) => {
+                    dist_int.lerp(time_int.percent_clamp_end(now))
+                }
+                _ => ped.get_dist_along(now, map),
+            }
+            .min(len);
+            let mut changed = ped.goal.clamp_to_sidewalk(map);
+
+            let path_ok = is_walking_path_open(ped.path.get_steps().iter().skip(1), map);
+            if !path_ok {
+                let rerouted = match on {
+                    Traversable::Lane(l) => map.pathfind(PathRequest {
+                        start: Position::new(l, dist),
+                        end: ped.goal.sidewalk_pos,
+                        can_use_bus_lanes: false,
+                        can_use_bike_lanes: false,
+                    }),
+                    Traversable::Turn(_) => None,
+                };
+                if let Some(path) = rerouted {
+                    ped.path = path;
+                    changed = true;
+                } else {
+                    evict.push(ped.id);
+                    continue;
+                }
+            }
+
+            match ped.state {
+                PedState::Crossing(ref dist_int, _) => {
+                    if changed || dist_int.start > len || dist_int.end > len {
+                        ped.state = ped.crossing_state(dist, now, map);
+                        scheduler.update(ped.state.get_end_time(), Command::UpdatePed(ped.id));
+                    }
+                }
+                PedState::WaitingToTurn(d) => {
+                    if changed {
+                        // Maybe waiting for a turn that's gone now
+                        ped.state = ped.crossing_state(dist, now, map);
+                        ped.blocked_since = None;
+                        scheduler.update(ped.state.get_end_time(), Command::UpdatePed(ped.id));
+                    } else if d != Distance::ZERO {
+                        ped.state = PedState::WaitingToTurn(len);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for id in &evict {
+            self.evict_ped(now, *id, intersections, scheduler);
+        }
+        evict
+    }
+
+    // Removes a pedestrian partway through its trip. Does nothing if the pedestrian is riding a
+    // bus, since they're not tracked here then.
+    pub fn evict_ped(
+        &mut self,
+        now: Duration,
+        id: PedestrianID,
+        intersections: &mut IntersectionSimState,
+        scheduler: &mut Scheduler,
+    ) {
+        if let Some(ped) = self.peds.remove(&id) {
+            self.peds_per_traversable
+                .remove(ped.path.current_step().as_traversable(), id);
+            scheduler.cancel(Command::UpdatePed(id));
+            intersections.agent_evicted(now, AgentID::Pedestrian(id), scheduler);
+        }
+    }
+
     pub fn debug_ped(&self, id: PedestrianID) {
         if let Some(ped) = self.peds.get(&id) {
             println!("{}", abstutil::to_json(ped));
@@ -403,8 +497,15 @@ impl Pedestrian {
                 PathStep::Turn(t) => map.get_t(t).geom.length(),
             }
         };
+        let slowdown = match self.path.current_step() {
+            PathStep::Lane(l) | PathStep::ContraflowLane(l) => map.get_parent(l).walking_slowdown(),
+            PathStep::Turn(_) => 1.0,
+        };
         let dist_int = DistanceInterval::new_walking(start_dist, end_dist);
-        let time_int = TimeInterval::new(start_time, start_time + dist_int.length() / self.speed);
+        let time_int = TimeInterval::new(
+            start_time,
+            start_time + (dist_int.length() / self.speed) * slowdown,
+        );
         PedState::Crossing(dist_int, time_int)
     }
 
@@ -622,3 +723,14 @@ fn find_crowds(
 
     (loners, crowds)
 }
+
+// Live map edits can delete turns and close sidewalks or crosswalks.
+pub fn is_walking_path_open<'a, I: Iterator<Item = &'a PathStep>>(mut steps: I, map: &Map) -> bool {
+    let sidewalk = [LaneType::Sidewalk];
+    steps.all(|step| match step {
+        PathStep::Lane(l) | PathStep::ContraflowLane(l) => !map.is_lane_closed(*l, &sidewalk),
+        PathStep::Turn(t) => {
+            map.maybe_get_t(*t).is_some() && !map.is_intersection_closed(t.parent, &sidewalk)
+        }
+    })
+}
@@ -1,7 +1,8 @@
-use crate::{ParkingSimState, ParkingSpot, SidewalkSpot, Vehicle};
-use geom::Distance;
+use crate::{ParkingSimState, ParkingSpot, SidewalkSpot, Vehicle, VehicleType};
+use geom::{Distance, Duration};
 use map_model::{
-    BuildingID, IntersectionID, LaneID, Map, Path, PathStep, Position, Traversable, TurnID,
+    BuildingID, IntersectionID, LaneID, Map, Path, PathRequest, PathStep, Position, Traversable,
+    TurnID,
 };
 use serde_derive::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
@@ -109,6 +110,131 @@ impl Router {
         &self.path
     }
 
+    // Live map edits might've deleted a turn, changed a lane type, or closed something somewhere
+    // ahead. The current step isn't checked; the vehicle is already there.
+    pub fn is_rest_of_path_valid(&self, vehicle_type: VehicleType, map: &Map) -> bool {
+        let lane_types = vehicle_type.lane_types();
+        self.path.get_steps().iter().skip(1).all(|step| match step {
+            PathStep::Lane(l) | PathStep::ContraflowLane(l) => {
+                map.lane_exists(*l)
+                    && vehicle_type.can_use_lane(map.get_l(*l).lane_type)
+                    && !map.is_lane_closed(*l, &lane_types)
+            }
+            PathStep::Turn(t) => {
+                map.maybe_get_t(*t).is_some()
+                    && map.is_turn_allowed(*t)
+                    && !map.is_intersection_closed(t.parent, &lane_types)
+            }
+        })
+    }
+
+    // Replaces everything after the current step. front is how far along the current lane the
+    // vehicle is; it's ignored if the vehicle is in a turn. Returns false if the goal can't be
+    // reached anymore.
+    pub fn reroute(
+        &mut self,
+        front: Distance,
+        vehicle: &Vehicle,
+        now: Duration,
+        map: &Map,
+    ) -> bool {
+        let end_lane = match self.goal {
+            Goal::ParkNearBuilding { target, .. } => map.find_driving_lane_near_building(target),
+            _ => self.path.last_step().as_lane(),
+        };
+        if !vehicle
+            .vehicle_type
+            .can_use_lane(map.get_l(end_lane).lane_type)
+        {
+            return false;
+        }
+        let end_len = map.get_l(end_lane).length();
+        let end_dist = match self.goal {
+            Goal::ParkNearBuilding { .. } | Goal::EndAtBorder { .. } => end_len,
+            Goal::BikeThenStop { end_dist } | Goal::FollowBusRoute { end_dist } => {
+                end_dist.min(end_len)
+            }
+        };
+
+        let start = match self.head() {
+            Traversable::Lane(l) => Position::new(l, front),
+            Traversable::Turn(t) => Position::new(t.dst, Distance::ZERO),
+        };
+        let mut path = match map.pathfind_at_time(
+            PathRequest {
+                start,
+                end: Position::new(end_lane, end_dist),
+                can_use_bus_lanes: vehicle.vehicle_type == VehicleType::Bus,
+                can_use_bike_lanes: vehicle.vehicle_type == VehicleType::Bike,
+            },
+            now,
+        ) {
+            Some(p) => p,
+            None => {
+                return false;
+            }
+        };
+        if let Traversable::Turn(t) = self.head() {
+            path.prepend(PathStep::Turn(t), map);
+        }
+        self.path = path;
+
+        match self.goal {
+            Goal::ParkNearBuilding { ref mut spot, .. } => {
+                *spot = None;
+            }
+            Goal::EndAtBorder {
+                end_dist: ref mut d,
+                ref mut i,
+            } => {
+                *d = end_dist;
+                *i = map.get_l(end_lane).dst_i;
+            }
+            Goal::BikeThenStop {
+                end_dist: ref mut d,
+            }
+            | Goal::FollowBusRoute {
+                end_dist: ref mut d,
+            } => {
+                *d = end_dist;
+            }
+        }
+        true
+    }
+
+    // Live map edits can change lane lengths and parking spots at the end of the path. Returns
+    // true if the goal changed.
+    pub fn refresh_goal(&mut self, parking: &ParkingSimState, map: &Map) -> bool {
+        let end_len = map.get_l(self.path.last_step().as_lane()).length();
+        match self.goal {
+            Goal::ParkNearBuilding { ref mut spot, .. } => {
+                if let Some((s, _)) = *spot {
+                    if !parking.spot_exists(s) {
+                        *spot = None;
+                        return true;
+                    }
+                }
+                false
+            }
+            Goal::EndAtBorder {
+                ref mut end_dist, ..
+            } => {
+                if *end_dist != end_len {
+                    *end_dist = end_len;
+                    return true;
+                }
+                false
+            }
+            Goal::BikeThenStop { ref mut end_dist } | Goal::FollowBusRoute { ref mut end_dist } => {
+                if *end_dist > end_len {
+                    *end_dist = end_len;
+                    return true;
+                }
+                false
+            }
+        }
+    }
+
     // Returns the step just finished
     pub fn advance(
         &mut self,
@@ -159,6 +159,25 @@ impl Scheduler {
         self.queued_commands.remove(&cmd.to_type());
     }
 
+    // Live map edits can invalidate agents that haven't spawned yet. The caller can fix up each
+    // pending spawn in place, or return false to cancel it.
+    pub fn retain_pending_spawns<F: FnMut(&mut Command) -> bool>(&mut self, mut keep: F) {
+        let mut cancel = Vec::new();
+        for (cmd_type, (cmd, _)) in self.queued_commands.iter_mut() {
+            match cmd {
+                Command::SpawnCar(_, _) | Command::SpawnPed(_) => {
+                    if !keep(cmd) {
+                        cancel.push(cmd_type.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+        for cmd_type in cancel {
+            self.queued_commands.remove(&cmd_type);
+        }
+    }
+
     // This API is safer than handing out a batch of items at a time, because while processing one
     // item, we might change the priority of other items or add new items. Don't make the caller
     // reconcile those changes -- just keep pulling items from here, one at a time.
@@ -0,0 +1,154 @@
+use crate::Sim;
+use abstutil::Timer;
+use geom::Duration;
+use map_model::{ControlTrafficSignal, IntersectionID, Map, MapEdits, TurnID};
+use std::collections::BTreeMap;
+
+// How much time local search moves between cycles at once
+const DURATION_STEP: Duration = Duration::const_seconds(5.0);
+
+// Tunes one traffic signal to the demand it sees. Webster's method gives a starting point, then
+// local search reorders cycles and shifts time between them, scoring each candidate by the delay
+// at the intersection in a short simulation.
+pub struct SignalOptimizer {
+    pub intersection: IntersectionID,
+    // How many agents made each turn over the observed duration
+    pub demand: BTreeMap<TurnID, usize>,
+    pub observed: Duration,
+}
+
+pub struct OptimizedSignal {
+    // Goes in MapEdits::traffic_signal_overrides
+    pub signal: ControlTrafficSignal,
+    // Total delay at the intersection over the evaluation run
+    pub delay_before: Duration,
+    pub delay_after: Duration,
+    pub candidates_tried: usize,
+}
+
+impl SignalOptimizer {
+    // Uses the turns agents have made so far.
+    pub fn from_sim(sim: &Sim, id: IntersectionID) -> SignalOptimizer {
+        SignalOptimizer {
+            intersection: id,
+            demand: sim
+                .get_traffic_stats()
+                .thruput_turns
+                .iter()
+                .filter(|(t, _)| t.parent == id)
+                .map(|(t, count)| (*t, *count))
+                .collect(),
+            observed: sim.time(),
+        }
+    }
+
+    // Keeps the current cycles and only changes their durations.
+    pub fn webster(&self, map: &Map) -> ControlTrafficSignal {
+        map.get_unpatched_traffic_signal(self.intersection)
+            .with_webster_timing(map, &self.demand, self.observed)
+    }
+
+    // Every candidate runs for the horizon from the savestate, which should be from a sim with
+    // the same demand. The map's edits are the same afterwards. Never returns something worse
+    // than the current signal.
+    pub fn optimize(
+        &self,
+        map: &mut Map,
+        savestate: &str,
+        horizon: Duration,
+        max_rounds: usize,
+        timer: &mut Timer,
+    ) -> OptimizedSignal {
+        timer.start(&format!("optimize traffic signal at {}", self.intersection));
+        let orig_edits = map.get_edits().clone();
+
+        let current = map.get_unpatched_traffic_signal(self.intersection).clone();
+        let delay_before = self.evaluate(&current, map, &orig_edits, savestate, horizon, timer);
+        let mut best = self.webster(map);
+        let mut best_delay = self.evaluate(&best, map, &orig_edits, savestate, horizon, timer);
+        let mut candidates_tried = 2;
+
+        for round in 0..max_rounds {
+            let mut improved = false;
+            for candidate in neighbors(&best, map) {
+                let delay = self.evaluate(&candidate, map, &orig_edits, savestate, horizon, timer);
+                candidates_tried += 1;
+                if delay < best_delay {
+                    best = candidate;
+                    best_delay = delay;
+                    improved = true;
+                }
+            }
+            timer.note(format!(
+                "After round {}, delay is {} (originally {})",
+                round + 1,
+                best_delay,
+                delay_before
+            ));
+            if !improved {
+                break;
+            }
+        }
+        if delay_before <= best_delay {
+            best = current;
+            best_delay = delay_before;
+        }
+
+        map.apply_edits(orig_edits, timer);
+        map.recalculate_pathfinding_after_edits(timer);
+        timer.stop(&format!("optimize traffic signal at {}", self.intersection));
+        OptimizedSignal {
+            signal: best,
+            delay_before,
+            delay_after: best_delay,
+            candidates_tried,
+        }
+    }
+
+    fn evaluate(
+        &self,
+        signal: &ControlTrafficSignal,
+        map: &mut Map,
+        orig_edits: &MapEdits,
+        savestate: &str,
+        horizon: Duration,
+        timer: &mut Timer,
+    ) -> Duration {
+        let mut edits = orig_edits.clone();
+        edits
+            .traffic_signal_overrides
+            .insert(self.intersection, signal.clone());
+        map.apply_edits(edits, timer);
+        map.recalculate_pathfinding_after_edits(timer);
+
+        let mut sim = Sim::load_savestate(savestate.to_string(), timer).unwrap();
+        sim.handle_live_edits(map, timer);
+        let start = sim.get_total_delay(self.intersection);
+        sim.step(map, horizon);
+        sim.get_total_delay(self.intersection) - start
+    }
+}
+
+// Shift time into or out of each cycle, or swap neighboring cycles.
+fn neighbors(signal: &ControlTrafficSignal, map: &Map) -> Vec<ControlTrafficSignal> {
+    let mut results = Vec::new();
+    for idx in 0..signal.cycles.len() {
+        let mut longer = signal.clone();
+        longer.cycles[idx].duration += DURATION_STEP;
+        results.push(longer);
+
+        let cycle = &signal.cycles[idx];
+        if cycle.duration - DURATION_STEP >= cycle.min_duration(map) {
+            let mut shorter = signal.clone();
+            shorter.cycles[idx].duration -= DURATION_STEP;
+            results.push(shorter);
+        }
+
+        if idx + 1 < signal.cycles.len() {
+            let mut swapped = signal.clone();
+            swapped.cycles.swap(idx, idx + 1);
+            results.push(swapped);
+        }
+    }
+    results
+}
@@ -1,14 +1,17 @@
 use crate::{
-    AgentID, CarID, Command, CreateCar, DrawCarInput, DrawPedCrowdInput, DrawPedestrianInput,
-    DrivingGoal, DrivingSimState, Event, FinishedTrips, GetDrawAgents, IntersectionSimState,
-    ParkedCar, ParkingSimState, ParkingSpot, PedestrianID, Router, Scheduler, TransitSimState,
-    TripID, TripLeg, TripManager, TripPositions, TripSpawner, TripSpec, TripStatus, UnzoomedAgent,
-    VehicleSpec, VehicleType, WalkingSimState, BUS_LENGTH,
+    is_walking_path_open, AgentID, CarID, Command, CreateCar, DrawCarInput, DrawPedCrowdInput,
+    DrawPedestrianInput, DrivingGoal, DrivingSimState, Event, FinishedTrips, GetDrawAgents,
+    IntersectionSimState, ParkedCar, ParkingSimState, ParkingSpot, PedestrianID, Router, Scheduler,
+    TrafficStats, TransitSimState, TripID, TripLeg, TripManager, TripMode, TripPositions,
+    TripSpawner, TripSpec, TripStatus, UnzoomedAgent, VehicleSpec, VehicleType, WalkingSimState,
+    BUS_LENGTH,
 };
 use abstutil::{elapsed_seconds, Timer};
 use derivative::Derivative;
 use geom::{Distance, Duration, PolyLine, Pt2D};
-use map_model::{BuildingID, BusRoute, BusRouteID, IntersectionID, LaneID, Map, Path, Traversable};
+use map_model::{
+    BuildingID, BusRoute, BusRouteID, IntersectionID, LaneID, Map, Path, PathRequest, Traversable,
+};
 use serde_derive::{Deserialize, Serialize};
 use std::collections::{HashSet, VecDeque};
 use std::panic;
@@ -101,8 +104,7 @@ impl Sim {
             ped_id_counter: 0,
 
             map_name: map.get_name().to_string(),
-            // TODO
-            edits_name: "no_edits".to_string(),
+            edits_name: map.get_edits().edits_name.clone(),
             run_name: opts.run_name,
             step_count: 0,
             trip_positions: None,
@@ -166,6 +168,13 @@ impl Sim {
         );
     }
 
+    pub(crate) fn scheduled_trip_requests(
+        &self,
+        map: &Map,
+    ) -> Vec<(Duration, TripMode, Vec<PathRequest>)> {
+        self.spawner.static_requests(map, &self.parking)
+    }
+
     pub fn get_free_spots(&self, l: LaneID) -> Vec<ParkingSpot> {
         self.parking.get_free_spots(l)
     }
@@ -319,13 +328,52 @@ impl Sim {
 
 // Running
 impl Sim {
-    pub fn step(&mut self, map: &Map, dt: Duration) {
+    // Also switches the map's scheduled edits on and off at the right times.
+    pub fn step(&mut self, map: &mut Map, dt: Duration) {
+        let target_time = self.time + dt;
+        self.events_since_last_step.clear();
+        self.catch_up_scheduled_edits(map);
+        loop {
+            let stop_at = self.next_stop(map, target_time);
+            self.step_to(map, stop_at);
+            self.catch_up_scheduled_edits(map);
+            if self.time == target_time {
+                break;
+            }
+        }
+    }
+
+    // Steps towards target_time, but stops early when the map's scheduled edits need to switch on
+    // or off. The caller applies them and keeps going; the UI uses this to redraw what changed.
+    pub fn step_until_scheduled_change(&mut self, map: &Map, target_time: Duration) {
+        self.events_since_last_step.clear();
+        let stop_at = self.next_stop(map, target_time);
+        self.step_to(map, stop_at);
+    }
+
+    // Only makes a Timer (which prints when it's done) if there's something to do.
+    fn catch_up_scheduled_edits(&mut self, map: &mut Map) {
+        if !map
+            .get_edits()
+            .same_scheduled_edits_active(map.get_edits_time(), self.time)
+        {
+            self.apply_scheduled_edits(map, &mut Timer::new("apply scheduled map edits"));
+        }
+    }
+
+    fn next_stop(&self, map: &Map, target_time: Duration) -> Duration {
+        match map.get_edits().next_scheduled_change(self.time) {
+            Some(t) if t < target_time => t,
+            _ => target_time,
+        }
+    }
+
+    fn step_to(&mut self, map: &Map, target_time: Duration) {
         self.step_count += 1;
         if !self.spawner.is_done() {
             panic!("Forgot to call spawn_all_trips");
         }
 
-        let target_time = self.time + dt;
         let mut savestate_at: Option<Duration> = None;
         while let Some((cmd, time)) = self.scheduler.get_next(target_time) {
             // Many commands might be scheduled for a particular time. Savestate at the END of a
@@ -443,14 +491,13 @@ impl Sim {
 
         self.trip_positions = None;
 
-        self.events_since_last_step.clear();
         self.events_since_last_step
             .extend(self.trips.collect_events());
         self.events_since_last_step
             .extend(self.transit.collect_events());
     }
 
-    pub fn timed_step(&mut self, map: &Map, dt: Duration, timer: &mut Timer) {
+    pub fn timed_step(&mut self, map: &mut Map, dt: Duration, timer: &mut Timer) {
         // TODO Ideally print every second or so
         let orig_time = self.time;
         let chunks = (dt / Duration::seconds(10.0)).ceil() as usize;
@@ -469,7 +516,7 @@ impl Sim {
         assert_eq!(self.time, orig_time + dt);
     }
 
-    pub fn time_limited_step(&mut self, map: &Map, dt: Duration, real_time_limit: Duration) {
+    pub fn time_limited_step(&mut self, map: &mut Map, dt: Duration, real_time_limit: Duration) {
         let started_at = Instant::now();
         let goal_time = self.time + dt;
 
@@ -494,15 +541,182 @@ impl Sim {
     }
 }
 
+// Live map edits
+impl Sim {
+    // Call after the map's edits have changed and pathfinding has been recalculated. Agents that
+    // can't cope with the new map are removed and their trips aborted.
+    pub fn handle_live_edits(&mut self, map: &Map, timer: &mut Timer) {
+        timer.start("handle live edits");
+        let now = self.time;
+        self.intersections
+            .handle_live_edits(now, map, &mut self.scheduler);
+
+        let mut evict_cars = Vec::new();
+        let mut evict_peds = Vec::new();
+        for parked_car in self.parking.handle_live_edits(map) {
+            for agent in self.trips.abort_trips_using_car(now, parked_car.vehicle.id) {
+                match agent {
+                    AgentID::Car(id) => evict_cars.push(id),
+                    AgentID::Pedestrian(id) => evict_peds.push(id),
+                }
+            }
+        }
+
+        let (broken_buses, passengers) = self.transit.handle_live_edits(map);
+        evict_cars.extend(broken_buses.clone());
+        for ped in passengers {
+            self.trips.abort_trip_midway(now, AgentID::Pedestrian(ped));
+            evict_peds.push(ped);
+        }
+
+        let evicted_cars = self.driving.handle_live_edits(
+            now,
+            map,
+            evict_cars,
+            &mut self.parking,
+            &mut self.intersections,
+            &mut self.scheduler,
+        );
+        for car in evicted_cars {
+            if car.1 == VehicleType::Bus && !broken_buses.contains(&car) {
+                for ped in self.transit.bus_evicted(car) {
+                    self.trips.abort_trip_midway(now, AgentID::Pedestrian(ped));
+                }
+            }
+            // Cars evicted because of a parked car already had their trip aborted.
+            if self.trips.agent_to_trip(AgentID::Car(car)).is_some() {
+                self.trips.abort_trip_midway(now, AgentID::Car(car));
+            }
+        }
+
+        for ped in evict_peds {
+            self.walking
+                .evict_ped(now, ped, &mut self.intersections, &mut self.scheduler);
+        }
+        for ped in
+            self.walking
+                .handle_live_edits(now, map, &mut self.intersections, &mut self.scheduler)
+        {
+            self.trips.abort_trip_midway(now, AgentID::Pedestrian(ped));
+        }
+
+        self.trips.handle_live_edits(map);
+        let parking = &self.parking;
+        let trips = &mut self.trips;
+        self.scheduler
+            .retain_pending_spawns(|cmd| fix_pending_spawn(cmd, now, map, parking, trips));
+
+        self.edits_name = map.get_edits().edits_name.clone();
+        self.trip_positions = None;
+        timer.stop("handle live edits");
+    }
+
+    // Switches the map's scheduled edits on or off to match the current time, if needed. Stepping
+    // does this automatically; call it directly after loading a savestate.
+    pub fn apply_scheduled_edits(&mut self, map: &mut Map, timer: &mut Timer) {
+        if map
+            .get_edits()
+            .same_scheduled_edits_active(map.get_edits_time(), self.time)
+        {
+            return;
+        }
+        map.set_edits_time(self.time, timer);
+        map.recalculate_pathfinding_after_edits(timer);
+        self.handle_live_edits(map, timer);
+    }
+}
+
+// Returns false if the spawn should be canceled.
+fn fix_pending_spawn(
+    cmd: &mut Command,
+    now: Duration,
+    map: &Map,
+    parking: &ParkingSimState,
+    trips: &mut TripManager,
+) -> bool {
+    match *cmd {
+        Command::SpawnCar(ref mut create, _) => {
+            if trips.is_trip_done_or_aborted(create.trip) {
+                return false;
+            }
+            let lane = create.router.head().as_lane();
+            if let Some(ref parked_car) = create.maybe_parked_car {
+                if parking.get_car_at_spot(parked_car.spot).as_ref() != Some(parked_car) {
+                    println!(
+                        "Aborting {} because {} was evicted by map edits",
+                        create.trip, parked_car.vehicle.id
+                    );
+                    trips.abort_trip_failed_start(create.trip);
+                    return false;
+                }
+                let pos = parked_car.get_driving_pos(parking, map);
+                if pos.lane() == lane {
+                    create.start_dist = pos.dist_along();
+                }
+            }
+            let ok = map.lane_exists(lane)
+                && create
+                    .vehicle
+                    .vehicle_type
+                    .can_use_lane(map.get_l(lane).lane_type)
+                && create.start_dist <= map.get_l(lane).length()
+                && (create
+                    .router
+                    .is_rest_of_path_valid(create.vehicle.vehicle_type, map)
+                    || create
+                        .router
+                        .reroute(create.start_dist, &create.vehicle, now, map));
+            if !ok {
+                println!(
+                    "Aborting {} because map edits broke where it starts or where it's going",
+                    create.trip
+                );
+                trips.abort_trip_failed_start(create.trip);
+                return false;
+            }
+            create.router.refresh_goal(parking, map);
+            true
+        }
+        Command::SpawnPed(ref mut create) => {
+            if trips.is_trip_done_or_aborted(create.trip) {
+                return false;
+            }
+            let moved_start = create.start.clamp_to_sidewalk(map);
+            let moved_goal = create.goal.clamp_to_sidewalk(map);
+            let path_ok = is_walking_path_open(create.path.get_steps().iter(), map);
+            if path_ok && !moved_start && !moved_goal {
+                return true;
+            }
+            if let Some(path) = map.pathfind(PathRequest {
+                start: create.start.sidewalk_pos,
+                end: create.goal.sidewalk_pos,
+                can_use_bus_lanes: false,
+                can_use_bike_lanes: false,
+            }) {
+                create.path = path;
+                true
+            } else {
+                println!(
+                    "Aborting {} because map edits broke the walking path",
+                    create.trip
+                );
+                trips.abort_trip_failed_start(create.trip);
+                false
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
 // Helpers to run the sim
 impl Sim {
-    pub fn just_run_until_done(&mut self, map: &Map, time_limit: Option<Duration>) {
+    pub fn just_run_until_done(&mut self, map: &mut Map, time_limit: Option<Duration>) {
         self.run_until_done(map, |_, _| {}, time_limit);
     }
 
     pub fn run_until_done<F: Fn(&Sim, &Map)>(
         &mut self,
-        map: &Map,
+        map: &mut Map,
         callback: F,
         // Interpreted as a relative time
         time_limit: Option<Duration>,
@@ -519,7 +733,7 @@ impl Sim {
             };
 
             match panic::catch_unwind(panic::AssertUnwindSafe(|| {
-                self.step(&map, dt);
+                self.step(map, dt);
             })) {
                 Ok(()) => {}
                 Err(err) => {
@@ -560,7 +774,7 @@ impl Sim {
 
     pub fn run_until_expectations_met(
         &mut self,
-        map: &Map,
+        map: &mut Map,
         all_expectations: Vec<Event>,
         // Interpreted as a relative time
         time_limit: Duration,
@@ -569,7 +783,7 @@ impl Sim {
         // TODO Doesn't stop early once all expectations are met.
 
         let mut expectations = VecDeque::from(all_expectations);
-        self.step(&map, self.time() + time_limit);
+        self.step(map, self.time() + time_limit);
         for ev in self.get_events_since_last_step() {
             if ev == expectations.front().unwrap() {
                 println!("At {}, met expectation {:?}", self.time, ev);
@@ -725,6 +939,10 @@ impl Sim {
         self.trips.trip_to_agent(id)
     }
 
+    pub fn trip_exists(&self, id: TripID) -> bool {
+        self.trips.trip_exists(id)
+    }
+
     pub fn trip_status(&self, id: TripID) -> Option<TripStatus> {
         self.trips.trip_status(id)
     }
@@ -814,6 +1032,14 @@ impl Sim {
     pub fn get_accepted_agents(&self, id: IntersectionID) -> HashSet<AgentID> {
         self.intersections.get_accepted_agents(id)
     }
+
+    pub fn get_traffic_stats(&self) -> &TrafficStats {
+        self.intersections.get_stats()
+    }
+
+    pub fn get_total_delay(&self, id: IntersectionID) -> Duration {
+        self.intersections.total_delay(self.time, id)
+    }
 }
 
 pub enum TripResult {
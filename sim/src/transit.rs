@@ -230,6 +230,70 @@ impl TransitSimState {
         false
     }
 
+    // Live map edits can move bus stops or cut routes. Returns the buses serving routes that no
+    // longer work, and the pedestrians riding or waiting for them, all of which should be removed.
+    // TODO Pedestrians still walking to a stop on a broken route will wait there forever.
+    pub fn handle_live_edits(&mut self, map: &Map) -> (Vec<CarID>, Vec<PedestrianID>) {
+        let mut evict_buses = Vec::new();
+        let mut evict_peds = Vec::new();
+        for (id, route) in self.routes.iter_mut() {
+            let driving_pos: Vec<Position> = route
+                .stops
+                .iter()
+                .map(|s| map.get_bs(s.id).driving_pos)
+                .collect();
+            let mut paths = Vec::new();
+            for (idx, stop) in route.stops.iter().enumerate() {
+                if let Some(path) = map.pathfind(PathRequest {
+                    start: driving_pos[idx],
+                    end: driving_pos[stop.next_stop_idx],
+                    can_use_bike_lanes: false,
+                    can_use_bus_lanes: true,
+                }) {
+                    paths.push(path);
+                } else {
+                    break;
+                }
+            }
+
+            if paths.len() == route.stops.len() {
+                for ((stop, pos), path) in route.stops.iter_mut().zip(driving_pos).zip(paths) {
+                    stop.driving_pos = pos;
+                    stop.path_to_next_stop = path;
+                }
+                continue;
+            }
+
+            println!("{} is broken by map edits; removing its buses", id);
+            for bus in route.buses.drain(..) {
+                evict_buses.push(bus);
+                let b = self.buses.remove(&bus).unwrap();
+                evict_peds.extend(b.passengers.into_iter().map(|(ped, _)| ped));
+            }
+            let mut still_waiting = Vec::new();
+            for (ped, stop1, route_id, stop2) in self.peds_waiting.drain(..) {
+                if route_id == *id {
+                    evict_peds.push(ped);
+                } else {
+                    still_waiting.push((ped, stop1, route_id, stop2));
+                }
+            }
+            self.peds_waiting = still_waiting;
+        }
+        (evict_buses, evict_peds)
+    }
+
+    // The bus was removed from the simulation. Returns its passengers, who should be removed too.
+    pub fn bus_evicted(&mut self, bus: CarID) -> Vec<PedestrianID> {
+        let b = self.buses.remove(&bus).unwrap();
+        self.routes
+            .get_mut(&b.route)
+            .unwrap()
+            .buses
+            .retain(|id| *id != bus);
+        b.passengers.into_iter().map(|(ped, _)| ped).collect()
+    }
+
     pub fn collect_events(&mut self) -> Vec<Event> {
         self.events.drain(..).collect()
     }
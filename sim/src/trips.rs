@@ -141,12 +141,15 @@ impl TripManager {
 
         let start = parked_car.get_driving_pos(parking, map);
         let end = drive_to.goal_pos(map);
-        let path = if let Some(p) = map.pathfind(PathRequest {
-            start,
-            end,
-            can_use_bus_lanes: false,
-            can_use_bike_lanes: false,
-        }) {
+        let path = if let Some(p) = map.pathfind_at_time(
+            PathRequest {
+                start,
+                end,
+                can_use_bus_lanes: false,
+                can_use_bike_lanes: false,
+            },
+            now,
+        ) {
             p
         } else {
             println!(
@@ -193,12 +196,15 @@ impl TripManager {
         };
 
         let end = drive_to.goal_pos(map);
-        let path = if let Some(p) = map.pathfind(PathRequest {
-            start: driving_pos,
-            end,
-            can_use_bus_lanes: false,
-            can_use_bike_lanes: true,
-        }) {
+        let path = if let Some(p) = map.pathfind_at_time(
+            PathRequest {
+                start: driving_pos,
+                end,
+                can_use_bus_lanes: false,
+                can_use_bike_lanes: true,
+            },
+            now,
+        ) {
             p
         } else {
             println!(
@@ -277,7 +283,10 @@ impl TripManager {
         match trip.legs[0] {
             TripLeg::Walk(p, _, ref spot) => {
                 assert_eq!(p, ped);
-                assert_eq!(*spot, SidewalkSpot::bus_stop(stop, map));
+                assert_eq!(
+                    spot.connection,
+                    SidewalkSpot::bus_stop(stop, map).connection
+                );
             }
             _ => unreachable!(),
         }
@@ -372,6 +381,69 @@ impl TripManager {
         }
     }
 
+    // The agent was evicted from the simulation partway through its trip, usually by live map
+    // edits.
+    pub fn abort_trip_midway(&mut self, now: Duration, agent: AgentID) {
+        let trip = &mut self.trips[self.active_trip_mode.remove(&agent).unwrap().0];
+        println!(
+            "Aborting {} at {} because {} was evicted by map edits",
+            trip.id, now, agent
+        );
+        trip.aborted = true;
+        if trip.is_bus_trip() {
+            self.num_bus_trips -= 1;
+        } else {
+            self.unfinished_trips -= 1;
+        }
+    }
+
+    // A parked car vanished. Abort every trip that still needs it, returning any agent currently
+    // active on one of those trips, so the caller can remove it.
+    pub fn abort_trips_using_car(&mut self, now: Duration, car: CarID) -> Vec<AgentID> {
+        let mut agents = Vec::new();
+        for idx in 0..self.trips.len() {
+            let trip = &self.trips[idx];
+            if trip.finished_at.is_some() || trip.aborted {
+                continue;
+            }
+            let uses_car = trip.legs.iter().any(|leg| match leg {
+                TripLeg::Drive(ref vehicle, _) => vehicle.id == car,
+                _ => false,
+            });
+            if !uses_car {
+                continue;
+            }
+            let id = trip.id;
+            if let Some(agent) = self
+                .active_trip_mode
+                .iter()
+                .find(|(_, t)| **t == id)
+                .map(|(a, _)| *a)
+            {
+                self.abort_trip_midway(now, agent);
+                agents.push(agent);
+            } else {
+                println!(
+                    "Aborting {} at {} because {} was evicted by map edits",
+                    id, now, car
+                );
+                self.abort_trip_failed_start(id);
+            }
+        }
+        agents
+    }
+
+    // Live map edits can shorten sidewalks that later walking legs end on.
+    pub fn handle_live_edits(&mut self, map: &Map) {
+        for trip in self.trips.iter_mut() {
+            for leg in trip.legs.iter_mut() {
+                if let TripLeg::Walk(_, _, ref mut spot) = leg {
+                    spot.clamp_to_sidewalk(map);
+                }
+            }
+        }
+    }
+
     pub fn active_agents(&self) -> Vec<AgentID> {
         self.active_trip_mode.keys().cloned().collect()
     }
@@ -433,6 +505,10 @@ impl TripManager {
         self.unfinished_trips == 0
     }
 
+    pub fn trip_exists(&self, id: TripID) -> bool {
+        id.0 < self.trips.len()
+    }
+
     pub fn is_trip_done_or_aborted(&self, id: TripID) -> bool {
         let trip = &self.trips[id.0];
         trip.finished_at.is_some() || trip.aborted
@@ -443,15 +519,16 @@ impl TripManager {
     }
 
     pub fn trip_status(&self, id: TripID) -> Option<TripStatus> {
-        let trip = &self.trips[id.0];
+        let trip = self.trips.get(id.0)?;
         let start = trip.start.clone()?;
-        let end = match trip.legs.back() {
-            Some(TripLeg::Walk(_, _, ref spot)) => match spot.connection {
+        // Finished trips have no legs left
+        let end = match trip.legs.back()? {
+            TripLeg::Walk(_, _, ref spot) => match spot.connection {
                 SidewalkPOI::Building(b) => TripEnd::Bldg(b),
                 SidewalkPOI::Border(i) => TripEnd::Border(i),
                 _ => unreachable!(),
             },
-            Some(TripLeg::Drive(_, ref goal)) => match goal {
+            TripLeg::Drive(_, ref goal) => match goal {
                 DrivingGoal::ParkNear(b) => TripEnd::Bldg(*b),
                 DrivingGoal::Border(i, _) => TripEnd::Border(*i),
             },
@@ -527,7 +604,8 @@ impl Trip {
         match self.legs.pop_front() {
             Some(TripLeg::Walk(p, _, spot)) => {
                 assert_eq!(ped, p);
-                assert_eq!(goal, spot);
+                // Live map edits can shift where the spot is along the sidewalk
+                assert_eq!(goal.connection, spot.connection);
             }
             _ => unreachable!(),
         }
@@ -545,12 +623,33 @@ pub enum TripLeg {
 }
 
 // As of a moment in time, not necessarily the end of the simulation
+#[derive(Serialize, Deserialize)]
 pub struct FinishedTrips {
     pub unfinished_trips: usize,
     // (..., ..., time to complete trip)
     pub finished_trips: Vec<(TripID, TripMode, Duration)>,
 }
 
+impl FinishedTrips {
+    // Matches up trips that finished in both runs. Only makes sense when both runs come from the
+    // same scenario and RNG seed, so that TripIDs refer to the same thing.
+    // (..., ..., time in self, time in other)
+    pub fn pair_with(self, other: FinishedTrips) -> Vec<(TripID, TripMode, Duration, Duration)> {
+        let other_trips: BTreeMap<TripID, Duration> = other
+            .finished_trips
+            .into_iter()
+            .map(|(id, _, time)| (id, time))
+            .collect();
+        let mut pairs = Vec::new();
+        for (id, mode, time1) in self.finished_trips {
+            if let Some(time2) = other_trips.get(&id) {
+                pairs.push((id, mode, time1, *time2));
+            }
+        }
+        pairs
+    }
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, PartialOrd, Ord)]
 pub enum TripMode {
     Walk,
@@ -567,11 +666,13 @@ pub enum TripStart {
     Appearing(Position),
 }
 
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub enum TripEnd {
     Bldg(BuildingID),
     Border(IntersectionID),
 }
 
+#[derive(Serialize)]
 pub struct TripStatus {
     pub start: TripStart,
     pub end: TripEnd,
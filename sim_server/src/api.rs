@@ -0,0 +1,255 @@
+use abstutil::Timer;
+use geom::{Duration, Pt2D};
+use map_model::{Map, MapEdits};
+use rand_xorshift::XorShiftRng;
+use serde::de::DeserializeOwned;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sim::{
+    AgentID, CarID, GetDrawAgents, PedestrianID, Scenario, Sim, SimFlags, SpawnTrip, TripID,
+};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+pub enum RpcError {
+    UnknownMethod,
+    BadParams(String),
+    Failed(String),
+}
+
+pub struct Server {
+    flags: SimFlags,
+    map: Map,
+    sim: Sim,
+    rng: XorShiftRng,
+    // What reset re-instantiates. None when the server started from a savestate.
+    scenario: Option<Scenario>,
+}
+
+#[derive(Deserialize)]
+struct StepParams {
+    seconds: f64,
+}
+
+#[derive(Deserialize)]
+struct TripParams {
+    trip: TripID,
+}
+
+// Exactly one should be set.
+#[derive(Deserialize)]
+struct EditsParams {
+    edits_name: Option<String>,
+    edits: Option<MapEdits>,
+}
+
+#[derive(Deserialize)]
+struct SpawnParams {
+    trips: Vec<SpawnTrip>,
+}
+
+#[derive(Deserialize)]
+struct SavestateParams {
+    path: String,
+}
+
+#[derive(Serialize)]
+struct AgentInfo {
+    id: AgentID,
+    trip: Option<TripID>,
+    pos: Pt2D,
+}
+
+#[derive(Serialize)]
+struct SpawnedTrip {
+    ped: Option<PedestrianID>,
+    car: Option<CarID>,
+}
+
+impl Server {
+    pub fn new(flags: SimFlags, num_agents: Option<usize>, timer: &mut Timer) -> Server {
+        let (map, mut sim, mut rng) = flags.load(timer);
+
+        // TODO not the ideal way to distinguish what thing we loaded
+        let scenario = if flags.load.starts_with(Path::new("../data/scenarios/")) {
+            let s: Scenario = abstutil::read_binary(flags.load.to_str().unwrap(), timer)
+                .expect("loading scenario failed");
+            Some(s)
+        } else if flags.load.starts_with(Path::new("../data/raw_maps/"))
+            || flags.load.starts_with(Path::new("../data/maps/"))
+        {
+            let s = if let Some(n) = num_agents {
+                Scenario::scaled_run(&map, n)
+            } else {
+                Scenario::small_run(&map)
+            };
+            s.instantiate(&mut sim, &map, &mut rng, timer);
+            Some(s)
+        } else {
+            None
+        };
+
+        Server {
+            flags,
+            map,
+            sim,
+            rng,
+            scenario,
+        }
+    }
+
+    // Methods:
+    // - status: the current time, and whether all trips are done
+    // - step {seconds}: run the simulation forwards
+    // - run_until_done: run until all trips finish
+    // - agents: every agent currently on a trip, with its position
+    // - trip_status {trip}: where a trip starts and ends, and the agent currently doing it
+    // - finished_trips: same as FinishedTrips
+    // - apply_edits {edits_name or edits}: change the map without restarting the simulation
+    // - reset: reload the map and restart the scenario with the original RNG seed
+    // - spawn_trips {trips}: add SpawnTrips, departing no earlier than the current time
+    // - save_savestate: returns the path
+    // - load_savestate {path}
+    pub fn handle(&mut self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "status" => Ok(self.status()),
+            "step" => {
+                let p: StepParams = parse(params)?;
+                if p.seconds <= 0.0 {
+                    return Err(RpcError::BadParams("seconds must be positive".to_string()));
+                }
+                self.sim.step(&mut self.map, Duration::seconds(p.seconds));
+                Ok(self.status())
+            }
+            "run_until_done" => {
+                self.sim.just_run_until_done(&mut self.map, None);
+                Ok(self.status())
+            }
+            "agents" => Ok(to_value(self.agents())),
+            "trip_status" => {
+                let p: TripParams = parse(params)?;
+                if !self.sim.trip_exists(p.trip) {
+                    return Err(RpcError::BadParams(format!("{} doesn't exist", p.trip)));
+                }
+                Ok(json!({
+                    "status": self.sim.trip_status(p.trip),
+                    "agent": self.sim.trip_to_agent(p.trip),
+                }))
+            }
+            "finished_trips" => Ok(to_value(self.sim.get_finished_trips())),
+            "apply_edits" => {
+                let p: EditsParams = parse(params)?;
+                let edits = match (p.edits_name, p.edits) {
+                    (Some(name), None) => MapEdits::load(self.map.get_name(), &name),
+                    (None, Some(edits)) => edits,
+                    _ => {
+                        return Err(RpcError::BadParams(
+                            "Pass exactly one of edits_name and edits".to_string(),
+                        ));
+                    }
+                };
+                let mut timer = Timer::new("apply edits");
+                self.map.apply_edits(edits, &mut timer);
+                self.map.recalculate_pathfinding_after_edits(&mut timer);
+                self.sim.handle_live_edits(&self.map, &mut timer);
+                Ok(self.status())
+            }
+            "reset" => {
+                self.reset(&mut Timer::new("reset"))?;
+                Ok(self.status())
+            }
+            "spawn_trips" => {
+                let p: SpawnParams = parse(params)?;
+                let now = self.sim.time();
+                if let Some(t) = p.trips.iter().find(|t| t.depart() < now) {
+                    return Err(RpcError::BadParams(format!(
+                        "A trip departs at {}, but it's already {}",
+                        t.depart(),
+                        now
+                    )));
+                }
+                let mut spawned = Vec::new();
+                for t in p.trips {
+                    let (ped, car) = t.schedule(&mut self.sim, &self.map, &mut self.rng);
+                    spawned.push(SpawnedTrip { ped, car });
+                }
+                self.sim
+                    .spawn_all_trips(&self.map, &mut Timer::new("spawn trips"), true);
+                Ok(to_value(spawned))
+            }
+            "save_savestate" => Ok(json!({ "path": self.sim.save() })),
+            "load_savestate" => {
+                let p: SavestateParams = parse(params)?;
+                if !Path::new(&p.path).exists() {
+                    return Err(RpcError::BadParams(format!("{} doesn't exist", p.path)));
+                }
+                let mut flags = self.flags.clone();
+                flags.load = PathBuf::from(p.path);
+                // This also loads the map and the edits the savestate was made with.
+                let (map, sim, _) = flags.load(&mut Timer::new("load savestate"));
+                self.map = map;
+                self.sim = sim;
+                Ok(self.status())
+            }
+            _ => Err(RpcError::UnknownMethod),
+        }
+    }
+
+    fn status(&self) -> Value {
+        json!({
+            "time": self.sim.time(),
+            "is_done": self.sim.is_done(),
+            "summary": self.sim.summary(),
+        })
+    }
+
+    fn agents(&self) -> Vec<AgentInfo> {
+        let active: HashSet<AgentID> = self.sim.active_agents().into_iter().collect();
+        let cars = self
+            .sim
+            .get_all_draw_cars(&self.map)
+            .into_iter()
+            .map(|c| (AgentID::Car(c.id), c.body.last_pt()));
+        let peds = self
+            .sim
+            .get_all_draw_peds(&self.map)
+            .into_iter()
+            .map(|p| (AgentID::Pedestrian(p.id), p.pos));
+        cars.chain(peds)
+            .filter(|(id, _)| active.contains(id))
+            .map(|(id, pos)| AgentInfo {
+                id,
+                trip: self.sim.agent_to_trip(id),
+                pos,
+            })
+            .collect()
+    }
+
+    // Starts over from the files, since a crash might have left the map half-edited.
+    fn reset(&mut self, timer: &mut Timer) -> Result<(), RpcError> {
+        let scenario = if let Some(ref s) = self.scenario {
+            s
+        } else {
+            return Err(RpcError::Failed(
+                "Started from a savestate, so there's no scenario to restart".to_string(),
+            ));
+        };
+        let (map, mut sim, mut rng) = self.flags.load(timer);
+        // Loading a scenario already instantiates it
+        if !self.flags.load.starts_with(Path::new("../data/scenarios/")) {
+            scenario.instantiate(&mut sim, &map, &mut rng, timer);
+        }
+        self.map = map;
+        self.sim = sim;
+        self.rng = rng;
+        Ok(())
+    }
+}
+
+fn parse<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|err| RpcError::BadParams(err.to_string()))
+}
+
+fn to_value<T: serde::Serialize>(x: T) -> Value {
+    serde_json::to_value(x).unwrap()
+}
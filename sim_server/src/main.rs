@@ -0,0 +1,153 @@
+mod api;
+
+use abstutil::Timer;
+use serde_json::{json, Value};
+use sim::SimFlags;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "sim_server")]
+pub struct Flags {
+    #[structopt(flatten)]
+    pub sim_flags: SimFlags,
+
+    /// Number of agents to generate when loading a map instead of a scenario. If unspecified,
+    /// trips to/from borders will be included.
+    #[structopt(long = "num_agents")]
+    pub num_agents: Option<usize>,
+
+    /// Only listens on localhost
+    #[structopt(long = "port", default_value = "8765")]
+    pub port: u16,
+}
+
+// Speaks JSON-RPC 2.0 over HTTP. POST a request like
+// {"jsonrpc": "2.0", "id": 1, "method": "step", "params": {"seconds": 60}} to any path. See
+// api.rs for the methods. Requests are handled one at a time, in order.
+fn main() {
+    let flags = Flags::from_args();
+    let mut timer = Timer::new("setup sim_server");
+    let mut server = api::Server::new(flags.sim_flags.clone(), flags.num_agents, &mut timer);
+    timer.done();
+
+    let listener = TcpListener::bind(("127.0.0.1", flags.port)).unwrap();
+    println!("Listening on http://127.0.0.1:{}", flags.port);
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(err) = handle_connection(stream, &mut server) {
+                    println!("Connection broke: {}", err);
+                }
+            }
+            Err(err) => println!("Couldn't accept connection: {}", err),
+        }
+    }
+}
+
+fn handle_connection(stream: TcpStream, server: &mut api::Server) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    // Keep-alive, so scripts stepping in a tight loop don't pay for a new connection every time.
+    loop {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line)? == 0 {
+            return Ok(());
+        }
+        // None if the header is malformed
+        let mut content_length = Some(0);
+        let mut close = false;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header)?;
+            let header = header.trim();
+            if header.is_empty() {
+                break;
+            }
+            let lower = header.to_lowercase();
+            if lower.starts_with("content-length:") {
+                content_length = lower["content-length:".len()..].trim().parse().ok();
+            } else if lower == "connection: close" {
+                close = true;
+            }
+        }
+        let content_length = if let Some(n) = content_length {
+            n
+        } else {
+            // There's no telling where the body ends, so give up on the connection.
+            let response =
+                error_response(Value::Null, -32600, "Malformed Content-Length".to_string());
+            return write_response(&mut writer, "400 Bad Request", &response);
+        };
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+
+        let (status, response) = if request_line.starts_with("POST ") {
+            ("200 OK", handle_request(&body, server))
+        } else {
+            (
+                "405 Method Not Allowed",
+                error_response(Value::Null, -32600, "Only POST is supported".to_string()),
+            )
+        };
+        write_response(&mut writer, status, &response)?;
+        if close {
+            return Ok(());
+        }
+    }
+}
+
+fn write_response(writer: &mut TcpStream, status: &str, response: &Value) -> std::io::Result<()> {
+    let response = response.to_string();
+    write!(
+        writer,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        status,
+        response.len(),
+        response
+    )?;
+    writer.flush()
+}
+
+fn handle_request(body: &[u8], server: &mut api::Server) -> Value {
+    let request: Value = match serde_json::from_slice(body) {
+        Ok(r) => r,
+        Err(err) => {
+            return error_response(Value::Null, -32700, format!("Parse error: {}", err));
+        }
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = if let Some(m) = request.get("method").and_then(|m| m.as_str()) {
+        m.to_string()
+    } else {
+        return error_response(id, -32600, "Missing method".to_string());
+    };
+    let params = request.get("params").cloned().unwrap_or_else(|| json!({}));
+
+    // The simulation panics on internal errors. Report them to the client, who can reset.
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        server.handle(&method, params)
+    }));
+    match result {
+        Ok(Ok(result)) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+        Ok(Err(api::RpcError::UnknownMethod)) => {
+            error_response(id, -32601, format!("Unknown method {}", method))
+        }
+        Ok(Err(api::RpcError::BadParams(msg))) => error_response(id, -32602, msg),
+        Ok(Err(api::RpcError::Failed(msg))) => error_response(id, -32000, msg),
+        Err(_) => error_response(
+            id,
+            -32001,
+            format!(
+                "The simulation crashed during {}; reset or load a savestate",
+                method
+            ),
+        ),
+    }
+}
+
+fn error_response(id: Value, code: i64, message: String) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+}
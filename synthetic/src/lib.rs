@@ -221,6 +221,8 @@ impl Model {
                     IntersectionType::TrafficSignal => Color::GREEN,
                     IntersectionType::StopSign => Color::RED,
                     IntersectionType::Border => Color::BLUE,
+                    IntersectionType::Uncontrolled => Color::YELLOW,
+                    IntersectionType::Roundabout => Color::ORANGE,
                 }
             };
             g.draw_circle(color, &i.circle());
@@ -324,6 +326,10 @@ impl Model {
             if let Some(ref label) = r.back_label {
                 osm_tags.insert("back_label".to_string(), label.to_string());
             }
+            let orig_id = raw_data::OriginalRoad {
+                pt1: gps(self.intersections[&r.i1].center),
+                pt2: gps(self.intersections[&r.i2].center),
+            };
             map.roads.insert(
                 *id,
                 raw_data::Road {
@@ -333,10 +339,8 @@ impl Model {
                         self.intersections[&r.i1].center,
                         self.intersections[&r.i2].center,
                     ],
-                    orig_id: raw_data::OriginalRoad {
-                        pt1: gps(self.intersections[&r.i1].center),
-                        pt2: gps(self.intersections[&r.i2].center),
-                    },
+                    orig_id,
+                    segment_id: orig_id,
                     osm_tags,
                     osm_way_id: id.0 as i64,
                     parking_lane_fwd: r.lanes.fwd.contains(&LaneType::Parking),
@@ -495,7 +499,9 @@ impl Model {
         let i = self.intersections.get_mut(&id).unwrap();
         i.intersection_type = match i.intersection_type {
             IntersectionType::StopSign => IntersectionType::TrafficSignal,
-            IntersectionType::TrafficSignal => {
+            IntersectionType::TrafficSignal => IntersectionType::Uncontrolled,
+            IntersectionType::Uncontrolled => IntersectionType::Roundabout,
+            IntersectionType::Roundabout => {
                 let num_roads = self
                     .roads
                     .values()
@@ -1,5 +1,6 @@
 use crate::runner::TestRunner;
-use geom::{Duration, Line, PolyLine, Pt2D};
+use geom::{Duration, GPSBounds, Line, LonLat, PolyLine, Pt2D};
+use kml::StatePlane;
 use std::str::FromStr;
 
 #[allow(clippy::unreadable_literal)]
@@ -55,6 +56,107 @@ pub fn run(t: &mut TestRunner) {
             Ok(Duration::seconds(3723.5))
         );
     });
+
+    t.run_fast("read_shapefile", |_| {
+        let (x, y) = (-122.3, 47.6);
+        // Clockwise outer ring with a counter-clockwise hole
+        let outer = vec![
+            (0.0, 0.0),
+            (0.0, 0.01),
+            (0.01, 0.01),
+            (0.01, 0.0),
+            (0.0, 0.0),
+        ];
+        let hole = vec![
+            (0.002, 0.002),
+            (0.008, 0.002),
+            (0.008, 0.008),
+            (0.002, 0.008),
+            (0.002, 0.002),
+        ];
+        let mut polygon = Vec::new();
+        polygon.extend_from_slice(&5_i32.to_le_bytes());
+        polygon.extend_from_slice(&[0; 32]);
+        polygon.extend_from_slice(&2_i32.to_le_bytes());
+        polygon.extend_from_slice(&10_i32.to_le_bytes());
+        polygon.extend_from_slice(&0_i32.to_le_bytes());
+        polygon.extend_from_slice(&5_i32.to_le_bytes());
+        for (dx, dy) in outer.into_iter().chain(hole.into_iter()) {
+            polygon.extend_from_slice(&(x + dx).to_le_bytes());
+            polygon.extend_from_slice(&(y + dy).to_le_bytes());
+        }
+        let mut point = Vec::new();
+        point.extend_from_slice(&1_i32.to_le_bytes());
+        point.extend_from_slice(&x.to_le_bytes());
+        point.extend_from_slice(&y.to_le_bytes());
+
+        let mut shp = vec![0; 100];
+        shp[0..4].copy_from_slice(&9994_i32.to_be_bytes());
+        for (idx, content) in vec![polygon, point].into_iter().enumerate() {
+            shp.extend_from_slice(&(idx as i32 + 1).to_be_bytes());
+            shp.extend_from_slice(&(content.len() as i32 / 2).to_be_bytes());
+            shp.extend_from_slice(&content);
+        }
+        std::fs::write("read_shapefile.shp", &shp).unwrap();
+
+        // One character field called "name"
+        let mut dbf = vec![0; 32];
+        dbf[0] = 3;
+        dbf[4..8].copy_from_slice(&2_u32.to_le_bytes());
+        dbf[8..10].copy_from_slice(&65_u16.to_le_bytes());
+        dbf[10..12].copy_from_slice(&6_u16.to_le_bytes());
+        let mut field = vec![0; 32];
+        field[0..4].copy_from_slice(b"name");
+        field[11] = b'C';
+        field[16] = 5;
+        dbf.extend_from_slice(&field);
+        dbf.push(0x0D);
+        dbf.extend_from_slice(b" park ");
+        dbf.extend_from_slice(b" tree ");
+        std::fs::write("read_shapefile.dbf", &dbf).unwrap();
+
+        let mut gps_bounds = GPSBounds::new();
+        gps_bounds.update(LonLat::new(x - 1.0, y - 1.0));
+        gps_bounds.update(LonLat::new(x + 1.0, y + 1.0));
+        let shapes = kml::load_any(
+            "read_shapefile.shp",
+            &gps_bounds,
+            None,
+            &mut abstutil::Timer::throwaway(),
+        )
+        .unwrap()
+        .shapes;
+        // The hole is dropped, like for GeoJSON
+        assert_eq!(shapes.len(), 2);
+        assert_eq!(shapes[0].points.len(), 5);
+        assert_eq!(shapes[0].attributes["name"], "park");
+        assert_eq!(shapes[1].points, vec![LonLat::new(x, y)]);
+        assert_eq!(shapes[1].attributes["name"], "tree");
+
+        // A negative record length
+        shp[104..108].copy_from_slice(&(-1_i32).to_be_bytes());
+        std::fs::write("read_shapefile.shp", &shp).unwrap();
+        assert!(kml::load_any(
+            "read_shapefile.shp",
+            &gps_bounds,
+            None,
+            &mut abstutil::Timer::throwaway()
+        )
+        .is_err());
+    });
+
+    t.run_fast("state_plane_origin", |_| {
+        // The false easting at the latitude of origin should land exactly on the origin, in both
+        // the meter and foot variants of the zone.
+        let meters = StatePlane::from_epsg(32148).unwrap().to_gps(500_000.0, 0.0);
+        let feet = StatePlane::from_name("urn:ogc:def:crs:EPSG::2926")
+            .unwrap()
+            .to_gps(500_000.0 * 3937.0 / 1200.0, 0.0);
+        for pt in vec![meters, feet] {
+            assert!((pt.longitude - -120.833_333_333).abs() < 1e-6);
+            assert!((pt.latitude - 47.0).abs() < 1e-6);
+        }
+    });
 }
 
 // TODO test that shifting lines and polylines is a reversible operation
@@ -2,6 +2,7 @@ mod geom;
 mod map_conversion;
 mod parking;
 mod runner;
+mod scenarios;
 mod sim_completion;
 mod sim_determinism;
 mod transit;
@@ -15,6 +16,7 @@ fn main() {
     geom::run(t.suite("geom"));
     map_conversion::run(t.suite("map_conversion"));
     parking::run(t.suite("parking"));
+    scenarios::run(t.suite("scenarios"));
     sim_completion::run(t.suite("sim_completion"));
     sim_determinism::run(t.suite("sim_determinism"));
     transit::run(t.suite("transit"));
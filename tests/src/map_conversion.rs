@@ -1,12 +1,16 @@
 use crate::runner::TestRunner;
 use abstutil;
 use convert_osm;
+use geom::{Distance, Duration, Speed};
 use map_model;
+use std::collections::BTreeMap;
 
 pub fn run(t: &mut TestRunner) {
     t.run_slow("convert_osm_twice", |_| {
         let flags = convert_osm::Flags {
             osm: "../data/input/montlake.osm".to_string(),
+            osc: String::new(),
+            osm_cache: String::new(),
             parking_shapes: "../data/shapes/blockface.bin".to_string(),
             offstreet_parking: "../data/input/offstreet_parking.kml".to_string(),
             gtfs: "../data/input/google_transit_2018_18_08".to_string(),
@@ -14,6 +18,7 @@ pub fn run(t: &mut TestRunner) {
             clip: abstutil::path_polygon("montlake"),
             output: "convert_osm_twice.bin".to_string(),
             fast_dev: false,
+            footways_and_alleys: false,
         };
 
         let map1 = convert_osm::convert(&flags, &mut abstutil::Timer::throwaway());
@@ -27,6 +32,91 @@ pub fn run(t: &mut TestRunner) {
         }
     });
 
+    t.run_slow("read_pbf_matches_xml", |_| {
+        use convert_osm::reader;
+        let boundary = convert_osm::read_osmosis_polygon("../data/polygons/montlake.poly");
+        let mut timer = abstutil::Timer::throwaway();
+        // import.sh makes the PBF from the XML, which osmosis clipped with completeWays
+        let xml = reader::read("../data/input/montlake.osm", &boundary, &mut timer);
+        let pbf = reader::read("../data/input/montlake.osm.pbf", &boundary, &mut timer);
+
+        assert!(xml.ways.keys().eq(pbf.ways.keys()));
+        assert!(xml.nodes.keys().eq(pbf.nodes.keys()));
+        for (id, way) in &xml.ways {
+            assert_eq!(way.nodes, pbf.ways[id].nodes);
+            assert_eq!(way.tags, pbf.ways[id].tags);
+        }
+    });
+
+    t.run_fast("apply_osc_clips", |_| {
+        use convert_osm::reader::{apply_osc, Document, Node, Way};
+        let boundary = convert_osm::read_osmosis_polygon("../data/polygons/montlake.poly");
+        let inside = boundary
+            .boundary_polygon
+            .center()
+            .to_gps(&boundary.gps_bounds)
+            .unwrap();
+        let node = |pt| Node {
+            pt,
+            tags: BTreeMap::new(),
+        };
+        let way = |nodes| Way {
+            nodes,
+            tags: BTreeMap::new(),
+        };
+
+        let mut doc = Document {
+            nodes: BTreeMap::new(),
+            ways: BTreeMap::new(),
+            relations: BTreeMap::new(),
+        };
+        doc.nodes.insert(1, node(inside));
+        doc.nodes.insert(2, node(geom::LonLat::new(0.0, 0.0)));
+        doc.ways.insert(10, way(vec![1, 2]));
+
+        let (lon, lat) = (inside.longitude, inside.latitude);
+        let path = format!("{}/apply_osc_clips.osc", std::env::temp_dir().display());
+        std::fs::write(
+            &path,
+            format!(
+                r#"<osmChange version="0.6">
+  <create>
+    <node id="3" lon="{}" lat="{}"/>
+    <node id="4" lon="1.0" lat="1.0"/>
+    <node id="5" lon="2.0" lat="2.0"/>
+    <way id="11"><nd ref="3"/><nd ref="4"/><tag k="highway" v="residential"/></way>
+    <way id="12"><nd ref="5"/></way>
+    <relation id="20"><member type="way" ref="12" role=""/></relation>
+    <relation id="21"><member type="way" ref="11" role="from"/></relation>
+  </create>
+  <modify>
+    <way id="10"><nd ref="1"/><nd ref="99"/></way>
+  </modify>
+  <delete>
+    <node id="2"/>
+  </delete>
+</osmChange>"#,
+                lon, lat
+            ),
+        )
+        .unwrap();
+        apply_osc(
+            &mut doc,
+            &path,
+            &boundary,
+            &mut abstutil::Timer::throwaway(),
+        );
+        std::fs::remove_file(&path).unwrap();
+
+        // Way 11 touches the boundary, so it keeps its outside node. Way 12 doesn't.
+        assert_eq!(doc.nodes.keys().cloned().collect::<Vec<_>>(), vec![1, 3, 4]);
+        assert_eq!(doc.ways.keys().cloned().collect::<Vec<_>>(), vec![10, 11]);
+        assert_eq!(doc.ways[&11].tags["highway"], "residential");
+        // Node 99 was never in the doc, but the way is still updated, with a warning.
+        assert_eq!(doc.ways[&10].nodes, vec![1, 99]);
+        assert_eq!(doc.relations.keys().cloned().collect::<Vec<_>>(), vec![21]);
+    });
+
     t.run_slow("raw_to_map_twice", |_| {
         let map1 = map_model::Map::new(
             &abstutil::path_raw_map("montlake"),
@@ -47,6 +137,76 @@ pub fn run(t: &mut TestRunner) {
         }
     });
 
+    t.run_slow("migrate_edits_on_split_way", |_| {
+        use map_model::{raw_data, LaneType, Map, MapEdits, MapMigration, RoadID};
+        let mut timer = abstutil::Timer::throwaway();
+        let load_raw = |timer: &mut abstutil::Timer| -> raw_data::Map {
+            abstutil::read_binary(&abstutil::path_raw_map("montlake"), timer).unwrap()
+        };
+        let old = Map::create_from_raw("montlake".to_string(), load_raw(&mut timer), &mut timer);
+        // A re-import can number everything differently
+        let mut raw = load_raw(&mut timer);
+        raw.roads = std::mem::replace(&mut raw.roads, BTreeMap::new())
+            .into_iter()
+            .rev()
+            .enumerate()
+            .map(|(idx, (_, r))| (raw_data::StableRoadID(idx), r))
+            .collect();
+        let new = Map::create_from_raw("montlake".to_string(), raw, &mut timer);
+
+        let mut roads_per_way: BTreeMap<i64, Vec<RoadID>> = BTreeMap::new();
+        for r in old.all_roads() {
+            roads_per_way
+                .entry(r.osm_way_id)
+                .or_insert_with(Vec::new)
+                .push(r.id);
+        }
+        let target = roads_per_way
+            .values()
+            .filter(|roads| roads.len() >= 3)
+            .map(|roads| roads[1])
+            .find(|r| {
+                let orig = old.get_r(*r).segment_id;
+                old.all_roads()
+                    .iter()
+                    .filter(|x| x.segment_id == orig)
+                    .count()
+                    == 1
+            })
+            .expect("no OSM way was split into 3 roads");
+        let lane = old.get_r(target).all_lanes()[0];
+
+        let mut edits = MapEdits::new("montlake".to_string());
+        edits
+            .speed_limit_overrides
+            .insert(target, Speed::miles_per_hour(15.0));
+        edits.lane_overrides.insert(lane, LaneType::Bus);
+        let migrated = edits.migrate(&mut MapMigration::new(&old, &new));
+
+        let new_r = *migrated.speed_limit_overrides.keys().next().unwrap();
+        assert_eq!(new.get_r(new_r).segment_id, old.get_r(target).segment_id);
+        assert_eq!(new.get_r(new_r).center_pts, old.get_r(target).center_pts);
+        let new_l = *migrated.lane_overrides.keys().next().unwrap();
+        assert_eq!(new.get_l(new_l).parent, new_r);
+    });
+
+    t.run_slow("export_geojson", |_| {
+        let map = map_model::Map::new(
+            &abstutil::path_raw_map("montlake"),
+            &mut abstutil::Timer::throwaway(),
+        )
+        .unwrap();
+        let geojson = map.to_geojson(&map_model::FeatureProperties::default());
+        assert_eq!(
+            geojson["features"].as_array().unwrap().len(),
+            map.all_roads().len()
+                + map.all_lanes().len()
+                + map.all_intersections().len()
+                + map.all_buildings().len()
+                + map.all_bus_stops().len()
+        );
+    });
+
     t.run_slow("bigger_map_loads", |_| {
         map_model::Map::new(
             &abstutil::path_raw_map("23rd"),
@@ -62,4 +222,903 @@ pub fn run(t: &mut TestRunner) {
         )
         .expect("huge_seattle broke");
     });
+
+    t.run_slow("add_and_remove_lanes", |_| {
+        use map_model::LaneType;
+        let mut map = map_model::Map::new(
+            &abstutil::path_raw_map("montlake"),
+            &mut abstutil::Timer::throwaway(),
+        )
+        .unwrap();
+        let mut timer = abstutil::Timer::throwaway();
+
+        // A road diet: a bus lane instead of a driving lane, and a new protected bike lane
+        let r = map
+            .all_roads()
+            .iter()
+            .find(|r| {
+                let (fwd, _) = r.get_lane_types();
+                fwd.iter().filter(|lt| **lt == LaneType::Driving).count() >= 2
+                    && fwd.contains(&LaneType::Sidewalk)
+                    && r.all_bus_stops(&map).is_empty()
+            })
+            .expect("no road with two driving lanes")
+            .id;
+        let orig_lanes = map.get_r(r).all_lanes();
+        let num_lanes = map.all_lanes().len();
+
+        let mut cs = map.get_r(r).get_cross_section(&map);
+        cs.fwd.remove(0);
+        cs.fwd.insert(0, (LaneType::Bus, Distance::meters(3.25)));
+        let sidewalk = cs
+            .fwd
+            .iter()
+            .position(|(lt, _)| *lt == LaneType::Sidewalk)
+            .unwrap();
+        cs.fwd
+            .insert(sidewalk, (LaneType::Biking, Distance::meters(2.75)));
+        let mut edits = map.get_edits().clone();
+        edits.cross_section_overrides.insert(r, cs.clone());
+        map.apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+        assert_eq!(map.get_r(r).get_cross_section(&map), cs);
+        assert_eq!(map.all_lanes().len(), num_lanes + 1);
+        for l in map.get_r(r).all_lanes() {
+            let lane = map.get_l(l);
+            assert!(map.lane_exists(l));
+            assert!(map.get_i(lane.src_i).outgoing_lanes.contains(&l));
+            assert!(map.get_i(lane.dst_i).incoming_lanes.contains(&l));
+        }
+
+        // Reverting gets the original lanes back, and the extra one isn't part of the road
+        let mut edits = map.get_edits().clone();
+        edits.cross_section_overrides.clear();
+        map.apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+        assert_eq!(map.get_r(r).all_lanes(), orig_lanes);
+        assert!(!map.lane_exists(map_model::LaneID(num_lanes)));
+    });
+
+    t.run_slow("added_lane_ids_dont_depend_on_edit_order", |_| {
+        use map_model::{LaneID, LaneType, Map, RoadID};
+        let load = || {
+            map_model::Map::new(
+                &abstutil::path_raw_map("montlake"),
+                &mut abstutil::Timer::throwaway(),
+            )
+            .unwrap()
+        };
+        let mut map = load();
+        let mut timer = abstutil::Timer::throwaway();
+
+        let roads: Vec<RoadID> = map
+            .all_roads()
+            .iter()
+            .filter(|r| {
+                let (fwd, _) = r.get_lane_types();
+                fwd.contains(&LaneType::Driving) && r.all_bus_stops(&map).is_empty()
+            })
+            .map(|r| r.id)
+            .take(2)
+            .collect();
+        let (r1, r2) = (roads[0], roads[1]);
+        let num_lanes = map.all_lanes().len();
+        let with_extra_lane = |map: &Map, r: RoadID| {
+            let mut cs = map.get_r(r).get_cross_section(map);
+            cs.fwd.insert(0, (LaneType::Driving, Distance::meters(3.0)));
+            cs
+        };
+        let added_lane = |map: &Map, r: RoadID| -> LaneID {
+            map.get_r(r)
+                .all_lanes()
+                .into_iter()
+                .find(|l| l.0 >= num_lanes)
+                .unwrap()
+        };
+
+        // Widen the later road first, and turn its new lane into a bike lane
+        let mut edits = map.get_edits().clone();
+        edits
+            .cross_section_overrides
+            .insert(r2, with_extra_lane(&map, r2));
+        map.apply_edits(edits, &mut timer);
+        let mut edits = map.get_edits().clone();
+        edits
+            .lane_overrides
+            .insert(added_lane(&map, r2), LaneType::Biking);
+        map.apply_edits(edits, &mut timer);
+
+        // Then the earlier road, like the editor does it
+        let before = map.get_edits().clone();
+        let mut edits = before.clone();
+        edits
+            .cross_section_overrides
+            .insert(r1, with_extra_lane(&map, r1));
+        map.renumber_added_lanes(&before, &mut edits);
+        map.apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+        assert_eq!(added_lane(&map, r1), LaneID(num_lanes));
+        assert_eq!(added_lane(&map, r2), LaneID(num_lanes + 1));
+        assert_eq!(map.get_l(added_lane(&map, r2)).lane_type, LaneType::Biking);
+
+        // Loading the same edits from scratch gives the same lanes
+        let mut fresh = load();
+        fresh.apply_edits(map.get_edits().clone(), &mut timer);
+        fresh.recalculate_pathfinding_after_edits(&mut timer);
+        for r in vec![r1, r2] {
+            assert_eq!(
+                fresh.get_r(r).children_forwards,
+                map.get_r(r).children_forwards
+            );
+            assert_eq!(
+                fresh.get_r(r).children_backwards,
+                map.get_r(r).children_backwards
+            );
+        }
+    });
+
+    t.run_slow("merge_edits_that_both_add_lanes", |_| {
+        use map_model::{raw_data, LaneType, Map, MapEdits, MapMigration, RoadID};
+        let mut timer = abstutil::Timer::throwaway();
+        let load_raw = |timer: &mut abstutil::Timer| -> raw_data::Map {
+            abstutil::read_binary(&abstutil::path_raw_map("montlake"), timer).unwrap()
+        };
+        let mut map =
+            Map::create_from_raw("montlake".to_string(), load_raw(&mut timer), &mut timer);
+        let roads: Vec<RoadID> = map
+            .all_roads()
+            .iter()
+            .filter(|r| {
+                let (fwd, _) = r.get_lane_types();
+                fwd.contains(&LaneType::Driving) && r.all_bus_stops(&map).is_empty()
+            })
+            .map(|r| r.id)
+            .take(2)
+            .collect();
+        let (r1, r2) = (roads[0], roads[1]);
+        let num_lanes = map.all_lanes().len();
+        // Each side adds a lane to a different road, which gets the first free ID, then edits it.
+        let add_lane = |r: RoadID, lt: LaneType| {
+            let mut edits = MapEdits::new("montlake".to_string());
+            let mut cs = map.get_r(r).get_cross_section(&map);
+            cs.fwd.insert(0, (LaneType::Driving, Distance::meters(3.0)));
+            edits.cross_section_overrides.insert(r, cs);
+            edits
+                .lane_overrides
+                .insert(map_model::LaneID(num_lanes), lt);
+            edits
+        };
+        let base = MapEdits::new("montlake".to_string());
+        let ours = add_lane(r2, LaneType::Biking);
+        let theirs = add_lane(r1, LaneType::Bus);
+
+        let (merged, conflicts) = MapEdits::merge(&base, &ours, &theirs, &map);
+        assert!(conflicts.is_empty());
+        assert_eq!(merged.lane_overrides.len(), 2);
+        let diff = ours.diff(&merged, &map);
+        assert!(diff.removed.is_empty());
+        assert!(diff.changed.is_empty());
+        assert_eq!(diff.added.len(), 2);
+
+        map.apply_edits(merged.clone(), &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+        let added_lane_type = |map: &Map, r: RoadID| {
+            let l = map
+                .get_r(r)
+                .all_lanes()
+                .into_iter()
+                .find(|l| l.0 >= num_lanes)
+                .unwrap();
+            map.get_l(l).lane_type
+        };
+        assert_eq!(added_lane_type(&map, r1), LaneType::Bus);
+        assert_eq!(added_lane_type(&map, r2), LaneType::Biking);
+
+        // A re-import that numbers the roads the other way around numbers the added lanes the
+        // other way around too.
+        let old = Map::create_from_raw("montlake".to_string(), load_raw(&mut timer), &mut timer);
+        let mut raw = load_raw(&mut timer);
+        raw.roads = std::mem::replace(&mut raw.roads, BTreeMap::new())
+            .into_iter()
+            .rev()
+            .enumerate()
+            .map(|(idx, (_, r))| (raw_data::StableRoadID(idx), r))
+            .collect();
+        let mut new = Map::create_from_raw("montlake".to_string(), raw, &mut timer);
+        let mut migration = MapMigration::new(&old, &new);
+        let migrated = merged.migrate(&mut migration);
+        assert!(migration.problems.is_empty(), "{:?}", migration.problems);
+        let new_road = |r: RoadID| {
+            new.all_roads()
+                .iter()
+                .find(|x| x.segment_id == old.get_r(r).segment_id)
+                .unwrap()
+                .id
+        };
+        let (new_r1, new_r2) = (new_road(r1), new_road(r2));
+        new.apply_edits(migrated, &mut timer);
+        new.recalculate_pathfinding_after_edits(&mut timer);
+        assert_eq!(added_lane_type(&new, new_r1), LaneType::Bus);
+        assert_eq!(added_lane_type(&new, new_r2), LaneType::Biking);
+    });
+
+    t.run_slow("widen_lanes", |_| {
+        use map_model::{IntersectionType, LaneType};
+        let mut map = map_model::Map::new(
+            &abstutil::path_raw_map("montlake"),
+            &mut abstutil::Timer::throwaway(),
+        )
+        .unwrap();
+        let mut timer = abstutil::Timer::throwaway();
+
+        let r = map
+            .all_roads()
+            .iter()
+            .find(|r| {
+                let (fwd, _) = r.get_lane_types();
+                fwd.contains(&LaneType::Driving)
+                    && map.get_i(r.dst_i).intersection_type != IntersectionType::Border
+            })
+            .expect("no road with a driving lane")
+            .id;
+        let l = map
+            .get_r(r)
+            .children_forwards
+            .iter()
+            .find(|(_, lt)| *lt == LaneType::Driving)
+            .unwrap()
+            .0;
+        let old_width = map.get_r(r).fwd_width;
+
+        let mut edits = map.get_edits().clone();
+        edits
+            .lane_width_overrides
+            .insert(l, map.get_l(l).width + Distance::meters(3.0));
+        map.apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+
+        // The intersection polygon reaches the new edge of the road.
+        let road = map.get_r(r);
+        assert_eq!(road.fwd_width, old_width + Distance::meters(3.0));
+        let corner = road
+            .center_pts
+            .shift_right(road.fwd_width)
+            .get(&mut timer)
+            .last_pt();
+        assert!(map
+            .get_i(road.dst_i)
+            .polygon
+            .points()
+            .iter()
+            .any(|pt| pt.approx_eq(corner, Distance::meters(0.1))));
+    });
+
+    t.run_slow("signal_edits_survive_lane_edits", |_| {
+        use map_model::IntersectionType;
+        let mut map = map_model::Map::new(
+            &abstutil::path_raw_map("montlake"),
+            &mut abstutil::Timer::throwaway(),
+        )
+        .unwrap();
+        let mut timer = abstutil::Timer::throwaway();
+
+        let i = map
+            .all_intersections()
+            .iter()
+            .find(|i| i.intersection_type == IntersectionType::TrafficSignal)
+            .expect("no traffic signals")
+            .id;
+        let mut signal = map.get_traffic_signal(i).clone();
+        signal.cycles[0].duration = signal.cycles[0].duration + Duration::seconds(5.0);
+        let mut edits = map.get_edits().clone();
+        edits.traffic_signal_overrides.insert(i, signal.clone());
+        map.apply_edits(edits, &mut timer);
+
+        // Widening a lane rebuilds the intersection, but the signal edits stay.
+        let l = map.get_i(i).incoming_lanes[0];
+        let mut edits = map.get_edits().clone();
+        edits
+            .lane_width_overrides
+            .insert(l, map.get_l(l).width + Distance::meters(1.0));
+        map.apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+        assert_eq!(map.get_traffic_signal(i), &signal);
+    });
+
+    t.run_slow("ban_and_allow_turns", |_| {
+        use map_model::{
+            IntersectionType, LaneType, PathRequest, PathStep, Position, TurnOverride,
+        };
+        let mut map = map_model::Map::new(
+            &abstutil::path_raw_map("montlake"),
+            &mut abstutil::Timer::throwaway(),
+        )
+        .unwrap();
+        let mut timer = abstutil::Timer::throwaway();
+
+        // No left turn at a traffic signal
+        let turn = map
+            .all_turns()
+            .values()
+            .find(|t| {
+                t.turn_type == map_model::TurnType::Left
+                    && map.get_i(t.id.parent).intersection_type == IntersectionType::TrafficSignal
+                    && map.get_l(t.id.src).lane_type == LaneType::Driving
+                    && map.get_l(t.id.dst).lane_type == LaneType::Driving
+                    && map.is_turn_allowed(t.id)
+            })
+            .expect("no left turn at a traffic signal")
+            .id;
+        let orig_signal = map.get_traffic_signal(turn.parent).clone();
+        let mut edits = map.get_edits().clone();
+        edits.turn_overrides.insert(turn, TurnOverride::Banned);
+        map.apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+        assert!(!map.is_turn_allowed(turn));
+        assert_ne!(map.get_traffic_signal(turn.parent), &orig_signal);
+        // Edits to the signal start from the policy without the ban.
+        assert_eq!(map.get_unpatched_traffic_signal(turn.parent), &orig_signal);
+
+        let req = PathRequest {
+            start: Position::new(turn.src, Distance::ZERO),
+            end: Position::new(turn.dst, map.get_l(turn.dst).length()),
+            can_use_bike_lanes: false,
+            can_use_bus_lanes: false,
+        };
+        let path = map.pathfind(req).expect("no way around the banned turn");
+        assert!(!path.get_steps().contains(&PathStep::Turn(turn)));
+
+        // Switching the ban to an allow puts the signal back how it was
+        let mut edits = map.get_edits().clone();
+        edits.turn_overrides.insert(turn, TurnOverride::Allowed);
+        map.apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+        assert!(map.is_turn_allowed(turn));
+        assert_eq!(map.get_traffic_signal(turn.parent), &orig_signal);
+    });
+
+    t.run_slow("stop_signs_favor_bigger_roads", |_| {
+        use map_model::{IntersectionType, StopSignConfidence, TurnPriority};
+        let map = map_model::Map::new(
+            &abstutil::path_raw_map("montlake"),
+            &mut abstutil::Timer::throwaway(),
+        )
+        .unwrap();
+
+        for i in map.all_intersections() {
+            if i.intersection_type != IntersectionType::StopSign
+                || map_model::ControlStopSign::default_confidence(&map, i.id)
+                    != StopSignConfidence::Medium
+            {
+                continue;
+            }
+            // Nobody on the biggest road stops.
+            let highest_rank = i
+                .incoming_lanes
+                .iter()
+                .filter(|l| map.get_l(**l).lane_type.is_for_moving_vehicles())
+                .map(|l| map.get_parent(*l).get_rank())
+                .max()
+                .unwrap();
+            let ss = map.get_stop_sign(i.id);
+            for t in &i.turns {
+                let src = map.get_l(t.src);
+                if src.lane_type.is_for_moving_vehicles()
+                    && map.get_parent(src.id).get_rank() == highest_rank
+                {
+                    assert_ne!(ss.get_priority(*t), TurnPriority::Stop);
+                }
+            }
+        }
+    });
+
+    t.run_fast("signal_cycle_durations", |_| {
+        use map_model::{ControlTrafficSignal, Cycle, IntersectionID};
+        let mut signal = ControlTrafficSignal {
+            id: IntersectionID(0),
+            cycles: vec![Cycle::new(IntersectionID(0)), Cycle::new(IntersectionID(0))],
+        };
+        signal.cycles[0].duration = Duration::seconds(10.0);
+        signal.cycles[1].duration = Duration::seconds(50.0);
+        assert_eq!(signal.period(), Duration::seconds(60.0));
+
+        let (idx, _, remaining) = signal.current_cycle_and_remaining_time(Duration::seconds(4.0));
+        assert_eq!((idx, remaining), (0, Duration::seconds(6.0)));
+        let (idx, _, remaining) = signal.current_cycle_and_remaining_time(Duration::seconds(15.0));
+        assert_eq!((idx, remaining), (1, Duration::seconds(45.0)));
+        // Wraps around
+        let (idx, _, remaining) = signal.current_cycle_and_remaining_time(Duration::seconds(62.0));
+        assert_eq!((idx, remaining), (0, Duration::seconds(8.0)));
+    });
+
+    t.run_slow("webster_timing", |_| {
+        use map_model::{IntersectionType, TurnPriority};
+        let map = map_model::Map::new(
+            &abstutil::path_raw_map("montlake"),
+            &mut abstutil::Timer::throwaway(),
+        )
+        .unwrap();
+
+        // Lots of cars want the turns only the first cycle serves.
+        let (signal, demand) = map
+            .all_intersections()
+            .iter()
+            .filter(|i| {
+                i.intersection_type == IntersectionType::TrafficSignal
+                    && map.get_traffic_signal(i.id).cycles.len() >= 2
+            })
+            .find_map(|i| {
+                let signal = map.get_traffic_signal(i.id);
+                let mut demand = BTreeMap::new();
+                for t in &signal.cycles[0].priority_turns {
+                    if !map.get_t(*t).between_sidewalks()
+                        && signal.cycles[1..]
+                            .iter()
+                            .all(|c| c.get_priority(*t) == TurnPriority::Banned)
+                    {
+                        demand.insert(*t, 1200);
+                    }
+                }
+                if demand.is_empty() {
+                    None
+                } else {
+                    Some((signal, demand))
+                }
+            })
+            .expect("no traffic signal with turns only its first cycle serves");
+        let timed = signal.with_webster_timing(&map, &demand, Duration::minutes(60));
+        for (idx, cycle) in timed.cycles.iter().enumerate() {
+            assert!(cycle.duration >= cycle.min_duration(&map));
+            if idx > 0 {
+                assert!(timed.cycles[0].duration >= cycle.duration);
+            }
+        }
+    });
+
+    t.run_fast("turn_restriction_conditions", |_| {
+        let cond = map_model::TimeCondition::parse("Mo-Fr 07:00-09:00,16:00-18:00; Sa 22:00-02:00")
+            .unwrap();
+        let at = |day: usize, hour: usize, min: usize| {
+            Duration::minutes(day * 24 * 60 + hour * 60 + min)
+        };
+        // Monday morning rush
+        assert!(cond.active_at(at(0, 8, 30)));
+        assert!(!cond.active_at(at(0, 9, 0)));
+        assert!(cond.active_at(at(4, 17, 0)));
+        // Sunday afternoon
+        assert!(!cond.active_at(at(6, 17, 0)));
+        // Saturday night spills into Sunday
+        assert!(cond.active_at(at(5, 23, 0)));
+        assert!(cond.active_at(at(6, 1, 0)));
+        assert!(!cond.active_at(at(6, 2, 0)));
+        // The next week
+        assert!(cond.active_at(at(7, 8, 0)));
+
+        assert!(map_model::TimeCondition::parse("sunrise-sunset").is_none());
+    });
+
+    t.run_slow("closures_apply_with_restrictions", |_| {
+        use map_model::{
+            raw_data, Closure, LaneType, Map, PathRequest, PathStep, Position, RoadID,
+        };
+        let mut timer = abstutil::Timer::throwaway();
+        let load_raw = |timer: &mut abstutil::Timer| -> raw_data::Map {
+            abstutil::read_binary(&abstutil::path_raw_map("montlake"), timer).unwrap()
+        };
+        let map = Map::create_from_raw("montlake".to_string(), load_raw(&mut timer), &mut timer);
+        let roads = |map: &Map, req: &PathRequest, time: Duration| -> Vec<RoadID> {
+            let path = map.pathfind_at_time(req.clone(), time).expect("no path");
+            path.get_steps()
+                .iter()
+                .filter_map(|step| match step {
+                    PathStep::Lane(l) => Some(map.get_l(*l).parent),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let driving: Vec<_> = map
+            .all_lanes()
+            .iter()
+            .filter(|l| l.lane_type == LaneType::Driving)
+            .map(|l| l.id)
+            .collect();
+        let req = driving
+            .iter()
+            .rev()
+            .map(|end| PathRequest {
+                start: Position::new(driving[0], Distance::ZERO),
+                end: Position::new(*end, Distance::ZERO),
+                can_use_bike_lanes: false,
+                can_use_bus_lanes: false,
+            })
+            .find(|req| map.pathfind(req.clone()).is_some())
+            .unwrap();
+        let rush_hour = Duration::hours(8);
+        let orig_roads = roads(&map, &req, rush_hour);
+
+        // Ban the first turn onto a different OSM way during rush hour, which only the slow
+        // fallback search handles
+        let (from, to) = orig_roads
+            .windows(2)
+            .map(|pair| (map.get_r(pair[0]).osm_way_id, map.get_r(pair[1]).osm_way_id))
+            .find(|(from, to)| from != to)
+            .expect("path stays on one OSM way");
+        let mut raw = load_raw(&mut timer);
+        raw.turn_restrictions
+            .entry(from)
+            .or_insert_with(Vec::new)
+            .push(raw_data::TurnRestriction {
+                restriction: "no_straight_on".to_string(),
+                via_ways: Vec::new(),
+                to_way: to,
+                except: Vec::new(),
+                conditions: Some("Mo-Fr 07:00-09:00".to_string()),
+            });
+        let mut map = Map::create_from_raw("montlake".to_string(), raw, &mut timer);
+        let detour = roads(&map, &req, rush_hour);
+        assert_ne!(detour, orig_roads);
+
+        // Closing the detour has to be respected by the fallback search too
+        let closed = *detour
+            .iter()
+            .find(|r| !orig_roads.contains(r))
+            .expect("detour uses no new roads");
+        let mut edits = map.get_edits().clone();
+        edits.road_closures.insert(closed, Closure::everyone());
+        map.apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+        if let Some(path) = map.pathfind_at_time(req.clone(), rush_hour) {
+            assert!(path.get_steps().iter().all(|step| match step {
+                PathStep::Lane(l) => map.get_l(*l).parent != closed,
+                _ => true,
+            }));
+        }
+    });
+
+    t.run_fast("closure_modes", |_| {
+        use map_model::{Closure, LaneType};
+        let car = [LaneType::Driving];
+        let bike = [LaneType::Driving, LaneType::Biking];
+        let bus = [LaneType::Driving, LaneType::Bus];
+        let ped = [LaneType::Sidewalk];
+
+        // Like a stay healthy street
+        let no_cars = Closure {
+            cars: true,
+            bikes: false,
+            buses: false,
+            pedestrians: false,
+        };
+        assert!(no_cars.applies_to(&car));
+        assert!(!no_cars.applies_to(&bike));
+        assert!(!no_cars.applies_to(&bus));
+        assert!(!no_cars.applies_to(&ped));
+
+        let everyone = Closure::everyone();
+        for lane_types in &[&car[..], &bike[..], &bus[..], &ped[..]] {
+            assert!(everyone.applies_to(lane_types));
+        }
+    });
+
+    t.run_fast("load_old_edits", |_| {
+        // Saved before most kinds of edits existed
+        std::fs::write(
+            "load_old_edits.json",
+            r#"{"map_name": "montlake", "edits_name": "old", "lane_overrides": {},
+            "stop_sign_overrides": {}, "traffic_signal_overrides": {}}"#,
+        )
+        .unwrap();
+        let edits: map_model::MapEdits = abstutil::read_json("load_old_edits.json").unwrap();
+        assert_eq!(edits.edits_name, "old");
+        assert!(edits.scheduled.is_empty());
+        assert!(edits.turn_overrides.is_empty());
+        assert!(edits.provenance.is_empty());
+    });
+
+    t.run_fast("merge_edits", |_| {
+        use map_model::{EditID, EditProvenance, LaneID, LaneType, Map, MapEdits, RoadID};
+        // Without any cross-section edits, lane IDs don't depend on the map.
+        let map = Map::blank();
+        let mut base = MapEdits::new("montlake".to_string());
+        base.lane_overrides.insert(LaneID(1), LaneType::Bus);
+
+        let mut ours = base.clone();
+        ours.lane_overrides.insert(LaneID(2), LaneType::Biking);
+        ours.speed_limit_overrides
+            .insert(RoadID(0), Speed::miles_per_hour(25.0));
+
+        let mut theirs = base.clone();
+        theirs.lane_overrides.remove(&LaneID(1));
+        theirs.lane_overrides.insert(LaneID(3), LaneType::Parking);
+        theirs
+            .speed_limit_overrides
+            .insert(RoadID(0), Speed::miles_per_hour(30.0));
+        theirs.record_provenance(&base, "them", "more parking", &map);
+
+        let (merged, conflicts) = MapEdits::merge(&base, &ours, &theirs, &map);
+        // Only theirs touched lane 1, and only one side touched lanes 2 and 3.
+        assert!(!merged.lane_overrides.contains_key(&LaneID(1)));
+        assert_eq!(merged.lane_overrides[&LaneID(2)], LaneType::Biking);
+        assert_eq!(merged.lane_overrides[&LaneID(3)], LaneType::Parking);
+        assert_eq!(
+            merged.provenance[&EditID::LaneType(LaneID(3))].author,
+            "them"
+        );
+        // Both changed the speed limit, so ours wins.
+        assert_eq!(
+            merged.speed_limit_overrides[&RoadID(0)],
+            Speed::miles_per_hour(25.0)
+        );
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].id, EditID::SpeedLimit(RoadID(0)));
+        assert_eq!(conflicts[0].ours, None);
+        assert_eq!(
+            conflicts[0]
+                .theirs
+                .as_ref()
+                .map(|p: &EditProvenance| p.rationale.as_str()),
+            Some("more parking")
+        );
+
+        let diff = ours.diff(&merged, &map);
+        assert_eq!(diff.added, vec![EditID::LaneType(LaneID(3))]);
+        assert_eq!(diff.removed, vec![EditID::LaneType(LaneID(1))]);
+        assert!(diff.changed.is_empty());
+        assert!(merged.diff(&merged, &map).is_empty());
+
+        // Saving the merge only credits what isn't credited yet.
+        let mut saved = merged.clone();
+        saved.record_provenance(&ours, "us", "merged", &map);
+        assert_eq!(
+            saved.provenance[&EditID::LaneType(LaneID(3))].author,
+            "them"
+        );
+        let mut changed = saved.clone();
+        changed.lane_overrides.insert(LaneID(3), LaneType::Bus);
+        changed.lane_overrides.insert(LaneID(4), LaneType::Bus);
+        changed.record_provenance(&saved, "us", "more buses", &map);
+        assert_eq!(
+            changed.provenance[&EditID::LaneType(LaneID(3))].author,
+            "us"
+        );
+        assert_eq!(
+            changed.provenance[&EditID::LaneType(LaneID(4))].author,
+            "us"
+        );
+    });
+
+    t.run_fast("parse_maxspeed", |_| {
+        use map_model::parse_maxspeed;
+        assert_eq!(parse_maxspeed("25 mph"), Some(Speed::miles_per_hour(25.0)));
+        assert_eq!(parse_maxspeed("25mph"), Some(Speed::miles_per_hour(25.0)));
+        assert_eq!(parse_maxspeed("50"), Some(Speed::km_per_hour(50.0)));
+        assert_eq!(parse_maxspeed("50 km/h"), Some(Speed::km_per_hour(50.0)));
+        assert_eq!(
+            parse_maxspeed("30 mph;35 mph"),
+            Some(Speed::miles_per_hour(30.0))
+        );
+        assert_eq!(parse_maxspeed("none"), None);
+        assert_eq!(parse_maxspeed("US:urban"), None);
+    });
+
+    t.run_fast("tag_stop_signs", |_| {
+        use convert_osm::StopSign;
+        use geom::{LonLat, Pt2D};
+        use map_model::raw_data;
+        use std::collections::HashMap;
+
+        // A way from (0, 0) to (100, 0), with one node near the start
+        let pts = vec![
+            Pt2D::new(0.0, 0.0),
+            Pt2D::new(10.0, 0.0),
+            Pt2D::new(100.0, 0.0),
+        ];
+        let tag = |signs: Vec<(usize, bool, Option<&str>)>| -> BTreeMap<String, String> {
+            let mut r = raw_data::Road {
+                i1: raw_data::StableIntersectionID(0),
+                i2: raw_data::StableIntersectionID(1),
+                center_points: pts.clone(),
+                orig_id: raw_data::OriginalRoad {
+                    pt1: LonLat::new(0.0, 0.0),
+                    pt2: LonLat::new(1.0, 0.0),
+                },
+                segment_id: raw_data::OriginalRoad {
+                    pt1: LonLat::new(0.0, 0.0),
+                    pt2: LonLat::new(1.0, 0.0),
+                },
+                osm_tags: BTreeMap::new(),
+                osm_way_id: 1,
+                parking_lane_fwd: false,
+                parking_lane_back: false,
+            };
+            let stop_signs: HashMap<_, _> = signs
+                .into_iter()
+                .map(|(idx, all_way, direction)| {
+                    (
+                        pts[idx].to_hashable(),
+                        StopSign {
+                            all_way,
+                            direction: direction.map(|d| d.to_string()),
+                        },
+                    )
+                })
+                .collect();
+            convert_osm::tag_stop_signs(&mut r, &stop_signs);
+            r.osm_tags
+        };
+        let stop = |fwd: &str, back: &str| -> BTreeMap<String, String> {
+            let mut tags = BTreeMap::new();
+            if !fwd.is_empty() {
+                tags.insert("abst:stop_fwd".to_string(), fwd.to_string());
+            }
+            if !back.is_empty() {
+                tags.insert("abst:stop_back".to_string(), back.to_string());
+            }
+            tags
+        };
+
+        // On the intersection node, stop=all means everyone stops and otherwise minor roads do.
+        assert_eq!(tag(vec![(2, false, None)]), stop("minor", ""));
+        assert_eq!(tag(vec![(0, true, None)]), stop("", "yes"));
+        // On the way, the direction says which end it's for.
+        assert_eq!(tag(vec![(1, false, Some("forward"))]), stop("yes", ""));
+        assert_eq!(tag(vec![(1, false, Some("backward"))]), stop("", "yes"));
+        // Without one, guess the nearer end.
+        assert_eq!(tag(vec![(1, false, None)]), stop("", "yes"));
+        // A sign on the way wins over one at the intersection.
+        assert_eq!(
+            tag(vec![(2, false, None), (1, false, Some("forward"))]),
+            stop("yes", "")
+        );
+    });
+
+    t.run_fast("footways_and_alleys", |_| {
+        use map_model::{get_lane_types, LaneType};
+        let tags = |pairs: Vec<(&str, &str)>| -> BTreeMap<String, String> {
+            pairs
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+
+        let footway = tags(vec![("highway", "footway")]);
+        assert!(!convert_osm::is_road(&footway, false));
+        assert!(convert_osm::is_road(&footway, true));
+        assert!(!convert_osm::is_road(
+            &tags(vec![("highway", "footway"), ("footway", "sidewalk")]),
+            true
+        ));
+        assert!(!convert_osm::is_road(
+            &tags(vec![("highway", "pedestrian"), ("area", "yes")]),
+            true
+        ));
+        assert!(convert_osm::is_road(
+            &tags(vec![("highway", "service"), ("service", "alley")]),
+            true
+        ));
+        assert!(!convert_osm::is_road(
+            &tags(vec![("highway", "service"), ("service", "driveway")]),
+            true
+        ));
+        assert!(!convert_osm::is_road(
+            &tags(vec![("highway", "service")]),
+            true
+        ));
+
+        assert_eq!(
+            get_lane_types(&footway, false, false),
+            (vec![LaneType::Sidewalk], Vec::new())
+        );
+        assert_eq!(
+            get_lane_types(
+                &tags(vec![("highway", "path"), ("bicycle", "designated")]),
+                false,
+                false
+            ),
+            (
+                vec![LaneType::Biking, LaneType::Sidewalk],
+                vec![LaneType::Biking]
+            )
+        );
+        assert_eq!(
+            get_lane_types(&tags(vec![("highway", "steps")]), false, false),
+            (vec![LaneType::Sidewalk], Vec::new())
+        );
+        assert_eq!(
+            get_lane_types(
+                &tags(vec![
+                    ("highway", "cycleway"),
+                    ("oneway", "yes"),
+                    ("foot", "no")
+                ]),
+                false,
+                false
+            ),
+            (vec![LaneType::Biking], Vec::new())
+        );
+    });
+
+    t.run_slow("stairs_slow_down_walking", |_| {
+        let flags = convert_osm::Flags {
+            osm: "../data/input/montlake.osm".to_string(),
+            osc: String::new(),
+            osm_cache: String::new(),
+            parking_shapes: "../data/shapes/blockface.bin".to_string(),
+            offstreet_parking: "../data/input/offstreet_parking.kml".to_string(),
+            gtfs: "../data/input/google_transit_2018_18_08".to_string(),
+            neighborhoods: "../data/input/neighborhoods.geojson".to_string(),
+            clip: abstutil::path_polygon("montlake"),
+            output: "stairs_slow_down_walking.bin".to_string(),
+            fast_dev: false,
+            footways_and_alleys: true,
+        };
+        let raw = convert_osm::convert(&flags, &mut abstutil::Timer::throwaway());
+        let map = map_model::Map::create_from_raw(
+            "montlake".to_string(),
+            raw,
+            &mut abstutil::Timer::throwaway(),
+        );
+
+        let mut found_stairs = false;
+        for r in map.all_roads() {
+            if r.osm_tags.get("highway") == Some(&"steps".to_string()) {
+                found_stairs = true;
+                assert!(r.walking_slowdown() > 1.0);
+            } else {
+                assert_eq!(r.walking_slowdown(), 1.0);
+            }
+            assert!(
+                r.osm_tags.get("highway") != Some(&"service".to_string())
+                    || r.osm_tags.get("service") == Some(&"alley".to_string())
+            );
+        }
+        assert!(found_stairs);
+    });
+
+    t.run_fast("lane_widths_from_osm", |_| {
+        use map_model::{get_lane_widths, LaneType};
+        let tags = |pairs: Vec<(&str, &str)>| -> BTreeMap<String, String> {
+            pairs
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        let fwd = vec![LaneType::Driving, LaneType::Driving, LaneType::Sidewalk];
+
+        let (widths, _) = get_lane_widths(
+            &tags(vec![
+                ("width:lanes", "3.5|12'"),
+                ("sidewalk:width", "1.5 m"),
+            ]),
+            &fwd,
+            &Vec::new(),
+        );
+        assert_eq!(
+            widths,
+            vec![
+                Distance::meters(3.5),
+                Distance::meters(12.0 * 0.3048),
+                Distance::meters(1.5)
+            ]
+        );
+
+        // The carriageway width stretches the defaults, but leaves sidewalks alone.
+        let (widths, _) = get_lane_widths(&tags(vec![("width", "9")]), &fwd, &Vec::new());
+        assert_eq!(
+            widths,
+            vec![
+                Distance::meters(4.5),
+                Distance::meters(4.5),
+                LaneType::Sidewalk.default_width()
+            ]
+        );
+
+        // Nonsense is ignored
+        let (widths, _) = get_lane_widths(&tags(vec![("width", "wide")]), &fwd, &Vec::new());
+        assert_eq!(
+            widths,
+            fwd.iter().map(|lt| lt.default_width()).collect::<Vec<_>>()
+        );
+    });
 }
@@ -7,8 +7,9 @@ pub fn run(t: &mut TestRunner) {
     // TODO Lots of boilerplate between these two. Can we do better?
 
     t.run_slow("park_on_goal_st", |h| {
-        let (map, mut sim, mut rng) = SimFlags::synthetic_test("parking_test", "park_on_goal_st")
-            .load(&mut Timer::throwaway());
+        let (mut map, mut sim, mut rng) =
+            SimFlags::synthetic_test("parking_test", "park_on_goal_st")
+                .load(&mut Timer::throwaway());
         let north_bldg = map.bldg("north").id;
         let south_bldg = map.bldg("south").id;
         let north_parking = map.parking_lane("north", 23).id;
@@ -33,18 +34,18 @@ pub fn run(t: &mut TestRunner) {
         h.setup_done(&sim);
 
         sim.run_until_expectations_met(
-            &map,
+            &mut map,
             vec![Event::CarReachedParkingSpot(
                 car,
                 ParkingSpot::new(north_parking, 4),
             )],
             Duration::minutes(6),
         );
-        sim.just_run_until_done(&map, Some(Duration::minutes(1)));
+        sim.just_run_until_done(&mut map, Some(Duration::minutes(1)));
     });
 
     t.run_slow("wander_around_for_parking", |h| {
-        let (map, mut sim, mut rng) =
+        let (mut map, mut sim, mut rng) =
             SimFlags::synthetic_test("parking_test", "wander_around_for_parking")
                 .load(&mut Timer::throwaway());
         let north_bldg = map.bldg("north").id;
@@ -71,13 +72,13 @@ pub fn run(t: &mut TestRunner) {
         h.setup_done(&sim);
 
         sim.run_until_expectations_met(
-            &map,
+            &mut map,
             vec![Event::CarReachedParkingSpot(
                 car,
                 ParkingSpot::new(south_parking, 0),
             )],
             Duration::minutes(6),
         );
-        sim.just_run_until_done(&map, Some(Duration::minutes(1)));
+        sim.just_run_until_done(&mut map, Some(Duration::minutes(1)));
     });
 }
@@ -0,0 +1,151 @@
+use crate::runner::TestRunner;
+use abstutil::Timer;
+use geom::Duration;
+use map_model::LaneType;
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use sim::{
+    compare_trips, confidence_interval, is_significant, FinishedTrips, ImpactPreview,
+    OriginDestination, Scenario, SimFlags, SpawnOverTime, TripID, TripMode,
+};
+
+pub fn run(t: &mut TestRunner) {
+    t.run_fast("scale_and_window", |_| {
+        let s = Scenario {
+            scenario_name: "base".to_string(),
+            map_name: "montlake".to_string(),
+            seed_parked_cars: Vec::new(),
+            spawn_over_time: vec![SpawnOverTime {
+                num_agents: 100,
+                start_time: Duration::minutes(6 * 60),
+                stop_time: Duration::minutes(10 * 60),
+                start_from_neighborhood: "_everywhere_".to_string(),
+                goal: OriginDestination::Neighborhood("_everywhere_".to_string()),
+                percent_biking: 0.5,
+                percent_use_transit: 0.5,
+            }],
+            border_spawn_over_time: Vec::new(),
+            individ_trips: Vec::new(),
+        };
+
+        let more = s.scale(1.2, &mut XorShiftRng::from_seed([42; 16]), "more");
+        assert_eq!(more.spawn_over_time[0].num_agents, 120);
+
+        // Half of the spawning window overlaps 7-9am
+        let peak = s.remap_time_window(
+            Duration::minutes(7 * 60),
+            Duration::minutes(9 * 60),
+            Duration::minutes(7 * 60),
+            Duration::minutes(8 * 60),
+            "peak",
+        );
+        assert_eq!(peak.spawn_over_time[0].num_agents, 50);
+        assert_eq!(
+            peak.spawn_over_time[0].start_time,
+            Duration::minutes(7 * 60)
+        );
+        assert_eq!(peak.spawn_over_time[0].stop_time, Duration::minutes(8 * 60));
+
+        let early = s.shift_time(-Duration::minutes(8 * 60), "early");
+        assert_eq!(early.spawn_over_time[0].num_agents, 50);
+        assert_eq!(early.spawn_over_time[0].start_time, Duration::ZERO);
+    });
+
+    t.run_fast("batch_statistics", |_| {
+        let secs = |list: &[f64]| -> Vec<Duration> {
+            list.iter().map(|x| Duration::seconds(*x)).collect()
+        };
+
+        let (mean, half_width) = confidence_interval(&secs(&[10.0, 12.0, 14.0]));
+        assert_eq!(mean, Duration::seconds(12.0));
+        // t(2) * sqrt(4 / 3)
+        assert!((half_width.inner_seconds() - 4.969).abs() < 0.001);
+        assert_eq!(
+            confidence_interval(&secs(&[10.0])),
+            (Duration::seconds(10.0), Duration::ZERO)
+        );
+
+        let base = secs(&[10.0, 12.0, 14.0]);
+        assert!(!is_significant(&base, &secs(&[11.0, 13.0, 15.0])));
+        assert!(is_significant(&base, &secs(&[110.0, 112.0, 114.0])));
+        assert!(is_significant(&secs(&[5.0, 5.0]), &secs(&[6.0, 6.0])));
+        assert!(!is_significant(&secs(&[10.0]), &secs(&[100.0])));
+    });
+
+    t.run_fast("ab_test_deltas", |_| {
+        let secs = Duration::seconds;
+        let run1 = FinishedTrips {
+            unfinished_trips: 0,
+            finished_trips: vec![
+                (TripID(0), TripMode::Drive, secs(100.0)),
+                (TripID(1), TripMode::Drive, secs(200.0)),
+                (TripID(2), TripMode::Walk, secs(300.0)),
+                (TripID(3), TripMode::Drive, secs(50.0)),
+            ],
+        };
+        // Trip 3 doesn't finish this time.
+        let run2 = FinishedTrips {
+            unfinished_trips: 1,
+            finished_trips: vec![
+                (TripID(0), TripMode::Drive, secs(90.0)),
+                (TripID(1), TripMode::Drive, secs(230.0)),
+                (TripID(2), TripMode::Walk, secs(300.0)),
+            ],
+        };
+
+        let (trips, per_mode) = compare_trips(run1, run2);
+        assert_eq!(
+            trips.iter().map(|t| (t.trip, t.delta)).collect::<Vec<_>>(),
+            vec![
+                (TripID(0), secs(-10.0)),
+                (TripID(1), secs(30.0)),
+                (TripID(2), Duration::ZERO)
+            ]
+        );
+        assert_eq!(per_mode.len(), 2);
+
+        let drive = &per_mode[&TripMode::Drive];
+        assert_eq!(drive.num_trips, 2);
+        assert_eq!(drive.num_same, 0);
+        assert_eq!(drive.num_faster, 1);
+        assert_eq!(drive.num_slower, 1);
+        assert_eq!(drive.total_time1, secs(300.0));
+        assert_eq!(drive.total_time2, secs(320.0));
+        assert_eq!(drive.mean_delta, secs(10.0));
+        assert_eq!(drive.delta_50_percentile, Some(secs(30.0)));
+        assert_eq!(drive.delta_99_percentile, Some(secs(30.0)));
+
+        let walk = &per_mode[&TripMode::Walk];
+        assert_eq!(walk.num_trips, 1);
+        assert_eq!(walk.num_same, 1);
+        assert_eq!(walk.mean_delta, Duration::ZERO);
+        assert_eq!(walk.delta_50_percentile, None);
+    });
+
+    t.run_slow("impact_preview", |_| {
+        let (mut map, _, mut rng) =
+            SimFlags::for_test("impact_preview").load(&mut Timer::throwaway());
+        let mut timer = Timer::throwaway();
+        let preview = ImpactPreview::new(&Scenario::small_run(&map), &map, &mut rng, &mut timer);
+
+        // Nothing changed yet
+        let summary = preview.compare(&map, &mut timer);
+        assert!(summary.num_trips > 0);
+        assert!(summary.changed_routes.is_empty());
+        assert!(summary.newly_impossible.is_empty());
+        assert!(summary.newly_possible.is_empty());
+        assert_eq!(summary.length_before, summary.length_after);
+
+        let mut edits = map.get_edits().clone();
+        edits.edits_name = "impact_preview".to_string();
+        for l in map.all_lanes() {
+            if l.lane_type == LaneType::Driving && l.id.0 % 10 == 0 {
+                edits.lane_overrides.insert(l.id, LaneType::Bus);
+            }
+        }
+        map.apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+        let summary = preview.compare(&map, &mut timer);
+        assert!(!summary.changed_routes.is_empty() || !summary.newly_impossible.is_empty());
+    });
+}
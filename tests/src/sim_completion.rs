@@ -1,15 +1,211 @@
 use crate::runner::TestRunner;
 use abstutil::Timer;
 use geom::Duration;
-use sim::{Scenario, SimFlags};
+use map_model::{
+    IntersectionID, IntersectionType, LaneID, LaneType, PathStep, ScheduledEdits, TurnType,
+};
+use sim::{AgentID, Scenario, SignalOptimizer, SimFlags};
+use std::collections::BTreeMap;
 
 pub fn run(t: &mut TestRunner) {
     t.run_slow("small_spawn_completes", |h| {
         let mut flags = SimFlags::for_test("aorta_model_completes");
         flags.savestate_every = Some(Duration::seconds(30.0));
-        let (map, mut sim, mut rng) = flags.load(&mut Timer::throwaway());
+        let (mut map, mut sim, mut rng) = flags.load(&mut Timer::throwaway());
         Scenario::small_run(&map).instantiate(&mut sim, &map, &mut rng, &mut Timer::throwaway());
         h.setup_done(&sim);
-        sim.just_run_until_done(&map, Some(Duration::minutes(70)));
+        sim.just_run_until_done(&mut map, Some(Duration::minutes(70)));
+    });
+    t.run_slow("live_edits_complete", |h| {
+        let (mut map, mut sim, mut rng) =
+            SimFlags::for_test("live_edits_complete").load(&mut Timer::throwaway());
+        Scenario::small_run(&map).instantiate(&mut sim, &map, &mut rng, &mut Timer::throwaway());
+        h.setup_done(&sim);
+        sim.timed_step(&mut map, Duration::minutes(5), &mut Timer::throwaway());
+
+        // Take away some driving lanes partway through the day.
+        let mut edits = map.get_edits().clone();
+        edits.edits_name = "live_edits_complete".to_string();
+        for l in map.all_lanes() {
+            if l.lane_type == LaneType::Driving && l.id.0 % 10 == 0 {
+                edits.lane_overrides.insert(l.id, LaneType::Bus);
+            }
+        }
+        let mut timer = Timer::throwaway();
+        map.apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+        sim.handle_live_edits(&map, &mut timer);
+
+        sim.just_run_until_done(&mut map, Some(Duration::minutes(70)));
+    });
+    t.run_slow("delete_turn_under_moving_car", |h| {
+        let (mut map, mut sim, mut rng) =
+            SimFlags::for_test("delete_turn_under_moving_car").load(&mut Timer::throwaway());
+        Scenario::small_run(&map).instantiate(&mut sim, &map, &mut rng, &mut Timer::throwaway());
+        h.setup_done(&sim);
+        sim.timed_step(&mut map, Duration::minutes(5), &mut Timer::throwaway());
+
+        // Take away the next turn some car is about to make.
+        let turn = sim
+            .active_agents()
+            .into_iter()
+            .filter_map(|a| match a {
+                AgentID::Car(_) => sim.get_path(a),
+                _ => None,
+            })
+            .filter_map(|path| {
+                path.get_steps().iter().skip(1).find_map(|step| match step {
+                    PathStep::Turn(t) => Some(*t),
+                    _ => None,
+                })
+            })
+            .next()
+            .expect("no car has a turn coming up");
+        let turn_type = map.get_t(turn).turn_type;
+        let mut edits = map.get_edits().clone();
+        edits.edits_name = "delete_turn_under_moving_car".to_string();
+        edits.allowed_turns_overrides.insert(
+            turn.src,
+            vec![
+                TurnType::Straight,
+                TurnType::LaneChangeLeft,
+                TurnType::LaneChangeRight,
+                TurnType::Right,
+                TurnType::Left,
+            ]
+            .into_iter()
+            .filter(|tt| *tt != turn_type)
+            .collect(),
+        );
+        let mut timer = Timer::throwaway();
+        map.apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+        sim.handle_live_edits(&map, &mut timer);
+
+        // Nobody still plans to use a turn that's gone.
+        assert!(map.maybe_get_t(turn).is_none());
+        for a in sim.active_agents() {
+            if let Some(path) = sim.get_path(a) {
+                for step in path.get_steps() {
+                    if let PathStep::Turn(t) = step {
+                        assert!(map.maybe_get_t(*t).is_some(), "{} still uses {}", a, t);
+                    }
+                }
+            }
+        }
+
+        sim.just_run_until_done(&mut map, Some(Duration::minutes(70)));
+    });
+    t.run_slow("intersection_type_edits_complete", |h| {
+        let (mut map, mut sim, mut rng) =
+            SimFlags::for_test("intersection_type_edits_complete").load(&mut Timer::throwaway());
+        Scenario::small_run(&map).instantiate(&mut sim, &map, &mut rng, &mut Timer::throwaway());
+        h.setup_done(&sim);
+        sim.timed_step(&mut map, Duration::minutes(5), &mut Timer::throwaway());
+
+        // Cycle through every kind of control partway through the day.
+        let mut edits = map.get_edits().clone();
+        edits.edits_name = "intersection_type_edits_complete".to_string();
+        for i in map.all_intersections() {
+            let new_type = match i.id.0 % 4 {
+                0 => IntersectionType::StopSign,
+                1 => IntersectionType::TrafficSignal,
+                2 => IntersectionType::Uncontrolled,
+                _ => IntersectionType::Roundabout,
+            };
+            if i.intersection_type != IntersectionType::Border && i.intersection_type != new_type {
+                edits.intersection_type_overrides.insert(i.id, new_type);
+            }
+        }
+        let mut timer = Timer::throwaway();
+        map.apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+        sim.handle_live_edits(&map, &mut timer);
+
+        for i in map.all_intersections() {
+            if i.intersection_type == IntersectionType::TrafficSignal {
+                assert!(map.maybe_get_traffic_signal(i.id).is_some());
+            } else if i.intersection_type.is_unsignalized() {
+                assert!(map.maybe_get_stop_sign(i.id).is_some());
+            }
+        }
+
+        sim.just_run_until_done(&mut map, Some(Duration::minutes(70)));
+    });
+    t.run_slow("scheduled_edits_complete", |h| {
+        let (mut map, mut sim, mut rng) =
+            SimFlags::for_test("scheduled_edits_complete").load(&mut Timer::throwaway());
+
+        // Some peak-hour bus lanes
+        let mut window = ScheduledEdits {
+            start: Duration::minutes(2),
+            end: Duration::minutes(10),
+            lane_overrides: BTreeMap::new(),
+            traffic_signal_overrides: BTreeMap::new(),
+        };
+        for l in map.all_lanes() {
+            if l.lane_type == LaneType::Driving && l.id.0 % 10 == 0 {
+                window.lane_overrides.insert(l.id, LaneType::Bus);
+            }
+        }
+        let window_lanes: Vec<LaneID> = window.lane_overrides.keys().cloned().collect();
+        let mut edits = map.get_edits().clone();
+        edits.edits_name = "scheduled_edits_complete".to_string();
+        edits.scheduled.push(window);
+        let mut timer = Timer::throwaway();
+        map.apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+
+        Scenario::small_run(&map).instantiate(&mut sim, &map, &mut rng, &mut timer);
+        h.setup_done(&sim);
+
+        // Plain stepping switches the window on and off, even when one step spans a boundary.
+        sim.timed_step(&mut map, Duration::minutes(5), &mut timer);
+        let bus_lane = *window_lanes
+            .iter()
+            .find(|l| map.get_l(**l).lane_type == LaneType::Bus)
+            .expect("the scheduled bus lanes aren't active");
+        sim.step(&mut map, Duration::minutes(6));
+        assert_eq!(map.get_l(bus_lane).lane_type, LaneType::Driving);
+
+        sim.just_run_until_done(&mut map, Some(Duration::minutes(70)));
+    });
+    t.run_slow("signal_optimizer_never_worse", |h| {
+        let (mut map, mut sim, mut rng) =
+            SimFlags::for_test("signal_optimizer_never_worse").load(&mut Timer::throwaway());
+        Scenario::small_run(&map).instantiate(&mut sim, &map, &mut rng, &mut Timer::throwaway());
+        h.setup_done(&sim);
+        sim.timed_step(&mut map, Duration::minutes(5), &mut Timer::throwaway());
+
+        // Tune the busiest signal so far
+        let thruput = |i: IntersectionID| -> usize {
+            sim.get_traffic_stats()
+                .thruput_turns
+                .iter()
+                .filter(|(t, _)| t.parent == i)
+                .map(|(_, count)| *count)
+                .sum()
+        };
+        let i = map
+            .all_intersections()
+            .iter()
+            .filter(|i| i.intersection_type == IntersectionType::TrafficSignal)
+            .max_by_key(|i| thruput(i.id))
+            .expect("no traffic signals")
+            .id;
+        let optimizer = SignalOptimizer::from_sim(&sim, i);
+        assert!(!optimizer.demand.is_empty());
+        let savestate = sim.save();
+        let orig_edits = map.get_edits().clone();
+        let result = optimizer.optimize(
+            &mut map,
+            &savestate,
+            Duration::minutes(2),
+            1,
+            &mut Timer::throwaway(),
+        );
+        assert!(result.delay_after <= result.delay_before);
+        assert!(result.candidates_tried > 2);
+        assert!(map.get_edits().diff(&orig_edits, &map).is_empty());
     });
 }
@@ -5,7 +5,7 @@ use sim::{Scenario, Sim, SimFlags, SimOptions};
 
 pub fn run(t: &mut TestRunner) {
     t.run_slow("serialization", |_| {
-        let (map, mut sim, mut rng) =
+        let (mut map, mut sim, mut rng) =
             SimFlags::for_test("serialization").load(&mut Timer::throwaway());
         Scenario::small_run(&map).instantiate(&mut sim, &map, &mut rng, &mut Timer::throwaway());
 
@@ -18,7 +18,7 @@ pub fn run(t: &mut TestRunner) {
     t.run_slow("from_scratch", |_| {
         println!("Creating two simulations");
         let flags = SimFlags::for_test("from_scratch_1");
-        let (map, mut sim1, _) = flags.load(&mut Timer::throwaway());
+        let (mut map, mut sim1, _) = flags.load(&mut Timer::throwaway());
         let mut sim2 = Sim::new(&map, SimOptions::new("from_scratch_2"));
         Scenario::small_run(&map).instantiate(
             &mut sim1,
@@ -43,15 +43,15 @@ pub fn run(t: &mut TestRunner) {
                     sim2.save()
                 );
             }
-            sim1.step(&map, dt);
-            sim2.step(&map, dt);
+            sim1.step(&mut map, dt);
+            sim2.step(&mut map, dt);
         }
     });
 
     t.run_slow("with_savestating", |_| {
         println!("Creating two simulations");
         let flags = SimFlags::for_test("with_savestating_1");
-        let (map, mut sim1, _) = flags.load(&mut Timer::throwaway());
+        let (mut map, mut sim1, _) = flags.load(&mut Timer::throwaway());
         let mut sim2 = Sim::new(&map, SimOptions::new("with_savestating_2"));
         Scenario::small_run(&map).instantiate(
             &mut sim1,
@@ -66,8 +66,8 @@ pub fn run(t: &mut TestRunner) {
             &mut Timer::throwaway(),
         );
 
-        sim1.step(&map, Duration::minutes(10));
-        sim2.step(&map, Duration::minutes(10));
+        sim1.step(&mut map, Duration::minutes(10));
+        sim2.step(&mut map, Duration::minutes(10));
 
         if sim1 != sim2 {
             panic!(
@@ -79,7 +79,7 @@ pub fn run(t: &mut TestRunner) {
 
         let sim1_save = sim1.save();
 
-        sim1.step(&map, Duration::seconds(30.0));
+        sim1.step(&mut map, Duration::seconds(30.0));
 
         if sim1 == sim2 {
             panic!(
@@ -7,7 +7,7 @@ pub fn run(t: &mut TestRunner) {
     t.run_slow("bus_reaches_stops", |h| {
         let mut flags = SimFlags::for_test("bus_reaches_stops");
         flags.savestate_every = Some(Duration::seconds(30.0));
-        let (map, mut sim, _) = flags.load(&mut Timer::throwaway());
+        let (mut map, mut sim, _) = flags.load(&mut Timer::throwaway());
         let route = map.get_bus_route("49").unwrap();
         let buses = sim.seed_bus_route(route, &map, &mut Timer::throwaway());
         let bus = buses[0];
@@ -20,20 +20,21 @@ pub fn run(t: &mut TestRunner) {
             expectations.push(Event::BusDepartedFromStop(bus, *stop));
         }
 
-        sim.run_until_expectations_met(&map, expectations, Duration::minutes(10));
+        sim.run_until_expectations_met(&mut map, expectations, Duration::minutes(10));
         // Make sure buses don't block a sim from being considered done
-        sim.just_run_until_done(&map, Some(Duration::minutes(11)));
+        sim.just_run_until_done(&mut map, Some(Duration::minutes(11)));
     });
 
     t.run_slow("ped_uses_bus", |h| {
         let mut flags = SimFlags::for_test("ped_uses_bus");
         flags.savestate_every = Some(Duration::seconds(30.0));
-        let (map, mut sim, mut rng) = flags.load(&mut Timer::throwaway());
+        let (mut map, mut sim, mut rng) = flags.load(&mut Timer::throwaway());
         let route = map.get_bus_route("49").unwrap();
         let buses = sim.seed_bus_route(route, &map, &mut Timer::throwaway());
         let bus = buses[0];
         let ped_stop1 = route.stops[1];
         let ped_stop2 = route.stops[2];
+        let next_stop = route.stops[3];
         // TODO These should be buildings near the two stops. Programmatically find these?
         let start_bldg = *map
             .get_l(map.get_bs(ped_stop1).sidewalk_pos.lane())
@@ -64,7 +65,7 @@ pub fn run(t: &mut TestRunner) {
         h.setup_done(&sim);
 
         sim.run_until_expectations_met(
-            &map,
+            &mut map,
             vec![
                 Event::PedReachedBusStop(ped, ped_stop1),
                 Event::BusArrivedAtStop(bus, ped_stop1),
@@ -74,7 +75,7 @@ pub fn run(t: &mut TestRunner) {
                 Event::PedLeavesBus(ped, bus),
                 Event::PedReachedBuilding(ped, goal_bldg),
                 Event::BusDepartedFromStop(bus, ped_stop2),
-                Event::BusArrivedAtStop(bus, route.stops[3]),
+                Event::BusArrivedAtStop(bus, next_stop),
             ],
             Duration::minutes(9),
         );
@@ -8,7 +8,7 @@ pub fn run(t: &mut TestRunner) {
     t.run_slow("bike_from_border", |h| {
         let mut flags = SimFlags::for_test("bike_from_border");
         flags.savestate_every = Some(Duration::seconds(30.0));
-        let (map, mut sim, mut rng) = flags.load(&mut Timer::throwaway());
+        let (mut map, mut sim, mut rng) = flags.load(&mut Timer::throwaway());
         // TODO Hardcoding IDs is fragile
         let goal_bldg = BuildingID(319);
         let (ped, bike) = sim.schedule_trip(
@@ -24,17 +24,15 @@ pub fn run(t: &mut TestRunner) {
         sim.spawn_all_trips(&map, &mut Timer::throwaway(), false);
         h.setup_done(&sim);
 
+        let goal_sidewalk = map.get_b(goal_bldg).front_path.sidewalk.lane();
         sim.run_until_expectations_met(
-            &map,
+            &mut map,
             vec![
-                Event::BikeStoppedAtSidewalk(
-                    bike.unwrap(),
-                    map.get_b(goal_bldg).front_path.sidewalk.lane(),
-                ),
+                Event::BikeStoppedAtSidewalk(bike.unwrap(), goal_sidewalk),
                 Event::PedReachedBuilding(ped.unwrap(), goal_bldg),
             ],
             Duration::minutes(7),
         );
-        sim.just_run_until_done(&map, Some(Duration::minutes(1)));
+        sim.just_run_until_done(&mut map, Some(Duration::minutes(1)));
     });
 }